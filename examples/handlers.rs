@@ -0,0 +1,83 @@
+// The types and handlers only exist for the spec they declare
+#![allow(dead_code)]
+
+use damascus::{
+    JsonSchema, aat::AAT, endpoint, generate::typescript::TypeScriptGenerator, spec::Spec,
+};
+
+#[derive(JsonSchema)]
+pub struct Machine {
+    name: String,
+    image: String,
+}
+
+#[derive(JsonSchema)]
+pub struct Status {
+    running: bool,
+}
+
+#[derive(JsonSchema)]
+pub struct Log {
+    line: String,
+}
+
+#[derive(JsonSchema)]
+pub struct LogRequest {
+    follow: Option<bool>,
+}
+
+#[derive(Debug)]
+struct Error;
+
+// Handlers register themselves under the service named after their module
+mod machines {
+    use super::*;
+
+    #[endpoint(get, "machines/{name}")]
+    pub fn get(
+        #[header("x-ignition-namespace")] namespace: String,
+        name: String,
+    ) -> Result<Machine, Error> {
+        let _ = (namespace, name);
+        Err(Error)
+    }
+
+    #[endpoint(get, "machines")]
+    pub fn list(
+        #[header("x-ignition-namespace")] namespace: Option<String>,
+    ) -> Result<Vec<(Machine, Status)>, Error> {
+        let _ = namespace;
+        Ok(vec![])
+    }
+
+    #[endpoint(put, "machines", invalidates = "machines")]
+    pub fn apply(#[body] machine: Machine) -> Result<(), Error> {
+        let _ = machine;
+        Ok(())
+    }
+
+    #[endpoint(get, "machines/{name}/logs", ws, response = Log)]
+    pub fn logs(name: String, #[query] request: LogRequest) -> std::sync::mpsc::Receiver<Log> {
+        let _ = (name, request);
+        std::sync::mpsc::channel().1
+    }
+}
+
+struct Auth;
+
+impl Auth {
+    #[endpoint(post, "auth/login", service = "auth")]
+    fn login(&self, #[header("authorization", "Bearer {token}")] token: String) -> String {
+        token
+    }
+}
+
+fn main() {
+    let spec = Spec::new("ignition").registered_endpoints();
+
+    let aat = AAT::from_spec(&spec).expect("Failed to convert spec");
+    aat.validate().expect("Invalid spec");
+
+    let client = TypeScriptGenerator::generate(&aat).expect("Failed to generate client");
+    println!("{}", client);
+}
@@ -5,7 +5,7 @@ use damascus::{
     aat::AAT,
     generate::typescript::TypeScriptGenerator,
     path,
-    spec::{Spec, Upgrade},
+    spec::{Spec, Upgrade, WsAuth},
     type_of, type_of_tuple,
 };
 use damascus_meta::header_value;
@@ -232,6 +232,7 @@ fn main() {
             "x-ignition-token",
             header_value!("Bearer {token}" use token: String),
         )
+        .ws_auth(WsAuth::Protocol("ignition".to_string()))
         .service("machines", |service| {
             service
                 .get("get", path!("machines", name: String), |endpoint| {
@@ -257,10 +258,12 @@ fn main() {
                     },
                 )
                 .put("apply", path!("machines"), |endpoint| {
-                    endpoint.body(type_of!(Machine))
+                    endpoint.body(type_of!(Machine)).invalidates("machines")
                 })
                 .delete("delete", path!("machines", name: String), |endpoint| {
-                    endpoint.header("x-ignition-namespace", header_value!(namespace: String))
+                    endpoint
+                        .header("x-ignition-namespace", header_value!(namespace: String))
+                        .invalidates("machines.list")
                 })
                 .get(
                     "logs",
@@ -286,7 +289,7 @@ fn main() {
                     endpoint.body(type_of!(MachineVolumeBinding))
                 })
                 .delete("delete", path!("volumes", name: String), |endpoint| {
-                    endpoint
+                    endpoint.invalidates("volumes")
                 })
         })
         .service("auth", |service| {
@@ -0,0 +1,509 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::ext::IdentExt;
+use syn::parse::Parse;
+use syn::spanned::Spanned;
+
+use crate::header::check_pattern;
+use crate::path::{PathCheck, TemplateSegment, parse_template};
+
+/// The arguments of `#[endpoint(get, "machines/{name}", service = "machines", ...)]`
+pub struct EndpointArgs {
+    method: syn::Ident,
+    template: syn::LitStr,
+    service: Option<syn::LitStr>,
+    name: Option<syn::LitStr>,
+    response: Option<syn::Type>,
+    invalidates: Vec<syn::LitStr>,
+    ws: bool,
+}
+
+impl Parse for EndpointArgs {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let method = input.parse::<syn::Ident>()?;
+        if !["get", "post", "put", "delete", "patch"].contains(&method.to_string().as_str()) {
+            return Err(syn::Error::new(
+                method.span(),
+                "Expected one of `get`, `post`, `put`, `delete` or `patch`",
+            ));
+        }
+        input.parse::<syn::Token![,]>()?;
+        let template = input.parse::<syn::LitStr>()?;
+
+        let mut args = Self {
+            method,
+            template,
+            service: None,
+            name: None,
+            response: None,
+            invalidates: Vec::new(),
+            ws: false,
+        };
+        while !input.is_empty() {
+            input.parse::<syn::Token![,]>()?;
+            if input.is_empty() {
+                break;
+            }
+            let key = input.parse::<syn::Ident>()?;
+            match key.to_string().as_str() {
+                "ws" => args.ws = true,
+                "service" | "name" | "invalidates" | "response" => {
+                    input.parse::<syn::Token![=]>()?;
+                    match key.to_string().as_str() {
+                        "service" => args.service = Some(input.parse()?),
+                        "name" => args.name = Some(input.parse()?),
+                        "invalidates" => args.invalidates.push(input.parse()?),
+                        _ => args.response = Some(input.parse()?),
+                    }
+                }
+                _ => {
+                    return Err(syn::Error::new(
+                        key.span(),
+                        "Expected `service`, `name`, `response`, `invalidates` or `ws`",
+                    ));
+                }
+            }
+        }
+
+        Ok(args)
+    }
+}
+
+/// What a handler's signature says about the request
+#[derive(Default)]
+struct Inferred {
+    path: Vec<(String, syn::Type)>,
+    query: Option<syn::Type>,
+    body: Option<syn::Type>,
+    headers: Vec<(syn::LitStr, Option<syn::LitStr>, String, syn::Type)>,
+}
+
+pub fn expand(args: EndpointArgs, mut function: syn::ItemFn) -> syn::Result<TokenStream> {
+    let template = parse_template(&args.template)?;
+    let mut check = PathCheck::default();
+    for (segment, span) in &template {
+        match segment {
+            TemplateSegment::Literal(_) => check.segment(None, false, *span)?,
+            TemplateSegment::Param { name, wildcard, .. } => {
+                check.segment(Some(name), *wildcard, *span)?
+            }
+        }
+    }
+    if let Some((_, span)) = template.iter().find(|(segment, _)| {
+        matches!(
+            segment,
+            TemplateSegment::Param {
+                r#type: Some(_),
+                ..
+            }
+        )
+    }) {
+        return Err(syn::Error::new(
+            *span,
+            "Path parameter types come from the handler's arguments",
+        ));
+    }
+    let params = template
+        .iter()
+        .filter_map(|(segment, _)| match segment {
+            TemplateSegment::Param { name, .. } => Some(name.clone()),
+            TemplateSegment::Literal(_) => None,
+        })
+        .collect::<Vec<_>>();
+
+    let mut inferred = Inferred::default();
+    for input in function.sig.inputs.iter_mut() {
+        if let syn::FnArg::Typed(arg) = input {
+            infer_argument(arg, &params, &mut inferred)?;
+        }
+    }
+    if let Some((name, _)) = inferred
+        .path
+        .iter()
+        .find(|(name, _)| !params.contains(name))
+    {
+        return Err(syn::Error::new(
+            args.template.span(),
+            format!("The path template has no `{{{}}}` parameter", name),
+        ));
+    }
+
+    let segments = template
+        .iter()
+        .map(|(segment, _)| match segment {
+            TemplateSegment::Literal(literal) => {
+                Ok(quote! { damascus::spec::PathSegment::Literal(#literal.to_string()) })
+            }
+            TemplateSegment::Param { name, wildcard, .. } => {
+                let mut matches = inferred.path.iter().filter(|(param, _)| param == name);
+                let Some((_, r#type)) = matches.next() else {
+                    return Err(syn::Error::new(
+                        args.template.span(),
+                        format!("Path parameter `{{{}}}` has no matching argument", name),
+                    ));
+                };
+                if matches.next().is_some() {
+                    return Err(syn::Error::new(
+                        args.template.span(),
+                        format!(
+                            "Path parameter `{{{}}}` is bound by more than one argument",
+                            name
+                        ),
+                    ));
+                }
+                if *wildcard {
+                    return Ok(quote! { damascus::spec::PathSegment::Wildcard(#name.to_string()) });
+                }
+                Ok(quote! {
+                    damascus::spec::PathSegment::Type {
+                        name: #name.to_string(),
+                        r#type: damascus::type_of!(#r#type),
+                    }
+                })
+            }
+        })
+        .collect::<syn::Result<Vec<_>>>()?;
+
+    let (response, stream) = match &args.response {
+        Some(response) => (Some(response.clone()), false),
+        None => response_type(&function.sig.output)?,
+    };
+    let response = match response {
+        Some(response) if args.ws || stream => {
+            let response = type_expr(&response);
+            quote! {
+                .response(#response.wrap_stream())
+                .upgrade(damascus::spec::Upgrade::Ws)
+            }
+        }
+        Some(response) => {
+            let response = type_expr(&response);
+            quote! { .response(#response) }
+        }
+        None if args.ws => {
+            return Err(syn::Error::new(
+                function.sig.output.span(),
+                "WebSocket endpoints need a response type to stream",
+            ));
+        }
+        None => quote! {},
+    };
+
+    let query = inferred.query.as_ref().map(|query| {
+        let query = type_expr(query);
+        quote! { .query(#query) }
+    });
+    let body = inferred.body.as_ref().map(|body| {
+        let body = type_expr(body);
+        quote! { .body(#body) }
+    });
+    let headers = inferred
+        .headers
+        .iter()
+        .map(|(header, pattern, name, r#type)| {
+            let value = match pattern {
+                Some(pattern) => quote! {
+                    damascus::spec::HeaderValue::Pattern {
+                        pattern: #pattern.to_string(),
+                        params: vec![damascus::spec::HeaderParam {
+                            name: #name.to_string(),
+                            r#type: damascus::type_of!(#r#type),
+                            format: None,
+                        }],
+                    }
+                },
+                None => quote! {
+                    damascus::spec::HeaderValue::Type {
+                        name: #name.to_string(),
+                        r#type: damascus::type_of!(#r#type),
+                    }
+                },
+            };
+            quote! { .header(#header, #value) }
+        });
+    let invalidates = &args.invalidates;
+
+    let name = match &args.name {
+        Some(name) => name.value(),
+        None => function.sig.ident.to_string(),
+    };
+    let method = match args.method.to_string().as_str() {
+        "get" => quote! { Get },
+        "post" => quote! { Post },
+        "put" => quote! { Put },
+        "delete" => quote! { Delete },
+        _ => quote! { Patch },
+    };
+    let service = match &args.service {
+        Some(service) => quote! { Some(#service) },
+        None => quote! { None },
+    };
+
+    // Registered from inside the body so handlers can also be trait or inherent methods
+    let registration: syn::Stmt = syn::parse_quote! {
+        const _: () = {
+            fn endpoint() -> damascus::spec::Endpoint {
+                damascus::spec::Endpoint::new(
+                    #name,
+                    damascus::spec::Method::#method,
+                    vec![#(#segments),*],
+                )
+                #query
+                #body
+                #(#headers)*
+                #response
+                #(.invalidates(#invalidates))*
+            }
+
+            damascus::inventory::submit! {
+                damascus::spec::Registration {
+                    module: module_path!(),
+                    service: #service,
+                    file: file!(),
+                    line: line!(),
+                    endpoint,
+                }
+            }
+        };
+    };
+    function.block.stmts.insert(0, registration);
+
+    Ok(quote! { #function })
+}
+
+/// Records what an argument contributes to the endpoint and strips the markers it carries
+fn infer_argument(
+    arg: &mut syn::PatType,
+    params: &[String],
+    inferred: &mut Inferred,
+) -> syn::Result<()> {
+    let mut marker = None;
+    let mut error = None;
+    arg.attrs.retain(|attr| {
+        let Some(ident) = attr.path().get_ident() else {
+            return true;
+        };
+        let kind = ident.to_string();
+        if !["path", "query", "body", "header"].contains(&kind.as_str()) {
+            return true;
+        }
+        if marker.is_some() {
+            error.get_or_insert(syn::Error::new(
+                attr.span(),
+                "An argument can only be one of `path`, `query`, `body` or `header`",
+            ));
+        }
+        marker = Some((kind, attr.clone()));
+        false
+    });
+    if let Some(error) = error {
+        return Err(error);
+    }
+
+    let r#type = (*arg.ty).clone();
+    match marker {
+        Some((kind, attr)) => match kind.as_str() {
+            "query" => set_once(&mut inferred.query, r#type, &attr, "query")?,
+            "body" => set_once(&mut inferred.body, r#type, &attr, "body")?,
+            "header" => {
+                let (header, pattern) =
+                    attr.parse_args_with(|input: syn::parse::ParseStream| {
+                        let header = input.parse::<syn::LitStr>()?;
+                        let mut pattern = None;
+                        if input.parse::<Option<syn::Token![,]>>()?.is_some() {
+                            pattern = Some(input.parse::<syn::LitStr>()?);
+                        }
+                        Ok((header, pattern))
+                    })?;
+                let name = binding_name(&arg.pat).ok_or_else(|| {
+                    syn::Error::new(arg.pat.span(), "Header arguments must be plain identifiers")
+                })?;
+                if let (Some(pattern), syn::Pat::Ident(binding)) = (&pattern, &*arg.pat) {
+                    check_pattern(pattern, &[&binding.ident])?;
+                }
+                inferred.headers.push((header, pattern, name, r#type));
+            }
+            _ => {
+                let name = binding_name(&arg.pat).ok_or_else(|| {
+                    syn::Error::new(arg.pat.span(), "Path arguments must be plain identifiers")
+                })?;
+                inferred.path.push((name, r#type));
+            }
+        },
+        None => match extractor(&r#type) {
+            Some(("Path", inner)) => {
+                let types = match inner {
+                    syn::Type::Tuple(tuple) => tuple.elems.iter().cloned().collect(),
+                    inner => vec![inner.clone()],
+                };
+                if types.len() != params.len() {
+                    return Err(syn::Error::new(
+                        arg.ty.span(),
+                        format!(
+                            "`Path` extracts {} parameters but the path template has {}",
+                            types.len(),
+                            params.len()
+                        ),
+                    ));
+                }
+                inferred.path.extend(params.iter().cloned().zip(types));
+            }
+            Some(("Query", inner)) => {
+                set_once(&mut inferred.query, inner.clone(), &arg.ty, "query")?
+            }
+            Some(("Json", inner)) => set_once(&mut inferred.body, inner.clone(), &arg.ty, "body")?,
+            _ => {
+                // Anything else, such as `State` or a `HeaderMap`, isn't part of the spec
+                if let Some(name) = binding_name(&arg.pat).filter(|name| params.contains(name)) {
+                    inferred.path.push((name, r#type));
+                }
+            }
+        },
+    }
+
+    Ok(())
+}
+
+fn set_once(
+    slot: &mut Option<syn::Type>,
+    r#type: syn::Type,
+    span: &impl Spanned,
+    what: &str,
+) -> syn::Result<()> {
+    if slot.replace(r#type).is_some() {
+        return Err(syn::Error::new(
+            span.span(),
+            format!("Only one argument can be the {}", what),
+        ));
+    }
+    Ok(())
+}
+
+fn binding_name(pat: &syn::Pat) -> Option<String> {
+    match pat {
+        syn::Pat::Ident(ident) => Some(ident.ident.unraw().to_string()),
+        _ => None,
+    }
+}
+
+/// `Path<T>`, `Query<T>` or `Json<T>`, matched by their last path segment so any framework's
+/// extractors of those names work
+fn extractor(r#type: &syn::Type) -> Option<(&'static str, &syn::Type)> {
+    let (name, inner) = generic_argument(r#type)?;
+    ["Path", "Query", "Json"]
+        .into_iter()
+        .find(|extractor| name == *extractor)
+        .map(|extractor| (extractor, inner))
+}
+
+/// The name and first type argument of a type like `Name<T>`
+fn generic_argument(r#type: &syn::Type) -> Option<(String, &syn::Type)> {
+    let syn::Type::Path(path) = r#type else {
+        return None;
+    };
+    let segment = path.path.segments.last()?;
+    let syn::PathArguments::AngleBracketed(arguments) = &segment.arguments else {
+        return None;
+    };
+    arguments.args.iter().find_map(|argument| match argument {
+        syn::GenericArgument::Type(inner) => Some((segment.ident.to_string(), inner)),
+        _ => None,
+    })
+}
+
+/// The type a handler responds with, and whether it's streamed. Futures, results and `Json`
+/// wrappers are looked through; `()` responds with nothing. Opaque responses such as
+/// `impl IntoResponse` need an explicit `response = ...`.
+fn response_type(output: &syn::ReturnType) -> syn::Result<(Option<syn::Type>, bool)> {
+    let syn::ReturnType::Type(_, r#type) = output else {
+        return Ok((None, false));
+    };
+    let mut r#type = (**r#type).clone();
+    loop {
+        if let Some(inner) = impl_trait_output(&r#type, "Future", "Output") {
+            r#type = inner;
+            continue;
+        }
+        if let Some(inner) = impl_trait_output(&r#type, "Stream", "Item") {
+            return Ok((Some(inner), true));
+        }
+        match generic_argument(&r#type) {
+            Some((name, inner)) if name == "Result" || name == "Json" => {
+                r#type = inner.clone();
+            }
+            _ => break,
+        }
+    }
+    match &r#type {
+        syn::Type::Tuple(tuple) if tuple.elems.is_empty() => Ok((None, false)),
+        syn::Type::ImplTrait(_) => Err(opaque_response(&r#type)),
+        syn::Type::Path(path)
+            if path
+                .path
+                .segments
+                .last()
+                .is_some_and(|segment| segment.ident == "Response") =>
+        {
+            Err(opaque_response(&r#type))
+        }
+        _ => Ok((Some(r#type), false)),
+    }
+}
+
+fn opaque_response(r#type: &syn::Type) -> syn::Error {
+    let what = match r#type {
+        syn::Type::ImplTrait(_) => "an `impl Trait`",
+        _ => "a `Response`",
+    };
+    syn::Error::new(
+        r#type.span(),
+        format!(
+            "The response type can't be inferred from {}, declare it with `response = ...`",
+            what
+        ),
+    )
+}
+
+/// `T` in `impl Trait<Assoc = T>`
+fn impl_trait_output(r#type: &syn::Type, r#trait: &str, assoc: &str) -> Option<syn::Type> {
+    let syn::Type::ImplTrait(impl_trait) = r#type else {
+        return None;
+    };
+    impl_trait.bounds.iter().find_map(|bound| {
+        let syn::TypeParamBound::Trait(bound) = bound else {
+            return None;
+        };
+        let segment = bound.path.segments.last()?;
+        if segment.ident != r#trait {
+            return None;
+        }
+        let syn::PathArguments::AngleBracketed(arguments) = &segment.arguments else {
+            return None;
+        };
+        arguments.args.iter().find_map(|argument| match argument {
+            syn::GenericArgument::AssocType(binding) if binding.ident == assoc => {
+                Some(binding.ty.clone())
+            }
+            _ => None,
+        })
+    })
+}
+
+/// A `damascus::spec::Type` expression, keeping tuples and lists structural the way
+/// `type_of_tuple!` and `wrap_list` would
+fn type_expr(r#type: &syn::Type) -> TokenStream {
+    match r#type {
+        syn::Type::Tuple(tuple) if !tuple.elems.is_empty() => {
+            let types = tuple.elems.iter().map(type_expr);
+            quote! { damascus::spec::Type::tuple(vec![#(#types),*]) }
+        }
+        syn::Type::Paren(paren) => type_expr(&paren.elem),
+        _ => match generic_argument(r#type) {
+            Some((name, inner)) if name == "Vec" => {
+                let inner = type_expr(inner);
+                quote! { #inner.wrap_list() }
+            }
+            _ => quote! { damascus::type_of!(#r#type) },
+        },
+    }
+}
@@ -1,5 +1,8 @@
+use syn::ext::IdentExt;
 use syn::parse::Parse;
 
+use crate::path::{is_identifier, subspan};
+
 pub enum HeaderValue {
     Literal(syn::LitStr),
     Ident(syn::Ident),
@@ -9,11 +12,53 @@ pub enum HeaderValue {
     },
     Pattern {
         pattern: String,
-        name: syn::Ident,
-        r#type: syn::Type,
+        params: Vec<PatternParam>,
     },
 }
 
+/// A `name: Type` or `name: Type as format` parameter of a header pattern
+pub struct PatternParam {
+    pub name: syn::Ident,
+    pub r#type: syn::Type,
+    pub format: Option<HeaderFormat>,
+}
+
+pub enum HeaderFormat {
+    Base64,
+    Lowercase,
+    IsoDate,
+}
+
+impl Parse for PatternParam {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let name = input.parse::<syn::Ident>()?;
+        input.parse::<syn::Token![:]>()?;
+        let r#type = input.parse::<syn::Type>()?;
+        let format = if input.peek(syn::Token![as]) {
+            input.parse::<syn::Token![as]>()?;
+            let format = input.parse::<syn::Ident>()?;
+            Some(match format.to_string().as_str() {
+                "base64" => HeaderFormat::Base64,
+                "lowercase" => HeaderFormat::Lowercase,
+                "iso_date" => HeaderFormat::IsoDate,
+                _ => {
+                    return Err(syn::Error::new(
+                        format.span(),
+                        "Expected `base64`, `lowercase` or `iso_date`",
+                    ));
+                }
+            })
+        } else {
+            None
+        };
+        Ok(Self {
+            name,
+            r#type,
+            format,
+        })
+    }
+}
+
 impl Parse for HeaderValue {
     fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
         if input.peek(syn::LitStr) {
@@ -23,14 +68,23 @@ impl Parse for HeaderValue {
             if input.peek(syn::Token![use]) {
                 input.parse::<syn::Token![use]>()?;
 
-                let name = input.parse::<syn::Ident>()?;
-                input.parse::<syn::Token![:]>()?;
+                let mut params = Vec::new();
+                loop {
+                    params.push(input.parse::<PatternParam>()?);
+                    if input.is_empty() {
+                        break;
+                    }
+                    input.parse::<syn::Token![,]>()?;
+                    if input.is_empty() {
+                        break;
+                    }
+                }
 
-                let r#type = input.parse::<syn::Type>()?;
+                let names = params.iter().map(|param| &param.name).collect::<Vec<_>>();
+                check_pattern(&lit_str, &names)?;
                 return Ok(Self::Pattern {
                     pattern: lit_str.value(),
-                    name,
-                    r#type,
+                    params,
                 });
             }
 
@@ -52,3 +106,106 @@ impl Parse for HeaderValue {
         }
     }
 }
+
+/// The `{name}` placeholders of a header pattern with their byte ranges, rejecting stray braces
+/// and placeholders that touch, whose values couldn't be told apart
+fn placeholders(pattern: &syn::LitStr) -> syn::Result<Vec<(String, std::ops::Range<usize>)>> {
+    let value = pattern.value();
+    let mut placeholders: Vec<(String, std::ops::Range<usize>)> = Vec::new();
+    let mut rest = value.as_str();
+    let mut offset = 0;
+    while let Some(start) = rest.find(['{', '}']) {
+        let start = offset + start;
+        if value[start..].starts_with('}') {
+            return Err(syn::Error::new(
+                subspan(pattern, start..start + 1),
+                "Unmatched `}` in header pattern",
+            ));
+        }
+        let Some(end) = value[start + 1..]
+            .find(['{', '}'])
+            .map(|end| start + 1 + end)
+        else {
+            return Err(syn::Error::new(
+                subspan(pattern, start..value.len()),
+                "Unclosed `{` in header pattern",
+            ));
+        };
+        let range = start..end + 1;
+        if value[end..].starts_with('{') {
+            return Err(syn::Error::new(
+                subspan(pattern, start..end),
+                "Unclosed `{` in header pattern",
+            ));
+        }
+        let name = &value[start + 1..end];
+        if !is_identifier(name) {
+            let message = if name.is_empty() {
+                "Placeholders must be named, as in `{token}`".to_string()
+            } else {
+                format!("`{{{}}}` is not a valid placeholder name", name)
+            };
+            return Err(syn::Error::new(subspan(pattern, range), message));
+        }
+        if placeholders
+            .last()
+            .is_some_and(|(_, previous)| previous.end == start)
+        {
+            return Err(syn::Error::new(
+                subspan(pattern, range),
+                "Placeholders must be separated by text",
+            ));
+        }
+        placeholders.push((name.to_string(), range));
+        offset = end + 1;
+        rest = &value[offset..];
+    }
+    Ok(placeholders)
+}
+
+/// Checks that every placeholder of a pattern is one of `params` and every param is used once
+pub fn check_pattern(pattern: &syn::LitStr, params: &[&syn::Ident]) -> syn::Result<()> {
+    let placeholders = placeholders(pattern)?;
+    let names = params
+        .iter()
+        .map(|param| param.unraw().to_string())
+        .collect::<Vec<_>>();
+
+    for (index, name) in names.iter().enumerate() {
+        if names[..index].contains(name) {
+            return Err(syn::Error::new(
+                params[index].span(),
+                format!("Parameter `{}` is declared more than once", name),
+            ));
+        }
+    }
+    for (index, (name, range)) in placeholders.iter().enumerate() {
+        if !names.contains(name) {
+            return Err(syn::Error::new(
+                subspan(pattern, range.clone()),
+                format!("Placeholder `{{{}}}` has no matching parameter", name),
+            ));
+        }
+        if placeholders[..index].iter().any(|(other, _)| other == name) {
+            return Err(syn::Error::new(
+                subspan(pattern, range.clone()),
+                format!("Placeholder `{{{}}}` appears more than once", name),
+            ));
+        }
+    }
+    for (param, name) in params.iter().zip(&names) {
+        if !placeholders
+            .iter()
+            .any(|(placeholder, _)| placeholder == name)
+        {
+            return Err(syn::Error::new(
+                param.span(),
+                format!(
+                    "Parameter `{}` has no `{{{}}}` placeholder in the pattern",
+                    name, name
+                ),
+            ));
+        }
+    }
+    Ok(())
+}
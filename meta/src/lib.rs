@@ -1,10 +1,12 @@
+mod endpoint;
 mod header;
 mod path;
 
 use quote::quote;
+use syn::ext::IdentExt;
 
 use crate::{
-    header::HeaderValue,
+    header::{HeaderFormat, HeaderValue},
     path::{PathSegment, PathSegments},
 };
 
@@ -14,6 +16,9 @@ use crate::{
         PathSegment::Type { name: "namespace", r#type: type_of!(String) },
         PathSegment::Literal("abcd"),
     ]
+   path!("/core/{namespace: String}/abcd") => the same segments, from a template
+   path!("/files/{*rest}") => [PathSegment::Literal("files"), PathSegment::Wildcard("rest")]
+   Only literals with a `{` or `/` are templates, split and checked; others stay one segment as they are
 */
 #[proc_macro]
 pub fn path(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
@@ -24,9 +29,9 @@ pub fn path(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
         .map(|segment| match segment {
             PathSegment::Literal(literal) => quote! { damascus::spec::PathSegment::Literal(#literal.to_string()) },
             PathSegment::Type { name, r#type } => {
-                let name = name.to_string();
                 quote! { damascus::spec::PathSegment::Type { name: #name.to_string(), r#type: damascus::type_of!(#r#type) } }
             }
+            PathSegment::Wildcard(name) => quote! { damascus::spec::PathSegment::Wildcard(#name.to_string()) },
         })
         .collect::<Vec<_>>();
 
@@ -37,7 +42,9 @@ pub fn path(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
    header_value!("the value of the header") => damascus::spec::HeaderValue::Literal("the value of the header".to_string())
    header_value!(any_identifier) => damascus::spec::HeaderValue::Literal(any_identifier.to_string())
    header_value!(namespace: String) => damascus::spec::HeaderValue::Type{ name: "namespace".to_string(), r#type: damascus::type_of!(String) })
-   header_value!("Bearer {}" use apiKey: String) => damascus::spec::HeaderValue::Pattern{ pattern: "Bearer {}".to_string(), name: "apiKey".to_string(), r#type: damascus::type_of!(String) })
+   header_value!("Bearer {apiKey}" use apiKey: String) => damascus::spec::HeaderValue::Pattern{ pattern: "Bearer {apiKey}".to_string(), params: vec![damascus::spec::HeaderParam{ name: "apiKey".to_string(), r#type: damascus::type_of!(String), format: None }] })
+   header_value!("{scheme} {token}" use scheme: String, token: String) => one param per placeholder, each used exactly once
+   header_value!("Signature {sig}" use sig: String as base64) => format: Some(damascus::spec::HeaderFormat::Base64), also `lowercase` and `iso_date`
 */
 #[proc_macro]
 pub fn header_value(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
@@ -55,18 +62,76 @@ pub fn header_value(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
                 quote! { damascus::spec::HeaderValue::Type{ name: #name.to_string(), r#type: damascus::type_of!(#r#type) } },
             )
         }
-        HeaderValue::Pattern {
-            pattern,
-            name,
-            r#type,
-        } => {
-            let pattern = pattern.to_string();
-            let name = name.to_string();
+        HeaderValue::Pattern { pattern, params } => {
+            let params = params.iter().map(|param| {
+                let name = param.name.unraw().to_string();
+                let r#type = &param.r#type;
+                let format = match param.format {
+                    Some(HeaderFormat::Base64) => quote! { Some(damascus::spec::HeaderFormat::Base64) },
+                    Some(HeaderFormat::Lowercase) => quote! { Some(damascus::spec::HeaderFormat::Lowercase) },
+                    Some(HeaderFormat::IsoDate) => quote! { Some(damascus::spec::HeaderFormat::IsoDate) },
+                    None => quote! { None },
+                };
+                quote! { damascus::spec::HeaderParam { name: #name.to_string(), r#type: damascus::type_of!(#r#type), format: #format } }
+            });
             proc_macro::TokenStream::from(
-                quote! { damascus::spec::HeaderValue::Pattern{ pattern: #pattern.to_string(), name: #name.to_string(), r#type: damascus::type_of!(#r#type) } },
+                quote! { damascus::spec::HeaderValue::Pattern{ pattern: #pattern.to_string(), params: vec![#(#params),*] } },
             )
         }
     };
 
     proc_macro::TokenStream::from(token_stream)
 }
+
+/*
+   #[endpoint(get, "machines/{name}")]
+   async fn get(name: String, #[header("x-ignition-namespace")] namespace: String) -> Result<Machine, Error> { ... }
+   => registers damascus::spec::Endpoint::new("get", Method::Get, path!("machines", name: String))
+          .header("x-ignition-namespace", header_value!(namespace: String))
+          .response(type_of!(Machine))
+      under the service named after the enclosing module, for Spec::registered_endpoints
+*/
+#[proc_macro_attribute]
+pub fn endpoint(
+    args: proc_macro::TokenStream,
+    input: proc_macro::TokenStream,
+) -> proc_macro::TokenStream {
+    let args = syn::parse_macro_input!(args as endpoint::EndpointArgs);
+    let function = syn::parse_macro_input!(input as syn::ItemFn);
+    endpoint::expand(args, function)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/*
+   #[rust_path]
+   #[derive(JsonSchema)]
+   pub struct Part { ... }
+   => the same item with #[schemars(extend("x-rust-path" = concat!(module_path!(), "::Part")))],
+      so the type keeps its path where it is only reached through another type's schema
+*/
+#[proc_macro_attribute]
+pub fn rust_path(
+    args: proc_macro::TokenStream,
+    input: proc_macro::TokenStream,
+) -> proc_macro::TokenStream {
+    if !args.is_empty() {
+        return syn::Error::new(
+            proc_macro2::TokenStream::from(args)
+                .into_iter()
+                .next()
+                .unwrap()
+                .span(),
+            "`rust_path` takes no arguments",
+        )
+        .into_compile_error()
+        .into();
+    }
+    let mut item = syn::parse_macro_input!(input as syn::DeriveInput);
+    // After the derive that introduces it, as helper attributes must be
+    let path = format!("::{}", item.ident.unraw());
+    item.attrs.push(syn::parse_quote! {
+        #[schemars(extend("x-rust-path" = concat!(module_path!(), #path)))]
+    });
+    quote! { #item }.into()
+}
@@ -1,3 +1,6 @@
+use std::ops::Range;
+
+use proc_macro2::Span;
 use syn::parse::Parse;
 
 pub struct PathSegments {
@@ -7,12 +10,73 @@ pub struct PathSegments {
 impl Parse for PathSegments {
     fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
         let mut segments = Vec::new();
+        let mut check = PathCheck::default();
         while !input.is_empty() {
             if input.peek(syn::token::Comma) {
                 input.parse::<syn::Token!(,)>()?;
+                continue;
             }
 
-            segments.push(input.parse()?);
+            let parsed = if input.peek(syn::Ident) {
+                let name = input.parse::<syn::Ident>()?;
+                input.parse::<syn::Token!(:)>()?;
+                let r#type = input.parse::<syn::Type>()?;
+                vec![(
+                    PathSegment::Type {
+                        name: name.to_string(),
+                        r#type,
+                    },
+                    name.span(),
+                )]
+            } else {
+                let template = input.parse::<syn::LitStr>()?;
+                // Plain literals are segments as they are; only templates are split and checked
+                if !template.value().contains(['{', '/']) {
+                    check.segment(None, false, template.span())?;
+                    segments.push(PathSegment::Literal(template.value()));
+                    continue;
+                }
+                parse_template(&template)?
+                    .into_iter()
+                    .map(|(segment, span)| {
+                        let segment = match segment {
+                            TemplateSegment::Literal(literal) => PathSegment::Literal(literal),
+                            TemplateSegment::Param {
+                                name,
+                                r#type: Some(r#type),
+                                wildcard: false,
+                            } => PathSegment::Type {
+                                name,
+                                r#type: *r#type,
+                            },
+                            TemplateSegment::Param {
+                                name,
+                                wildcard: false,
+                                ..
+                            } => {
+                                return Err(syn::Error::new(
+                                    span,
+                                    format!(
+                                        "`{{{}}}` needs a type, as in `{{{}: String}}`",
+                                        name, name
+                                    ),
+                                ));
+                            }
+                            TemplateSegment::Param { name, .. } => PathSegment::Wildcard(name),
+                        };
+                        Ok((segment, span))
+                    })
+                    .collect::<syn::Result<Vec<_>>>()?
+            };
+
+            for (segment, span) in parsed {
+                match &segment {
+                    PathSegment::Literal(_) => check.segment(None, false, span)?,
+                    PathSegment::Type { name, .. } => check.segment(Some(name), false, span)?,
+                    PathSegment::Wildcard(name) => check.segment(Some(name), true, span)?,
+                }
+                segments.push(segment);
+            }
         }
 
         Ok(Self { segments })
@@ -20,19 +84,166 @@ impl Parse for PathSegments {
 }
 
 pub enum PathSegment {
-    Literal(syn::LitStr),
-    Type { name: syn::Ident, r#type: syn::Type },
+    Literal(String),
+    Type {
+        name: String,
+        r#type: syn::Type,
+    },
+    /// Captures the rest of the path, slashes included, as a string
+    Wildcard(String),
 }
 
-impl Parse for PathSegment {
-    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
-        if input.peek(syn::Ident) {
-            let name = input.parse::<syn::Ident>()?;
-            input.parse::<syn::Token!(:)>()?;
-            let r#type = input.parse::<syn::Type>()?;
-            Ok(Self::Type { name, r#type })
-        } else {
-            Ok(Self::Literal(input.parse::<syn::LitStr>()?))
+/// Rejects repeated parameter names and segments after a wildcard across a whole path
+#[derive(Default)]
+pub struct PathCheck {
+    names: Vec<String>,
+    wildcard: Option<String>,
+}
+
+impl PathCheck {
+    pub fn segment(&mut self, param: Option<&str>, wildcard: bool, span: Span) -> syn::Result<()> {
+        if let Some(wildcard) = &self.wildcard {
+            return Err(syn::Error::new(
+                span,
+                format!("Nothing can follow the wildcard `{{*{}}}`", wildcard),
+            ));
+        }
+        if let Some(name) = param {
+            if self.names.iter().any(|existing| existing == name) {
+                return Err(syn::Error::new(
+                    span,
+                    format!("Duplicate path parameter `{}`", name),
+                ));
+            }
+            self.names.push(name.to_string());
+            if wildcard {
+                self.wildcard = Some(name.to_string());
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A `/`-separated part of a path template such as `"/machines/{name: String}/{*rest}"`
+pub enum TemplateSegment {
+    Literal(String),
+    Param {
+        name: String,
+        r#type: Option<Box<syn::Type>>,
+        wildcard: bool,
+    },
+}
+
+/// Parses a path template, pairing each segment with the span of its text in the literal where
+/// the compiler can point into literals, and the whole literal elsewhere
+pub fn parse_template(template: &syn::LitStr) -> syn::Result<Vec<(TemplateSegment, Span)>> {
+    let value = template.value();
+    let mut segments = Vec::new();
+    let mut start = 0;
+    for (index, segment) in value.split('/').enumerate() {
+        let range = start..start + segment.len();
+        start = range.end + 1;
+        let span = subspan(template, range.clone());
+
+        if segment.is_empty() {
+            // Leading and trailing slashes are allowed, `//` is not
+            if index == 0 || range.start == value.len() {
+                continue;
+            }
+            return Err(syn::Error::new(span, "Empty path segment"));
+        }
+
+        let Some(inner) = segment
+            .strip_prefix('{')
+            .and_then(|segment| segment.strip_suffix('}'))
+            .filter(|inner| !inner.contains(['{', '}']))
+        else {
+            if segment.contains(['{', '}']) {
+                return Err(syn::Error::new(
+                    span,
+                    format!(
+                        "`{}` mixes text and a parameter; parameters must be whole segments",
+                        segment
+                    ),
+                ));
+            }
+            if let Some(c) = segment.chars().find(|c| !is_path_char(*c)) {
+                return Err(syn::Error::new(
+                    span,
+                    format!("Invalid character `{}` in path segment `{}`", c, segment),
+                ));
+            }
+            segments.push((TemplateSegment::Literal(segment.to_string()), span));
+            continue;
+        };
+
+        let (name, r#type) = match inner.split_once(':') {
+            Some((name, r#type)) => {
+                let r#type = syn::LitStr::new(r#type.trim(), span)
+                    .parse::<syn::Type>()
+                    .map_err(|error| {
+                        syn::Error::new(span, format!("Invalid type in `{}`: {}", segment, error))
+                    })?;
+                (name.trim(), Some(Box::new(r#type)))
+            }
+            None => (inner.trim(), None),
+        };
+        let (name, wildcard) = match name.strip_prefix('*') {
+            Some(name) => (name, true),
+            None => (name, false),
+        };
+        if !is_identifier(name) {
+            return Err(syn::Error::new(
+                span,
+                format!("`{}` is not a valid path parameter name", name),
+            ));
+        }
+        if wildcard && r#type.is_some() {
+            return Err(syn::Error::new(
+                span,
+                format!("Wildcard `{{*{}}}` always captures a `String`", name),
+            ));
         }
+        segments.push((
+            TemplateSegment::Param {
+                name: name.to_string(),
+                r#type,
+                wildcard,
+            },
+            span,
+        ));
+    }
+
+    Ok(segments)
+}
+
+/// Characters allowed unescaped in a path segment (RFC 3986 `pchar`), plus `%` for escapes
+fn is_path_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || "-._~!$&'()*+,;=:@%".contains(c)
+}
+
+pub fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && name != "_"
+}
+
+/// The span of `range` of a literal's value. Only literals without escapes can be mapped back to
+/// their source, and only nightly compilers can point into them.
+pub fn subspan(literal: &syn::LitStr, range: Range<usize>) -> Span {
+    let token = literal.token();
+    let source = token.to_string();
+    let (Some(open), Some(close)) = (source.find('"'), source.rfind('"')) else {
+        return literal.span();
+    };
+    let offset = open + 1;
+    if source.get(offset..close) != Some(literal.value().as_str()) {
+        return literal.span();
     }
+    token
+        .subspan(offset + range.start..offset + range.end)
+        .unwrap_or_else(|| literal.span())
 }
@@ -0,0 +1,498 @@
+//! Structural comparison of two AATs, e.g. a released API against the working tree.
+
+use super::equality::{constraints_are_equal, field_types_are_equal, types_are_structurally_equal};
+use super::types::*;
+use super::{AAT, get_type_name};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Changed,
+}
+
+/// A single difference between two AATs
+#[derive(Debug, Clone)]
+pub struct Change {
+    pub kind: ChangeKind,
+    /// What changed, e.g. `type MachineV1.name` or `endpoint machines.get`
+    pub location: String,
+    pub description: String,
+    /// Whether clients generated from the old AAT may stop working
+    pub breaking: bool,
+}
+
+impl std::fmt::Display for Change {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let sign = match self.kind {
+            ChangeKind::Added => '+',
+            ChangeKind::Removed => '-',
+            ChangeKind::Changed => '~',
+        };
+        let breaking = if self.breaking { " [breaking]" } else { "" };
+        write!(
+            f,
+            "{} {}: {}{}",
+            sign, self.location, self.description, breaking
+        )
+    }
+}
+
+/// Lists every difference between `old` and `new`, types first, then headers and services
+pub fn diff(old: &AAT, new: &AAT) -> Vec<Change> {
+    let mut changes = Vec::new();
+    diff_types(old, new, &mut changes);
+    diff_headers("headers", &old.headers, &new.headers, &mut changes);
+    diff_services(old, new, &mut changes);
+    changes
+}
+
+fn change(
+    kind: ChangeKind,
+    location: String,
+    description: impl Into<String>,
+    breaking: bool,
+) -> Change {
+    Change {
+        kind,
+        location,
+        description: description.into(),
+        breaking,
+    }
+}
+
+fn diff_types(old: &AAT, new: &AAT, changes: &mut Vec<Change>) {
+    for old_type in &old.types {
+        let name = get_type_name(old_type);
+        let location = format!("type {}", name);
+        match new.find_type(name) {
+            None => changes.push(change(ChangeKind::Removed, location, "type removed", true)),
+            Some(new_type) => match (old_type, new_type) {
+                (NamedType::Object(a), NamedType::Object(b)) => {
+                    diff_fields(name, &a.fields, &b.fields, changes)
+                }
+                (NamedType::Enum(a), NamedType::Enum(b)) => diff_enum(name, a, b, changes),
+                (a, b) if types_are_structurally_equal(a, b) => {}
+                (NamedType::Union(_), NamedType::Union(_)) => {
+                    changes.push(change(
+                        ChangeKind::Changed,
+                        location,
+                        "union variants changed",
+                        true,
+                    ));
+                }
+                (a, b) => changes.push(change(
+                    ChangeKind::Changed,
+                    location,
+                    format!("changed from {} to {}", kind_name(a), kind_name(b)),
+                    true,
+                )),
+            },
+        }
+    }
+    for new_type in &new.types {
+        let name = get_type_name(new_type);
+        if old.find_type(name).is_none() {
+            changes.push(change(
+                ChangeKind::Added,
+                format!("type {}", name),
+                "type added",
+                false,
+            ));
+        }
+    }
+}
+
+fn diff_fields(type_name: &str, old: &[Field], new: &[Field], changes: &mut Vec<Change>) {
+    for old_field in old {
+        let location = format!("type {}.{}", type_name, old_field.name);
+        match new.iter().find(|f| f.name == old_field.name) {
+            None => changes.push(change(ChangeKind::Removed, location, "field removed", true)),
+            Some(new_field) => {
+                if !field_types_are_equal(&old_field.r#type, &new_field.r#type) {
+                    changes.push(change(
+                        ChangeKind::Changed,
+                        location,
+                        format!(
+                            "type changed from {} to {}",
+                            describe(&old_field.r#type),
+                            describe(&new_field.r#type)
+                        ),
+                        true,
+                    ));
+                } else if !constraints_are_equal(&old_field.constraints, &new_field.constraints) {
+                    changes.push(change(
+                        ChangeKind::Changed,
+                        location,
+                        "constraints changed",
+                        true,
+                    ));
+                }
+            }
+        }
+    }
+    for new_field in new {
+        if !old.iter().any(|f| f.name == new_field.name) {
+            let required = !matches!(&*new_field.r#type, FieldType::Optional(_));
+            let description = if required {
+                "required field added"
+            } else {
+                "optional field added"
+            };
+            changes.push(change(
+                ChangeKind::Added,
+                format!("type {}.{}", type_name, new_field.name),
+                description,
+                required,
+            ));
+        }
+    }
+}
+
+fn diff_enum(type_name: &str, old: &EnumType, new: &EnumType, changes: &mut Vec<Change>) {
+    let value = |v: &EnumVariant| literal_string(&v.value);
+    for old_variant in &old.variants {
+        if !new.variants.iter().any(|v| value(v) == value(old_variant)) {
+            changes.push(change(
+                ChangeKind::Removed,
+                format!("type {}", type_name),
+                format!("variant {} removed", value(old_variant)),
+                true,
+            ));
+        }
+    }
+    for new_variant in &new.variants {
+        if !old.variants.iter().any(|v| value(v) == value(new_variant)) {
+            changes.push(change(
+                ChangeKind::Added,
+                format!("type {}", type_name),
+                format!("variant {} added", value(new_variant)),
+                false,
+            ));
+        }
+    }
+}
+
+fn diff_headers(scope: &str, old: &[Header], new: &[Header], changes: &mut Vec<Change>) {
+    for old_header in old {
+        let location = format!("{} {}", scope, old_header.name);
+        match new.iter().find(|h| h.name == old_header.name) {
+            None => changes.push(change(
+                ChangeKind::Removed,
+                location,
+                "header removed",
+                false,
+            )),
+            Some(new_header) => {
+                if header_signature(&old_header.value) != header_signature(&new_header.value) {
+                    changes.push(change(
+                        ChangeKind::Changed,
+                        location,
+                        "header value changed",
+                        true,
+                    ));
+                }
+            }
+        }
+    }
+    for new_header in new {
+        if !old.iter().any(|h| h.name == new_header.name) {
+            // New parameters change the generated client's signatures
+            let breaking = !matches!(new_header.value, HeaderValue::Literal(_));
+            changes.push(change(
+                ChangeKind::Added,
+                format!("{} {}", scope, new_header.name),
+                "header added",
+                breaking,
+            ));
+        }
+    }
+}
+
+fn diff_services(old: &AAT, new: &AAT, changes: &mut Vec<Change>) {
+    for old_service in &old.services {
+        let location = format!("service {}", old_service.name);
+        let Some(new_service) = new.services.iter().find(|s| s.name == old_service.name) else {
+            changes.push(change(
+                ChangeKind::Removed,
+                location,
+                "service removed",
+                true,
+            ));
+            continue;
+        };
+
+        diff_headers(
+            &format!("service {} header", old_service.name),
+            &old_service.headers,
+            &new_service.headers,
+            changes,
+        );
+
+        for old_endpoint in &old_service.endpoints {
+            let location = format!("endpoint {}.{}", old_service.name, old_endpoint.name);
+            match new_service
+                .endpoints
+                .iter()
+                .find(|e| e.name == old_endpoint.name)
+            {
+                None => changes.push(change(
+                    ChangeKind::Removed,
+                    location,
+                    "endpoint removed",
+                    true,
+                )),
+                Some(new_endpoint) => diff_endpoint(&location, old_endpoint, new_endpoint, changes),
+            }
+        }
+        for new_endpoint in &new_service.endpoints {
+            if !old_service
+                .endpoints
+                .iter()
+                .any(|e| e.name == new_endpoint.name)
+            {
+                changes.push(change(
+                    ChangeKind::Added,
+                    format!("endpoint {}.{}", new_service.name, new_endpoint.name),
+                    "endpoint added",
+                    false,
+                ));
+            }
+        }
+    }
+    for new_service in &new.services {
+        if !old.services.iter().any(|s| s.name == new_service.name) {
+            changes.push(change(
+                ChangeKind::Added,
+                format!("service {}", new_service.name),
+                "service added",
+                false,
+            ));
+        }
+    }
+}
+
+fn diff_endpoint(location: &str, old: &Endpoint, new: &Endpoint, changes: &mut Vec<Change>) {
+    let mut changed = |description: String| {
+        changes.push(change(
+            ChangeKind::Changed,
+            location.to_string(),
+            description,
+            true,
+        ));
+    };
+
+    let (old_method, new_method) = (method_name(&old.method), method_name(&new.method));
+    if old_method != new_method {
+        changed(format!(
+            "method changed from {} to {}",
+            old_method, new_method
+        ));
+    }
+    let (old_path, new_path) = (path_string(&old.path), path_string(&new.path));
+    if old_path != new_path {
+        changed(format!("path changed from {} to {}", old_path, new_path));
+    }
+    for (label, old_type, new_type) in [
+        ("query", &old.query, &new.query),
+        ("body", &old.body, &new.body),
+    ] {
+        match (old_type, new_type) {
+            (None, None) => {}
+            (Some(a), Some(b)) if field_types_are_equal(a, b) => {}
+            (a, b) => changed(format!(
+                "{} changed from {} to {}",
+                label,
+                a.as_ref()
+                    .map(describe)
+                    .unwrap_or_else(|| "none".to_string()),
+                b.as_ref()
+                    .map(describe)
+                    .unwrap_or_else(|| "none".to_string())
+            )),
+        }
+    }
+    if !field_types_are_equal(&old.response, &new.response) {
+        changed(format!(
+            "response changed from {} to {}",
+            describe(&old.response),
+            describe(&new.response)
+        ));
+    }
+    if old.upgrade.is_some() != new.upgrade.is_some() {
+        changed("upgrade changed".to_string());
+    }
+    if old.ws_auth != new.ws_auth {
+        changed("websocket auth fallback changed".to_string());
+    }
+    diff_headers(
+        &format!("{} header", location),
+        &old.headers,
+        &new.headers,
+        changes,
+    );
+}
+
+fn kind_name(named_type: &NamedType) -> &'static str {
+    match named_type {
+        NamedType::Object(_) => "object",
+        NamedType::Union(_) => "union",
+        NamedType::Enum(_) => "enum",
+    }
+}
+
+pub(super) fn method_name(method: &HttpMethod) -> &'static str {
+    match method {
+        HttpMethod::Get => "GET",
+        HttpMethod::Post => "POST",
+        HttpMethod::Put => "PUT",
+        HttpMethod::Delete => "DELETE",
+        HttpMethod::Patch => "PATCH",
+    }
+}
+
+fn path_string(path: &[PathSegment]) -> String {
+    let mut result = String::new();
+    for segment in path {
+        match segment {
+            PathSegment::Literal(lit) => result.push_str(&format!("/{}", lit)),
+            PathSegment::Parameter { name, r#type } => {
+                result.push_str(&format!("/{{{}: {}}}", name, describe(r#type)))
+            }
+            PathSegment::Wildcard { name } => result.push_str(&format!("/{{*{}}}", name)),
+        }
+    }
+    if result.is_empty() {
+        "/".to_string()
+    } else {
+        result
+    }
+}
+
+fn header_signature(value: &HeaderValue) -> String {
+    match value {
+        HeaderValue::Literal(lit) => lit.clone(),
+        HeaderValue::Parameter { name, field_type } => {
+            format!("{{{}: {}}}", name, describe(field_type))
+        }
+        HeaderValue::Pattern { parts } => parts
+            .iter()
+            .map(|part| match part {
+                HeaderPart::Literal(lit) => lit.clone(),
+                HeaderPart::Param {
+                    name,
+                    field_type,
+                    format,
+                } => match format {
+                    Some(format) => format!(
+                        "{{{}: {} as {}}}",
+                        name,
+                        describe(field_type),
+                        serde_json::to_value(format)
+                            .ok()
+                            .and_then(|v| v.as_str().map(String::from))
+                            .unwrap_or_default()
+                    ),
+                    None => format!("{{{}: {}}}", name, describe(field_type)),
+                },
+            })
+            .collect(),
+    }
+}
+
+/// Short human-readable form of a field type, e.g. `list<MachineV1>`
+fn describe(field_type: &FieldType) -> String {
+    match field_type {
+        FieldType::Primitive(PrimitiveType::Bool) => "bool".to_string(),
+        FieldType::Primitive(PrimitiveType::Int(Some(format))) => serde_json::to_value(format)
+            .ok()
+            .and_then(|v| v.as_str().map(String::from))
+            .unwrap_or_default(),
+        FieldType::Primitive(PrimitiveType::Int(None)) => "int".to_string(),
+        FieldType::Primitive(PrimitiveType::Float) => "float".to_string(),
+        FieldType::Primitive(PrimitiveType::String(_)) => "string".to_string(),
+        FieldType::Literal(lit) => literal_string(lit),
+        FieldType::Optional(inner) => format!("optional<{}>", describe(inner)),
+        FieldType::List(inner) => format!("list<{}>", describe(inner)),
+        FieldType::Map(inner) => format!("map<{}>", describe(inner)),
+        FieldType::Stream(inner) => format!("stream<{}>", describe(inner)),
+        FieldType::Reference(name) => name.clone(),
+        FieldType::Intersection(types) => {
+            types.iter().map(describe).collect::<Vec<_>>().join(" & ")
+        }
+        FieldType::Tuple(types) => format!(
+            "({})",
+            types.iter().map(describe).collect::<Vec<_>>().join(", ")
+        ),
+        FieldType::Any => "any".to_string(),
+    }
+}
+
+fn literal_string(lit: &LiteralType) -> String {
+    match lit {
+        LiteralType::String(s) => format!("\"{}\"", s),
+        LiteralType::Int(i) => i.to_string(),
+        LiteralType::Float(f) => f.to_string(),
+        LiteralType::Bool(b) => b.to_string(),
+        LiteralType::Null => "null".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spec::{PathSegment, Spec, Type};
+    use schemars::JsonSchema;
+
+    #[derive(JsonSchema)]
+    #[allow(dead_code)]
+    struct MachineV1 {
+        name: String,
+        cpus: u32,
+    }
+
+    #[derive(JsonSchema)]
+    #[allow(dead_code)]
+    struct MachineV2 {
+        name: String,
+        memory: Option<u32>,
+    }
+
+    fn aat(machine: Type, with_delete: bool) -> AAT {
+        let spec = Spec::new("test").service("machines", |service| {
+            let path = vec![PathSegment::Literal("machines".into())];
+            let service = service.get("get", path.clone(), |e| e.response(machine));
+            if with_delete {
+                service.delete("delete", path, |e| e)
+            } else {
+                service
+            }
+        });
+        AAT::from_spec(&spec).unwrap()
+    }
+
+    #[test]
+    fn identical_aats_have_no_changes() {
+        let old = aat(Type::schema_named::<MachineV1>("Machine"), true);
+        let new = aat(Type::schema_named::<MachineV1>("Machine"), true);
+        assert!(diff(&old, &new).is_empty());
+    }
+
+    #[test]
+    fn classifies_field_and_endpoint_changes() {
+        let old = aat(Type::schema_named::<MachineV1>("Machine"), true);
+        let new = aat(Type::schema_named::<MachineV2>("Machine"), false);
+        let changes = diff(&old, &new)
+            .iter()
+            .map(|change| change.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            changes,
+            [
+                "- type Machine.cpus: field removed [breaking]",
+                "+ type Machine.memory: optional field added",
+                "- endpoint machines.delete: endpoint removed [breaking]",
+            ]
+        );
+    }
+}
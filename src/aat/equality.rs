@@ -12,6 +12,45 @@ pub fn types_are_structurally_equal(a: &NamedType, b: &NamedType) -> bool {
     }
 }
 
+/// Adds the examples of `other` missing from `target`, two structurally equal types registered
+/// under the same name
+pub fn merge_examples(target: &mut NamedType, other: &NamedType) {
+    match (target, other) {
+        (NamedType::Object(target), NamedType::Object(other)) => {
+            merge_object_examples(target, other)
+        }
+        (NamedType::Union(target), NamedType::Union(other)) => {
+            merge_values(&mut target.examples, &other.examples);
+            for (target, other) in target.variants.iter_mut().zip(&other.variants) {
+                if let (UnionTypeVariantMode::Object(target), UnionTypeVariantMode::Object(other)) =
+                    (&mut *target.mode, &*other.mode)
+                {
+                    merge_object_examples(target, other);
+                }
+            }
+        }
+        (NamedType::Enum(target), NamedType::Enum(other)) => {
+            merge_values(&mut target.examples, &other.examples)
+        }
+        _ => {}
+    }
+}
+
+fn merge_object_examples(target: &mut ObjectType, other: &ObjectType) {
+    merge_values(&mut target.examples, &other.examples);
+    for (target, other) in target.fields.iter_mut().zip(&other.fields) {
+        merge_values(&mut target.examples, &other.examples);
+    }
+}
+
+fn merge_values(target: &mut Vec<serde_json::Value>, other: &[serde_json::Value]) {
+    for value in other {
+        if !target.contains(value) {
+            target.push(value.clone());
+        }
+    }
+}
+
 fn objects_are_equal(a: &ObjectType, b: &ObjectType) -> bool {
     if a.fields.len() != b.fields.len() {
         return false;
@@ -88,7 +127,7 @@ fn enums_are_equal(a: &EnumType, b: &EnumType) -> bool {
         })
 }
 
-fn field_types_are_equal(a: &FieldType, b: &FieldType) -> bool {
+pub fn field_types_are_equal(a: &FieldType, b: &FieldType) -> bool {
     match (a, b) {
         (FieldType::Primitive(a_prim), FieldType::Primitive(b_prim)) => {
             primitives_are_equal(a_prim, b_prim)
@@ -129,7 +168,7 @@ fn field_types_are_equal(a: &FieldType, b: &FieldType) -> bool {
 fn primitives_are_equal(a: &PrimitiveType, b: &PrimitiveType) -> bool {
     match (a, b) {
         (PrimitiveType::Bool, PrimitiveType::Bool) => true,
-        (PrimitiveType::Int, PrimitiveType::Int) => true,
+        (PrimitiveType::Int(a_fmt), PrimitiveType::Int(b_fmt)) => a_fmt == b_fmt,
         (PrimitiveType::Float, PrimitiveType::Float) => true,
         (PrimitiveType::String(a_fmt), PrimitiveType::String(b_fmt)) => a_fmt == b_fmt,
         _ => false,
@@ -154,7 +193,7 @@ fn literals_are_equal(a: &LiteralType, b: &LiteralType) -> bool {
     }
 }
 
-fn constraints_are_equal(a: &Option<Constraints>, b: &Option<Constraints>) -> bool {
+pub fn constraints_are_equal(a: &Option<Constraints>, b: &Option<Constraints>) -> bool {
     match (a, b) {
         (None, None) => true,
         (Some(a_c), Some(b_c)) => {
@@ -0,0 +1,827 @@
+use super::{AAT, get_type_name, types::*};
+use anyhow::{Result, anyhow, bail};
+use regex_syntax::hir::{Class, Hir, HirKind};
+use serde_json::{Map, Number, Value};
+
+/// Maximum number of nested references followed before optional data is left out
+const DEFAULT_MAX_DEPTH: usize = 4;
+
+/// Strings generated for a pattern or format before giving up on meeting its length constraints
+const PATTERN_ATTEMPTS: usize = 32;
+
+const WORDS: &[&str] = &[
+    "alpha", "bravo", "charlie", "delta", "echo", "foxtrot", "golf", "hotel", "india", "juliet",
+    "kilo", "lima", "mike", "november", "oscar", "papa", "quebec", "romeo", "sierra", "tango",
+];
+
+/// Produces deterministic example JSON values for AAT types.
+///
+/// The same seed always yields the same values for the same AAT, so the output can be
+/// committed as fixtures, used in docs or served from a mock server.
+pub struct ExampleGenerator<'a> {
+    aat: &'a AAT,
+    rng: SplitMix64,
+    use_declared_examples: bool,
+    max_depth: usize,
+    depth: usize,
+    /// How far unbounded or wide repetitions in patterns may go past their minimum
+    extra_repetitions: u32,
+}
+
+impl<'a> ExampleGenerator<'a> {
+    pub fn new(aat: &'a AAT, seed: u64) -> Self {
+        Self {
+            aat,
+            rng: SplitMix64::new(seed),
+            use_declared_examples: true,
+            max_depth: DEFAULT_MAX_DEPTH,
+            depth: 0,
+            extra_repetitions: 3,
+        }
+    }
+
+    /// Whether examples declared in the schema (or via the DSL) are preferred over generated values
+    pub fn use_declared_examples(mut self, enabled: bool) -> Self {
+        self.use_declared_examples = enabled;
+        self
+    }
+
+    /// How many references deep optional fields and collections are still populated
+    pub fn max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Generates an example for a named type
+    pub fn named_type(&mut self, named_type: &NamedType) -> Result<Value> {
+        match named_type {
+            NamedType::Object(obj) => {
+                if let Some(example) = self.pick_declared(&obj.examples) {
+                    return Ok(example);
+                }
+                self.object(obj)
+            }
+            NamedType::Union(union) => {
+                if let Some(example) = self.pick_declared(&union.examples) {
+                    return Ok(example);
+                }
+                self.union(union)
+            }
+            NamedType::Enum(enum_type) => {
+                if let Some(example) = self.pick_declared(&enum_type.examples) {
+                    return Ok(example);
+                }
+                if enum_type.variants.is_empty() {
+                    return Err(anyhow!("Enum '{}' has no variants", enum_type.name));
+                }
+                let idx = self.rng.below(enum_type.variants.len() as u64) as usize;
+                Ok(literal_to_json(&enum_type.variants[idx].value))
+            }
+        }
+    }
+
+    /// Generates an example for a field type without any constraints
+    pub fn field_type(&mut self, field_type: &FieldType) -> Result<Value> {
+        self.field_type_with_constraints(field_type, None)
+    }
+
+    /// Generates an example for an object field, honoring its constraints and declared examples
+    pub fn field(&mut self, field: &Field) -> Result<Value> {
+        if let Some(example) = self.pick_declared(&field.examples) {
+            return Ok(example);
+        }
+        self.field_type_with_constraints(&field.r#type, field.constraints.as_ref())
+    }
+
+    fn field_type_with_constraints(
+        &mut self,
+        field_type: &FieldType,
+        constraints: Option<&Constraints>,
+    ) -> Result<Value> {
+        match field_type {
+            FieldType::Primitive(prim) => self.primitive(prim, constraints),
+            FieldType::Literal(lit) => Ok(literal_to_json(lit)),
+            FieldType::Optional(inner) => {
+                if self.depth >= self.max_depth {
+                    Ok(Value::Null)
+                } else {
+                    self.field_type_with_constraints(inner, constraints)
+                }
+            }
+            FieldType::List(inner) => self.list(inner, constraints),
+            FieldType::Map(inner) => {
+                let mut map = Map::new();
+                if self.depth < self.max_depth {
+                    let key = self.word();
+                    map.insert(key, self.field_type(inner)?);
+                }
+                Ok(Value::Object(map))
+            }
+            FieldType::Stream(inner) => self.field_type_with_constraints(inner, constraints),
+            FieldType::Reference(name) => {
+                let named_type = self
+                    .aat
+                    .types
+                    .iter()
+                    .find(|t| get_type_name(t) == name)
+                    .ok_or_else(|| anyhow!("Reference to undefined type '{}'", name))?;
+                self.depth += 1;
+                let result = self.named_type(named_type);
+                self.depth -= 1;
+                result
+            }
+            FieldType::Intersection(types) => {
+                let mut merged = Map::new();
+                for t in types {
+                    match self.field_type(t)? {
+                        Value::Object(obj) => merged.extend(obj),
+                        Value::Null => {}
+                        other => {
+                            return Err(anyhow!(
+                                "Cannot build an intersection example from non-object value {}",
+                                other
+                            ));
+                        }
+                    }
+                }
+                Ok(Value::Object(merged))
+            }
+            FieldType::Tuple(types) => {
+                let items: Result<Vec<_>> = types.iter().map(|t| self.field_type(t)).collect();
+                Ok(Value::Array(items?))
+            }
+            FieldType::Any => Ok(Value::Null),
+        }
+    }
+
+    fn object(&mut self, obj: &ObjectType) -> Result<Value> {
+        let mut map = Map::new();
+        for field in &obj.fields {
+            let is_optional = matches!(&*field.r#type, FieldType::Optional(_));
+            // Optional fields are dropped once we are deep enough, which also breaks recursion
+            if is_optional && self.depth >= self.max_depth {
+                continue;
+            }
+            map.insert(field.name.clone(), self.field(field)?);
+        }
+        Ok(Value::Object(map))
+    }
+
+    fn union(&mut self, union: &UnionType) -> Result<Value> {
+        if union.variants.is_empty() {
+            return Err(anyhow!("Union '{}' has no variants", union.name));
+        }
+
+        let idx = self.rng.below(union.variants.len() as u64) as usize;
+        let variant = &union.variants[idx];
+
+        let obj = match &*variant.mode {
+            UnionTypeVariantMode::Literal(lit) => return Ok(literal_to_json(lit)),
+            UnionTypeVariantMode::Object(obj) => obj,
+        };
+
+        let mut value = self.object(obj)?;
+
+        if let Some(discriminator) = &union.discriminator {
+            // Internally tagged: the discriminator property sits next to the fields
+            let tag = discriminator.tag_for(variant.name.as_deref().unwrap_or(&obj.name));
+            if let Value::Object(map) = &mut value {
+                map.insert(discriminator.property_name.clone(), Value::String(tag));
+            }
+            return Ok(value);
+        }
+
+        match &variant.name {
+            // Tuple variants already carry the variant name as their only field
+            Some(name) if !(obj.fields.len() == 1 && obj.fields[0].name == *name) => {
+                let mut wrapper = Map::new();
+                wrapper.insert(name.clone(), value);
+                Ok(Value::Object(wrapper))
+            }
+            _ => Ok(value),
+        }
+    }
+
+    fn list(&mut self, inner: &FieldType, constraints: Option<&Constraints>) -> Result<Value> {
+        let min_items = constraints.and_then(|c| c.min_items).unwrap_or(0);
+        let max_items = constraints
+            .and_then(|c| c.max_items)
+            .unwrap_or(usize::MAX)
+            .max(min_items);
+        let unique = constraints.and_then(|c| c.unique_items).unwrap_or(false);
+
+        let count = if self.depth >= self.max_depth {
+            min_items
+        } else {
+            let lo = min_items.max(1).min(max_items);
+            let hi = max_items.min(lo + 1);
+            lo + self.rng.below((hi - lo) as u64 + 1) as usize
+        };
+
+        let mut items: Vec<Value> = Vec::with_capacity(count);
+        let mut attempts = 0;
+        while items.len() < count {
+            let item = self.field_type(inner)?;
+            attempts += 1;
+            if unique && items.contains(&item) {
+                if attempts > count * 16 {
+                    return Err(anyhow!(
+                        "Could not generate {} unique items for a list example",
+                        count
+                    ));
+                }
+                continue;
+            }
+            items.push(item);
+        }
+        Ok(Value::Array(items))
+    }
+
+    fn primitive(
+        &mut self,
+        prim: &PrimitiveType,
+        constraints: Option<&Constraints>,
+    ) -> Result<Value> {
+        match prim {
+            PrimitiveType::Bool => Ok(Value::Bool(self.rng.below(2) == 1)),
+            PrimitiveType::Int(_) => self.int(constraints),
+            PrimitiveType::Float => self.float(constraints),
+            PrimitiveType::String(format) => self.string(format.as_ref(), constraints),
+        }
+    }
+
+    fn int(&mut self, constraints: Option<&Constraints>) -> Result<Value> {
+        let (mut lo, mut hi) = (0i64, 100i64);
+        if let Some(c) = constraints {
+            if let Some(min) = c.minimum {
+                lo = to_i64(min.ceil());
+            }
+            if let Some(min) = c.exclusive_minimum {
+                lo = to_i64(min.floor())
+                    .checked_add(1)
+                    .ok_or_else(|| anyhow!("No integer is greater than {}", min))?;
+            }
+            if let Some(max) = c.maximum {
+                hi = to_i64(max.floor());
+            }
+            if let Some(max) = c.exclusive_maximum {
+                hi = to_i64(max.ceil())
+                    .checked_sub(1)
+                    .ok_or_else(|| anyhow!("No integer is less than {}", max))?;
+            }
+            // Keep the default window when only one bound is set
+            if c.minimum.is_some() || c.exclusive_minimum.is_some() {
+                if c.maximum.is_none() && c.exclusive_maximum.is_none() {
+                    hi = lo.saturating_add(100);
+                }
+            } else if c.maximum.is_some() || c.exclusive_maximum.is_some() {
+                lo = hi.saturating_sub(100).max(hi.min(0));
+            }
+        }
+        if lo > hi {
+            return Err(anyhow!("No integer satisfies the bounds [{}, {}]", lo, hi));
+        }
+
+        let multiple = constraints.and_then(|c| c.multiple_of);
+        let value = match multiple {
+            Some(m) if m >= 1.0 && m.fract() == 0.0 => {
+                let m = m as i64;
+                let first = lo.div_euclid(m) + if lo.rem_euclid(m) == 0 { 0 } else { 1 };
+                let last = hi.div_euclid(m);
+                if first > last {
+                    return Err(anyhow!("No multiple of {} lies within [{}, {}]", m, lo, hi));
+                }
+                first.saturating_add_unsigned(self.below_span(first, last)) * m
+            }
+            _ => lo.saturating_add_unsigned(self.below_span(lo, hi)),
+        };
+        Ok(Value::Number(value.into()))
+    }
+
+    /// A random offset from `lo` that stays within `hi`, even when the span exceeds `i64`
+    fn below_span(&mut self, lo: i64, hi: i64) -> u64 {
+        self.rng.below(hi.abs_diff(lo).saturating_add(1))
+    }
+
+    fn float(&mut self, constraints: Option<&Constraints>) -> Result<Value> {
+        let (mut lo, mut hi) = (0.0f64, 100.0f64);
+        if let Some(c) = constraints {
+            if let Some(min) = c.minimum.or(c.exclusive_minimum) {
+                lo = min;
+                hi = lo + 100.0;
+            }
+            if let Some(max) = c.maximum.or(c.exclusive_maximum) {
+                hi = max;
+                if c.minimum.is_none() && c.exclusive_minimum.is_none() {
+                    lo = hi - 100.0;
+                }
+            }
+        }
+
+        let value = match constraints.and_then(|c| c.multiple_of) {
+            Some(m) => {
+                let mut first = (lo / m).ceil();
+                let mut last = (hi / m).floor();
+                let c = constraints;
+                if c.and_then(|c| c.exclusive_minimum)
+                    .is_some_and(|min| first * m <= min)
+                {
+                    first += 1.0;
+                }
+                if c.and_then(|c| c.exclusive_maximum)
+                    .is_some_and(|max| last * m >= max)
+                {
+                    last -= 1.0;
+                }
+                if first > last {
+                    return Err(anyhow!("No multiple of {} lies within [{}, {}]", m, lo, hi));
+                }
+                (first + self.rng.below((last - first) as u64 + 1) as f64) * m
+            }
+            None => {
+                // Two decimals keep examples readable; stay inside exclusive bounds
+                let span = ((hi - lo) * 100.0).floor().max(0.0) as u64;
+                let mut v = lo + self.rng.below(span + 1) as f64 / 100.0;
+                let c = constraints;
+                if c.and_then(|c| c.exclusive_minimum)
+                    .is_some_and(|min| v <= min)
+                {
+                    v = (lo + hi) / 2.0;
+                }
+                if c.and_then(|c| c.exclusive_maximum)
+                    .is_some_and(|max| v >= max)
+                {
+                    v = (lo + hi) / 2.0;
+                }
+                v
+            }
+        };
+
+        Number::from_f64(value)
+            .map(Value::Number)
+            .ok_or_else(|| anyhow!("Generated float {} is not a valid JSON number", value))
+    }
+
+    fn string(
+        &mut self,
+        format: Option<&StringFormat>,
+        constraints: Option<&Constraints>,
+    ) -> Result<Value> {
+        let min_len = constraints.and_then(|c| c.min_length).unwrap_or(0);
+        let max_len = constraints.and_then(|c| c.max_length).unwrap_or(usize::MAX);
+        let fits = |s: &str| (min_len..=max_len).contains(&s.chars().count());
+
+        if let Some(format) = format {
+            // Padding or cutting would break the format, so only fresh values are tried
+            for _ in 0..PATTERN_ATTEMPTS {
+                let s = self.formatted_string(format);
+                if fits(&s) {
+                    return Ok(Value::String(s));
+                }
+            }
+            bail!(
+                "Could not generate a {:?} string with a length of {}",
+                format,
+                describe_length(min_len, max_len)
+            );
+        }
+
+        if let Some(pattern) = constraints.and_then(|c| c.pattern.as_ref()) {
+            let hir = regex_syntax::parse(pattern)
+                .map_err(|e| anyhow!("Invalid pattern '{}': {}", pattern, e))?;
+            // Later attempts let repetitions grow far enough to reach the minimum length
+            for attempt in 0..PATTERN_ATTEMPTS {
+                self.extra_repetitions = if attempt < PATTERN_ATTEMPTS / 2 {
+                    3
+                } else {
+                    (min_len.max(2) * 2) as u32
+                };
+                let mut out = String::new();
+                self.string_from_hir(&hir, &mut out);
+                if fits(&out) {
+                    return Ok(Value::String(out));
+                }
+            }
+            bail!(
+                "Could not generate a string matching '{}' with a length of {}",
+                pattern,
+                describe_length(min_len, max_len)
+            );
+        }
+
+        let mut s = self.word();
+        while s.chars().count() < min_len {
+            s.push('-');
+            s.push_str(&self.word());
+        }
+        if s.chars().count() > max_len {
+            s = s.chars().take(max_len).collect();
+        }
+        Ok(Value::String(s))
+    }
+
+    fn formatted_string(&mut self, format: &StringFormat) -> String {
+        match format {
+            StringFormat::DateTime => {
+                let (date, time) = (self.date(), self.time());
+                format!("{}T{}Z", date, time)
+            }
+            StringFormat::Date => self.date(),
+            StringFormat::Time => self.time(),
+            StringFormat::Uuid => {
+                let hi = self.rng.next();
+                let lo = self.rng.next();
+                // Version 4, RFC 4122 variant
+                format!(
+                    "{:08x}-{:04x}-4{:03x}-{:x}{:03x}-{:012x}",
+                    hi >> 32,
+                    (hi >> 16) & 0xffff,
+                    hi & 0x0fff,
+                    8 + (lo >> 62),
+                    (lo >> 48) & 0x0fff,
+                    lo & 0xffff_ffff_ffff
+                )
+            }
+            StringFormat::Email => format!("{}@example.com", self.word()),
+            StringFormat::Uri => format!("https://example.com/{}", self.word()),
+            StringFormat::Hostname => format!("{}.example.com", self.word()),
+            StringFormat::Ipv4 => format!(
+                "10.{}.{}.{}",
+                self.rng.below(256),
+                self.rng.below(256),
+                1 + self.rng.below(254)
+            ),
+            StringFormat::Ipv6 => format!(
+                "fd00::{:x}:{:x}",
+                self.rng.below(0x10000),
+                1 + self.rng.below(0xffff)
+            ),
+        }
+    }
+
+    fn date(&mut self) -> String {
+        // Days between 2020-01-01 and 2029-12-31
+        let days = 18262 + self.rng.below(3653) as i64;
+        let (y, m, d) = civil_from_days(days);
+        format!("{:04}-{:02}-{:02}", y, m, d)
+    }
+
+    fn time(&mut self) -> String {
+        let secs = self.rng.below(86400);
+        format!(
+            "{:02}:{:02}:{:02}",
+            secs / 3600,
+            (secs / 60) % 60,
+            secs % 60
+        )
+    }
+
+    fn word(&mut self) -> String {
+        WORDS[self.rng.below(WORDS.len() as u64) as usize].to_string()
+    }
+
+    /// Builds a string matching a parsed regular expression
+    fn string_from_hir(&mut self, hir: &Hir, out: &mut String) {
+        match hir.kind() {
+            HirKind::Empty | HirKind::Look(_) => {}
+            HirKind::Literal(lit) => out.push_str(&String::from_utf8_lossy(&lit.0)),
+            HirKind::Class(Class::Unicode(class)) => {
+                // Prefer printable ASCII so `\d`, `\w` or `.` produce readable examples
+                let ascii: Vec<(u32, u32)> = class
+                    .ranges()
+                    .iter()
+                    .map(|r| (r.start() as u32, r.end() as u32))
+                    .filter(|&(start, end)| start <= 0x7e && end >= 0x21)
+                    .map(|(start, end)| (start.max(0x21), end.min(0x7e)))
+                    .collect();
+                let ranges: Vec<(u32, u32)> = if ascii.is_empty() {
+                    class
+                        .ranges()
+                        .iter()
+                        .map(|r| (r.start() as u32, r.end() as u32))
+                        .collect()
+                } else {
+                    ascii
+                };
+                if ranges.is_empty() {
+                    return;
+                }
+                let (start, end) = ranges[self.rng.below(ranges.len() as u64) as usize];
+                let c = char::from_u32(start + self.rng.below((end - start) as u64 + 1) as u32)
+                    .unwrap_or('a');
+                out.push(c);
+            }
+            HirKind::Class(Class::Bytes(class)) => {
+                let ranges = class.ranges();
+                if ranges.is_empty() {
+                    return;
+                }
+                let range = ranges[self.rng.below(ranges.len() as u64) as usize];
+                let b =
+                    range.start() + self.rng.below((range.end() - range.start()) as u64 + 1) as u8;
+                out.push(if b.is_ascii() { b as char } else { 'a' });
+            }
+            HirKind::Repetition(rep) => {
+                let min = rep.min;
+                let max = rep
+                    .max
+                    .unwrap_or(u32::MAX)
+                    .min(min.saturating_add(self.extra_repetitions));
+                let count = min + self.rng.below((max - min) as u64 + 1) as u32;
+                for _ in 0..count {
+                    self.string_from_hir(&rep.sub, out);
+                }
+            }
+            HirKind::Capture(capture) => self.string_from_hir(&capture.sub, out),
+            HirKind::Concat(parts) => {
+                for part in parts {
+                    self.string_from_hir(part, out);
+                }
+            }
+            HirKind::Alternation(branches) => {
+                let idx = self.rng.below(branches.len() as u64) as usize;
+                self.string_from_hir(&branches[idx], out);
+            }
+        }
+    }
+
+    fn pick_declared(&mut self, examples: &[Value]) -> Option<Value> {
+        if !self.use_declared_examples || examples.is_empty() {
+            return None;
+        }
+        let idx = self.rng.below(examples.len() as u64) as usize;
+        Some(examples[idx].clone())
+    }
+}
+
+/// Generates an example value for a field type using the given seed
+pub fn example_for_field_type(aat: &AAT, field_type: &FieldType, seed: u64) -> Result<Value> {
+    ExampleGenerator::new(aat, seed).field_type(field_type)
+}
+
+/// Generates an example value for a named type using the given seed
+pub fn example_for_named_type(aat: &AAT, named_type: &NamedType, seed: u64) -> Result<Value> {
+    ExampleGenerator::new(aat, seed).named_type(named_type)
+}
+
+/// Describes a `min_length`/`max_length` range for error messages
+fn describe_length(min_len: usize, max_len: usize) -> String {
+    match max_len {
+        usize::MAX => format!("at least {}", min_len),
+        max_len => format!("between {} and {}", min_len, max_len),
+    }
+}
+
+/// Converts a whole-number bound to an integer, clamped to the `i64` range
+fn to_i64(bound: f64) -> i64 {
+    bound.clamp(i64::MIN as f64, i64::MAX as f64) as i64
+}
+
+fn literal_to_json(lit: &LiteralType) -> Value {
+    match lit {
+        LiteralType::String(s) => Value::String(s.clone()),
+        LiteralType::Int(i) => Value::Number((*i).into()),
+        LiteralType::Float(f) => Number::from_f64(*f)
+            .map(Value::Number)
+            .unwrap_or(Value::Null),
+        LiteralType::Bool(b) => Value::Bool(*b),
+        LiteralType::Null => Value::Null,
+    }
+}
+
+/// Converts days since the Unix epoch to a (year, month, day) civil date
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let y = yoe + era * 400 + if m <= 2 { 1 } else { 0 };
+    (y, m, d)
+}
+
+/// Small, dependency-free PRNG so examples are stable across platforms and releases
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform value in `0..bound` (returns 0 for an empty range)
+    fn below(&mut self, bound: u64) -> u64 {
+        if bound == 0 { 0 } else { self.next() % bound }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::returning;
+    use crate::spec::Type;
+    use schemars::JsonSchema;
+    use serde_json::json;
+
+    #[derive(JsonSchema)]
+    #[allow(dead_code)]
+    struct Machine {
+        name: String,
+        #[schemars(range(min = 1, max = 8))]
+        cpus: u32,
+        #[schemars(length(min = 2, max = 4))]
+        code: String,
+        tags: Vec<String>,
+    }
+
+    fn bounds(
+        minimum: Option<f64>,
+        maximum: Option<f64>,
+        exclusive_minimum: Option<f64>,
+        exclusive_maximum: Option<f64>,
+    ) -> Constraints {
+        Constraints {
+            minimum,
+            maximum,
+            exclusive_minimum,
+            exclusive_maximum,
+            multiple_of: None,
+            min_length: None,
+            max_length: None,
+            pattern: None,
+            min_items: None,
+            max_items: None,
+            unique_items: None,
+        }
+    }
+
+    #[test]
+    fn same_seed_gives_same_example() {
+        let aat = returning(Type::schema::<Machine>());
+        let machine = aat.find_type("Machine").unwrap();
+        let first = example_for_named_type(&aat, machine, 7).unwrap();
+        let second = example_for_named_type(&aat, machine, 7).unwrap();
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn examples_respect_constraints() {
+        let aat = returning(Type::schema::<Machine>());
+        let machine = aat.find_type("Machine").unwrap();
+        for seed in 0..50 {
+            let example = example_for_named_type(&aat, machine, seed).unwrap();
+            let cpus = example["cpus"].as_i64().unwrap();
+            assert!((1..=8).contains(&cpus), "cpus {} out of range", cpus);
+            let code = example["code"].as_str().unwrap().chars().count();
+            assert!((2..=4).contains(&code), "code length {} out of range", code);
+        }
+    }
+
+    #[test]
+    fn integer_bounds_near_i64_limits() {
+        let aat = AAT::new();
+        let mut generator = ExampleGenerator::new(&aat, 3);
+
+        let full = bounds(Some(i64::MIN as f64), Some(i64::MAX as f64), None, None);
+        assert!(generator.int(Some(&full)).unwrap().is_i64());
+
+        let below_max = bounds(None, None, None, Some(i64::MAX as f64));
+        assert!(generator.int(Some(&below_max)).unwrap().is_i64());
+
+        let above_min = bounds(None, None, Some(i64::MIN as f64), None);
+        assert!(generator.int(Some(&above_min)).unwrap().is_i64());
+
+        let out_of_range = bounds(None, None, Some(1e20), None);
+        assert!(generator.int(Some(&out_of_range)).is_err());
+    }
+
+    #[test]
+    fn float_multiples_stay_inside_exclusive_bounds() {
+        #[derive(JsonSchema)]
+        #[allow(dead_code)]
+        struct Reading {
+            #[schemars(extend("exclusiveMinimum" = 0, "exclusiveMaximum" = 3, "multipleOf" = 1))]
+            level: f64,
+        }
+
+        let aat = returning(Type::schema::<Reading>());
+        let reading = aat.find_type("Reading").unwrap();
+        for seed in 0..50 {
+            let example = example_for_named_type(&aat, reading, seed).unwrap();
+            assert_eq!(
+                crate::aat::runtime::validate_named(&example, reading, &aat),
+                Ok(()),
+                "{example}"
+            );
+        }
+    }
+
+    #[test]
+    fn examples_of_wrapper_types_reach_the_schema() {
+        let machine = json!({ "name": "a", "cpus": 2, "code": "ab", "tags": [] });
+        let list_aat = returning(Type::list(Type::schema::<Machine>()).example(json!([machine])));
+        let Some(NamedType::Object(obj)) = list_aat.find_type("Machine") else {
+            panic!("Machine is not an object");
+        };
+        assert_eq!(obj.examples, vec![machine.clone()]);
+        let named = list_aat.find_type("Machine").unwrap();
+        for seed in 0..5 {
+            assert_eq!(
+                example_for_named_type(&list_aat, named, seed).unwrap(),
+                machine
+            );
+        }
+
+        // `null` only says the value may be left out, so the schema keeps just the object example
+        let other = json!({ "name": "b", "cpus": 1, "code": "cd", "tags": ["x"] });
+        let optional = Type::optional(Type::schema::<Machine>())
+            .example(Value::Null)
+            .example(other.clone());
+        let optional_aat = returning(optional);
+        let named = optional_aat.find_type("Machine").unwrap();
+        for seed in 0..5 {
+            assert_eq!(
+                example_for_named_type(&optional_aat, named, seed).unwrap(),
+                other
+            );
+        }
+        let response = &optional_aat.services[0].endpoints[0].response;
+        assert_eq!(
+            example_for_field_type(&optional_aat, response, 0).unwrap(),
+            other
+        );
+    }
+
+    #[test]
+    fn pattern_examples_respect_length() {
+        let aat = AAT::new();
+        let mut generator = ExampleGenerator::new(&aat, 11);
+        let constraints = |pattern: &str, min_length, max_length| Constraints {
+            min_length,
+            max_length,
+            pattern: Some(pattern.to_string()),
+            ..bounds(None, None, None, None)
+        };
+
+        let long = constraints("^[a-z]+$", Some(12), None);
+        let short = constraints("^[a-z]{2,40}$", None, Some(5));
+        for _ in 0..20 {
+            let value = generator.string(None, Some(&long)).unwrap();
+            assert!(value.as_str().unwrap().len() >= 12, "{value} is too short");
+            let value = generator.string(None, Some(&short)).unwrap();
+            assert!(value.as_str().unwrap().len() <= 5, "{value} is too long");
+        }
+
+        let impossible = constraints("^[a-z]{3}$", Some(4), None);
+        assert!(generator.string(None, Some(&impossible)).is_err());
+    }
+
+    #[test]
+    fn formatted_examples_respect_length() {
+        let aat = AAT::new();
+        let mut generator = ExampleGenerator::new(&aat, 5);
+        let length = |min_length, max_length| Constraints {
+            min_length,
+            max_length,
+            ..bounds(None, None, None, None)
+        };
+
+        // Words are 4 to 8 characters long, so `@example.com` addresses are 16 to 20
+        let short_email = length(None, Some(17));
+        for _ in 0..20 {
+            let value = generator
+                .string(Some(&StringFormat::Email), Some(&short_email))
+                .unwrap();
+            let value = value.as_str().unwrap();
+            assert!(
+                value.ends_with("@example.com") && value.len() <= 17,
+                "{value}"
+            );
+        }
+
+        let uuid = length(Some(36), Some(36));
+        assert!(
+            generator
+                .string(Some(&StringFormat::Uuid), Some(&uuid))
+                .is_ok()
+        );
+        let short_uuid = length(None, Some(8));
+        let error = generator
+            .string(Some(&StringFormat::Uuid), Some(&short_uuid))
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Could not generate a Uuid string with a length of between 0 and 8"
+        );
+    }
+}
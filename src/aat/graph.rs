@@ -0,0 +1,191 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use super::get_type_name;
+use super::types::*;
+
+/// Every named type with the names of the types it refers to directly
+pub fn type_graph(types: &[NamedType]) -> BTreeMap<String, BTreeSet<String>> {
+    types
+        .iter()
+        .map(|named_type| {
+            (
+                get_type_name(named_type).to_string(),
+                named_type_references(named_type),
+            )
+        })
+        .collect()
+}
+
+/// Names of the types referred to by fields, union variants and intersections of a named type
+pub fn named_type_references(named_type: &NamedType) -> BTreeSet<String> {
+    let mut references = BTreeSet::new();
+    named_type.visit_references(&mut |name| {
+        references.insert(name.to_string());
+    });
+    references
+}
+
+/// Names of the types used by headers and endpoints, the roots of the type graph
+pub fn endpoint_references(headers: &[Header], services: &[Service]) -> BTreeSet<String> {
+    let mut field_types: Vec<&FieldType> = headers_types(headers);
+    for service in services {
+        field_types.extend(headers_types(&service.headers));
+        for endpoint in &service.endpoints {
+            field_types.extend(headers_types(&endpoint.headers));
+            for segment in &endpoint.path {
+                if let PathSegment::Parameter { r#type, .. } = segment {
+                    field_types.push(r#type);
+                }
+            }
+            field_types.extend([&endpoint.query, &endpoint.body].into_iter().flatten());
+            field_types.push(&endpoint.response);
+        }
+    }
+
+    let mut references = BTreeSet::new();
+    for field_type in field_types {
+        field_type.visit_references(&mut |name| {
+            references.insert(name.to_string());
+        });
+    }
+    references
+}
+
+/// `roots` and every type reachable from them. Names without a type are kept, but lead nowhere.
+pub fn reachable(
+    graph: &BTreeMap<String, BTreeSet<String>>,
+    roots: BTreeSet<String>,
+) -> BTreeSet<String> {
+    let mut pending = roots.iter().cloned().collect::<Vec<_>>();
+    let mut reached = roots;
+    while let Some(name) = pending.pop() {
+        for dependency in graph.get(&name).into_iter().flatten() {
+            if reached.insert(dependency.clone()) {
+                pending.push(dependency.clone());
+            }
+        }
+    }
+    reached
+}
+
+fn headers_types(headers: &[Header]) -> Vec<&FieldType> {
+    headers
+        .iter()
+        .flat_map(|header| header.value.params())
+        .map(|(_, field_type)| field_type)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn graph(edges: &[(&str, &[&str])]) -> BTreeMap<String, BTreeSet<String>> {
+        edges
+            .iter()
+            .map(|(name, references)| {
+                let references = references.iter().map(|r| r.to_string()).collect();
+                (name.to_string(), references)
+            })
+            .collect()
+    }
+
+    fn names(names: &[&str]) -> BTreeSet<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn reaches_dependencies_through_cycles() {
+        let graph = graph(&[
+            ("Machine", &["Status", "Volume"]),
+            ("Status", &["Machine"]),
+            ("Volume", &[]),
+            ("Log", &["Volume"]),
+        ]);
+        assert_eq!(
+            reachable(&graph, names(&["Status"])),
+            names(&["Machine", "Status", "Volume"])
+        );
+    }
+
+    #[test]
+    fn keeps_roots_without_a_type() {
+        let graph = graph(&[("Log", &[])]);
+        assert_eq!(reachable(&graph, names(&["Missing"])), names(&["Missing"]));
+    }
+
+    #[test]
+    fn follows_nested_field_types() {
+        let object = NamedType::Object(ObjectType {
+            name: "Fleet".to_string(),
+            fields: vec![Field {
+                name: "machines".to_string(),
+                r#type: Box::new(FieldType::Optional(Box::new(FieldType::Map(Box::new(
+                    FieldType::Tuple(vec![
+                        FieldType::List(Box::new(FieldType::Reference("Machine".to_string()))),
+                        FieldType::Stream(Box::new(FieldType::Reference("Log".to_string()))),
+                    ]),
+                ))))),
+                constraints: Default::default(),
+                examples: vec![],
+            }],
+            examples: vec![],
+            rust_path: None,
+        });
+        assert_eq!(named_type_references(&object), names(&["Log", "Machine"]));
+    }
+
+    #[test]
+    fn renames_references_in_union_variants() {
+        let field = |name: &str, r#type: FieldType| Field {
+            name: name.to_string(),
+            r#type: Box::new(r#type),
+            constraints: Default::default(),
+            examples: vec![],
+        };
+        let object = |fields| ObjectType {
+            name: String::new(),
+            fields,
+            examples: vec![],
+            rust_path: None,
+        };
+        let mut union = NamedType::Union(UnionType {
+            name: "Event".to_string(),
+            discriminator: None,
+            variants: vec![
+                UnionTypeVariant {
+                    name: Some("started".to_string()),
+                    mode: Box::new(UnionTypeVariantMode::Object(object(vec![field(
+                        "machine",
+                        FieldType::Reference("Machine".to_string()),
+                    )]))),
+                },
+                UnionTypeVariant {
+                    name: None,
+                    mode: Box::new(UnionTypeVariantMode::Literal(LiteralType::String(
+                        "stopped".to_string(),
+                    ))),
+                },
+                UnionTypeVariant {
+                    name: Some("logged".to_string()),
+                    mode: Box::new(UnionTypeVariantMode::Object(object(vec![field(
+                        "lines",
+                        FieldType::Intersection(vec![
+                            FieldType::Reference("Log".to_string()),
+                            FieldType::Reference("Machine".to_string()),
+                        ]),
+                    )]))),
+                },
+            ],
+            examples: vec![],
+            rust_path: None,
+        });
+
+        union.visit_references_mut(&mut |name| name.insert_str(0, "Api"));
+        assert_eq!(
+            named_type_references(&union),
+            names(&["ApiLog", "ApiMachine"])
+        );
+        assert_eq!(get_type_name(&union), "Event");
+    }
+}
@@ -0,0 +1,479 @@
+use std::collections::{BTreeMap, HashMap};
+
+use super::report::{IssueKind, Severity, ValidationReport};
+use super::runtime::describe_literal;
+use super::{AAT, types::*};
+use crate::case::to_camel_case;
+
+/// A design rule checked by [`lint`]. Every rule is on by default and reports warnings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum LintRule {
+    /// `GET` and `DELETE` endpoints have no body; many proxies drop it
+    NoBodyOnGetOrDelete,
+    /// A `Stream` response can only be sent over `Upgrade::Ws`
+    StreamRequiresWebSocket,
+    /// Query types are objects whose fields are scalars, or optional or lists of scalars
+    FlatQuery,
+    /// Enum variants are strings, which read better on the wire than numbers
+    StringEnums,
+    /// Endpoint names are `snake_case`
+    SnakeCaseEndpointNames,
+    /// Header names are lowercase HTTP tokens
+    HeaderNames,
+    /// Field and endpoint names stay distinct after `camelCase` conversion
+    CamelCaseCollisions,
+}
+
+impl LintRule {
+    pub const ALL: [LintRule; 7] = [
+        LintRule::NoBodyOnGetOrDelete,
+        LintRule::StreamRequiresWebSocket,
+        LintRule::FlatQuery,
+        LintRule::StringEnums,
+        LintRule::SnakeCaseEndpointNames,
+        LintRule::HeaderNames,
+        LintRule::CamelCaseCollisions,
+    ];
+
+    /// Stable identifier used as the issue code
+    pub fn code(&self) -> &'static str {
+        match self {
+            LintRule::NoBodyOnGetOrDelete => "no-body-on-get-delete",
+            LintRule::StreamRequiresWebSocket => "stream-requires-websocket",
+            LintRule::FlatQuery => "flat-query",
+            LintRule::StringEnums => "string-enums",
+            LintRule::SnakeCaseEndpointNames => "snake-case-endpoint-names",
+            LintRule::HeaderNames => "header-names",
+            LintRule::CamelCaseCollisions => "camel-case-collisions",
+        }
+    }
+}
+
+/// Which rules [`lint`] checks and how seriously their findings are taken
+#[derive(Debug, Clone)]
+pub struct LintConfig {
+    rules: BTreeMap<LintRule, Severity>,
+}
+
+impl Default for LintConfig {
+    /// Every rule, reporting warnings
+    fn default() -> Self {
+        Self {
+            rules: LintRule::ALL
+                .into_iter()
+                .map(|rule| (rule, Severity::Warning))
+                .collect(),
+        }
+    }
+}
+
+impl LintConfig {
+    /// No rules; enable them one by one
+    pub fn none() -> Self {
+        Self {
+            rules: BTreeMap::new(),
+        }
+    }
+
+    /// Checks `rule`, reporting warnings
+    pub fn enable(mut self, rule: LintRule) -> Self {
+        self.rules.insert(rule, Severity::Warning);
+        self
+    }
+
+    /// Checks `rule`, reporting errors so [`ValidationReport::is_valid`] fails
+    pub fn deny(mut self, rule: LintRule) -> Self {
+        self.rules.insert(rule, Severity::Error);
+        self
+    }
+
+    pub fn disable(mut self, rule: LintRule) -> Self {
+        self.rules.remove(&rule);
+        self
+    }
+
+    pub fn is_enabled(&self, rule: LintRule) -> bool {
+        self.rules.contains_key(&rule)
+    }
+}
+
+/// Checks the AAT against the enabled design rules
+pub fn lint(aat: &AAT, config: &LintConfig) -> ValidationReport {
+    let mut linter = Linter {
+        aat,
+        config,
+        report: ValidationReport::default(),
+    };
+    linter.headers(&aat.headers, "headers");
+    for service in &aat.services {
+        linter.service(service);
+    }
+    for named_type in &aat.types {
+        linter.named_type(named_type);
+    }
+    linter.report
+}
+
+struct Linter<'a> {
+    aat: &'a AAT,
+    config: &'a LintConfig,
+    report: ValidationReport,
+}
+
+impl Linter<'_> {
+    fn finding(&mut self, rule: LintRule, location: impl Into<String>, message: String) {
+        let kind = IssueKind::Lint { rule, message };
+        match self.config.rules.get(&rule) {
+            Some(Severity::Error) => self.report.error(location, kind),
+            Some(Severity::Warning) => self.report.warning(location, kind),
+            None => {}
+        }
+    }
+
+    fn service(&mut self, service: &Service) {
+        let location = format!("services.{}", service.name);
+        self.headers(&service.headers, &format!("{}.headers", location));
+        self.collisions(
+            service
+                .endpoints
+                .iter()
+                .map(|endpoint| endpoint.name.as_str()),
+            &format!("{}.endpoints", location),
+            "Endpoints",
+        );
+        for endpoint in &service.endpoints {
+            self.endpoint(
+                endpoint,
+                &format!("{}.endpoints.{}", location, endpoint.name),
+            );
+        }
+    }
+
+    fn endpoint(&mut self, endpoint: &Endpoint, location: &str) {
+        if endpoint.body.is_some()
+            && matches!(endpoint.method, HttpMethod::Get | HttpMethod::Delete)
+        {
+            self.finding(
+                LintRule::NoBodyOnGetOrDelete,
+                format!("{}.body", location),
+                format!(
+                    "{} endpoint '{}' has a body",
+                    super::diff::method_name(&endpoint.method),
+                    endpoint.name
+                ),
+            );
+        }
+
+        if matches!(endpoint.response, FieldType::Stream(_)) && endpoint.upgrade.is_none() {
+            self.finding(
+                LintRule::StreamRequiresWebSocket,
+                format!("{}.response", location),
+                format!(
+                    "Endpoint '{}' streams its response without a WebSocket upgrade",
+                    endpoint.name
+                ),
+            );
+        }
+
+        if let Some(query) = &endpoint.query {
+            self.query(query, &format!("{}.query", location));
+        }
+
+        if !is_snake_case(&endpoint.name) {
+            self.finding(
+                LintRule::SnakeCaseEndpointNames,
+                location,
+                format!("Endpoint name '{}' is not snake_case", endpoint.name),
+            );
+        }
+
+        self.headers(&endpoint.headers, &format!("{}.headers", location));
+    }
+
+    fn query(&mut self, query: &FieldType, location: &str) {
+        let object = match query {
+            FieldType::Reference(name) => match self.aat.find_type(name) {
+                Some(NamedType::Object(object)) => object,
+                // Undefined types are reported by validation
+                None => return,
+                Some(_) => {
+                    self.finding(
+                        LintRule::FlatQuery,
+                        location,
+                        format!("Query type '{}' is not an object", name),
+                    );
+                    return;
+                }
+            },
+            _ => {
+                self.finding(
+                    LintRule::FlatQuery,
+                    location,
+                    "Query type is not a named object".to_string(),
+                );
+                return;
+            }
+        };
+        for field in &object.fields {
+            if !self.is_flat(&field.r#type, true) {
+                self.finding(
+                    LintRule::FlatQuery,
+                    format!("{}.fields.{}", location, field.name),
+                    format!(
+                        "Query field '{}' of '{}' is not a scalar or a list of scalars",
+                        field.name, object.name
+                    ),
+                );
+            }
+        }
+    }
+
+    /// Whether a query field can be written as one or repeated `name=value` pairs
+    fn is_flat(&self, field_type: &FieldType, allow_list: bool) -> bool {
+        match field_type {
+            FieldType::Primitive(_) | FieldType::Literal(_) => true,
+            FieldType::Optional(inner) => self.is_flat(inner, allow_list),
+            FieldType::List(inner) => allow_list && self.is_flat(inner, false),
+            FieldType::Reference(name) => {
+                matches!(self.aat.find_type(name), Some(NamedType::Enum(_)) | None)
+            }
+            FieldType::Map(_)
+            | FieldType::Tuple(_)
+            | FieldType::Intersection(_)
+            | FieldType::Stream(_)
+            | FieldType::Any => false,
+        }
+    }
+
+    fn headers(&mut self, headers: &[Header], location: &str) {
+        for header in headers {
+            let valid = !header.name.is_empty() && header.name.chars().all(is_token_char);
+            let message = if !valid {
+                format!("Header name '{}' is not a valid HTTP token", header.name)
+            } else if header.name.chars().any(|c| c.is_ascii_uppercase()) {
+                format!("Header name '{}' is not lowercase", header.name)
+            } else {
+                continue;
+            };
+            self.finding(
+                LintRule::HeaderNames,
+                format!("{}.{}", location, header.name),
+                message,
+            );
+        }
+    }
+
+    fn named_type(&mut self, named_type: &NamedType) {
+        match named_type {
+            NamedType::Object(object) => {
+                self.collisions(
+                    object.fields.iter().map(|field| field.name.as_str()),
+                    &format!("types.{}.fields", object.name),
+                    "Fields",
+                );
+            }
+            NamedType::Union(union) => {
+                for variant in &union.variants {
+                    if let UnionTypeVariantMode::Object(object) = &*variant.mode {
+                        self.collisions(
+                            object.fields.iter().map(|field| field.name.as_str()),
+                            &format!(
+                                "types.{}.variants.{}.fields",
+                                union.name,
+                                variant.name.as_deref().unwrap_or("unnamed")
+                            ),
+                            "Fields",
+                        );
+                    }
+                }
+            }
+            NamedType::Enum(enum_type) => {
+                if let Some(variant) = enum_type
+                    .variants
+                    .iter()
+                    .find(|variant| !matches!(variant.value, LiteralType::String(_)))
+                {
+                    self.finding(
+                        LintRule::StringEnums,
+                        format!("types.{}", enum_type.name),
+                        format!(
+                            "Enum '{}' has the non-string variant {}",
+                            enum_type.name,
+                            describe_literal(&variant.value)
+                        ),
+                    );
+                }
+            }
+        }
+    }
+
+    /// Reports names that become the same identifier in generated TypeScript
+    fn collisions<'n>(&mut self, names: impl Iterator<Item = &'n str>, location: &str, what: &str) {
+        let mut seen: HashMap<String, &str> = HashMap::new();
+        for name in names {
+            let camel = to_camel_case(name);
+            match seen.get(&camel) {
+                Some(previous) if *previous != name => {
+                    let message = format!(
+                        "{} '{}' and '{}' are both '{}' in camelCase",
+                        what, previous, name, camel
+                    );
+                    self.finding(
+                        LintRule::CamelCaseCollisions,
+                        format!("{}.{}", location, name),
+                        message,
+                    );
+                }
+                Some(_) => {}
+                None => {
+                    seen.insert(camel, name);
+                }
+            }
+        }
+    }
+}
+
+fn is_snake_case(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_lowercase())
+        && !name.ends_with('_')
+        && !name.contains("__")
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+}
+
+/// `tchar` of RFC 9110
+fn is_token_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || "!#$%&'*+-.^_`|~".contains(c)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spec::{self, HeaderValue, Spec, Type};
+    use schemars::JsonSchema;
+
+    #[derive(JsonSchema)]
+    #[allow(dead_code)]
+    struct Machine {
+        created_at: u64,
+        #[serde(rename = "created-at")]
+        created: u64,
+    }
+
+    #[derive(JsonSchema)]
+    #[allow(dead_code)]
+    struct Filter {
+        name: Option<String>,
+        tags: Vec<String>,
+        groups: Vec<Vec<String>>,
+        like: Option<Machine>,
+    }
+
+    fn aat() -> AAT {
+        let path = |literal: &str| vec![spec::PathSegment::Literal(literal.into())];
+        let spec = Spec::new("test")
+            .header("X-Api-Key", HeaderValue::Literal("secret".into()))
+            .service("machines", |service| {
+                service
+                    .get("list", path("machines"), |e| {
+                        e.query(Type::schema::<Filter>())
+                            .response(Type::list(Type::schema::<Machine>()))
+                    })
+                    .delete("remove", path("machines"), |e| {
+                        e.body(Type::schema::<Filter>())
+                    })
+                    .get("watchLogs", path("logs"), |e| {
+                        e.response(Type::stream(Type::schema::<String>()))
+                    })
+            });
+        let mut aat = AAT::from_spec(&spec).unwrap();
+        aat.types.push(NamedType::Enum(EnumType {
+            name: "Level".into(),
+            variants: vec![EnumVariant {
+                value: LiteralType::Int(1),
+                description: None,
+            }],
+            examples: vec![],
+            rust_path: None,
+        }));
+        aat
+    }
+
+    fn findings(report: &ValidationReport) -> Vec<String> {
+        report
+            .issues
+            .iter()
+            .map(|issue| issue.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn reports_every_rule() {
+        let report = lint(&aat(), &LintConfig::default());
+        assert!(report.is_valid());
+        let endpoints = "services.machines.endpoints";
+        assert_eq!(
+            findings(&report),
+            [
+                "warning[header-names] headers.X-Api-Key: Header name 'X-Api-Key' is not lowercase"
+                    .to_string(),
+                format!(
+                    "warning[flat-query] {}.list.query.fields.groups: Query field 'groups' of \
+                     'Filter' is not a scalar or a list of scalars",
+                    endpoints
+                ),
+                format!(
+                    "warning[flat-query] {}.list.query.fields.like: Query field 'like' of 'Filter' \
+                     is not a scalar or a list of scalars",
+                    endpoints
+                ),
+                format!(
+                    "warning[no-body-on-get-delete] {}.remove.body: DELETE endpoint 'remove' has \
+                     a body",
+                    endpoints
+                ),
+                format!(
+                    "warning[stream-requires-websocket] {}.watchLogs.response: Endpoint \
+                     'watchLogs' streams its response without a WebSocket upgrade",
+                    endpoints
+                ),
+                format!(
+                    "warning[snake-case-endpoint-names] {}.watchLogs: Endpoint name 'watchLogs' is \
+                     not snake_case",
+                    endpoints
+                ),
+                "warning[camel-case-collisions] types.Machine.fields.created_at: Fields \
+                 'created-at' and 'created_at' are both 'createdAt' in camelCase"
+                    .to_string(),
+                "warning[string-enums] types.Level: Enum 'Level' has the non-string variant 1"
+                    .to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn rules_can_be_denied_and_disabled() {
+        let aat = aat();
+
+        let report = lint(&aat, &LintConfig::none().deny(LintRule::StringEnums));
+        assert!(!report.is_valid());
+        assert_eq!(report.errors().count(), 1);
+        assert_eq!(report.warnings().count(), 0);
+
+        let config = LintConfig::default()
+            .disable(LintRule::FlatQuery)
+            .disable(LintRule::HeaderNames);
+        assert!(!config.is_enabled(LintRule::FlatQuery));
+        let codes: Vec<&str> = lint(&aat, &config)
+            .issues
+            .iter()
+            .map(|issue| issue.kind.code())
+            .collect();
+        assert!(!codes.contains(&"flat-query"));
+        assert!(!codes.contains(&"header-names"));
+        assert_eq!(codes.len(), 5);
+
+        assert!(lint(&aat, &LintConfig::none()).issues.is_empty());
+    }
+}
@@ -1,23 +1,43 @@
+pub mod diff;
+pub mod examples;
+pub mod lint;
+pub mod report;
+pub mod runtime;
+
 mod equality;
+mod graph;
 mod schema;
 mod types;
 mod validation;
 
+use crate::case::to_pascal_case;
+use crate::spec::{CollisionStrategy, TypeNaming};
 use anyhow::{Result, bail};
-use equality::types_are_structurally_equal;
+use equality::{merge_examples, types_are_structurally_equal};
+use report::ValidationReport;
 use schema::{schema_to_field_type, schema_to_type};
 use schemars::Schema;
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 pub use types::*;
-use validation::{validate_path_parameter_type, validate_references};
+use validation::{
+    validate_invalidations, validate_path_parameter_type, validate_references, validate_routes,
+};
 
 /* Abstract API Tree */
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AAT {
     pub types: Vec<NamedType>,
     pub services: Vec<Service>,
     pub headers: Vec<Header>,
+    #[serde(skip)]
     type_names: std::collections::HashSet<String>,
+    #[serde(skip)]
+    type_naming: TypeNaming,
+    /// Service being imported, for [`CollisionStrategy::QualifyWithService`]
+    #[serde(skip)]
+    current_service: Option<String>,
 }
 
 impl AAT {
@@ -27,12 +47,93 @@ impl AAT {
             services: vec![],
             headers: vec![],
             type_names: std::collections::HashSet::new(),
+            type_naming: TypeNaming::default(),
+            current_service: None,
         }
     }
 
-    /// Validates that all type references in the AAT resolve to actual types
+    /// Looks up a named type by name
+    pub fn find_type(&self, name: &str) -> Option<&NamedType> {
+        self.types.iter().find(|t| get_type_name(t) == name)
+    }
+
+    /// Validates that all type references in the AAT resolve to actual types, that cache
+    /// invalidation targets exist and that no two endpoints share a name or route. The error is
+    /// the [`ValidationReport`] listing every problem.
     pub fn validate(&self) -> Result<()> {
-        validate_references(&self.services, &self.types)
+        Ok(self.validation_report().into_result()?)
+    }
+
+    /// Collects every problem [`AAT::validate`] checks for, with its location
+    pub fn validation_report(&self) -> ValidationReport {
+        let mut report = ValidationReport::default();
+        validate_references(&self.headers, &self.services, &self.types, &mut report);
+        validate_invalidations(&self.services, &mut report);
+        validate_routes(&self.services, &mut report);
+        report
+    }
+
+    /// Every type with the names of the types its fields, union variants and intersections refer
+    /// to directly
+    pub fn type_graph(&self) -> BTreeMap<String, BTreeSet<String>> {
+        graph::type_graph(&self.types)
+    }
+
+    /// Names of the types `name` refers to directly, or `None` if there is no such type
+    pub fn type_dependencies(&self, name: &str) -> Option<BTreeSet<String>> {
+        self.find_type(name).map(graph::named_type_references)
+    }
+
+    /// Names of the types used by endpoints and headers, directly or through other types
+    pub fn reachable_types(&self) -> BTreeSet<String> {
+        let roots = graph::endpoint_references(&self.headers, &self.services);
+        graph::reachable(&self.type_graph(), roots)
+    }
+
+    /// Keeps only the named services, along with invalidations that target them. Types used only
+    /// by removed services stay until [`AAT::prune_unreachable_types`].
+    pub fn retain_services(&mut self, names: &[&str]) -> Result<()> {
+        if let Some(name) = names
+            .iter()
+            .find(|name| !self.services.iter().any(|service| service.name == **name))
+        {
+            bail!("No service named '{}'", name);
+        }
+        self.services
+            .retain(|service| names.contains(&service.name.as_str()));
+        for service in &mut self.services {
+            for endpoint in &mut service.endpoints {
+                endpoint.invalidates.retain(|target| {
+                    let service = target.split_once('.').map_or(target.as_str(), |(s, _)| s);
+                    names.contains(&service)
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Removes the types no endpoint or header uses, directly or through other types
+    pub fn prune_unreachable_types(&mut self) {
+        let reachable = self.reachable_types();
+        self.types
+            .retain(|named_type| reachable.contains(get_type_name(named_type)));
+        self.type_names.retain(|name| reachable.contains(name));
+    }
+
+    /// Serializes the AAT to JSON (the `*.aat.json` format read by the CLI)
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// Reads an AAT previously written with [`AAT::to_json`]
+    pub fn from_json(json: &str) -> Result<Self> {
+        let mut aat: Self = serde_json::from_str(json)?;
+        aat.type_names = aat
+            .types
+            .iter()
+            .map(|t| get_type_name(t).to_string())
+            .collect();
+        Ok(aat)
     }
 
     pub fn from_spec(spec: &crate::spec::Spec) -> Result<Self> {
@@ -43,7 +144,7 @@ impl AAT {
     }
 
     /// Sort types, services, and endpoints alphabetically to ensure deterministic output
-    fn sort(&mut self) {
+    pub(crate) fn sort(&mut self) {
         // Sort types by name
         self.types
             .sort_by(|a, b| get_type_name(a).cmp(get_type_name(b)));
@@ -60,6 +161,8 @@ impl AAT {
     pub fn import_from_spec(&mut self, spec: &crate::spec::Spec) -> Result<()> {
         use crate::spec::PathSegment as SpecPathSegment;
 
+        self.type_naming = spec.type_naming_rules().clone();
+
         // Convert root-level headers
         for (name, header_value) in spec.headers() {
             let aat_header = self.spec_header_value_to_aat(name, header_value)?;
@@ -68,6 +171,7 @@ impl AAT {
 
         // Iterate over services
         for spec_service in spec.services() {
+            self.current_service = Some(spec_service.name().to_string());
             let mut aat_service = Service {
                 name: spec_service.name().to_string(),
                 endpoints: vec![],
@@ -82,6 +186,13 @@ impl AAT {
 
             // Iterate over endpoints
             for spec_endpoint in spec_service.endpoints() {
+                // Names for types without a title, e.g. `MachinesListQuery`
+                let type_prefix = format!(
+                    "{}{}",
+                    to_pascal_case(spec_service.name()),
+                    to_pascal_case(spec_endpoint.name())
+                );
+
                 // Extract schemas from path segments and convert path
                 let mut aat_path = Vec::new();
                 for segment in spec_endpoint.path() {
@@ -92,32 +203,40 @@ impl AAT {
                         SpecPathSegment::Type { name, r#type } => {
                             // Validate that path parameter type is simple
                             validate_path_parameter_type(r#type)?;
-                            let field_type = self.spec_type_to_field_type(r#type)?;
+                            let name_hint = format!("{}{}", type_prefix, to_pascal_case(name));
+                            let field_type = self.spec_type_to_field_type(r#type, &name_hint)?;
                             aat_path.push(PathSegment::Parameter {
                                 name: name.clone(),
                                 r#type: field_type,
                             });
                         }
+                        SpecPathSegment::Wildcard(name) => {
+                            aat_path.push(PathSegment::Wildcard { name: name.clone() });
+                        }
                     }
                 }
 
                 // Extract schema from query type
                 let query_field_type = if let Some(query_type) = spec_endpoint.query_type() {
-                    Some(self.spec_type_to_field_type(query_type)?)
+                    let name_hint = format!("{}Query", type_prefix);
+                    Some(self.spec_type_to_field_type(query_type, &name_hint)?)
                 } else {
                     None
                 };
 
                 // Extract schema from body type
                 let body_field_type = if let Some(body_type) = spec_endpoint.body_type() {
-                    Some(self.spec_type_to_field_type(body_type)?)
+                    let name_hint = format!("{}Body", type_prefix);
+                    Some(self.spec_type_to_field_type(body_type, &name_hint)?)
                 } else {
                     None
                 };
 
                 // Extract schema from response type
-                let response_field_type =
-                    self.spec_type_to_field_type(spec_endpoint.response_type())?;
+                let response_field_type = self.spec_type_to_field_type(
+                    spec_endpoint.response_type(),
+                    &format!("{}Response", type_prefix),
+                )?;
 
                 // Convert HTTP method
                 let method = match spec_endpoint.method() {
@@ -140,6 +259,18 @@ impl AAT {
                     crate::spec::Upgrade::Ws => Upgrade::Ws,
                 });
 
+                // The spec-wide WebSocket auth fallback applies unless the endpoint overrides it
+                let ws_auth = upgrade
+                    .as_ref()
+                    .and_then(|_| spec_endpoint.ws_auth_strategy().or(spec.ws_auth_strategy()))
+                    .map(|auth| match auth {
+                        crate::spec::WsAuth::Query => WsAuth::Query,
+                        crate::spec::WsAuth::Protocol(protocol) => {
+                            WsAuth::Protocol(protocol.clone())
+                        }
+                        crate::spec::WsAuth::Message => WsAuth::Message,
+                    });
+
                 // Create AAT endpoint
                 let aat_endpoint = Endpoint {
                     name: spec_endpoint.name().to_string(),
@@ -149,6 +280,8 @@ impl AAT {
                     body: body_field_type,
                     response: response_field_type,
                     upgrade,
+                    ws_auth,
+                    invalidates: spec_endpoint.invalidations().to_vec(),
                     headers: aat_endpoint_headers,
                 };
 
@@ -157,6 +290,7 @@ impl AAT {
 
             self.services.push(aat_service);
         }
+        self.current_service = None;
 
         Ok(())
     }
@@ -171,24 +305,15 @@ impl AAT {
         let aat_value = match header_value {
             SpecHeaderValue::Literal(lit) => HeaderValue::Literal(lit.clone()),
             SpecHeaderValue::Type { name, r#type } => {
-                let field_type = self.spec_type_to_field_type(r#type)?;
+                let field_type = self.spec_type_to_field_type(r#type, &to_pascal_case(name))?;
                 HeaderValue::Parameter {
                     name: name.clone(),
                     field_type,
                 }
             }
-            SpecHeaderValue::Pattern {
-                pattern,
-                name: param_name,
-                r#type,
-            } => {
-                let field_type = self.spec_type_to_field_type(r#type)?;
-                HeaderValue::Pattern {
-                    pattern: pattern.clone(),
-                    param_name: param_name.clone(),
-                    field_type,
-                }
-            }
+            SpecHeaderValue::Pattern { pattern, params } => HeaderValue::Pattern {
+                parts: self.spec_pattern_to_parts(name, pattern, params)?,
+            },
         };
 
         Ok(Header {
@@ -197,7 +322,114 @@ impl AAT {
         })
     }
 
-    fn spec_type_to_field_type(&mut self, r#type: &crate::spec::Type) -> Result<FieldType> {
+    /// Splits a header pattern into literal text and the parameters its `{name}` placeholders
+    /// stand for. Every parameter must be used once, and placeholders must be separated by text
+    /// so servers can tell their values apart.
+    fn spec_pattern_to_parts(
+        &mut self,
+        header: &str,
+        pattern: &str,
+        params: &[crate::spec::HeaderParam],
+    ) -> Result<Vec<HeaderPart>> {
+        use crate::spec::HeaderFormat as SpecHeaderFormat;
+
+        let mut parts = Vec::new();
+        let mut used: Vec<&str> = Vec::new();
+        let mut rest = pattern;
+        loop {
+            let start = rest.find('{').unwrap_or(rest.len());
+            let literal = &rest[..start];
+            if literal.contains('}') {
+                bail!("Header '{}' has an unmatched '}}' in '{}'", header, pattern);
+            }
+            if !literal.is_empty() {
+                parts.push(HeaderPart::Literal(literal.to_string()));
+            } else if start < rest.len() && matches!(parts.last(), Some(HeaderPart::Param { .. })) {
+                bail!(
+                    "Placeholders of header '{}' must be separated by text in '{}'",
+                    header,
+                    pattern
+                );
+            }
+            if start == rest.len() {
+                break;
+            }
+
+            let Some(end) = rest[start..].find('}').map(|end| start + end) else {
+                bail!("Header '{}' has an unclosed '{{' in '{}'", header, pattern);
+            };
+            let placeholder = &rest[start + 1..end];
+            let Some(param) = params.iter().find(|param| param.name == placeholder) else {
+                bail!(
+                    "Header '{}' has no parameter for the placeholder '{{{}}}'",
+                    header,
+                    placeholder
+                );
+            };
+            if used.contains(&placeholder) {
+                bail!(
+                    "Header '{}' uses the placeholder '{{{}}}' more than once",
+                    header,
+                    placeholder
+                );
+            }
+            used.push(placeholder);
+
+            let name_hint = format!("{}{}", to_pascal_case(header), to_pascal_case(&param.name));
+            let field_type = self.spec_type_to_field_type(&param.r#type, &name_hint)?;
+            let format = match param.format {
+                None => None,
+                Some(SpecHeaderFormat::Base64) => Some(HeaderFormat::Base64),
+                Some(SpecHeaderFormat::Lowercase) => Some(HeaderFormat::Lowercase),
+                Some(SpecHeaderFormat::IsoDate) => {
+                    let inner = match &field_type {
+                        FieldType::Optional(inner) => inner,
+                        field_type => field_type,
+                    };
+                    if !matches!(
+                        inner,
+                        FieldType::Primitive(PrimitiveType::String(Some(
+                            StringFormat::Date | StringFormat::DateTime
+                        )))
+                    ) {
+                        bail!(
+                            "Parameter '{}' of header '{}' must be a date or date-time string \
+                             to be sent as an ISO date",
+                            param.name,
+                            header
+                        );
+                    }
+                    Some(HeaderFormat::IsoDate)
+                }
+            };
+            parts.push(HeaderPart::Param {
+                name: param.name.clone(),
+                field_type,
+                format,
+            });
+            rest = &rest[end + 1..];
+        }
+
+        if let Some(param) = params
+            .iter()
+            .find(|param| !used.contains(&param.name.as_str()))
+        {
+            bail!(
+                "Header '{}' has no '{{{}}}' placeholder in '{}'",
+                header,
+                param.name,
+                pattern
+            );
+        }
+        Ok(parts)
+    }
+
+    /// Converts a spec type, naming untitled objects, unions and enums after `name_hint`
+    fn spec_type_to_field_type(
+        &mut self,
+        r#type: &crate::spec::Type,
+        name_hint: &str,
+    ) -> Result<FieldType> {
         use crate::spec::Type;
 
         match r#type {
@@ -209,26 +441,27 @@ impl AAT {
                     schema_to_field_type(schema)
                 } else {
                     // Create named types for objects, unions, enums
-                    let name = self.add_schema_and_get_name(schema)?;
+                    let name = self.add_schema_and_get_name(schema, name_hint)?;
                     Ok(FieldType::Reference(name))
                 }
             }
             Type::List(inner) => {
-                let inner_type = self.spec_type_to_field_type(inner)?;
+                let inner_type = self.spec_type_to_field_type(inner, name_hint)?;
                 Ok(FieldType::List(Box::new(inner_type)))
             }
             Type::Optional(inner) => {
-                let inner_type = self.spec_type_to_field_type(inner)?;
+                let inner_type = self.spec_type_to_field_type(inner, name_hint)?;
                 Ok(FieldType::Optional(Box::new(inner_type)))
             }
             Type::Stream(inner) => {
-                let inner_type = self.spec_type_to_field_type(inner)?;
+                let inner_type = self.spec_type_to_field_type(inner, name_hint)?;
                 Ok(FieldType::Stream(Box::new(inner_type)))
             }
             Type::Tuple(types) => {
                 let mut field_types = Vec::new();
-                for t in types {
-                    field_types.push(self.spec_type_to_field_type(t)?);
+                for (index, t) in types.iter().enumerate() {
+                    let name_hint = format!("{}{}", name_hint, index);
+                    field_types.push(self.spec_type_to_field_type(t, &name_hint)?);
                 }
                 Ok(FieldType::Tuple(field_types))
             }
@@ -240,10 +473,11 @@ impl AAT {
         }
     }
 
-    pub fn append_types_from_schema(&mut self, schema: &Schema, root_name: &str) -> Result<()> {
+    /// Adds the root schema as `root_name` along with its definitions, applying the spec's
+    /// [`TypeNaming`] rules. Returns the name the root type ended up with.
+    pub fn append_types_from_schema(&mut self, schema: &Schema, root_name: &str) -> Result<String> {
         // Convert the root schema itself
-        let root_type = schema_to_type(schema, root_name)?;
-        self.add_type_with_dedup_check(root_type)?;
+        let mut batch = vec![schema_to_type(schema, root_name)?];
 
         // Process definitions if present
         if let Some(obj) = schema.as_object() {
@@ -251,14 +485,109 @@ impl AAT {
                 for (name, def_value) in defs {
                     // Convert Value to Schema
                     if let Ok(def_schema) = Schema::try_from(def_value.clone()) {
-                        let def_type = schema_to_type(&def_schema, name)?;
-                        self.add_type_with_dedup_check(def_type)?;
+                        batch.push(schema_to_type(&def_schema, name)?);
                     }
                 }
             }
         }
 
-        Ok(())
+        // Rename rules come first
+        let renames = batch
+            .iter()
+            .filter_map(|named_type| {
+                let name = get_type_name(named_type);
+                let renamed = self.type_naming.apply(name, get_rust_path(named_type));
+                (renamed != name).then(|| (name.to_string(), renamed))
+            })
+            .collect::<HashMap<_, _>>();
+        rename_types(&mut batch, &renames);
+
+        // Renaming a type changes the types referring to it, which may then collide as well
+        loop {
+            let mut renames = HashMap::new();
+            for named_type in &batch {
+                let name = get_type_name(named_type);
+                let collides = self
+                    .find_type(name)
+                    .is_some_and(|existing| !types_are_structurally_equal(existing, named_type));
+                if collides {
+                    let renamed = self.resolve_collision(named_type, &batch)?;
+                    renames.insert(name.to_string(), renamed);
+                }
+            }
+            if renames.is_empty() {
+                break;
+            }
+            rename_types(&mut batch, &renames);
+        }
+
+        let root_name = get_type_name(&batch[0]).to_string();
+        for named_type in batch {
+            self.add_type_with_dedup_check(named_type)?;
+        }
+        Ok(root_name)
+    }
+
+    /// A free name for a type whose name is taken by a different type, following the spec's
+    /// [`CollisionStrategy`]
+    fn resolve_collision(&self, named_type: &NamedType, batch: &[NamedType]) -> Result<String> {
+        let name = get_type_name(named_type);
+        let rust_path = get_rust_path(named_type);
+        let qualified = match self.type_naming.collision_strategy() {
+            CollisionStrategy::Error => bail!(
+                "Type name collision: a type named '{}'{} already exists with a different \
+                 structure. Name one with `Type::schema_named`, rename it with `TypeNaming` or \
+                 choose a `CollisionStrategy`.",
+                name,
+                rust_path
+                    .map(|path| format!(" (from {})", path))
+                    .unwrap_or_default()
+            ),
+            CollisionStrategy::QualifyWithModule => match rust_path.and_then(parent_module) {
+                Some(module) => Some(format!("{}{}", to_pascal_case(module), name)),
+                None => bail!(
+                    "Type name collision: a type named '{}' already exists with a different \
+                     structure, and its module is unknown. Nested types only know their module \
+                     when they are marked `#[damascus::rust_path]`; otherwise rename one with \
+                     `TypeNaming`.",
+                    name
+                ),
+            },
+            CollisionStrategy::QualifyWithService => self
+                .current_service
+                .as_ref()
+                .map(|service| format!("{}{}", to_pascal_case(service), name)),
+            CollisionStrategy::Suffix => None,
+        };
+
+        let base = qualified.clone().unwrap_or_else(|| name.to_string());
+        let is_free = |candidate: &String| {
+            !batch.iter().any(|other| get_type_name(other) == candidate)
+                && self
+                    .find_type(candidate)
+                    .is_none_or(|existing| types_are_structurally_equal(existing, named_type))
+        };
+        let candidate = qualified
+            .into_iter()
+            .chain((2..).map(|counter| format!("{}{}", base, counter)))
+            .find(is_free);
+        Ok(candidate.unwrap_or(base))
+    }
+
+    /// Converts a schema to a field type, adding objects, unions and enums as named types.
+    /// Named types take their name from the schema `title`, falling back to `name_hint`.
+    pub fn add_schema(&mut self, schema: &Schema, name_hint: &str) -> Result<FieldType> {
+        if should_inline_schema(schema) {
+            return schema_to_field_type(schema);
+        }
+        let name = schema
+            .as_object()
+            .and_then(|obj| obj.get("title"))
+            .and_then(|v| v.as_str())
+            .unwrap_or(name_hint)
+            .to_string();
+        let name = self.append_types_from_schema(schema, &name)?;
+        Ok(FieldType::Reference(name))
     }
 
     /// Adds a type to the AAT, checking for duplicate names with different structures
@@ -266,7 +595,11 @@ impl AAT {
         let type_name = get_type_name(&new_type);
 
         // Check if a type with this name already exists
-        if let Some(existing_type) = self.types.iter().find(|t| get_type_name(t) == type_name) {
+        if let Some(existing_type) = self
+            .types
+            .iter_mut()
+            .find(|t| get_type_name(t) == type_name)
+        {
             // If the structures are different, this is an error
             if !types_are_structurally_equal(existing_type, &new_type) {
                 bail!(
@@ -274,7 +607,13 @@ impl AAT {
                     type_name
                 );
             }
-            // Otherwise, it's a duplicate of the same type, so we can skip it
+            // Otherwise, it's a duplicate of the same type, which may bring more examples, and
+            // its Rust path when the type was first reached without one
+            merge_examples(existing_type, &new_type);
+            let rust_path = rust_path_mut(existing_type);
+            if rust_path.is_none() {
+                *rust_path = get_rust_path(&new_type).map(str::to_string);
+            }
             return Ok(());
         }
 
@@ -284,7 +623,7 @@ impl AAT {
         Ok(())
     }
 
-    fn extract_schema_name(&self, schema: &Schema) -> Result<String> {
+    fn extract_schema_name(&self, schema: &Schema, name_hint: &str) -> Result<String> {
         let obj = schema
             .as_object()
             .ok_or_else(|| anyhow::anyhow!("Schema must be an object to extract name"))?;
@@ -301,27 +640,25 @@ impl AAT {
             }
         }
 
-        // Last resort: generate a unique name based on existing types
+        // Last resort: name it after where it is used, numbered if that name is taken
+        let mut candidate = name_hint.to_string();
         let mut counter = 1;
-        loop {
-            let candidate = format!("AnonymousType{}", counter);
-            if !self.type_names.contains(&candidate) {
-                return Ok(candidate);
-            }
+        while self.type_names.contains(&candidate) {
             counter += 1;
+            candidate = format!("{}{}", name_hint, counter);
         }
+        Ok(candidate)
     }
 
     /// Helper to extract schema name and add schema to types list
     /// Returns the schema name for use in references
-    fn add_schema_and_get_name(&mut self, schema: &Schema) -> Result<String> {
-        let name = self.extract_schema_name(schema)?;
-        self.append_types_from_schema(schema, &name)?;
-        Ok(name)
+    fn add_schema_and_get_name(&mut self, schema: &Schema, name_hint: &str) -> Result<String> {
+        let name = self.extract_schema_name(schema, name_hint)?;
+        self.append_types_from_schema(schema, &name)
     }
 }
 
-fn get_type_name(named_type: &NamedType) -> &str {
+pub(crate) fn get_type_name(named_type: &NamedType) -> &str {
     match named_type {
         NamedType::Object(obj) => &obj.name,
         NamedType::Union(union) => &union.name,
@@ -329,6 +666,45 @@ fn get_type_name(named_type: &NamedType) -> &str {
     }
 }
 
+fn get_rust_path(named_type: &NamedType) -> Option<&str> {
+    match named_type {
+        NamedType::Object(obj) => obj.rust_path.as_deref(),
+        NamedType::Union(union) => union.rust_path.as_deref(),
+        NamedType::Enum(enum_type) => enum_type.rust_path.as_deref(),
+    }
+}
+
+fn rust_path_mut(named_type: &mut NamedType) -> &mut Option<String> {
+    match named_type {
+        NamedType::Object(obj) => &mut obj.rust_path,
+        NamedType::Union(union) => &mut union.rust_path,
+        NamedType::Enum(enum_type) => &mut enum_type.rust_path,
+    }
+}
+
+/// The module a type is defined in, `v2` for `my_crate::v2::Machine<my_crate::Id>`
+fn parent_module(rust_path: &str) -> Option<&str> {
+    let path = rust_path.split('<').next().unwrap_or(rust_path);
+    path.rsplit("::").nth(1)
+}
+
+/// Renames types of a batch and the references between them
+fn rename_types(batch: &mut [NamedType], renames: &HashMap<String, String>) {
+    let mut rename = |name: &mut String| {
+        if let Some(renamed) = renames.get(name.as_str()) {
+            *name = renamed.clone();
+        }
+    };
+    for named_type in batch {
+        match named_type {
+            NamedType::Object(obj) => rename(&mut obj.name),
+            NamedType::Union(union) => rename(&mut union.name),
+            NamedType::Enum(enum_type) => rename(&mut enum_type.name),
+        }
+        named_type.visit_references_mut(&mut rename);
+    }
+}
+
 /// Checks if a schema should be inlined (primitives, arrays, maps) or named (objects, unions, enums)
 fn should_inline_schema(schema: &Schema) -> bool {
     if let Some(obj) = schema.as_object() {
@@ -354,11 +730,469 @@ fn should_inline_schema(schema: &Schema) -> bool {
 
 fn extract_ref_name(reference: &str) -> Result<String> {
     // References typically look like "#/definitions/TypeName" or "#/$defs/TypeName"
+    // (or "#/components/schemas/TypeName" in OpenAPI documents)
     if let Some(name) = reference.strip_prefix("#/definitions/") {
         Ok(name.to_string())
     } else if let Some(name) = reference.strip_prefix("#/$defs/") {
         Ok(name.to_string())
+    } else if let Some(name) = reference.strip_prefix("#/components/schemas/") {
+        Ok(name.to_string())
     } else {
         bail!("Unsupported reference format: {}", reference)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use schemars::JsonSchema;
+    use serde_json::json;
+
+    use super::*;
+    use crate::fixtures::{Timestamp, machines};
+    use crate::spec::{self, Spec, Type, TypeNaming};
+
+    mod v1 {
+        use schemars::JsonSchema;
+
+        #[derive(JsonSchema)]
+        #[allow(dead_code)]
+        pub struct Machine {
+            pub name: String,
+        }
+
+        #[crate::rust_path]
+        #[derive(JsonSchema)]
+        #[allow(dead_code)]
+        pub struct Part {
+            pub serial: String,
+        }
+    }
+
+    mod v2 {
+        use schemars::JsonSchema;
+
+        #[derive(JsonSchema)]
+        #[allow(dead_code)]
+        pub struct Machine {
+            pub id: u32,
+        }
+
+        #[crate::rust_path]
+        #[derive(JsonSchema)]
+        #[allow(dead_code)]
+        pub struct Part {
+            pub serial: u32,
+        }
+    }
+
+    #[derive(JsonSchema)]
+    #[allow(dead_code)]
+    struct Fleet {
+        part: v1::Part,
+    }
+
+    #[derive(JsonSchema)]
+    #[allow(dead_code)]
+    struct Depot {
+        part: v2::Part,
+    }
+
+    #[derive(JsonSchema)]
+    #[allow(dead_code)]
+    struct Garage {
+        machine: v1::Machine,
+    }
+
+    #[derive(JsonSchema)]
+    #[allow(dead_code)]
+    struct Yard {
+        machine: v2::Machine,
+    }
+
+    fn two_endpoints(naming: TypeNaming, first: Type, second: Type) -> Spec {
+        Spec::new("test")
+            .type_naming(naming)
+            .service("machines", |service| {
+                service
+                    .get(
+                        "fleet",
+                        vec![spec::PathSegment::Literal("fleet".into())],
+                        |e| e.response(first),
+                    )
+                    .get(
+                        "depot",
+                        vec![spec::PathSegment::Literal("depot".into())],
+                        |e| e.response(second),
+                    )
+            })
+    }
+
+    fn type_names(aat: &AAT) -> Vec<&str> {
+        let mut names = aat.types.iter().map(get_type_name).collect::<Vec<_>>();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn qualifies_root_types_with_their_module() {
+        let naming = TypeNaming::new().on_collision(CollisionStrategy::QualifyWithModule);
+        let spec = two_endpoints(
+            naming,
+            Type::schema::<v1::Machine>(),
+            Type::schema::<v2::Machine>(),
+        );
+        let aat = AAT::from_spec(&spec).unwrap();
+        assert_eq!(type_names(&aat), ["Machine", "V2Machine"]);
+    }
+
+    #[test]
+    fn qualifies_nested_types_that_record_their_path() {
+        let naming = TypeNaming::new().on_collision(CollisionStrategy::QualifyWithModule);
+        let spec = two_endpoints(naming, Type::schema::<Fleet>(), Type::schema::<Depot>());
+        let aat = AAT::from_spec(&spec).unwrap();
+        assert_eq!(type_names(&aat), ["Depot", "Fleet", "Part", "V2Part"]);
+        let rust_path = |name: &str| aat.find_type(name).and_then(get_rust_path);
+        assert_eq!(rust_path("Part"), Some("damascus::aat::tests::v1::Part"));
+        assert_eq!(rust_path("V2Part"), Some("damascus::aat::tests::v2::Part"));
+    }
+
+    #[test]
+    fn nested_types_without_a_path_cannot_be_qualified() {
+        let naming = TypeNaming::new().on_collision(CollisionStrategy::QualifyWithModule);
+        let spec = two_endpoints(naming, Type::schema::<Garage>(), Type::schema::<Yard>());
+        let error = AAT::from_spec(&spec).unwrap_err().to_string();
+        assert!(error.contains("'Machine'"), "{error}");
+        assert!(error.contains("module is unknown"), "{error}");
+    }
+
+    #[test]
+    fn nested_types_take_their_path_from_a_later_root() {
+        let spec = two_endpoints(
+            TypeNaming::new(),
+            Type::schema::<Garage>(),
+            Type::schema::<v1::Machine>(),
+        );
+        let aat = AAT::from_spec(&spec).unwrap();
+        let machine = aat.find_type("Machine").unwrap();
+        assert_eq!(
+            get_rust_path(machine),
+            Some("damascus::aat::tests::v1::Machine")
+        );
+    }
+
+    #[test]
+    fn renames_nested_types_by_path() {
+        let naming = TypeNaming::new().rename("v2::Part", "PartV2");
+        let spec = two_endpoints(naming, Type::schema::<Fleet>(), Type::schema::<Depot>());
+        let aat = AAT::from_spec(&spec).unwrap();
+        assert_eq!(type_names(&aat), ["Depot", "Fleet", "Part", "PartV2"]);
+    }
+
+    #[derive(JsonSchema)]
+    #[allow(dead_code)]
+    enum Shape {
+        Circle(Circle),
+        Empty,
+    }
+
+    #[derive(JsonSchema)]
+    #[allow(dead_code)]
+    struct Circle {
+        center: Point,
+    }
+
+    #[derive(JsonSchema)]
+    #[allow(dead_code)]
+    struct Point {
+        x: f64,
+    }
+
+    #[derive(JsonSchema)]
+    #[allow(dead_code)]
+    struct Orphan {
+        name: String,
+    }
+
+    /// `Labelled` marks things with a point and a tag at once, which schemars never derives
+    fn labelled() -> Type {
+        Type::Schema(schemars::json_schema!({
+            "title": "Labelled",
+            "type": "object",
+            "properties": {
+                "mark": { "allOf": [{ "$ref": "#/$defs/Point" }, { "$ref": "#/$defs/Tag" }] }
+            },
+            "required": ["mark"],
+            "$defs": {
+                "Point": {
+                    "type": "object",
+                    "properties": { "x": { "type": "number" } },
+                    "required": ["x"]
+                },
+                "Tag": {
+                    "type": "object",
+                    "properties": { "label": { "type": "string" } },
+                    "required": ["label"]
+                }
+            }
+        }))
+    }
+
+    fn shapes_and_orphans() -> AAT {
+        let spec = Spec::new("test")
+            .service("shapes", |service| {
+                service
+                    .get(
+                        "shape",
+                        vec![spec::PathSegment::Literal("shape".into())],
+                        |e| e.response(Type::schema::<Shape>()),
+                    )
+                    .post(
+                        "label",
+                        vec![spec::PathSegment::Literal("label".into())],
+                        |e| {
+                            e.body(labelled())
+                                .invalidates("shapes.shape")
+                                .invalidates("orphans")
+                        },
+                    )
+            })
+            .service("orphans", |service| {
+                service.get(
+                    "orphan",
+                    vec![spec::PathSegment::Literal("orphan".into())],
+                    |e| e.response(Type::schema::<Orphan>()),
+                )
+            });
+        AAT::from_spec(&spec).unwrap()
+    }
+
+    #[test]
+    fn lists_the_types_a_type_depends_on() {
+        let aat = shapes_and_orphans();
+        let dependencies = |name| {
+            aat.type_dependencies(name)
+                .map(|names| names.into_iter().collect::<Vec<_>>())
+        };
+        assert_eq!(dependencies("Shape").unwrap(), ["Circle"]);
+        assert_eq!(dependencies("Labelled").unwrap(), ["Point", "Tag"]);
+        assert_eq!(dependencies("Point").unwrap(), Vec::<String>::new());
+        assert_eq!(dependencies("Missing"), None);
+    }
+
+    #[test]
+    fn retains_services_and_their_invalidations() {
+        let mut aat = shapes_and_orphans();
+        aat.retain_services(&["shapes"]).unwrap();
+        assert_eq!(aat.services.len(), 1);
+        let label = &aat.services[0].endpoints[0];
+        assert_eq!(label.name, "label");
+        assert_eq!(label.invalidates, ["shapes.shape"]);
+
+        let error = aat.retain_services(&["orphans"]).unwrap_err();
+        assert_eq!(error.to_string(), "No service named 'orphans'");
+    }
+
+    #[test]
+    fn prunes_types_through_unions_and_intersections() {
+        let mut aat = shapes_and_orphans();
+        aat.prune_unreachable_types();
+        assert!(type_names(&aat).contains(&"Orphan"));
+
+        aat.retain_services(&["shapes"]).unwrap();
+        aat.prune_unreachable_types();
+        assert_eq!(
+            type_names(&aat),
+            ["Circle", "Labelled", "Point", "Shape", "Tag"]
+        );
+    }
+
+    #[test]
+    fn merges_examples_of_duplicate_types() {
+        let first = Type::schema::<Point>().example(json!({ "x": 1.0 }));
+        let second = Type::schema::<Point>().example(json!({ "x": 2.0 }));
+        let spec = two_endpoints(TypeNaming::new(), first, second);
+        let aat = AAT::from_spec(&spec).unwrap();
+        let Some(NamedType::Object(point)) = aat.find_type("Point") else {
+            panic!("Point is not an object");
+        };
+        assert_eq!(point.examples, [json!({ "x": 1.0 }), json!({ "x": 2.0 })]);
+    }
+
+    #[derive(JsonSchema)]
+    #[allow(dead_code)]
+    struct Counters {
+        small: u8,
+        offset: i16,
+        total: u64,
+        balance: i128,
+        index: usize,
+    }
+
+    #[test]
+    fn reads_the_integer_width_from_the_format() {
+        let spec = two_endpoints(
+            TypeNaming::new(),
+            Type::schema::<Counters>(),
+            Type::schema::<Counters>(),
+        );
+        let aat = AAT::from_spec(&spec).unwrap();
+        let Some(NamedType::Object(counters)) = aat.find_type("Counters") else {
+            panic!("Counters is not an object");
+        };
+        let int_format = |name: &str| {
+            let field = counters.fields.iter().find(|f| f.name == name).unwrap();
+            match &*field.r#type {
+                FieldType::Primitive(PrimitiveType::Int(format)) => *format,
+                other => panic!("{} is not an integer: {:?}", name, other),
+            }
+        };
+        assert_eq!(int_format("small"), Some(IntFormat::UInt8));
+        assert_eq!(int_format("offset"), Some(IntFormat::Int16));
+        assert_eq!(int_format("total"), Some(IntFormat::UInt64));
+        assert_eq!(int_format("balance"), Some(IntFormat::Int128));
+        assert_eq!(int_format("index"), Some(IntFormat::UInt));
+        assert!(IntFormat::UInt64.exceeds_f64_precision());
+        assert!(!IntFormat::UInt32.exceeds_f64_precision());
+        assert!(!IntFormat::UInt.exceeds_f64_precision());
+    }
+
+    fn signed(pattern: &str, params: Vec<(&str, Type, Option<spec::HeaderFormat>)>) -> Result<AAT> {
+        let value = spec::HeaderValue::Pattern {
+            pattern: pattern.to_string(),
+            params: params
+                .into_iter()
+                .map(|(name, r#type, format)| spec::HeaderParam {
+                    name: name.to_string(),
+                    r#type,
+                    format,
+                })
+                .collect(),
+        };
+        AAT::from_spec(&machines(|e| e.header("x-signature", value)))
+    }
+
+    #[test]
+    fn splits_header_patterns_into_parts() {
+        let aat = signed(
+            "key={key}, at={at}; {sig}",
+            vec![
+                (
+                    "key",
+                    Type::schema::<String>(),
+                    Some(spec::HeaderFormat::Lowercase),
+                ),
+                (
+                    "at",
+                    Type::schema::<Timestamp>(),
+                    Some(spec::HeaderFormat::IsoDate),
+                ),
+                (
+                    "sig",
+                    Type::schema::<String>(),
+                    Some(spec::HeaderFormat::Base64),
+                ),
+            ],
+        )
+        .unwrap();
+        let header = &aat.services[0].endpoints[0].headers[0];
+        let HeaderValue::Pattern { parts } = &header.value else {
+            panic!("x-signature is not a pattern: {:?}", header.value);
+        };
+        let parts: Vec<_> = parts
+            .iter()
+            .map(|part| match part {
+                HeaderPart::Literal(text) => (text.as_str(), None),
+                HeaderPart::Param { name, format, .. } => (name.as_str(), format.clone()),
+            })
+            .collect();
+        assert_eq!(
+            parts,
+            vec![
+                ("key=", None),
+                ("key", Some(HeaderFormat::Lowercase)),
+                (", at=", None),
+                ("at", Some(HeaderFormat::IsoDate)),
+                ("; ", None),
+                ("sig", Some(HeaderFormat::Base64)),
+            ]
+        );
+    }
+
+    #[test]
+    fn header_formats_are_written_in_snake_case() {
+        let aat = signed(
+            "{at}/{sig}",
+            vec![
+                (
+                    "at",
+                    Type::optional(Type::schema::<Timestamp>()),
+                    Some(spec::HeaderFormat::IsoDate),
+                ),
+                ("sig", Type::schema::<String>(), None),
+            ],
+        )
+        .unwrap();
+        let value = serde_json::to_value(&aat.services[0].endpoints[0].headers[0].value).unwrap();
+        let parts = &value["pattern"]["parts"];
+        assert_eq!(parts[0]["param"]["format"], json!("iso_date"));
+        assert_eq!(parts[1], json!({ "literal": "/" }));
+        // Parameters sent as they are leave the format out
+        assert!(parts[2]["param"].get("format").is_none());
+        assert_eq!(
+            serde_json::to_value(HeaderFormat::Base64).unwrap(),
+            json!("base64")
+        );
+        assert_eq!(
+            serde_json::to_value(HeaderFormat::Lowercase).unwrap(),
+            json!("lowercase")
+        );
+    }
+
+    #[test]
+    fn iso_dates_need_a_date_type() {
+        let error = signed(
+            "at={at}",
+            vec![(
+                "at",
+                Type::schema::<String>(),
+                Some(spec::HeaderFormat::IsoDate),
+            )],
+        )
+        .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Parameter 'at' of header 'x-signature' must be a date or date-time string to be sent as an ISO date"
+        );
+    }
+
+    #[test]
+    fn rejects_header_patterns_that_dont_match_their_parameters() {
+        let error = |pattern: &str, params: &[&str]| {
+            let params = params
+                .iter()
+                .map(|name| (*name, Type::schema::<String>(), None))
+                .collect();
+            signed(pattern, params).unwrap_err().to_string()
+        };
+        assert_eq!(
+            error("{key}{sig}", &["key", "sig"]),
+            "Placeholders of header 'x-signature' must be separated by text in '{key}{sig}'"
+        );
+        assert_eq!(
+            error("{key}:{key}", &["key"]),
+            "Header 'x-signature' uses the placeholder '{key}' more than once"
+        );
+        assert_eq!(
+            error("{key}:{sig}", &["key"]),
+            "Header 'x-signature' has no parameter for the placeholder '{sig}'"
+        );
+        assert_eq!(
+            error("{key}", &["key", "sig"]),
+            "Header 'x-signature' has no '{sig}' placeholder in '{key}'"
+        );
+        assert_eq!(
+            error("{key", &["key"]),
+            "Header 'x-signature' has an unclosed '{' in '{key'"
+        );
+    }
+}
@@ -0,0 +1,273 @@
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::fmt;
+
+use super::lint::LintRule;
+
+/// Whether a problem makes the AAT unusable or only deserves attention
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Error => write!(f, "error"),
+            Severity::Warning => write!(f, "warning"),
+        }
+    }
+}
+
+/// What is wrong, independent of where
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum IssueKind {
+    #[error("Reference to undefined type '{0}'")]
+    UndefinedType(String),
+    #[error("Wildcard path segment '{0}' must be the last segment")]
+    WildcardNotLast(String),
+    #[error("Path parameter '{name}' {reason}")]
+    InvalidPathParameter { name: String, reason: String },
+    #[error("Invalidates unknown target '{0}'")]
+    UnknownInvalidation(String),
+    #[error("Endpoint name '{0}' is used more than once in its service")]
+    DuplicateEndpoint(String),
+    #[error("Path parameter '{0}' appears more than once in the path")]
+    DuplicatePathParameter(String),
+    #[error("Route '{route}' is already used by endpoint '{endpoint}'")]
+    RouteConflict { route: String, endpoint: String },
+    #[error(
+        "Route '{route}' overlaps '{other}' of endpoint '{endpoint}'; requests matching both go \
+         to the one with literal segments"
+    )]
+    RouteShadowed {
+        route: String,
+        other: String,
+        endpoint: String,
+    },
+    /// A finding of a [`lint`](super::lint) rule
+    #[error("{message}")]
+    Lint { rule: LintRule, message: String },
+}
+
+impl IssueKind {
+    /// Stable identifier of the kind of problem, for filtering and documentation
+    pub fn code(&self) -> &'static str {
+        match self {
+            IssueKind::UndefinedType(_) => "undefined-type",
+            IssueKind::WildcardNotLast(_) => "wildcard-not-last",
+            IssueKind::InvalidPathParameter { .. } => "invalid-path-parameter",
+            IssueKind::UnknownInvalidation(_) => "unknown-invalidation",
+            IssueKind::DuplicateEndpoint(_) => "duplicate-endpoint",
+            IssueKind::DuplicatePathParameter(_) => "duplicate-path-parameter",
+            IssueKind::RouteConflict { .. } => "route-conflict",
+            IssueKind::RouteShadowed { .. } => "route-shadowed",
+            IssueKind::Lint { rule, .. } => rule.code(),
+        }
+    }
+}
+
+/// A problem and where in the AAT it is
+#[derive(Debug, Clone, PartialEq)]
+pub struct Issue {
+    pub severity: Severity,
+    /// Dotted path to the offending item, e.g. `services.machines.endpoints.get.path.name`
+    pub location: String,
+    pub kind: IssueKind,
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}[{}] {}: {}",
+            self.severity,
+            self.kind.code(),
+            self.location,
+            self.kind
+        )
+    }
+}
+
+/// Every problem found while validating an AAT.
+///
+/// As an error, it lists each issue on its own line.
+#[derive(Debug, Clone, Default, PartialEq, thiserror::Error)]
+pub struct ValidationReport {
+    pub issues: Vec<Issue>,
+}
+
+impl fmt::Display for ValidationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (index, issue) in self.issues.iter().enumerate() {
+            if index > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{}", issue)?;
+        }
+        Ok(())
+    }
+}
+
+impl ValidationReport {
+    pub fn error(&mut self, location: impl Into<String>, kind: IssueKind) {
+        self.issues.push(Issue {
+            severity: Severity::Error,
+            location: location.into(),
+            kind,
+        });
+    }
+
+    pub fn warning(&mut self, location: impl Into<String>, kind: IssueKind) {
+        self.issues.push(Issue {
+            severity: Severity::Warning,
+            location: location.into(),
+            kind,
+        });
+    }
+
+    pub fn errors(&self) -> impl Iterator<Item = &Issue> {
+        self.issues
+            .iter()
+            .filter(|issue| issue.severity == Severity::Error)
+    }
+
+    pub fn warnings(&self) -> impl Iterator<Item = &Issue> {
+        self.issues
+            .iter()
+            .filter(|issue| issue.severity == Severity::Warning)
+    }
+
+    /// Whether there are no errors; warnings are allowed
+    pub fn is_valid(&self) -> bool {
+        self.errors().next().is_none()
+    }
+
+    /// `Err(self)` if there are errors
+    pub fn into_result(self) -> Result<(), Self> {
+        if self.is_valid() { Ok(()) } else { Err(self) }
+    }
+
+    /// Multi-line text for terminals, one block per issue followed by a summary
+    pub fn render_pretty(&self) -> String {
+        let mut output = String::new();
+        for issue in &self.issues {
+            output.push_str(&format!(
+                "{}[{}]: {}\n  --> {}\n\n",
+                issue.severity,
+                issue.kind.code(),
+                issue.kind,
+                issue.location
+            ));
+        }
+        let errors = self.errors().count();
+        let warnings = self.warnings().count();
+        output.push_str(&format!(
+            "{} {}, {} {}\n",
+            errors,
+            if errors == 1 { "error" } else { "errors" },
+            warnings,
+            if warnings == 1 { "warning" } else { "warnings" }
+        ));
+        output
+    }
+
+    /// `{"valid": .., "issues": [{"severity", "code", "location", "message"}]}` for tools
+    pub fn render_json(&self) -> String {
+        let issues: Vec<_> = self
+            .issues
+            .iter()
+            .map(|issue| {
+                json!({
+                    "severity": issue.severity,
+                    "code": issue.kind.code(),
+                    "location": issue.location,
+                    "message": issue.kind.to_string(),
+                })
+            })
+            .collect();
+        let report = json!({ "valid": self.is_valid(), "issues": issues });
+        serde_json::to_string_pretty(&report).unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report() -> ValidationReport {
+        let mut report = ValidationReport::default();
+        report.error(
+            "services.machines.endpoints.get.response",
+            IssueKind::UndefinedType("Machine".into()),
+        );
+        report.warning(
+            "services.machines.endpoints.logs",
+            IssueKind::RouteShadowed {
+                route: "/machines/{id}".into(),
+                other: "/machines/logs".into(),
+                endpoint: "machines.list".into(),
+            },
+        );
+        report
+    }
+
+    #[test]
+    fn warnings_alone_are_valid() {
+        let mut warnings = report();
+        warnings.issues.remove(0);
+        assert!(warnings.is_valid());
+        assert_eq!(warnings.clone().into_result(), Ok(()));
+
+        let report = report();
+        assert!(!report.is_valid());
+        assert_eq!(report.errors().count(), 1);
+        assert_eq!(report.warnings().count(), 1);
+        assert_eq!(report.clone().into_result(), Err(report));
+    }
+
+    #[test]
+    fn displays_one_issue_per_line() {
+        assert_eq!(
+            report().to_string(),
+            "error[undefined-type] services.machines.endpoints.get.response: Reference to \
+             undefined type 'Machine'\n\
+             warning[route-shadowed] services.machines.endpoints.logs: Route '/machines/{id}' \
+             overlaps '/machines/logs' of endpoint 'machines.list'; requests matching both go to \
+             the one with literal segments"
+        );
+    }
+
+    #[test]
+    fn renders_pretty_with_a_summary() {
+        let pretty = report().render_pretty();
+        assert!(pretty.starts_with(
+            "error[undefined-type]: Reference to undefined type 'Machine'\n  \
+             --> services.machines.endpoints.get.response\n\n"
+        ));
+        assert!(pretty.ends_with("1 error, 1 warning\n"));
+        assert_eq!(
+            ValidationReport::default().render_pretty(),
+            "0 errors, 0 warnings\n"
+        );
+    }
+
+    #[test]
+    fn renders_json_for_tools() {
+        let json: serde_json::Value = serde_json::from_str(&report().render_json()).unwrap();
+        assert_eq!(json["valid"], false);
+        assert_eq!(
+            json["issues"][0],
+            json!({
+                "severity": "error",
+                "code": "undefined-type",
+                "location": "services.machines.endpoints.get.response",
+                "message": "Reference to undefined type 'Machine'",
+            })
+        );
+        assert_eq!(json["issues"][1]["severity"], "warning");
+        assert_eq!(json["issues"][1]["code"], "route-shadowed");
+    }
+}
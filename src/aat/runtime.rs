@@ -0,0 +1,668 @@
+use super::{AAT, get_type_name, types::*};
+use regex::Regex;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::LazyLock;
+
+static DATE_TIME_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^\d{4}-(0[1-9]|1[0-2])-(0[1-9]|[12]\d|3[01])[Tt]([01]\d|2[0-3]):[0-5]\d:([0-5]\d|60)(\.\d+)?([Zz]|[+-]([01]\d|2[0-3]):[0-5]\d)$").unwrap()
+});
+static DATE_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^\d{4}-(0[1-9]|1[0-2])-(0[1-9]|[12]\d|3[01])$").unwrap());
+static TIME_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^([01]\d|2[0-3]):[0-5]\d:([0-5]\d|60)(\.\d+)?([Zz]|[+-]([01]\d|2[0-3]):[0-5]\d)?$")
+        .unwrap()
+});
+static UUID_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^[0-9a-fA-F]{8}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{12}$")
+        .unwrap()
+});
+static EMAIL_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^[^@\s]+@[^@\s]+\.[^@\s]+$").unwrap());
+static URI_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^[a-zA-Z][a-zA-Z0-9+.-]*:\S*$").unwrap());
+static HOSTNAME_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^[a-zA-Z0-9]([a-zA-Z0-9-]{0,61}[a-zA-Z0-9])?(\.[a-zA-Z0-9]([a-zA-Z0-9-]{0,61}[a-zA-Z0-9])?)*$")
+        .unwrap()
+});
+
+/// A single problem found while validating a JSON value against an AAT type
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValidationError {
+    /// JSON pointer to the offending value (empty for the root)
+    pub path: String,
+    pub message: String,
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.path.is_empty() {
+            write!(f, "{}", self.message)
+        } else {
+            write!(f, "{}: {}", self.path, self.message)
+        }
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
+/// Validates a JSON value against a field type, resolving references through the AAT.
+///
+/// All problems are collected rather than stopping at the first one.
+pub fn validate(value: &Value, ty: &FieldType, aat: &AAT) -> Result<(), Vec<ValidationError>> {
+    let mut validator = Validator::new(aat);
+    validator.field_type(value, ty, None, "");
+    if validator.errors.is_empty() {
+        Ok(())
+    } else {
+        Err(validator.errors)
+    }
+}
+
+/// Validates a JSON value against a named type
+pub fn validate_named(
+    value: &Value,
+    named_type: &NamedType,
+    aat: &AAT,
+) -> Result<(), Vec<ValidationError>> {
+    let mut validator = Validator::new(aat);
+    validator.named_type(value, named_type, "");
+    if validator.errors.is_empty() {
+        Ok(())
+    } else {
+        Err(validator.errors)
+    }
+}
+
+struct Validator<'a> {
+    aat: &'a AAT,
+    patterns: HashMap<String, Option<Regex>>,
+    errors: Vec<ValidationError>,
+}
+
+impl<'a> Validator<'a> {
+    fn new(aat: &'a AAT) -> Self {
+        Self {
+            aat,
+            patterns: HashMap::new(),
+            errors: vec![],
+        }
+    }
+
+    fn error(&mut self, path: &str, message: impl Into<String>) {
+        self.errors.push(ValidationError {
+            path: path.to_string(),
+            message: message.into(),
+        });
+    }
+
+    /// Runs a nested validation and returns its errors without recording them
+    fn attempt(&mut self, f: impl FnOnce(&mut Self)) -> Vec<ValidationError> {
+        let saved = std::mem::take(&mut self.errors);
+        f(self);
+        std::mem::replace(&mut self.errors, saved)
+    }
+
+    fn field_type(
+        &mut self,
+        value: &Value,
+        ty: &FieldType,
+        constraints: Option<&Constraints>,
+        path: &str,
+    ) {
+        match ty {
+            FieldType::Primitive(prim) => self.primitive(value, prim, constraints, path),
+            FieldType::Literal(lit) => {
+                if !literal_matches(lit, value) {
+                    self.error(path, format!("Expected {}", describe_literal(lit)));
+                }
+            }
+            FieldType::Optional(inner) => {
+                if !value.is_null() {
+                    self.field_type(value, inner, constraints, path);
+                }
+            }
+            FieldType::List(inner) => {
+                let Some(items) = value.as_array() else {
+                    self.error(path, "Expected an array");
+                    return;
+                };
+                if let Some(c) = constraints {
+                    self.array_constraints(items, c, path);
+                }
+                for (i, item) in items.iter().enumerate() {
+                    self.field_type(item, inner, None, &format!("{}/{}", path, i));
+                }
+            }
+            FieldType::Map(inner) => {
+                let Some(map) = value.as_object() else {
+                    self.error(path, "Expected an object");
+                    return;
+                };
+                for (key, item) in map {
+                    self.field_type(item, inner, None, &child_path(path, key));
+                }
+            }
+            FieldType::Stream(inner) => self.field_type(value, inner, constraints, path),
+            FieldType::Reference(name) => {
+                let aat = self.aat;
+                match aat.types.iter().find(|t| get_type_name(t) == name) {
+                    Some(named_type) => self.named_type(value, named_type, path),
+                    None => self.error(path, format!("Reference to undefined type '{}'", name)),
+                }
+            }
+            FieldType::Intersection(types) => {
+                for t in types {
+                    self.field_type(value, t, None, path);
+                }
+            }
+            FieldType::Tuple(types) => {
+                let Some(items) = value.as_array() else {
+                    self.error(path, "Expected an array");
+                    return;
+                };
+                if items.len() != types.len() {
+                    self.error(
+                        path,
+                        format!(
+                            "Expected a tuple of {} items, got {}",
+                            types.len(),
+                            items.len()
+                        ),
+                    );
+                    return;
+                }
+                for (i, (item, t)) in items.iter().zip(types).enumerate() {
+                    self.field_type(item, t, None, &format!("{}/{}", path, i));
+                }
+            }
+            FieldType::Any => {}
+        }
+    }
+
+    fn named_type(&mut self, value: &Value, named_type: &NamedType, path: &str) {
+        match named_type {
+            NamedType::Object(obj) => {
+                let Some(map) = value.as_object() else {
+                    self.error(path, format!("Expected an object of type '{}'", obj.name));
+                    return;
+                };
+                self.object_fields(map, obj, path);
+            }
+            NamedType::Enum(enum_type) => {
+                if !enum_type
+                    .variants
+                    .iter()
+                    .any(|v| literal_matches(&v.value, value))
+                {
+                    let allowed = enum_type
+                        .variants
+                        .iter()
+                        .map(|v| describe_literal(&v.value))
+                        .collect::<Vec<_>>()
+                        .join(", ");
+                    self.error(
+                        path,
+                        format!(
+                            "Value is not a member of enum '{}' (expected one of {})",
+                            enum_type.name, allowed
+                        ),
+                    );
+                }
+            }
+            NamedType::Union(union) => self.union(value, union, path),
+        }
+    }
+
+    fn object_fields(&mut self, map: &Map<String, Value>, obj: &ObjectType, path: &str) {
+        for field in &obj.fields {
+            match map.get(&field.name) {
+                Some(field_value) => self.field_type(
+                    field_value,
+                    &field.r#type,
+                    field.constraints.as_ref(),
+                    &child_path(path, &field.name),
+                ),
+                None => {
+                    if !matches!(&*field.r#type, FieldType::Optional(_)) {
+                        self.error(path, format!("Missing required property '{}'", field.name));
+                    }
+                }
+            }
+        }
+    }
+
+    fn union(&mut self, value: &Value, union: &UnionType, path: &str) {
+        // Literal variants match by value
+        if union.variants.iter().any(|v| {
+            matches!(&*v.mode, UnionTypeVariantMode::Literal(lit) if literal_matches(lit, value))
+        }) {
+            return;
+        }
+
+        let Some(map) = value.as_object() else {
+            self.error(
+                path,
+                format!("Value does not match any variant of union '{}'", union.name),
+            );
+            return;
+        };
+
+        if let Some(discriminator) = &union.discriminator {
+            let Some(tag) = map
+                .get(&discriminator.property_name)
+                .and_then(|v| v.as_str())
+            else {
+                self.error(
+                    path,
+                    format!(
+                        "Missing discriminator property '{}'",
+                        discriminator.property_name
+                    ),
+                );
+                return;
+            };
+            let variant = union.variants.iter().find_map(|v| match &*v.mode {
+                UnionTypeVariantMode::Object(obj)
+                    if discriminator.tag_for(v.name.as_deref().unwrap_or(&obj.name)) == tag =>
+                {
+                    Some(obj)
+                }
+                _ => None,
+            });
+            match variant {
+                Some(obj) => {
+                    let fields = obj
+                        .fields
+                        .iter()
+                        .filter(|f| f.name != discriminator.property_name)
+                        .cloned()
+                        .collect();
+                    let obj = ObjectType {
+                        fields,
+                        ..obj.clone()
+                    };
+                    self.object_fields(map, &obj, path);
+                }
+                None => self.error(
+                    &child_path(path, &discriminator.property_name),
+                    format!(
+                        "Unknown discriminator value '{}' for union '{}'",
+                        tag, union.name
+                    ),
+                ),
+            }
+            return;
+        }
+
+        // Externally tagged variants are identified by their single key
+        for variant in &union.variants {
+            let UnionTypeVariantMode::Object(obj) = &*variant.mode else {
+                continue;
+            };
+            match &variant.name {
+                Some(name)
+                    if obj.fields.len() == 1
+                        && obj.fields[0].name == *name
+                        && map.len() == 1
+                        && map.contains_key(name) =>
+                {
+                    self.object_fields(map, obj, path);
+                    return;
+                }
+                Some(name) => {
+                    if map.len() == 1
+                        && let Some(inner) = map.get(name)
+                    {
+                        let inner_path = child_path(path, name);
+                        match inner.as_object() {
+                            Some(inner_map) => self.object_fields(inner_map, obj, &inner_path),
+                            None => self.error(&inner_path, "Expected an object"),
+                        }
+                        return;
+                    }
+                }
+                None => {}
+            }
+        }
+
+        // Untagged: accept the first variant that validates cleanly
+        for variant in &union.variants {
+            if let UnionTypeVariantMode::Object(obj) = &*variant.mode
+                && variant.name.is_none()
+                && self.attempt(|v| v.object_fields(map, obj, path)).is_empty()
+            {
+                return;
+            }
+        }
+
+        self.error(
+            path,
+            format!("Value does not match any variant of union '{}'", union.name),
+        );
+    }
+
+    fn primitive(
+        &mut self,
+        value: &Value,
+        prim: &PrimitiveType,
+        constraints: Option<&Constraints>,
+        path: &str,
+    ) {
+        match prim {
+            PrimitiveType::Bool => {
+                if !value.is_boolean() {
+                    self.error(path, "Expected a boolean");
+                }
+            }
+            PrimitiveType::Int(format) => {
+                let is_int = value.is_i64()
+                    || value.is_u64()
+                    || value.as_f64().is_some_and(|f| f.fract() == 0.0);
+                if !is_int {
+                    self.error(path, "Expected an integer");
+                    return;
+                }
+                if let Some(format) = format {
+                    let n = value
+                        .as_i64()
+                        .map(i128::from)
+                        .or_else(|| value.as_u64().map(i128::from))
+                        .or_else(|| value.as_f64().map(|f| f as i128))
+                        .unwrap_or_default();
+                    let (min, max) = format.range();
+                    if n < min || n > max {
+                        self.error(
+                            path,
+                            format!("Expected an integer between {} and {}", min, max),
+                        );
+                        return;
+                    }
+                }
+                if let (Some(c), Some(n)) = (constraints, value.as_f64()) {
+                    self.number_constraints(n, c, path);
+                }
+            }
+            PrimitiveType::Float => {
+                let Some(n) = value.as_f64() else {
+                    self.error(path, "Expected a number");
+                    return;
+                };
+                if let Some(c) = constraints {
+                    self.number_constraints(n, c, path);
+                }
+            }
+            PrimitiveType::String(format) => {
+                let Some(s) = value.as_str() else {
+                    self.error(path, "Expected a string");
+                    return;
+                };
+                if let Some(format) = format
+                    && !string_matches_format(s, format)
+                {
+                    self.error(
+                        path,
+                        format!("Expected a string in {} format", format_name(format)),
+                    );
+                }
+                if let Some(c) = constraints {
+                    self.string_constraints(s, c, path);
+                }
+            }
+        }
+    }
+
+    fn number_constraints(&mut self, n: f64, c: &Constraints, path: &str) {
+        if let Some(min) = c.minimum
+            && n < min
+        {
+            self.error(path, format!("Value {} must be >= {}", n, min));
+        }
+        if let Some(max) = c.maximum
+            && n > max
+        {
+            self.error(path, format!("Value {} must be <= {}", n, max));
+        }
+        if let Some(min) = c.exclusive_minimum
+            && n <= min
+        {
+            self.error(path, format!("Value {} must be > {}", n, min));
+        }
+        if let Some(max) = c.exclusive_maximum
+            && n >= max
+        {
+            self.error(path, format!("Value {} must be < {}", n, max));
+        }
+        if let Some(multiple) = c.multiple_of {
+            let quotient = n / multiple;
+            if (quotient - quotient.round()).abs() > 1e-9 {
+                self.error(
+                    path,
+                    format!("Value {} must be a multiple of {}", n, multiple),
+                );
+            }
+        }
+    }
+
+    fn string_constraints(&mut self, s: &str, c: &Constraints, path: &str) {
+        let len = s.chars().count();
+        if let Some(min) = c.min_length
+            && len < min
+        {
+            self.error(path, format!("String length {} must be >= {}", len, min));
+        }
+        if let Some(max) = c.max_length
+            && len > max
+        {
+            self.error(path, format!("String length {} must be <= {}", len, max));
+        }
+        if let Some(pattern) = &c.pattern {
+            let regex = self
+                .patterns
+                .entry(pattern.clone())
+                .or_insert_with(|| Regex::new(pattern).ok());
+            match regex {
+                Some(regex) => {
+                    if !regex.is_match(s) {
+                        self.error(path, format!("String does not match pattern '{}'", pattern));
+                    }
+                }
+                None => self.error(path, format!("Invalid pattern '{}' in schema", pattern)),
+            }
+        }
+    }
+
+    fn array_constraints(&mut self, items: &[Value], c: &Constraints, path: &str) {
+        if let Some(min) = c.min_items
+            && items.len() < min
+        {
+            self.error(
+                path,
+                format!(
+                    "Array must have at least {} items, got {}",
+                    min,
+                    items.len()
+                ),
+            );
+        }
+        if let Some(max) = c.max_items
+            && items.len() > max
+        {
+            self.error(
+                path,
+                format!("Array must have at most {} items, got {}", max, items.len()),
+            );
+        }
+        if c.unique_items == Some(true) {
+            for (i, item) in items.iter().enumerate() {
+                if items[..i].contains(item) {
+                    self.error(
+                        &format!("{}/{}", path, i),
+                        "Duplicate item in array with unique items",
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// Appends a key to a JSON pointer, escaping `~` and `/` as required by RFC 6901
+fn child_path(path: &str, key: &str) -> String {
+    format!("{}/{}", path, key.replace('~', "~0").replace('/', "~1"))
+}
+
+fn literal_matches(lit: &LiteralType, value: &Value) -> bool {
+    match (lit, value) {
+        (LiteralType::String(s), Value::String(v)) => s == v,
+        (LiteralType::Int(i), Value::Number(n)) => n.as_f64() == Some(*i as f64),
+        (LiteralType::Float(f), Value::Number(n)) => n.as_f64() == Some(*f),
+        (LiteralType::Bool(b), Value::Bool(v)) => b == v,
+        (LiteralType::Null, Value::Null) => true,
+        _ => false,
+    }
+}
+
+/// Renders a literal the way it appears in JSON
+pub(crate) fn describe_literal(lit: &LiteralType) -> String {
+    match lit {
+        LiteralType::String(s) => format!("\"{}\"", s),
+        LiteralType::Int(i) => i.to_string(),
+        LiteralType::Float(f) => f.to_string(),
+        LiteralType::Bool(b) => b.to_string(),
+        LiteralType::Null => "null".to_string(),
+    }
+}
+
+fn string_matches_format(s: &str, format: &StringFormat) -> bool {
+    match format {
+        StringFormat::DateTime => DATE_TIME_RE.is_match(s),
+        StringFormat::Date => DATE_RE.is_match(s),
+        StringFormat::Time => TIME_RE.is_match(s),
+        StringFormat::Uuid => UUID_RE.is_match(s),
+        StringFormat::Email => EMAIL_RE.is_match(s),
+        StringFormat::Uri => URI_RE.is_match(s),
+        StringFormat::Hostname => s.len() <= 253 && HOSTNAME_RE.is_match(s),
+        StringFormat::Ipv4 => s.parse::<std::net::Ipv4Addr>().is_ok(),
+        StringFormat::Ipv6 => s.parse::<std::net::Ipv6Addr>().is_ok(),
+    }
+}
+
+fn format_name(format: &StringFormat) -> &'static str {
+    match format {
+        StringFormat::DateTime => "date-time",
+        StringFormat::Date => "date",
+        StringFormat::Time => "time",
+        StringFormat::Uuid => "uuid",
+        StringFormat::Email => "email",
+        StringFormat::Uri => "uri",
+        StringFormat::Hostname => "hostname",
+        StringFormat::Ipv4 => "ipv4",
+        StringFormat::Ipv6 => "ipv6",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aat::examples::example_for_named_type;
+    use crate::fixtures::returning;
+    use crate::spec::Type;
+    use schemars::JsonSchema;
+    use serde_json::json;
+
+    #[derive(JsonSchema)]
+    #[allow(dead_code)]
+    struct Machine {
+        name: String,
+        cpus: u32,
+        offset: i8,
+        #[schemars(range(min = 1, max = 64))]
+        memory: u16,
+        created: Option<String>,
+        tags: Vec<String>,
+        state: State,
+    }
+
+    #[derive(JsonSchema)]
+    #[allow(dead_code)]
+    #[serde(rename_all = "snake_case")]
+    enum State {
+        Running { since: u64 },
+        Stopped { code: i32 },
+    }
+
+    fn aat() -> AAT {
+        returning(Type::schema::<Machine>())
+    }
+
+    fn machine() -> Value {
+        json!({
+            "name": "web",
+            "cpus": 2,
+            "offset": -3,
+            "memory": 8,
+            "tags": [],
+            "state": { "running": { "since": 1 } },
+        })
+    }
+
+    fn errors(value: &Value) -> Vec<String> {
+        let aat = aat();
+        match validate(value, &FieldType::Reference("Machine".into()), &aat) {
+            Ok(()) => vec![],
+            Err(errors) => errors.iter().map(|e| e.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn accepts_a_valid_value() {
+        assert_eq!(errors(&machine()), Vec::<String>::new());
+    }
+
+    #[test]
+    fn reports_every_problem_with_its_path() {
+        let mut value = machine();
+        value["name"] = json!(1);
+        value.as_object_mut().unwrap().remove("tags");
+        value["state"] = json!({ "paused": {} });
+        let errors = errors(&value);
+        assert_eq!(errors.len(), 3, "{:?}", errors);
+        assert!(errors.iter().any(|e| e.starts_with("/name")));
+    }
+
+    #[test]
+    fn checks_integer_ranges_of_formats() {
+        for (field, value) in [
+            ("cpus", json!(-1)),
+            ("cpus", json!(1u64 << 40)),
+            ("offset", json!(200)),
+            ("memory", json!(70_000)),
+        ] {
+            let mut machine = machine();
+            machine[field] = value.clone();
+            let errors = errors(&machine);
+            assert!(
+                errors
+                    .iter()
+                    .any(|e| e.contains("Expected an integer between")),
+                "{} = {}: {:?}",
+                field,
+                value,
+                errors
+            );
+        }
+    }
+
+    #[test]
+    fn generated_examples_validate() {
+        let aat = aat();
+        for named_type in &aat.types {
+            for seed in 0..20 {
+                let example = example_for_named_type(&aat, named_type, seed).unwrap();
+                let result = validate_named(&example, named_type, &aat);
+                assert!(result.is_ok(), "{}: {:?}", example, result);
+            }
+        }
+    }
+}
@@ -38,7 +38,7 @@ pub fn schema_to_type(schema: &Schema, name: &str) -> Result<NamedType> {
 fn schema_to_enum_type(
     name: &str,
     enum_values: &[Value],
-    _schema_obj: &Map<String, Value>,
+    schema_obj: &Map<String, Value>,
 ) -> Result<NamedType> {
     let variants: Result<Vec<EnumVariant>> = enum_values
         .iter()
@@ -54,6 +54,8 @@ fn schema_to_enum_type(
     Ok(NamedType::Enum(EnumType {
         name: name.to_string(),
         variants: variants?,
+        examples: extract_examples(Some(schema_obj)),
+        rust_path: extract_rust_path(schema_obj),
     }))
 }
 
@@ -96,6 +98,8 @@ fn schema_to_union_type(
         name: name.to_string(),
         discriminator,
         variants: all_variants,
+        examples: extract_examples(Some(schema_obj)),
+        rust_path: extract_rust_path(schema_obj),
     }))
 }
 
@@ -205,17 +209,21 @@ fn schema_to_object_type(name: &str, schema_obj: &Map<String, Value>) -> Result<
                             };
 
                             let constraints = extract_constraints_from_object(inner_field_value.as_object())?;
+                            let examples = extract_examples(inner_field_value.as_object());
 
                             fields.push(Field {
                                 name: inner_field_name.clone(),
                                 r#type: Box::new(field_type),
                                 constraints,
+                                examples,
                             });
                         }
 
                         return Ok(NamedType::Object(ObjectType {
                             name: field_name.clone(),
                             fields,
+                            examples: extract_examples(Some(field_obj)),
+                            rust_path: None,
                         }));
                     }
                 }
@@ -246,11 +254,13 @@ fn schema_to_object_type(name: &str, schema_obj: &Map<String, Value>) -> Result<
             };
 
             let constraints = extract_constraints_from_object(field_value.as_object())?;
+            let examples = extract_examples(field_value.as_object());
 
             fields.push(Field {
                 name: field_name.clone(),
                 r#type: Box::new(field_type),
                 constraints,
+                examples,
             });
         }
     }
@@ -258,6 +268,8 @@ fn schema_to_object_type(name: &str, schema_obj: &Map<String, Value>) -> Result<
     Ok(NamedType::Object(ObjectType {
         name: name.to_string(),
         fields,
+        examples: extract_examples(Some(schema_obj)),
+        rust_path: extract_rust_path(schema_obj),
     }))
 }
 
@@ -370,7 +382,13 @@ fn schema_object_to_field_type(obj: &Map<String, Value>) -> Result<FieldType> {
     let base_type = match type_str {
         Some("null") => FieldType::Literal(LiteralType::Null),
         Some("boolean") => FieldType::Primitive(PrimitiveType::Bool),
-        Some("integer") => FieldType::Primitive(PrimitiveType::Int),
+        Some("integer") => {
+            let format = obj
+                .get("format")
+                .and_then(|v| v.as_str())
+                .and_then(int_format_from_str);
+            FieldType::Primitive(PrimitiveType::Int(format))
+        }
         Some("number") => FieldType::Primitive(PrimitiveType::Float),
         Some("string") => {
             let format = obj
@@ -423,10 +441,13 @@ fn schema_object_to_field_type(obj: &Map<String, Value>) -> Result<FieldType> {
 
 fn extract_ref_name(reference: &str) -> Result<String> {
     // References typically look like "#/definitions/TypeName" or "#/$defs/TypeName"
+    // (or "#/components/schemas/TypeName" in OpenAPI documents)
     if let Some(name) = reference.strip_prefix("#/definitions/") {
         Ok(name.to_string())
     } else if let Some(name) = reference.strip_prefix("#/$defs/") {
         Ok(name.to_string())
+    } else if let Some(name) = reference.strip_prefix("#/components/schemas/") {
+        Ok(name.to_string())
     } else {
         bail!("Unsupported reference format: {}", reference)
     }
@@ -447,6 +468,24 @@ fn string_format_from_str(format: &str) -> Option<StringFormat> {
     }
 }
 
+fn int_format_from_str(format: &str) -> Option<IntFormat> {
+    match format {
+        "int8" => Some(IntFormat::Int8),
+        "int16" => Some(IntFormat::Int16),
+        "int32" => Some(IntFormat::Int32),
+        "int64" => Some(IntFormat::Int64),
+        "int128" => Some(IntFormat::Int128),
+        "int" => Some(IntFormat::Int),
+        "uint8" => Some(IntFormat::UInt8),
+        "uint16" => Some(IntFormat::UInt16),
+        "uint32" => Some(IntFormat::UInt32),
+        "uint64" => Some(IntFormat::UInt64),
+        "uint128" => Some(IntFormat::UInt128),
+        "uint" => Some(IntFormat::UInt),
+        _ => None,
+    }
+}
+
 fn json_value_to_literal(value: &serde_json::Value) -> Result<LiteralType> {
     match value {
         serde_json::Value::String(s) => Ok(LiteralType::String(s.clone())),
@@ -570,3 +609,24 @@ fn extract_constraints_from_object(
         Ok(None)
     }
 }
+
+/// Reads the JSON Schema `examples` keyword (and the older OpenAPI `example`)
+fn extract_examples(obj: Option<&Map<String, Value>>) -> Vec<Value> {
+    let obj = match obj {
+        Some(o) => o,
+        None => return vec![],
+    };
+
+    match (obj.get("examples"), obj.get("example")) {
+        (Some(Value::Array(examples)), _) => examples.clone(),
+        (_, Some(example)) => vec![example.clone()],
+        _ => vec![],
+    }
+}
+
+/// Reads the Rust path [`Type::schema`](crate::spec::Type::schema) records
+fn extract_rust_path(obj: &Map<String, Value>) -> Option<String> {
+    obj.get(crate::spec::RUST_PATH_KEY)
+        .and_then(|path| path.as_str())
+        .map(|path| path.to_string())
+}
@@ -1,14 +1,16 @@
 use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use std::collections::BTreeMap;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Service {
     pub name: String,
     pub endpoints: Vec<Endpoint>,
     pub headers: Vec<Header>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Endpoint {
     pub name: String,
     pub method: HttpMethod,
@@ -17,38 +19,94 @@ pub struct Endpoint {
     pub body: Option<FieldType>,
     pub response: FieldType,
     pub upgrade: Option<Upgrade>,
+    /// Header fallback for WebSocket clients that can't set headers
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ws_auth: Option<WsAuth>,
+    /// Services (`machines`) or endpoints (`machines.list`) whose cached results this endpoint
+    /// makes stale
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub invalidates: Vec<String>,
     pub headers: Vec<Header>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum Upgrade {
     Ws,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WsAuth {
+    /// Headers become query parameters of the same name
+    Query,
+    /// Headers are sent as a base64url JSON `Sec-WebSocket-Protocol` entry after this protocol
+    Protocol(String),
+    /// Headers are sent in a first `{"type":"auth","headers":{...}}` message
+    Message,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Header {
     pub name: String,
     pub value: HeaderValue,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum HeaderValue {
     /// A literal string value
     Literal(String),
     /// A dynamic value from a named parameter
-    Parameter {
+    Parameter { name: String, field_type: FieldType },
+    /// Literal text and parameters joined in order (e.g., "Bearer {token}")
+    Pattern { parts: Vec<HeaderPart> },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HeaderPart {
+    Literal(String),
+    Param {
         name: String,
         field_type: FieldType,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        format: Option<HeaderFormat>,
     },
-    /// A pattern with a placeholder (e.g., "Bearer {token}")
-    Pattern {
-        pattern: String,
-        param_name: String,
-        field_type: FieldType,
-    },
 }
 
-#[derive(Debug, Clone)]
+/// How a header parameter is written into its placeholder
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HeaderFormat {
+    /// Standard base64 of the value's UTF-8 text
+    Base64,
+    Lowercase,
+    /// ISO 8601, for `date` and `date-time` strings
+    IsoDate,
+}
+
+impl HeaderValue {
+    /// The parameters a header is built from, in declaration order
+    pub fn params(&self) -> Vec<(&str, &FieldType)> {
+        match self {
+            HeaderValue::Literal(_) => vec![],
+            HeaderValue::Parameter { name, field_type } => vec![(name.as_str(), field_type)],
+            HeaderValue::Pattern { parts } => parts
+                .iter()
+                .filter_map(|part| match part {
+                    HeaderPart::Param {
+                        name, field_type, ..
+                    } => Some((name.as_str(), field_type)),
+                    HeaderPart::Literal(_) => None,
+                })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum HttpMethod {
     Get,
     Post,
@@ -57,13 +115,22 @@ pub enum HttpMethod {
     Patch,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum PathSegment {
     Literal(String),
-    Parameter { name: String, r#type: FieldType },
+    Parameter {
+        name: String,
+        r#type: FieldType,
+    },
+    /// A trailing catch-all capturing the rest of the path as a string
+    Wildcard {
+        name: String,
+    },
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum FieldType {
     Primitive(PrimitiveType),
     Literal(LiteralType),
@@ -77,15 +144,116 @@ pub enum FieldType {
     Any,
 }
 
-#[derive(Debug, Clone)]
+impl FieldType {
+    /// Calls `visit` with the name of every type this one refers to, however deeply nested
+    pub fn visit_references(&self, visit: &mut impl FnMut(&str)) {
+        match self {
+            FieldType::Reference(name) => visit(name),
+            FieldType::Optional(inner)
+            | FieldType::List(inner)
+            | FieldType::Map(inner)
+            | FieldType::Stream(inner) => inner.visit_references(visit),
+            FieldType::Intersection(types) | FieldType::Tuple(types) => {
+                for t in types {
+                    t.visit_references(visit);
+                }
+            }
+            FieldType::Primitive(_) | FieldType::Literal(_) | FieldType::Any => {}
+        }
+    }
+
+    /// [`FieldType::visit_references`] with the names open to renaming
+    pub fn visit_references_mut(&mut self, visit: &mut impl FnMut(&mut String)) {
+        match self {
+            FieldType::Reference(name) => visit(name),
+            FieldType::Optional(inner)
+            | FieldType::List(inner)
+            | FieldType::Map(inner)
+            | FieldType::Stream(inner) => inner.visit_references_mut(visit),
+            FieldType::Intersection(types) | FieldType::Tuple(types) => {
+                for t in types {
+                    t.visit_references_mut(visit);
+                }
+            }
+            FieldType::Primitive(_) | FieldType::Literal(_) | FieldType::Any => {}
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum PrimitiveType {
     Bool,
-    Int,
+    Int(Option<IntFormat>),
     Float,
     String(Option<StringFormat>),
 }
 
-#[derive(Debug, Clone, PartialEq)]
+/// Width and signedness of an integer, taken from the schema `format` (`int32`, `uint64`, ...)
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IntFormat {
+    Int8,
+    Int16,
+    Int32,
+    Int64,
+    Int128,
+    /// Pointer-sized signed integer (`isize`)
+    Int,
+    UInt8,
+    UInt16,
+    UInt32,
+    UInt64,
+    UInt128,
+    /// Pointer-sized unsigned integer (`usize`)
+    UInt,
+}
+
+impl IntFormat {
+    /// Width in bits, or `None` for pointer-sized integers
+    pub fn bits(&self) -> Option<u32> {
+        match self {
+            IntFormat::Int8 | IntFormat::UInt8 => Some(8),
+            IntFormat::Int16 | IntFormat::UInt16 => Some(16),
+            IntFormat::Int32 | IntFormat::UInt32 => Some(32),
+            IntFormat::Int64 | IntFormat::UInt64 => Some(64),
+            IntFormat::Int128 | IntFormat::UInt128 => Some(128),
+            IntFormat::Int | IntFormat::UInt => None,
+        }
+    }
+
+    pub fn is_signed(&self) -> bool {
+        matches!(
+            self,
+            IntFormat::Int8
+                | IntFormat::Int16
+                | IntFormat::Int32
+                | IntFormat::Int64
+                | IntFormat::Int128
+                | IntFormat::Int
+        )
+    }
+
+    /// Smallest and largest value, taking pointer-sized integers as 64 bits. `UInt128` is capped
+    /// at `i128::MAX`.
+    pub fn range(&self) -> (i128, i128) {
+        let bits = self.bits().unwrap_or(64);
+        match (self.is_signed(), bits) {
+            (true, 128) => (i128::MIN, i128::MAX),
+            (true, bits) => (-(1 << (bits - 1)), (1 << (bits - 1)) - 1),
+            (false, 128) => (0, i128::MAX),
+            (false, bits) => (0, (1 << bits) - 1),
+        }
+    }
+
+    /// Whether values may not fit in an IEEE 754 double without losing precision
+    pub fn exceeds_f64_precision(&self) -> bool {
+        self.bits().is_some_and(|bits| bits > 53)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
 pub enum StringFormat {
     DateTime,
     Date,
@@ -98,7 +266,8 @@ pub enum StringFormat {
     Ipv6,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum LiteralType {
     String(String),
     Int(i64),
@@ -107,21 +276,69 @@ pub enum LiteralType {
     Null,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum NamedType {
     Object(ObjectType),
     Union(UnionType),
     Enum(EnumType),
 }
 
-#[derive(Debug, Clone)]
+impl NamedType {
+    /// Calls `visit` with the name of every type referred to by the fields of an object or of
+    /// its union variants
+    pub fn visit_references(&self, visit: &mut impl FnMut(&str)) {
+        let fields: Vec<&Field> = match self {
+            NamedType::Object(obj) => obj.fields.iter().collect(),
+            NamedType::Union(union) => union
+                .variants
+                .iter()
+                .filter_map(|variant| match &*variant.mode {
+                    UnionTypeVariantMode::Object(obj) => Some(&obj.fields),
+                    UnionTypeVariantMode::Literal(_) => None,
+                })
+                .flatten()
+                .collect(),
+            NamedType::Enum(_) => vec![],
+        };
+        for field in fields {
+            field.r#type.visit_references(visit);
+        }
+    }
+
+    /// [`NamedType::visit_references`] with the names open to renaming. The type's own name is
+    /// not visited.
+    pub fn visit_references_mut(&mut self, visit: &mut impl FnMut(&mut String)) {
+        let fields: Vec<&mut Field> = match self {
+            NamedType::Object(obj) => obj.fields.iter_mut().collect(),
+            NamedType::Union(union) => union
+                .variants
+                .iter_mut()
+                .filter_map(|variant| match &mut *variant.mode {
+                    UnionTypeVariantMode::Object(obj) => Some(&mut obj.fields),
+                    UnionTypeVariantMode::Literal(_) => None,
+                })
+                .flatten()
+                .collect(),
+            NamedType::Enum(_) => vec![],
+        };
+        for field in fields {
+            field.r#type.visit_references_mut(visit);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Field {
     pub name: String,
     pub r#type: Box<FieldType>,
     pub constraints: Option<Constraints>,
+    /// Example values declared through the schema (`examples`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub examples: Vec<Value>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Constraints {
     // Numeric constraints (minimum and exclusive_minimum are mutually exclusive)
     pub minimum: Option<f64>,
@@ -216,44 +433,76 @@ impl Constraints {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ObjectType {
     pub name: String,
     pub fields: Vec<Field>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub examples: Vec<Value>,
+    /// Rust path of the type the schema was generated from, e.g. `my_crate::v2::Machine`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rust_path: Option<String>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UnionType {
     pub name: String,
     pub discriminator: Option<Discriminator>,
     pub variants: Vec<UnionTypeVariant>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub examples: Vec<Value>,
+    /// Rust path of the type the schema was generated from, e.g. `my_crate::v2::Machine`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rust_path: Option<String>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Discriminator {
     pub property_name: String,
     pub mapping: Option<BTreeMap<String, String>>,
 }
 
-#[derive(Debug, Clone)]
+impl Discriminator {
+    /// The wire value of the discriminator property for the variant with the given name.
+    /// Mapping targets may be plain names or schema references (`#/$defs/Name`).
+    pub fn tag_for(&self, variant_name: &str) -> String {
+        self.mapping
+            .as_ref()
+            .and_then(|mapping| {
+                mapping
+                    .iter()
+                    .find(|(_, target)| target.rsplit('/').next() == Some(variant_name))
+                    .map(|(key, _)| key.clone())
+            })
+            .unwrap_or_else(|| variant_name.to_string())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UnionTypeVariant {
     pub name: Option<String>,
     pub mode: Box<UnionTypeVariantMode>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum UnionTypeVariantMode {
     Object(ObjectType),
     Literal(LiteralType),
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EnumType {
     pub name: String,
     pub variants: Vec<EnumVariant>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub examples: Vec<Value>,
+    /// Rust path of the type the schema was generated from, e.g. `my_crate::v2::Machine`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rust_path: Option<String>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EnumVariant {
     pub value: LiteralType,
     pub description: Option<String>,
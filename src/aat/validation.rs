@@ -1,62 +1,102 @@
+use super::diff::method_name;
+use super::report::{IssueKind, ValidationReport};
 use super::types::*;
 use anyhow::{Result, bail};
+use std::collections::HashSet;
 
-/// Validates that all type references in the AAT resolve to actual types
-pub fn validate_references(services: &[Service], types: &[NamedType]) -> Result<()> {
+/// Reports every type reference in the AAT that doesn't resolve, wildcards that aren't last and
+/// path parameters that can't be written into a URL
+pub fn validate_references(
+    headers: &[Header],
+    services: &[Service],
+    types: &[NamedType],
+    report: &mut ValidationReport,
+) {
     // Collect all type names for quick lookup
-    let valid_type_names: std::collections::HashSet<_> =
-        types.iter().map(|t| get_type_name(t)).collect();
+    let valid_type_names: HashSet<_> = types.iter().map(|t| get_type_name(t)).collect();
 
-    // Check all references in services/endpoints
+    validate_header_references(headers, "headers", &valid_type_names, report);
     for service in services {
+        let service_location = format!("services.{}", service.name);
+        validate_header_references(
+            &service.headers,
+            &format!("{}.headers", service_location),
+            &valid_type_names,
+            report,
+        );
+
         for endpoint in &service.endpoints {
+            let location = format!("{}.endpoints.{}", service_location, endpoint.name);
+
+            // A wildcard captures the rest of the path, so nothing can follow it
+            let leading = &endpoint.path[..endpoint.path.len().saturating_sub(1)];
+            for segment in leading {
+                if let PathSegment::Wildcard { name } = segment {
+                    report.error(
+                        format!("{}.path.{}", location, name),
+                        IssueKind::WildcardNotLast(name.clone()),
+                    );
+                }
+            }
+
             // Check path parameter types
             for segment in &endpoint.path {
                 if let PathSegment::Parameter { name, r#type } = segment {
-                    validate_field_type_references(r#type, &valid_type_names).map_err(|e| {
-                        anyhow::anyhow!(
-                            "Invalid reference in path parameter '{}' of endpoint '{}': {}",
-                            name,
-                            endpoint.name,
-                            e
-                        )
-                    })?;
+                    let param_location = format!("{}.path.{}", location, name);
+                    validate_field_type_references(
+                        r#type,
+                        &valid_type_names,
+                        &param_location,
+                        report,
+                    );
 
                     // Validate that path parameter is string-serializable
-                    validate_path_parameter_is_stringifiable(r#type, &endpoint.name, name, types)?;
+                    if let Err(reason) = path_parameter_is_stringifiable(r#type, types) {
+                        report.error(
+                            param_location,
+                            IssueKind::InvalidPathParameter {
+                                name: name.clone(),
+                                reason,
+                            },
+                        );
+                    }
                 }
             }
 
-            // Check query type
+            validate_header_references(
+                &endpoint.headers,
+                &format!("{}.headers", location),
+                &valid_type_names,
+                report,
+            );
+
             if let Some(query_type) = &endpoint.query {
-                validate_field_type_references(query_type, &valid_type_names).map_err(|e| {
-                    anyhow::anyhow!(
-                        "Invalid reference in query of endpoint '{}': {}",
-                        endpoint.name,
-                        e
-                    )
-                })?;
+                let query_location = format!("{}.query", location);
+                validate_field_type_references(
+                    query_type,
+                    &valid_type_names,
+                    &query_location,
+                    report,
+                );
             }
 
-            // Check body type
             if let Some(body_type) = &endpoint.body {
-                validate_field_type_references(body_type, &valid_type_names).map_err(|e| {
-                    anyhow::anyhow!(
-                        "Invalid reference in body of endpoint '{}': {}",
-                        endpoint.name,
-                        e
-                    )
-                })?;
+                let body_location = format!("{}.body", location);
+                validate_field_type_references(
+                    body_type,
+                    &valid_type_names,
+                    &body_location,
+                    report,
+                );
             }
 
-            // Check response type
-            validate_field_type_references(&endpoint.response, &valid_type_names).map_err(|e| {
-                anyhow::anyhow!(
-                    "Invalid reference in response of endpoint '{}': {}",
-                    endpoint.name,
-                    e
-                )
-            })?;
+            let response_location = format!("{}.response", location);
+            validate_field_type_references(
+                &endpoint.response,
+                &valid_type_names,
+                &response_location,
+                report,
+            );
         }
     }
 
@@ -65,27 +105,31 @@ pub fn validate_references(services: &[Service], types: &[NamedType]) -> Result<
         match named_type {
             NamedType::Object(obj) => {
                 for field in &obj.fields {
-                    validate_field_type_references(&field.r#type, &valid_type_names).map_err(
-                        |e| {
-                            anyhow::anyhow!(
-                                "Invalid reference in field '{}' of type '{}': {}",
-                                field.name,
-                                obj.name,
-                                e
-                            )
-                        },
-                    )?;
+                    let location = format!("types.{}.fields.{}", obj.name, field.name);
+                    validate_field_type_references(
+                        &field.r#type,
+                        &valid_type_names,
+                        &location,
+                        report,
+                    );
                 }
             }
             NamedType::Union(union) => {
                 for variant in &union.variants {
                     if let UnionTypeVariantMode::Object(obj) = &*variant.mode {
                         for field in &obj.fields {
-                            validate_field_type_references(&field.r#type, &valid_type_names)
-                                .map_err(|e| anyhow::anyhow!(
-                                    "Invalid reference in field '{}' of union variant '{}' in type '{}': {}",
-                                    field.name, variant.name.as_deref().unwrap_or("unnamed"), union.name, e
-                                ))?;
+                            let location = format!(
+                                "types.{}.variants.{}.fields.{}",
+                                union.name,
+                                variant.name.as_deref().unwrap_or("unnamed"),
+                                field.name
+                            );
+                            validate_field_type_references(
+                                &field.r#type,
+                                &valid_type_names,
+                                &location,
+                                report,
+                            );
                         }
                     }
                 }
@@ -95,41 +139,198 @@ pub fn validate_references(services: &[Service], types: &[NamedType]) -> Result<
             }
         }
     }
+}
 
-    Ok(())
+fn validate_header_references(
+    headers: &[Header],
+    location: &str,
+    valid_type_names: &HashSet<&str>,
+    report: &mut ValidationReport,
+) {
+    for header in headers {
+        let location = format!("{}.{}", location, header.name);
+        for (_, field_type) in header.value.params() {
+            validate_field_type_references(field_type, valid_type_names, &location, report);
+        }
+    }
 }
 
-fn validate_field_type_references(
-    field_type: &FieldType,
-    valid_type_names: &std::collections::HashSet<&str>,
-) -> Result<()> {
-    match field_type {
-        FieldType::Reference(ref_name) => {
-            if !valid_type_names.contains(ref_name.as_str()) {
-                bail!("Reference to undefined type '{}'", ref_name);
+/// Reports every `invalidates` target that names no existing service or `service.endpoint`
+pub fn validate_invalidations(services: &[Service], report: &mut ValidationReport) {
+    for service in services {
+        for endpoint in &service.endpoints {
+            for target in &endpoint.invalidates {
+                let (service_name, endpoint_name) = match target.split_once('.') {
+                    Some((service_name, endpoint_name)) => (service_name, Some(endpoint_name)),
+                    None => (target.as_str(), None),
+                };
+                let found = services
+                    .iter()
+                    .find(|s| s.name == service_name)
+                    .is_some_and(|s| {
+                        endpoint_name.is_none_or(|name| s.endpoints.iter().any(|e| e.name == name))
+                    });
+                if !found {
+                    report.error(
+                        format!(
+                            "services.{}.endpoints.{}.invalidates",
+                            service.name, endpoint.name
+                        ),
+                        IssueKind::UnknownInvalidation(target.clone()),
+                    );
+                }
             }
-            Ok(())
-        }
-        FieldType::Optional(inner) | FieldType::List(inner) | FieldType::Map(inner) | FieldType::Stream(inner) => {
-            validate_field_type_references(inner, valid_type_names)
         }
-        FieldType::Intersection(types) => {
-            for t in types {
-                validate_field_type_references(t, valid_type_names)?;
+    }
+}
+
+/// Reports endpoint names used twice in a service, path parameters named twice in a path, and
+/// routes of any two endpoints that match the same requests
+pub fn validate_routes(services: &[Service], report: &mut ValidationReport) {
+    let mut routes: Vec<(String, &Endpoint, Vec<RouteSegment>)> = Vec::new();
+    for service in services {
+        let mut names = HashSet::new();
+        for endpoint in &service.endpoints {
+            let location = format!("services.{}.endpoints.{}", service.name, endpoint.name);
+            if !names.insert(endpoint.name.as_str()) {
+                report.error(
+                    location.clone(),
+                    IssueKind::DuplicateEndpoint(endpoint.name.clone()),
+                );
+            }
+
+            let mut params = HashSet::new();
+            for segment in &endpoint.path {
+                let name = match segment {
+                    PathSegment::Parameter { name, .. } | PathSegment::Wildcard { name } => name,
+                    PathSegment::Literal(_) => continue,
+                };
+                if !params.insert(name.as_str()) {
+                    report.error(
+                        format!("{}.path.{}", location, name),
+                        IssueKind::DuplicatePathParameter(name.clone()),
+                    );
+                }
             }
-            Ok(())
+
+            let segments = route_segments(&endpoint.path);
+            for (other_name, other, other_segments) in &routes {
+                if other.method != endpoint.method {
+                    continue;
+                }
+                let kind = match route_overlap(&segments, other_segments) {
+                    Some(RouteOverlap::Identical) => IssueKind::RouteConflict {
+                        route: route_string(endpoint),
+                        endpoint: other_name.clone(),
+                    },
+                    Some(RouteOverlap::Partial) => IssueKind::RouteShadowed {
+                        route: route_string(endpoint),
+                        other: route_string(other),
+                        endpoint: other_name.clone(),
+                    },
+                    None => continue,
+                };
+                let location = format!("{}.path", location);
+                match kind {
+                    IssueKind::RouteConflict { .. } => report.error(location, kind),
+                    _ => report.warning(location, kind),
+                }
+            }
+            routes.push((
+                format!("{}.{}", service.name, endpoint.name),
+                endpoint,
+                segments,
+            ));
         }
-        FieldType::Tuple(types) => {
-            for t in types {
-                validate_field_type_references(t, valid_type_names)?;
+    }
+}
+
+/// A path segment as far as request matching is concerned
+#[derive(PartialEq)]
+enum RouteSegment<'a> {
+    Literal(&'a str),
+    Parameter,
+    Wildcard,
+}
+
+enum RouteOverlap {
+    /// The routes match exactly the same requests
+    Identical,
+    /// Some requests match both, e.g. `/machines/search` and `/machines/{name}`
+    Partial,
+}
+
+/// The segments a request path is matched against; literals may span several of them
+fn route_segments(path: &[PathSegment]) -> Vec<RouteSegment<'_>> {
+    let mut segments = Vec::new();
+    for segment in path {
+        match segment {
+            PathSegment::Literal(literal) => segments.extend(
+                literal
+                    .split('/')
+                    .filter(|part| !part.is_empty())
+                    .map(RouteSegment::Literal),
+            ),
+            PathSegment::Parameter { .. } => segments.push(RouteSegment::Parameter),
+            PathSegment::Wildcard { .. } => segments.push(RouteSegment::Wildcard),
+        }
+    }
+    segments
+}
+
+fn route_overlap(a: &[RouteSegment], b: &[RouteSegment]) -> Option<RouteOverlap> {
+    let mut identical = true;
+    for (a, b) in a.iter().zip(b) {
+        match (a, b) {
+            (RouteSegment::Wildcard, RouteSegment::Wildcard) if identical => {
+                return Some(RouteOverlap::Identical);
             }
-            Ok(())
+            // A wildcard matches whatever the other route has left
+            (RouteSegment::Wildcard, _) | (_, RouteSegment::Wildcard) => {
+                return Some(RouteOverlap::Partial);
+            }
+            (RouteSegment::Literal(a), RouteSegment::Literal(b)) if a != b => return None,
+            (a, b) => identical &= a == b,
         }
-        FieldType::Primitive(_) | FieldType::Literal(_) | FieldType::Any => {
-            // These don't contain references
-            Ok(())
+    }
+    if a.len() != b.len() {
+        None
+    } else if identical {
+        Some(RouteOverlap::Identical)
+    } else {
+        Some(RouteOverlap::Partial)
+    }
+}
+
+/// `GET /machines/{name}`
+fn route_string(endpoint: &Endpoint) -> String {
+    let mut path = String::new();
+    for segment in &endpoint.path {
+        match segment {
+            PathSegment::Literal(literal) => {
+                path.push_str(&format!("/{}", literal.trim_matches('/')))
+            }
+            PathSegment::Parameter { name, .. } => path.push_str(&format!("/{{{}}}", name)),
+            PathSegment::Wildcard { name } => path.push_str(&format!("/{{*{}}}", name)),
         }
     }
+    if path.is_empty() {
+        path.push('/');
+    }
+    format!("{} {}", method_name(&endpoint.method), path)
+}
+
+fn validate_field_type_references(
+    field_type: &FieldType,
+    valid_type_names: &HashSet<&str>,
+    location: &str,
+    report: &mut ValidationReport,
+) {
+    field_type.visit_references(&mut |name| {
+        if !valid_type_names.contains(name) {
+            report.error(location, IssueKind::UndefinedType(name.to_string()));
+        }
+    });
 }
 
 fn get_type_name(named_type: &NamedType) -> &str {
@@ -164,114 +365,176 @@ pub fn validate_path_parameter_type(r#type: &crate::spec::Type) -> Result<()> {
     }
 }
 
-/// Validates that a path parameter type can be serialized to a string
-fn validate_path_parameter_is_stringifiable(
+/// Checks that a path parameter type can be serialized to a string, explaining why not otherwise.
+/// References to undefined types pass, since they are reported on their own.
+fn path_parameter_is_stringifiable(
     field_type: &FieldType,
-    endpoint_name: &str,
-    param_name: &str,
     types: &[NamedType],
-) -> Result<()> {
+) -> std::result::Result<(), String> {
+    const ALLOWED: &str = "Path parameters must be primitives or string enums.";
     match field_type {
         FieldType::Primitive(PrimitiveType::String(_)) => Ok(()),
-        FieldType::Primitive(PrimitiveType::Int) => Ok(()),
+        FieldType::Primitive(PrimitiveType::Int(_)) => Ok(()),
         FieldType::Primitive(PrimitiveType::Float) => Ok(()),
         FieldType::Primitive(PrimitiveType::Bool) => Ok(()),
         FieldType::Literal(_) => Ok(()),
         FieldType::Reference(type_name) => {
             // Look up the referenced type
-            let named_type = types
-                .iter()
-                .find(|t| get_type_name(t) == type_name)
-                .ok_or_else(|| {
-                    anyhow::anyhow!(
-                        "Path parameter '{}' in endpoint '{}' references undefined type '{}'",
-                        param_name,
-                        endpoint_name,
-                        type_name
-                    )
-                })?;
+            let Some(named_type) = types.iter().find(|t| get_type_name(t) == type_name) else {
+                return Ok(());
+            };
 
             match named_type {
                 NamedType::Enum(enum_type) => {
                     // Validate all enum variants are string literals
-                    for variant in &enum_type.variants {
-                        if !matches!(variant.value, LiteralType::String(_)) {
-                            bail!(
-                                "Path parameter '{}' in endpoint '{}' references enum '{}' which has non-string variant. Only string enums are allowed in path parameters.",
-                                param_name,
-                                endpoint_name,
-                                type_name
-                            );
-                        }
+                    if enum_type
+                        .variants
+                        .iter()
+                        .any(|variant| !matches!(variant.value, LiteralType::String(_)))
+                    {
+                        return Err(format!(
+                            "references enum '{}' which has non-string variant. Only string enums are allowed in path parameters.",
+                            type_name
+                        ));
                     }
                     Ok(())
                 }
-                NamedType::Object(_) => {
-                    bail!(
-                        "Path parameter '{}' in endpoint '{}' cannot be an object type '{}'. Path parameters must be primitives or string enums.",
-                        param_name,
-                        endpoint_name,
-                        type_name
-                    )
-                }
-                NamedType::Union(_) => {
-                    bail!(
-                        "Path parameter '{}' in endpoint '{}' cannot be a union type '{}'. Path parameters must be primitives or string enums.",
-                        param_name,
-                        endpoint_name,
-                        type_name
-                    )
-                }
+                NamedType::Object(_) => Err(format!(
+                    "cannot be an object type '{}'. {}",
+                    type_name, ALLOWED
+                )),
+                NamedType::Union(_) => Err(format!(
+                    "cannot be a union type '{}'. {}",
+                    type_name, ALLOWED
+                )),
             }
         }
         FieldType::Optional(_) => {
-            bail!(
-                "Path parameter '{}' in endpoint '{}' cannot be optional. Path parameters are always required.",
-                param_name,
-                endpoint_name
-            )
-        }
-        FieldType::List(_) => {
-            bail!(
-                "Path parameter '{}' in endpoint '{}' cannot be a list. Use query parameters for arrays.",
-                param_name,
-                endpoint_name
-            )
-        }
-        FieldType::Map(_) => {
-            bail!(
-                "Path parameter '{}' in endpoint '{}' cannot be a map. Path parameters must be primitives or string enums.",
-                param_name,
-                endpoint_name
-            )
-        }
-        FieldType::Intersection(_) => {
-            bail!(
-                "Path parameter '{}' in endpoint '{}' cannot be an intersection type. Path parameters must be primitives or string enums.",
-                param_name,
-                endpoint_name
-            )
-        }
-        FieldType::Tuple(_) => {
-            bail!(
-                "Path parameter '{}' in endpoint '{}' cannot be a tuple type. Path parameters must be primitives or string enums.",
-                param_name,
-                endpoint_name
-            )
+            Err("cannot be optional. Path parameters are always required.".to_string())
         }
-        FieldType::Stream(_) => {
-            bail!(
-                "Path parameter '{}' in endpoint '{}' cannot be a stream type. Path parameters must be primitives or string enums.",
-                param_name,
-                endpoint_name
-            )
+        FieldType::List(_) => Err("cannot be a list. Use query parameters for arrays.".to_string()),
+        FieldType::Map(_) => Err(format!("cannot be a map. {}", ALLOWED)),
+        FieldType::Intersection(_) => Err(format!("cannot be an intersection type. {}", ALLOWED)),
+        FieldType::Tuple(_) => Err(format!("cannot be a tuple type. {}", ALLOWED)),
+        FieldType::Stream(_) => Err(format!("cannot be a stream type. {}", ALLOWED)),
+        FieldType::Any => Err(format!("cannot be 'any' type. {}", ALLOWED)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aat::AAT;
+    use crate::spec::{self, Spec, Type};
+
+    fn literal(value: &str) -> PathSegment {
+        PathSegment::Literal(value.into())
+    }
+
+    fn parameter(name: &str) -> PathSegment {
+        PathSegment::Parameter {
+            name: name.into(),
+            r#type: FieldType::Primitive(PrimitiveType::String(None)),
         }
-        FieldType::Any => {
-            bail!(
-                "Path parameter '{}' in endpoint '{}' cannot be 'any' type. Path parameters must be primitives or string enums.",
-                param_name,
-                endpoint_name
+    }
+
+    fn wildcard(name: &str) -> PathSegment {
+        PathSegment::Wildcard { name: name.into() }
+    }
+
+    fn overlap(a: &[PathSegment], b: &[PathSegment]) -> Option<RouteOverlap> {
+        route_overlap(&route_segments(a), &route_segments(b))
+    }
+
+    #[test]
+    fn wildcards_overlap_everything_below_them() {
+        let files = [literal("files"), wildcard("rest")];
+        assert!(matches!(
+            overlap(&files, &[literal("files/readme")]),
+            Some(RouteOverlap::Partial)
+        ));
+        assert!(matches!(
+            overlap(&[literal("files/readme/raw")], &files),
+            Some(RouteOverlap::Partial)
+        ));
+        assert!(matches!(
+            overlap(&files, &[literal("files"), wildcard("path")]),
+            Some(RouteOverlap::Identical)
+        ));
+        // A wildcard captures at least one segment
+        assert!(overlap(&files, &[literal("files")]).is_none());
+        assert!(overlap(&files, &[literal("images/logo")]).is_none());
+    }
+
+    #[test]
+    fn parameters_overlap_literals() {
+        let machine = [literal("machines"), parameter("id")];
+        assert!(matches!(
+            overlap(&machine, &[literal("machines/search")]),
+            Some(RouteOverlap::Partial)
+        ));
+        assert!(matches!(
+            overlap(&machine, &[literal("machines"), parameter("name")]),
+            Some(RouteOverlap::Identical)
+        ));
+        assert!(overlap(&machine, &[literal("machines/search/all")]).is_none());
+        assert!(overlap(&machine, &[literal("volumes/search")]).is_none());
+    }
+
+    fn codes(spec: Spec) -> Vec<(String, &'static str, String)> {
+        AAT::from_spec(&spec)
+            .unwrap()
+            .validation_report()
+            .issues
+            .into_iter()
+            .map(|issue| {
+                (
+                    issue.severity.to_string(),
+                    issue.kind.code(),
+                    issue.location,
+                )
+            })
+            .collect()
+    }
+
+    /// A literal followed by string parameters
+    fn route(literal: &str, parameters: &[&str]) -> Vec<spec::PathSegment> {
+        let mut path = vec![spec::PathSegment::Literal(literal.into())];
+        path.extend(parameters.iter().map(|name| spec::PathSegment::Type {
+            name: name.to_string(),
+            r#type: Type::schema::<String>(),
+        }));
+        path
+    }
+
+    #[test]
+    fn reports_duplicates_and_conflicting_routes() {
+        let spec = Spec::new("test").service("machines", |service| {
+            service
+                .get("get", route("machines", &["id"]), |e| e)
+                .get("search", route("machines/search", &[]), |e| e)
+                .get("find", route("machines", &["name"]), |e| e)
+                .get("get", route("other", &[]), |e| e)
+                .get("pair", route("pairs", &["id", "id"]), |e| e)
+                .delete("remove", route("machines", &["id"]), |e| e)
+        });
+        // Endpoints are checked in name order: find, get, get, pair, remove, search
+        let issue = |severity: &str, code, location: &str| {
+            (
+                severity.to_string(),
+                code,
+                format!("services.machines.endpoints.{}", location),
             )
-        }
+        };
+        assert_eq!(
+            codes(spec),
+            [
+                issue("error", "route-conflict", "get.path"),
+                issue("error", "duplicate-endpoint", "get"),
+                issue("error", "duplicate-path-parameter", "pair.path.id"),
+                issue("warning", "route-shadowed", "search.path"),
+                issue("warning", "route-shadowed", "search.path"),
+            ]
+        );
     }
 }
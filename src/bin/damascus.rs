@@ -0,0 +1,270 @@
+//! `damascus` command-line tool
+//!
+//! Exit codes: `0` on success, `1` when a check fails (breaking changes, invalid AAT),
+//! `2` on usage or I/O errors.
+
+use anyhow::{Context, Result};
+use clap::{Parser, Subcommand, ValueEnum};
+use damascus::aat::AAT;
+use damascus::aat::diff::diff;
+use damascus::generate::GeneratorRegistry;
+use serde_json::{Map, Value};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+#[derive(Parser)]
+#[command(
+    name = "damascus",
+    version,
+    about = "Generate clients from Damascus API specifications"
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Generate a client or server from a serialized AAT (`*.aat.json`)
+    Generate {
+        /// Generator to run
+        #[arg(long, value_enum)]
+        lang: Lang,
+        /// AAT file written with `AAT::to_json`
+        #[arg(long = "in", value_name = "FILE")]
+        input: PathBuf,
+        /// Output directory
+        #[arg(long, value_name = "DIR")]
+        out: PathBuf,
+        /// Generator options as a JSON object
+        #[arg(long, value_name = "JSON")]
+        options: Option<String>,
+        /// Only generate these comma-separated services, dropping types they don't use
+        #[arg(long, value_name = "NAMES", value_delimiter = ',')]
+        services: Vec<String>,
+        /// Emit runtime validators (TypeScript)
+        #[arg(long)]
+        validators: bool,
+        /// Emit Zod schemas (TypeScript)
+        #[arg(long, value_enum)]
+        zod: Option<Zod>,
+        /// Map `date-time` and `date` strings to `Date` (TypeScript)
+        #[arg(long)]
+        dates: bool,
+        /// Map 64-bit and wider integers to `bigint` (TypeScript)
+        #[arg(long)]
+        bigints: bool,
+        /// Write a tree of modules with an `index.ts` barrel instead of one file (TypeScript)
+        #[arg(long)]
+        modules: bool,
+        /// Also write `package.json` and `tsconfig` files for an npm package of this name (TypeScript)
+        #[arg(long, value_name = "NAME")]
+        package: Option<String>,
+        /// Also write `hooks.ts` with TanStack Query hooks (TypeScript)
+        #[arg(long)]
+        react_query: bool,
+    },
+    /// Convert an OpenAPI 3.x JSON document to an AAT
+    Import {
+        /// OpenAPI document
+        file: PathBuf,
+        /// Output file (defaults to stdout)
+        #[arg(long, value_name = "FILE")]
+        out: Option<PathBuf>,
+    },
+    /// List the changes between two AATs; fails if any of them is breaking
+    Diff { old: PathBuf, new: PathBuf },
+    /// Check that every type reference in an AAT resolves, listing all problems
+    Validate {
+        file: PathBuf,
+        /// Print the report as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Lang {
+    /// TypeScript client
+    #[value(alias = "ts")]
+    Typescript,
+    /// Rust client on reqwest
+    Rust,
+    /// Python client on requests
+    Python,
+    /// Axum server traits and router
+    Axum,
+}
+
+impl Lang {
+    /// Name of the generator in the built-in registry
+    fn generator(&self) -> &'static str {
+        match self {
+            Lang::Typescript => "typescript",
+            Lang::Rust => "rust",
+            Lang::Python => "python",
+            Lang::Axum => "axum",
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Zod {
+    Alongside,
+    Instead,
+}
+
+impl Zod {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Zod::Alongside => "alongside",
+            Zod::Instead => "instead",
+        }
+    }
+}
+
+/// Outcome of a command that ran to completion
+enum Outcome {
+    Success,
+    CheckFailed,
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    match run(cli.command) {
+        Ok(Outcome::Success) => ExitCode::SUCCESS,
+        Ok(Outcome::CheckFailed) => ExitCode::from(1),
+        Err(e) => {
+            eprintln!("error: {:#}", e);
+            ExitCode::from(2)
+        }
+    }
+}
+
+fn run(command: Command) -> Result<Outcome> {
+    match command {
+        Command::Generate {
+            lang,
+            input,
+            out,
+            options,
+            services,
+            validators,
+            zod,
+            dates,
+            bigints,
+            modules,
+            package,
+            react_query,
+        } => {
+            let mut aat = read_aat(&input)?;
+            if !services.is_empty() {
+                let services = services.iter().map(String::as_str).collect::<Vec<_>>();
+                aat.retain_services(&services)?;
+                aat.prune_unreachable_types();
+            }
+            let registry = GeneratorRegistry::with_builtin();
+            let name = lang.generator();
+
+            let mut options = match options {
+                Some(json) => match serde_json::from_str(&json).context("Invalid --options")? {
+                    Value::Object(options) => options,
+                    _ => anyhow::bail!("--options must be a JSON object"),
+                },
+                None => Map::new(),
+            };
+            // (flag, option key, value)
+            let typescript_flags = [
+                (
+                    "validators",
+                    "validators",
+                    validators.then_some(Value::Bool(true)),
+                ),
+                ("zod", "zod", zod.map(|zod| Value::from(zod.as_str()))),
+                ("dates", "dates", dates.then_some(Value::Bool(true))),
+                ("bigints", "bigints", bigints.then_some(Value::Bool(true))),
+                ("modules", "layout", modules.then(|| Value::from("modules"))),
+                (
+                    "package",
+                    "package",
+                    package.map(|name| serde_json::json!({ "name": name })),
+                ),
+                (
+                    "react-query",
+                    "react_query",
+                    react_query.then_some(Value::Bool(true)),
+                ),
+            ];
+            for (flag, key, value) in typescript_flags {
+                if let Some(value) = value {
+                    if name != "typescript" {
+                        anyhow::bail!("--{} only applies to the TypeScript generator", flag);
+                    }
+                    options.insert(key.to_string(), value);
+                }
+            }
+
+            let files = registry.generate(name, &aat, &Value::Object(options))?;
+            for file in files {
+                let path = out.join(&file.path);
+                if let Some(parent) = path.parent() {
+                    std::fs::create_dir_all(parent)
+                        .with_context(|| format!("Failed to create {}", parent.display()))?;
+                }
+                std::fs::write(&path, file.content)
+                    .with_context(|| format!("Failed to write {}", path.display()))?;
+                eprintln!("wrote {}", path.display());
+            }
+            Ok(Outcome::Success)
+        }
+        Command::Import { file, out } => {
+            let content = std::fs::read_to_string(&file)
+                .with_context(|| format!("Failed to read {}", file.display()))?;
+            let document: serde_json::Value = serde_json::from_str(&content)
+                .with_context(|| format!("{} is not valid JSON", file.display()))?;
+            let aat = damascus::import::openapi::from_openapi(&document)?;
+            if let Err(e) = aat.validate() {
+                eprintln!("warning: imported AAT does not validate: {}", e);
+            }
+            let json = aat.to_json()?;
+            match out {
+                Some(path) => std::fs::write(&path, json)
+                    .with_context(|| format!("Failed to write {}", path.display()))?,
+                None => println!("{}", json),
+            }
+            Ok(Outcome::Success)
+        }
+        Command::Diff { old, new } => {
+            let changes = diff(&read_aat(&old)?, &read_aat(&new)?);
+            for change in &changes {
+                println!("{}", change);
+            }
+            if changes.iter().any(|c| c.breaking) {
+                Ok(Outcome::CheckFailed)
+            } else {
+                Ok(Outcome::Success)
+            }
+        }
+        Command::Validate { file, json } => {
+            let report = read_aat(&file)?.validation_report();
+            if json {
+                println!("{}", report.render_json());
+            } else if report.issues.is_empty() {
+                println!("{} is valid", file.display());
+            } else {
+                print!("{}", report.render_pretty());
+            }
+            if report.is_valid() {
+                Ok(Outcome::Success)
+            } else {
+                Ok(Outcome::CheckFailed)
+            }
+        }
+    }
+}
+
+fn read_aat(path: &Path) -> Result<AAT> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    AAT::from_json(&content).with_context(|| format!("{} is not a valid AAT", path.display()))
+}
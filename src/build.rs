@@ -0,0 +1,239 @@
+//! Helpers for generating clients from a `build.rs`
+//!
+//! ```ignore
+//! fn main() {
+//!     damascus::build::Build::new(api::spec())
+//!         .out_dir("web/src/api")
+//!         .typescript(TypeScriptOptions::default())
+//!         .rerun_if_changed("api/src")
+//!         .check(std::env::var("DAMASCUS_CHECK").is_ok())
+//!         .run()
+//!         .unwrap();
+//! }
+//! ```
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use anyhow::{Context, Result, bail};
+
+use crate::aat::AAT;
+use crate::generate::axum::{AxumGenerator, AxumOptions};
+use crate::generate::python::{PythonGenerator, PythonOptions};
+use crate::generate::rust::{RustGenerator, RustOptions};
+use crate::generate::typescript::{TypeScriptGenerator, TypeScriptOptions};
+use crate::generate::{Generator, OutputFile};
+use crate::spec::Spec;
+
+type Target = Rc<dyn Fn(&AAT) -> Result<Vec<OutputFile>>>;
+
+#[derive(Clone)]
+pub struct Build {
+    spec: Spec,
+    out_dir: PathBuf,
+    targets: Vec<Target>,
+    rerun_if_changed: Vec<PathBuf>,
+    check: bool,
+}
+
+/// What [`Build::run`] did with each output file
+#[derive(Debug, Clone, Default)]
+pub struct BuildReport {
+    /// Files that were created or rewritten
+    pub written: Vec<PathBuf>,
+    /// Files whose content was already up to date
+    pub unchanged: Vec<PathBuf>,
+}
+
+impl Build {
+    /// Outputs are written relative to the current directory (the package root in a build script)
+    /// unless [`Build::out_dir`] is set
+    pub fn new(spec: Spec) -> Self {
+        Self {
+            spec,
+            out_dir: PathBuf::from("."),
+            targets: vec![],
+            rerun_if_changed: vec![],
+            check: false,
+        }
+    }
+
+    pub fn out_dir(mut self, out_dir: impl AsRef<Path>) -> Self {
+        self.out_dir = out_dir.as_ref().to_path_buf();
+        self
+    }
+
+    /// Adds a generator; its files are written relative to the output directory
+    pub fn generator<G: Generator + 'static>(mut self, generator: G, options: G::Options) -> Self {
+        self.targets
+            .push(Rc::new(move |aat| generator.generate(aat, &options)));
+        self
+    }
+
+    /// Generates a TypeScript client (`client.ts`)
+    pub fn typescript(self, options: TypeScriptOptions) -> Self {
+        self.generator(TypeScriptGenerator::new(), options)
+    }
+
+    /// Generates an Axum server trait and router per service (`server.rs`)
+    pub fn axum(self, options: AxumOptions) -> Self {
+        self.generator(AxumGenerator, options)
+    }
+
+    /// Generates a Rust client on `reqwest` (`client.rs`)
+    pub fn rust(self, options: RustOptions) -> Self {
+        self.generator(RustGenerator, options)
+    }
+
+    /// Generates a Python client on `requests` (`client.py`)
+    pub fn python(self, options: PythonOptions) -> Self {
+        self.generator(PythonGenerator, options)
+    }
+
+    /// Files or directories the spec is defined in; cargo re-runs the build script when they change
+    pub fn rerun_if_changed(mut self, path: impl AsRef<Path>) -> Self {
+        self.rerun_if_changed.push(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Instead of writing outputs, fail if any of them is missing or differs from the generated
+    /// content (for CI jobs that check committed clients are up to date)
+    pub fn check(mut self, check: bool) -> Self {
+        self.check = check;
+        self
+    }
+
+    pub fn run(&self) -> Result<BuildReport> {
+        self.run_with_output(&mut std::io::stdout())
+    }
+
+    /// [`Build::run`], writing cargo instructions to `output` instead of stdout
+    fn run_with_output(&self, output: &mut dyn Write) -> Result<BuildReport> {
+        for path in &self.rerun_if_changed {
+            writeln!(output, "cargo:rerun-if-changed={}", path.display())?;
+        }
+
+        let aat = AAT::from_spec(&self.spec)?;
+        aat.validate()?;
+
+        let mut report = BuildReport::default();
+        let mut stale = vec![];
+        for file in self.generate(&aat)? {
+            let path = self.out_dir.join(&file.path);
+            let content = file.content;
+            let current = std::fs::read_to_string(&path).ok();
+            if current.as_deref() == Some(content.as_str()) {
+                report.unchanged.push(path);
+                continue;
+            }
+
+            if self.check {
+                stale.push(path);
+                continue;
+            }
+
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)
+                    .with_context(|| format!("Failed to create {}", parent.display()))?;
+            }
+            std::fs::write(&path, content)
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+            report.written.push(path);
+        }
+
+        if !stale.is_empty() {
+            let files: Vec<String> = stale.iter().map(|p| p.display().to_string()).collect();
+            bail!(
+                "Generated files are out of date: {}. Re-run the build without check mode to update them",
+                files.join(", ")
+            );
+        }
+
+        Ok(report)
+    }
+
+    fn generate(&self, aat: &AAT) -> Result<Vec<OutputFile>> {
+        let mut outputs = vec![];
+        for target in &self.targets {
+            outputs.extend(target(aat)?);
+        }
+        Ok(outputs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{Machine, machines};
+    use crate::spec::Type;
+
+    fn build(out_dir: &Path) -> Build {
+        Build::new(machines(|e| e.response(Type::schema::<Machine>())))
+            .out_dir(out_dir)
+            .typescript(TypeScriptOptions::default())
+            .axum(AxumOptions::default())
+    }
+
+    #[test]
+    fn rewrites_only_changed_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let client = dir.path().join("client.ts");
+        let server = dir.path().join("server.rs");
+
+        let report = build(dir.path()).run().unwrap();
+        assert_eq!(report.written, [client.clone(), server.clone()]);
+        assert!(report.unchanged.is_empty());
+
+        std::fs::write(&server, "// edited").unwrap();
+        let modified = std::fs::metadata(&client).unwrap().modified().unwrap();
+        let report = build(dir.path()).run().unwrap();
+        assert_eq!(report.written, [server.as_path()]);
+        assert_eq!(report.unchanged, [client.as_path()]);
+        assert_eq!(
+            std::fs::metadata(&client).unwrap().modified().unwrap(),
+            modified
+        );
+        assert_ne!(std::fs::read_to_string(&server).unwrap(), "// edited");
+    }
+
+    #[test]
+    fn check_fails_on_stale_output_without_writing() {
+        let dir = tempfile::tempdir().unwrap();
+        build(dir.path()).run().unwrap();
+        let server = dir.path().join("server.rs");
+        std::fs::write(&server, "// edited").unwrap();
+        std::fs::remove_file(dir.path().join("client.ts")).unwrap();
+
+        let error = build(dir.path()).check(true).run().unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            format!(
+                "Generated files are out of date: {}, {}. Re-run the build without check mode to update them",
+                dir.path().join("client.ts").display(),
+                server.display()
+            )
+        );
+        assert_eq!(std::fs::read_to_string(&server).unwrap(), "// edited");
+        assert!(!dir.path().join("client.ts").exists());
+
+        build(dir.path()).run().unwrap();
+        let report = build(dir.path()).check(true).run().unwrap();
+        assert_eq!(report.unchanged.len(), 2);
+    }
+
+    #[test]
+    fn prints_rerun_if_changed_instructions() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut output = Vec::new();
+        build(dir.path())
+            .rerun_if_changed("api/src")
+            .rerun_if_changed("build.rs")
+            .run_with_output(&mut output)
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "cargo:rerun-if-changed=api/src\ncargo:rerun-if-changed=build.rs\n"
+        );
+    }
+}
@@ -0,0 +1,57 @@
+//! Case conversions for names taken from specs, shared by the AAT and the generators
+
+/// `machine-logs`, `machine_logs` and `machine logs` become `MachineLogs`
+pub(crate) fn to_pascal_case(s: &str) -> String {
+    s.split(|c: char| !c.is_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let mut chars = part.chars();
+            match chars.next() {
+                None => String::new(),
+                Some(first) => first.to_uppercase().chain(chars).collect(),
+            }
+        })
+        .collect()
+}
+
+/// `machine-logs` and `machine_logs` become `machineLogs`
+pub(crate) fn to_camel_case(s: &str) -> String {
+    let parts: Vec<&str> = s.split(&['-', '_'][..]).filter(|p| !p.is_empty()).collect();
+    if parts.is_empty() {
+        return String::new();
+    }
+
+    let mut result = parts[0].to_lowercase();
+    for part in &parts[1..] {
+        let mut chars = part.chars();
+        if let Some(first) = chars.next() {
+            result.push_str(&first.to_uppercase().collect::<String>());
+            result.push_str(&chars.collect::<String>().to_lowercase());
+        }
+    }
+    result
+}
+
+/// `machineLogs`, `MachineLogs` and `machine-logs` become `machine_logs`
+pub(crate) fn to_snake_case(s: &str) -> String {
+    let mut result = String::new();
+    let mut previous_lower = false;
+    for c in s.chars() {
+        if !c.is_alphanumeric() {
+            if !result.is_empty() && !result.ends_with('_') {
+                result.push('_');
+            }
+            previous_lower = false;
+        } else if c.is_uppercase() {
+            if previous_lower {
+                result.push('_');
+            }
+            result.extend(c.to_lowercase());
+            previous_lower = false;
+        } else {
+            result.push(c);
+            previous_lower = true;
+        }
+    }
+    result.trim_end_matches('_').to_string()
+}
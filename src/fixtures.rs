@@ -0,0 +1,42 @@
+//! Specs and types shared by the unit tests
+
+use schemars::JsonSchema;
+
+use crate::aat::AAT;
+use crate::spec::{Endpoint, PathSegment, Spec, Type};
+
+/// A named type for tests that need nothing more
+#[derive(JsonSchema)]
+#[allow(dead_code)]
+pub struct Machine {
+    pub name: String,
+}
+
+/// A `date-time` string, as `chrono::DateTime` describes itself
+pub struct Timestamp;
+
+impl JsonSchema for Timestamp {
+    fn schema_name() -> std::borrow::Cow<'static, str> {
+        "Timestamp".into()
+    }
+
+    fn json_schema(_: &mut schemars::SchemaGenerator) -> schemars::Schema {
+        schemars::json_schema!({ "type": "string", "format": "date-time" })
+    }
+}
+
+/// A spec with one `GET /machines` endpoint in the `machines` service, declared by `endpoint`
+pub fn machines(endpoint: impl FnOnce(Endpoint) -> Endpoint) -> Spec {
+    Spec::new("test").service("machines", |service| {
+        service.get(
+            "get",
+            vec![PathSegment::Literal("machines".into())],
+            endpoint,
+        )
+    })
+}
+
+/// The AAT of [`machines`] with an endpoint returning `response`
+pub fn returning(response: Type) -> AAT {
+    AAT::from_spec(&machines(|e| e.response(response))).unwrap()
+}
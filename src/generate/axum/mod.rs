@@ -0,0 +1,794 @@
+mod options;
+mod runtime;
+
+pub use options::*;
+
+use std::collections::HashSet;
+
+use anyhow::{Result, bail};
+use runtime::{Runtime, generate_runtime};
+
+use crate::aat::{
+    AAT, Endpoint, FieldType, Header, HeaderFormat, HeaderPart, HeaderValue, HttpMethod,
+    PathSegment, Service, WsAuth,
+};
+use crate::generate::rust::layout::{
+    CALL_WIDTH, MAX_WIDTH, call, escape, let_call, list, list_vertical,
+};
+use crate::generate::rust::types::{param_ident, pascal_case, rust_ident, rust_type};
+use crate::generate::writer::CodeWriter;
+use crate::generate::{Generator, OutputFile};
+
+/// Generates `server.rs`: one trait per service with a method per endpoint, and functions
+/// mounting implementations onto an `axum::Router`
+///
+/// The generated module needs `axum` (with the `ws` feature for WebSocket endpoints), `serde`,
+/// `serde_json` and, for WebSocket endpoints, `futures-util`.
+pub struct AxumGenerator;
+
+impl Generator for AxumGenerator {
+    type Options = AxumOptions;
+
+    fn name(&self) -> &str {
+        "axum"
+    }
+
+    fn generate(&self, aat: &AAT, options: &AxumOptions) -> Result<Vec<OutputFile>> {
+        Ok(vec![OutputFile::new(
+            "server.rs",
+            Self::generate_with_options(aat, options)?,
+        )])
+    }
+}
+
+impl AxumGenerator {
+    pub fn generate(aat: &AAT) -> Result<String> {
+        Self::generate_with_options(aat, &AxumOptions::default())
+    }
+
+    pub fn generate_with_options(aat: &AAT, options: &AxumOptions) -> Result<String> {
+        let services = aat
+            .services
+            .iter()
+            .map(|service| ServerService::new(aat, service))
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut runtime = Runtime::default();
+        for endpoint in services.iter().flat_map(|service| &service.endpoints) {
+            endpoint.register(&mut runtime);
+        }
+
+        let mut writer = CodeWriter::with_indent("    ");
+        writer.line("// Generated by Damascus");
+        writer.line("// Do not edit this file directly");
+        writer.empty_line();
+        writer.line("#![allow(clippy::too_many_arguments, clippy::result_large_err)]");
+        writer.empty_line();
+
+        writer.line("use std::future::Future;");
+        writer.line("use std::sync::Arc;");
+        writer.empty_line();
+        // Modules rather than items, so spec types named `Path` or `Message` don't clash
+        if runtime.websocket {
+            writer.line("use axum::extract::{self, ws};");
+        } else {
+            writer.line("use axum::extract;");
+        }
+        writer.line("use axum::response::{self, IntoResponse as _};");
+        writer.line("use axum::{http, routing};");
+        if runtime.websocket {
+            writer.line("use futures_util::StreamExt as _;");
+        }
+        if !aat.types.is_empty() {
+            writer.empty_line();
+            writer.line(&format!("use {}::*;", options.types_module));
+        }
+        writer.empty_line();
+
+        for service in &services {
+            generate_trait(&mut writer, service);
+            writer.empty_line();
+        }
+
+        generate_app_router(&mut writer, &services);
+        writer.empty_line();
+
+        for service in &services {
+            generate_router(&mut writer, service);
+            writer.empty_line();
+        }
+
+        for service in &services {
+            for endpoint in &service.endpoints {
+                generate_handler(&mut writer, service, endpoint);
+                writer.empty_line();
+            }
+        }
+
+        generate_runtime(&mut writer, &runtime);
+
+        let mut output = writer.into_string().trim_end().to_string();
+        output.push('\n');
+        Ok(output)
+    }
+}
+
+/// A service as seen by the server
+struct ServerService<'a> {
+    service: &'a Service,
+    trait_name: String,
+    endpoints: Vec<ServerEndpoint<'a>>,
+}
+
+impl<'a> ServerService<'a> {
+    fn new(aat: &'a AAT, service: &'a Service) -> Result<Self> {
+        Ok(Self {
+            service,
+            trait_name: format!("{}Service", pascal_case(&service.name)),
+            endpoints: service
+                .endpoints
+                .iter()
+                .map(|endpoint| ServerEndpoint::new(aat, service, endpoint))
+                .collect::<Result<_>>()?,
+        })
+    }
+
+    fn ident(&self) -> String {
+        rust_ident(&self.service.name)
+            .trim_start_matches("r#")
+            .to_string()
+    }
+}
+
+/// An endpoint with its arguments and response resolved to Rust types
+struct ServerEndpoint<'a> {
+    endpoint: &'a Endpoint,
+    /// `axum::routing` function name
+    method: &'static str,
+    route: String,
+    params: Vec<Param>,
+    response: Response,
+}
+
+/// The extractors, locals and runtime functions of the generated handlers, which arguments are
+/// renamed around
+const HANDLER_NAMES: &[&str] = &[
+    "service",
+    "headers",
+    "auth_query",
+    "upgrade",
+    "socket",
+    "stream",
+    "output",
+    "rejection",
+    "required_header",
+    "optional_header",
+    "query_headers",
+    "protocol_headers",
+    "message_headers",
+    "send_stream",
+    "close",
+    "close_rejected",
+];
+
+/// An argument of a service method
+struct Param {
+    ident: String,
+    rust_type: String,
+    source: Source,
+}
+
+enum Source {
+    /// Placeholder `index` of a header value, between `literals[index]` and `literals[index + 1]`
+    Header {
+        name: String,
+        literals: Vec<String>,
+        index: usize,
+        required: bool,
+        /// The type the placeholder is parsed as, when it differs from the argument's
+        wire_type: Option<String>,
+    },
+    Path,
+    Query,
+    Body,
+}
+
+enum Response {
+    /// Serialized as the JSON body
+    Json(String),
+    /// `204 No Content`
+    Empty,
+    /// Sent over a WebSocket, one JSON message per item
+    Stream(String),
+}
+
+impl<'a> ServerEndpoint<'a> {
+    fn new(aat: &AAT, service: &Service, endpoint: &'a Endpoint) -> Result<Self> {
+        let qualified_name = format!("{}.{}", service.name, endpoint.name);
+
+        // Endpoint headers override service headers, which override spec headers
+        let mut headers: Vec<&Header> = Vec::new();
+        for header in aat
+            .headers
+            .iter()
+            .chain(&service.headers)
+            .chain(&endpoint.headers)
+        {
+            headers.retain(|existing| !existing.name.eq_ignore_ascii_case(&header.name));
+            headers.push(header);
+        }
+
+        let mut params = Vec::new();
+        for header in headers {
+            let (literals, placeholders) = match &header.value {
+                HeaderValue::Literal(_) => continue,
+                HeaderValue::Parameter { name, field_type } => (
+                    vec![String::new(), String::new()],
+                    vec![(name, field_type, None)],
+                ),
+                HeaderValue::Pattern { parts } => pattern_placeholders(parts),
+            };
+            for (index, (param_name, field_type, format)) in placeholders.into_iter().enumerate() {
+                let wire_type = match format {
+                    Some(HeaderFormat::Base64) => {
+                        let value_type = match field_type {
+                            FieldType::Optional(inner) => inner,
+                            field_type => field_type,
+                        };
+                        Some(format!("Base64<{}>", rust_type(value_type)?))
+                    }
+                    // Sent as the text servers parse anyway
+                    Some(HeaderFormat::Lowercase | HeaderFormat::IsoDate) | None => None,
+                };
+                params.push(Param {
+                    ident: param_ident(param_name, HANDLER_NAMES),
+                    rust_type: rust_type(field_type)?,
+                    source: Source::Header {
+                        name: header.name.to_ascii_lowercase(),
+                        literals: literals.clone(),
+                        index,
+                        required: !matches!(field_type, FieldType::Optional(_)),
+                        wire_type,
+                    },
+                });
+            }
+        }
+
+        let mut route = String::new();
+        for segment in &endpoint.path {
+            match segment {
+                PathSegment::Literal(literal) => {
+                    route.push('/');
+                    route.push_str(literal.trim_matches('/'));
+                }
+                PathSegment::Parameter { name, r#type } => {
+                    route.push_str(&format!("/{{{}}}", name));
+                    params.push(Param {
+                        ident: param_ident(name, HANDLER_NAMES),
+                        rust_type: rust_type(r#type)?,
+                        source: Source::Path,
+                    });
+                }
+                PathSegment::Wildcard { name } => {
+                    route.push_str(&format!("/{{*{}}}", name));
+                    params.push(Param {
+                        ident: param_ident(name, HANDLER_NAMES),
+                        rust_type: "String".to_string(),
+                        source: Source::Path,
+                    });
+                }
+            }
+        }
+        if route.is_empty() {
+            route.push('/');
+        }
+
+        if let Some(query) = &endpoint.query {
+            params.push(Param {
+                ident: "query".to_string(),
+                rust_type: rust_type(query)?,
+                source: Source::Query,
+            });
+        }
+        if let Some(body) = &endpoint.body {
+            params.push(Param {
+                ident: "body".to_string(),
+                rust_type: rust_type(body)?,
+                source: Source::Body,
+            });
+        }
+
+        let mut seen = HashSet::new();
+        for param in &params {
+            if !seen.insert(&param.ident) {
+                bail!(
+                    "Endpoint '{}' has more than one argument named '{}'",
+                    qualified_name,
+                    param.ident
+                );
+            }
+        }
+
+        let response = match (&endpoint.upgrade, &endpoint.response) {
+            (Some(_), FieldType::Stream(item)) => Response::Stream(rust_type(item)?),
+            (Some(_), item) => Response::Stream(rust_type(item)?),
+            (None, FieldType::Stream(_)) => bail!(
+                "Endpoint '{}' streams its response without a WebSocket upgrade",
+                qualified_name
+            ),
+            (None, FieldType::Any) => Response::Empty,
+            (None, response) => Response::Json(rust_type(response)?),
+        };
+
+        Ok(Self {
+            endpoint,
+            method: match endpoint.method {
+                HttpMethod::Get => "get",
+                HttpMethod::Post => "post",
+                HttpMethod::Put => "put",
+                HttpMethod::Delete => "delete",
+                HttpMethod::Patch => "patch",
+            },
+            route,
+            params,
+            response,
+        })
+    }
+
+    fn ident(&self) -> String {
+        rust_ident(&self.endpoint.name)
+    }
+
+    fn is_websocket(&self) -> bool {
+        matches!(self.response, Response::Stream(_))
+    }
+
+    fn header_names(&self) -> Vec<&str> {
+        self.params
+            .iter()
+            .filter_map(|param| match &param.source {
+                Source::Header { name, .. } => Some(name.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// The WebSocket auth fallback in effect, if the endpoint has headers to carry
+    fn ws_auth(&self) -> Option<&WsAuth> {
+        if !self.is_websocket() || self.header_names().is_empty() {
+            return None;
+        }
+        self.endpoint.ws_auth.as_ref()
+    }
+
+    fn register(&self, runtime: &mut Runtime) {
+        for param in &self.params {
+            if let Source::Header {
+                required,
+                wire_type,
+                ..
+            } = &param.source
+            {
+                runtime.required_headers |= required;
+                runtime.optional_headers |= !required;
+                runtime.base64 |= wire_type.is_some();
+            }
+        }
+        runtime.websocket |= self.is_websocket();
+        match self.ws_auth() {
+            Some(WsAuth::Query) => runtime.query_auth = true,
+            Some(WsAuth::Protocol(_)) => runtime.protocol_auth = true,
+            Some(WsAuth::Message) => runtime.message_auth = true,
+            None => {}
+        }
+    }
+
+    /// `Result<T, Self::Error>`, the output of the method's future
+    fn output_type(&self) -> String {
+        let output = match &self.response {
+            Response::Json(rust_type) => rust_type.clone(),
+            Response::Empty => "()".to_string(),
+            Response::Stream(item) => format!(
+                "impl futures_util::Stream<Item = {}> + Send + 'static",
+                item
+            ),
+        };
+        format!("Result<{}, Self::Error>", output)
+    }
+
+    /// Calls the service method, turning its error into the response; `binding` starts the
+    /// statement (`let output = `)
+    fn write_call(&self, writer: &mut CodeWriter, binding: &str, terminator: &str) {
+        let args: Vec<&str> = self
+            .params
+            .iter()
+            .map(|param| param.ident.as_str())
+            .collect();
+        writer.line(&format!("{}service", binding));
+        writer.indent();
+        writer.line(&format!(".{}({})", self.ident(), args.join(", ")));
+        writer.line(".await");
+        writer.line(&format!(
+            ".map_err(response::IntoResponse::into_response){}",
+            terminator
+        ));
+        writer.dedent();
+    }
+}
+
+fn generate_trait(writer: &mut CodeWriter, service: &ServerService) {
+    writer.line(&format!(
+        "/// Endpoints of the `{}` service",
+        service.service.name
+    ));
+    writer.block(
+        &format!("pub trait {}: Send + Sync + 'static {{", service.trait_name),
+        "}",
+        |w| {
+            w.line("/// Turned into the response when an endpoint fails");
+            w.line("type Error: response::IntoResponse + Send;");
+            for endpoint in &service.endpoints {
+                w.empty_line();
+                let upgrade = if endpoint.is_websocket() {
+                    " (WebSocket)"
+                } else {
+                    ""
+                };
+                w.line(&format!(
+                    "/// `{} {}`{}",
+                    endpoint.method.to_ascii_uppercase(),
+                    endpoint.route,
+                    upgrade
+                ));
+                let mut params = vec!["&self".to_string()];
+                params.extend(
+                    endpoint
+                        .params
+                        .iter()
+                        .map(|param| format!("{}: {}", param.ident, param.rust_type)),
+                );
+                let output = endpoint.output_type();
+                let return_type = format!(" -> impl Future<Output = {}> + Send;", output);
+                if format!("    ){}", return_type).len() <= MAX_WIDTH {
+                    list(
+                        w,
+                        4,
+                        &format!("fn {}", endpoint.ident()),
+                        &params,
+                        &return_type,
+                    );
+                } else {
+                    // The signature spans several lines, so rustfmt puts every parameter on its own
+                    list_vertical(
+                        w,
+                        &format!("fn {}", endpoint.ident()),
+                        &params,
+                        " -> impl Future<",
+                    );
+                    w.line(&format!("    Output = {},", output));
+                    w.line("> + Send;");
+                }
+            }
+        },
+    );
+}
+
+/// One router for every service of the spec
+fn generate_app_router(writer: &mut CodeWriter, services: &[ServerService]) {
+    let params: Vec<String> = services
+        .iter()
+        .map(|service| format!("{}: impl {}", service.ident(), service.trait_name))
+        .collect();
+    writer.line("/// Serves every service of the spec");
+    list(writer, 0, "pub fn router", &params, " -> axum::Router {");
+    writer.indent();
+    let merges: Vec<String> = services
+        .iter()
+        .map(|service| format!(".merge({}_router({}))", service.ident(), service.ident()))
+        .collect();
+    let chain = format!("axum::Router::new(){}", merges.concat());
+    if chain.len() <= CALL_WIDTH {
+        writer.line(&chain);
+    } else {
+        writer.line("axum::Router::new()");
+        writer.indent();
+        for merge in &merges {
+            writer.line(merge);
+        }
+        writer.dedent();
+    }
+    writer.dedent();
+    writer.line("}");
+}
+
+fn generate_router(writer: &mut CodeWriter, service: &ServerService) {
+    // One route per path, with a method router per endpoint
+    let mut routes: Vec<(&str, Vec<&ServerEndpoint>)> = Vec::new();
+    for endpoint in &service.endpoints {
+        match routes
+            .iter_mut()
+            .find(|(route, _)| *route == endpoint.route)
+        {
+            Some((_, endpoints)) => endpoints.push(endpoint),
+            None => routes.push((&endpoint.route, vec![endpoint])),
+        }
+    }
+
+    writer.line(&format!(
+        "/// Routes of the `{}` service",
+        service.service.name
+    ));
+    writer.block(
+        &format!(
+            "pub fn {}_router<S: {}>(service: S) -> axum::Router {{",
+            service.ident(),
+            service.trait_name
+        ),
+        "}",
+        |w| {
+            w.line("axum::Router::new()");
+            w.indent();
+            for (route, endpoints) in &routes {
+                let methods: Vec<String> = endpoints
+                    .iter()
+                    .map(|endpoint| {
+                        format!(
+                            "{}({}::<S>)",
+                            endpoint.method,
+                            handler_name(service, endpoint)
+                        )
+                    })
+                    .collect();
+                let methods = format!("routing::{}", methods.join("."));
+                call(w, 8, ".route", &[format!("\"{}\"", route), methods], "");
+            }
+            w.line(".with_state(Arc::new(service))");
+            w.dedent();
+        },
+    );
+}
+
+fn handler_name(service: &ServerService, endpoint: &ServerEndpoint) -> String {
+    format!(
+        "{}_{}",
+        service.ident(),
+        endpoint.ident().trim_start_matches("r#")
+    )
+}
+
+fn generate_handler(writer: &mut CodeWriter, service: &ServerService, endpoint: &ServerEndpoint) {
+    let path_params: Vec<&Param> = endpoint
+        .params
+        .iter()
+        .filter(|param| matches!(param.source, Source::Path))
+        .collect();
+    let header_names = endpoint.header_names();
+    let ws_auth = endpoint.ws_auth();
+
+    // Extractors, with the body last as axum requires
+    let mut extractors = vec!["extract::State(service): extract::State<Arc<S>>".to_string()];
+    if !path_params.is_empty() {
+        let idents: Vec<&str> = path_params
+            .iter()
+            .map(|param| param.ident.as_str())
+            .collect();
+        let types: Vec<&str> = path_params
+            .iter()
+            .map(|param| param.rust_type.as_str())
+            .collect();
+        // One-element tuples need their trailing comma
+        let comma = if path_params.len() == 1 { "," } else { "" };
+        extractors.push(format!(
+            "extract::Path(({}{})): extract::Path<({}{})>",
+            idents.join(", "),
+            comma,
+            types.join(", "),
+            comma
+        ));
+    }
+    for param in &endpoint.params {
+        if let Source::Query = param.source {
+            extractors.push(format!(
+                "extract::Query({}): extract::Query<{}>",
+                param.ident, param.rust_type
+            ));
+        }
+    }
+    if matches!(ws_auth, Some(WsAuth::Query)) {
+        extractors
+            .push("extract::Query(auth_query): extract::Query<Vec<(String, String)>>".to_string());
+    }
+    if !header_names.is_empty() {
+        extractors.push("headers: http::HeaderMap".to_string());
+    }
+    if endpoint.is_websocket() {
+        extractors.push("upgrade: ws::WebSocketUpgrade".to_string());
+    }
+    for param in &endpoint.params {
+        if let Source::Body = param.source {
+            extractors.push(format!(
+                "extract::Json({}): extract::Json<{}>",
+                param.ident, param.rust_type
+            ));
+        }
+    }
+
+    list(
+        writer,
+        0,
+        &format!(
+            "async fn {}<S: {}>",
+            handler_name(service, endpoint),
+            service.trait_name
+        ),
+        &extractors,
+        " -> Result<response::Response, response::Response> {",
+    );
+    writer.indent();
+
+    let quoted_names: Vec<String> = header_names
+        .iter()
+        .map(|name| format!("\"{}\"", name))
+        .collect();
+    match ws_auth {
+        Some(WsAuth::Query) => call(
+            writer,
+            4,
+            "let headers = query_headers",
+            &[
+                "headers".to_string(),
+                "auth_query".to_string(),
+                format!("&[{}]", quoted_names.join(", ")),
+            ],
+            ";",
+        ),
+        Some(WsAuth::Protocol(protocol)) => writer.line(&format!(
+            "let headers = protocol_headers(headers, \"{}\")?;",
+            escape(protocol)
+        )),
+        Some(WsAuth::Message) | None => {}
+    }
+
+    if let Some(WsAuth::Message) = ws_auth {
+        // Headers arrive after the upgrade, so rejections become close frames
+        writer.block(
+            "Ok(upgrade.on_upgrade(move |mut socket| async move {",
+            "}))",
+            |w| {
+                let args = [
+                    "&mut socket".to_string(),
+                    "headers".to_string(),
+                    format!("&[{}]", quoted_names.join(", ")),
+                ];
+                let line = format!("let headers = message_headers({}).await;", args.join(", "));
+                if 8 + line.len() <= MAX_WIDTH && args.join(", ").len() <= CALL_WIDTH {
+                    w.line(&line);
+                } else {
+                    list_vertical(w, "let headers = message_headers", &args, "");
+                    w.line(".await;");
+                }
+                w.block("let Some(headers) = headers else {", "};", |w| {
+                    w.line(
+                        "return close(socket, ws::close_code::POLICY, \"Expected an auth message\").await;",
+                    );
+                });
+                w.block("let stream = async {", "};", |w| {
+                    generate_header_reads(w, 12, endpoint);
+                    endpoint.write_call(w, "", "");
+                });
+                w.block("match stream.await {", "}", |w| {
+                    w.line("Ok(stream) => send_stream(socket, stream).await,");
+                    w.line("Err(rejection) => close_rejected(socket, rejection).await,");
+                });
+            },
+        );
+    } else {
+        generate_header_reads(writer, 4, endpoint);
+        let binding = match endpoint.response {
+            Response::Json(_) => "let output = ",
+            Response::Empty => "",
+            Response::Stream(_) => "let stream = ",
+        };
+        endpoint.write_call(writer, binding, "?;");
+        match &endpoint.response {
+            Response::Json(_) => writer.line("Ok(axum::Json(output).into_response())"),
+            Response::Empty => writer.line("Ok(http::StatusCode::NO_CONTENT.into_response())"),
+            // The browser fails the handshake unless the server picks the protocol
+            Response::Stream(_) => match ws_auth {
+                Some(WsAuth::Protocol(protocol)) => {
+                    writer.line("Ok(upgrade");
+                    writer.indent();
+                    writer.line(&format!(".protocols([\"{}\"])", escape(protocol)));
+                    writer.line(".on_upgrade(move |socket| send_stream(socket, stream)))");
+                    writer.dedent();
+                }
+                _ => {
+                    writer.line("Ok(upgrade.on_upgrade(move |socket| send_stream(socket, stream)))")
+                }
+            },
+        }
+    }
+
+    writer.dedent();
+    writer.line("}");
+}
+
+/// The name, type and format of a header placeholder
+type Placeholder<'a> = (&'a String, &'a FieldType, Option<&'a HeaderFormat>);
+
+/// The literal text around the placeholders of a header pattern, and the placeholders in order.
+/// There is one more literal than placeholders; the first and last are empty when a placeholder
+/// starts or ends the value.
+fn pattern_placeholders(parts: &[HeaderPart]) -> (Vec<String>, Vec<Placeholder<'_>>) {
+    let mut literals = vec![String::new()];
+    let mut placeholders = Vec::new();
+    for part in parts {
+        match part {
+            HeaderPart::Literal(literal) => {
+                let last = literals.len() - 1;
+                literals[last].push_str(literal);
+            }
+            HeaderPart::Param {
+                name,
+                field_type,
+                format,
+            } => {
+                placeholders.push((name, field_type, format.as_ref()));
+                literals.push(String::new());
+            }
+        }
+    }
+    (literals, placeholders)
+}
+
+/// Reads the header arguments of an endpoint from `headers`, at `indent` columns
+fn generate_header_reads(writer: &mut CodeWriter, indent: usize, endpoint: &ServerEndpoint) {
+    for param in &endpoint.params {
+        if let Source::Header {
+            name,
+            literals,
+            index,
+            required,
+            wire_type,
+        } = &param.source
+        {
+            let function = if *required {
+                "required_header"
+            } else {
+                "optional_header"
+            };
+            let annotation = match (wire_type, required) {
+                (Some(wire_type), true) => format!(": {}", wire_type),
+                (Some(wire_type), false) => format!(": Option<{}>", wire_type),
+                (None, _) => String::new(),
+            };
+            let literals: Vec<String> = literals
+                .iter()
+                .map(|literal| format!("\"{}\"", escape(literal)))
+                .collect();
+            let_call(
+                writer,
+                indent,
+                &format!("{}{}", param.ident, annotation),
+                function,
+                &[
+                    "&headers".to_string(),
+                    format!("\"{}\"", name),
+                    format!("&[{}]", literals.join(", ")),
+                    index.to_string(),
+                ],
+                "?;",
+            );
+            if wire_type.is_some() {
+                writer.line(&if *required {
+                    format!("let {} = {}.0;", param.ident, param.ident)
+                } else {
+                    format!(
+                        "let {} = {}.map(|value| value.0);",
+                        param.ident, param.ident
+                    )
+                });
+            }
+        }
+    }
+}
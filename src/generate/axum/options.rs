@@ -0,0 +1,17 @@
+use serde::{Deserialize, Serialize};
+
+/// Options controlling the generated Axum server
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AxumOptions {
+    /// Module path the spec's named types are imported from, as seen from the generated module
+    pub types_module: String,
+}
+
+impl Default for AxumOptions {
+    fn default() -> Self {
+        Self {
+            types_module: "super".to_string(),
+        }
+    }
+}
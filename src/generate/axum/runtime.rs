@@ -0,0 +1,353 @@
+use crate::generate::writer::CodeWriter;
+
+/// Which helpers the generated handlers call, so unused ones aren't emitted
+#[derive(Debug, Default)]
+pub struct Runtime {
+    pub required_headers: bool,
+    pub optional_headers: bool,
+    /// Header placeholders sent as base64
+    pub base64: bool,
+    pub websocket: bool,
+    pub query_auth: bool,
+    pub protocol_auth: bool,
+    pub message_auth: bool,
+}
+
+impl Runtime {
+    fn headers(&self) -> bool {
+        self.required_headers || self.optional_headers
+    }
+
+    fn fallback_headers(&self) -> bool {
+        self.query_auth || self.protocol_auth || self.message_auth
+    }
+}
+
+pub fn generate_runtime(writer: &mut CodeWriter, runtime: &Runtime) {
+    if runtime.headers() || runtime.protocol_auth {
+        writer.block(
+            "fn bad_request(message: String) -> response::Response {",
+            "}",
+            |w| {
+                w.line("(http::StatusCode::BAD_REQUEST, message).into_response()");
+            },
+        );
+        writer.empty_line();
+    }
+
+    if runtime.required_headers {
+        writer.line("fn required_header<T: std::str::FromStr>(");
+        writer.line("    headers: &http::HeaderMap,");
+        writer.line("    name: &str,");
+        writer.line("    literals: &[&str],");
+        writer.line("    index: usize,");
+        writer.block(") -> Result<T, response::Response> {", "}", |w| {
+            w.line("optional_header(headers, name, literals, index)?");
+            w.line("    .ok_or_else(|| bad_request(format!(\"Missing header '{}'\", name)))");
+        });
+        writer.empty_line();
+    }
+
+    if runtime.headers() {
+        writer.line("/// Parses placeholder `index` of a header declared as `literals` with a placeholder between");
+        writer.line("/// each two of them");
+        writer.line("fn optional_header<T: std::str::FromStr>(");
+        writer.line("    headers: &http::HeaderMap,");
+        writer.line("    name: &str,");
+        writer.line("    literals: &[&str],");
+        writer.line("    index: usize,");
+        writer.block(") -> Result<Option<T>, response::Response> {", "}", |w| {
+            w.block("let Some(value) = headers.get(name) else {", "};", |w| {
+                w.line("return Ok(None);");
+            });
+            w.line("let value = value");
+            w.indent();
+            w.line(".to_str()");
+            w.line(".ok()");
+            w.line(".and_then(|value| placeholder(value, literals, index))");
+            w.block(".ok_or_else(|| {", "})?;", |w| {
+                w.line("bad_request(format!(");
+                w.line("    \"Header '{}' must look like '{}'\",");
+                w.line("    name,");
+                w.line("    literals.join(\"…\")");
+                w.line("))");
+            });
+            w.dedent();
+            w.line("value");
+            w.indent();
+            w.line(".parse()");
+            w.line(".map(Some)");
+            w.line(".map_err(|_| bad_request(format!(\"Invalid value for header '{}'\", name)))");
+            w.dedent();
+        });
+        writer.empty_line();
+
+        writer.line("/// The text between `literals[index]` and `literals[index + 1]` in `value`");
+        writer.block(
+            "fn placeholder<'a>(value: &'a str, literals: &[&str], index: usize) -> Option<&'a str> {",
+            "}",
+            |w| {
+                w.line("let (first, rest) = literals.split_first()?;");
+                w.line("let (last, separators) = rest.split_last()?;");
+                w.line("let mut rest = value.strip_prefix(first)?.strip_suffix(last)?;");
+                w.line("let mut placeholders = Vec::new();");
+                w.block("for separator in separators {", "}", |w| {
+                    w.line("let (placeholder, tail) = rest.split_once(separator)?;");
+                    w.line("placeholders.push(placeholder);");
+                    w.line("rest = tail;");
+                });
+                w.line("placeholders.push(rest);");
+                w.line("placeholders.get(index).copied()");
+            },
+        );
+        writer.empty_line();
+    }
+
+    if runtime.base64 {
+        writer.line("/// A header placeholder sent as the base64 of its text");
+        writer.line("struct Base64<T>(T);");
+        writer.empty_line();
+        writer.block("impl<T: std::str::FromStr> std::str::FromStr for Base64<T> {", "}", |w| {
+            w.line("type Err = ();");
+            w.empty_line();
+            w.block("fn from_str(value: &str) -> Result<Self, ()> {", "}", |w| {
+                w.line("let text = base64_decode(value).and_then(|bytes| String::from_utf8(bytes).ok());");
+                w.line("text.ok_or(())?.parse().map(Base64).map_err(|_| ())");
+            });
+        });
+        writer.empty_line();
+    }
+
+    if runtime.fallback_headers() {
+        writer.line(
+            "/// Adds a header sent through a WebSocket auth fallback; headers sent natively win",
+        );
+        writer.block(
+            "fn insert_fallback(headers: &mut http::HeaderMap, name: &str, value: &str) {",
+            "}",
+            |w| {
+                w.line("if let (Ok(name), Ok(value)) = (");
+                w.line("    http::HeaderName::try_from(name),");
+                w.line("    http::HeaderValue::try_from(value),");
+                w.block(") {", "}", |w| {
+                    w.line("headers.entry(name).or_insert(value);");
+                });
+            },
+        );
+        writer.empty_line();
+    }
+
+    if runtime.query_auth {
+        writer.line("/// Headers sent as query parameters of the same name");
+        writer.line("fn query_headers(");
+        writer.line("    mut headers: http::HeaderMap,");
+        writer.line("    query: Vec<(String, String)>,");
+        writer.line("    names: &[&str],");
+        writer.block(") -> http::HeaderMap {", "}", |w| {
+            w.block("for (name, value) in query {", "}", |w| {
+                w.block("if names.contains(&name.as_str()) {", "}", |w| {
+                    w.line("insert_fallback(&mut headers, &name, &value);");
+                });
+            });
+            w.line("headers");
+        });
+        writer.empty_line();
+    }
+
+    if runtime.protocol_auth {
+        writer.line(
+            "/// Headers sent as a base64url JSON `Sec-WebSocket-Protocol` entry after `protocol`",
+        );
+        writer.line("fn protocol_headers(");
+        writer.line("    mut headers: http::HeaderMap,");
+        writer.line("    protocol: &str,");
+        writer.block(
+            ") -> Result<http::HeaderMap, response::Response> {",
+            "}",
+            |w| {
+                w.line("let offered: Vec<String> = headers");
+                w.indent();
+                w.line(".get_all(http::header::SEC_WEBSOCKET_PROTOCOL)");
+                w.line(".iter()");
+                w.line(".filter_map(|value| value.to_str().ok())");
+                w.line(".flat_map(|value| value.split(','))");
+                w.line(".map(|value| value.trim().to_string())");
+                w.line(".collect();");
+                w.dedent();
+                w.line("let Some(position) = offered.iter().position(|offer| offer == protocol) else {");
+                w.line("    return Ok(headers);");
+                w.line("};");
+                w.line("let Some(encoded) = offered.get(position + 1) else {");
+                w.line("    return Ok(headers);");
+                w.line("};");
+                w.line("let fallback: std::collections::HashMap<String, String> = base64_decode(encoded)");
+                w.indent();
+                w.line(".and_then(|json| serde_json::from_slice(&json).ok())");
+                w.block(".ok_or_else(|| {", "})?;", |w| {
+                    w.line("bad_request(format!(");
+                    w.line("    \"Invalid headers in WebSocket protocol '{}'\",");
+                    w.line("    protocol");
+                    w.line("))");
+                });
+                w.dedent();
+                w.block("for (name, value) in fallback {", "}", |w| {
+                    w.line("insert_fallback(&mut headers, &name, &value);");
+                });
+                w.line("Ok(headers)");
+            },
+        );
+        writer.empty_line();
+    }
+
+    if runtime.protocol_auth || runtime.base64 {
+        writer.line("/// Decodes standard or URL-safe base64, with or without padding");
+        writer.block(
+            "fn base64_decode(input: &str) -> Option<Vec<u8>> {",
+            "}",
+            |w| {
+                w.line("let mut bytes = Vec::with_capacity(input.len() * 3 / 4);");
+                w.line("let (mut buffer, mut bits) = (0u32, 0);");
+                w.block("for c in input.bytes() {", "}", |w| {
+                    w.block("let value = match c {", "};", |w| {
+                        w.line("b'A'..=b'Z' => c - b'A',");
+                        w.line("b'a'..=b'z' => c - b'a' + 26,");
+                        w.line("b'0'..=b'9' => c - b'0' + 52,");
+                        w.line("b'+' | b'-' => 62,");
+                        w.line("b'/' | b'_' => 63,");
+                        w.line("b'=' => continue,");
+                        w.line("_ => return None,");
+                    });
+                    w.line("buffer = (buffer << 6) | u32::from(value);");
+                    w.line("bits += 6;");
+                    w.block("if bits >= 8 {", "}", |w| {
+                        w.line("bits -= 8;");
+                        w.line("bytes.push((buffer >> bits) as u8);");
+                    });
+                });
+                w.line("Some(bytes)");
+            },
+        );
+        writer.empty_line();
+    }
+
+    if runtime.message_auth {
+        writer.line("/// Headers sent in a first `{\"type\":\"auth\",\"headers\":{...}}` message. Clients that sent any");
+        writer.line("/// of `names` natively don't send one.");
+        writer.line("async fn message_headers(");
+        writer.line("    socket: &mut ws::WebSocket,");
+        writer.line("    mut headers: http::HeaderMap,");
+        writer.line("    names: &[&str],");
+        writer.block(") -> Option<http::HeaderMap> {", "}", |w| {
+            w.block(
+                "if names.iter().any(|name| headers.contains_key(*name)) {",
+                "}",
+                |w| {
+                    w.line("return Some(headers);");
+                },
+            );
+            w.block("let text = loop {", "};", |w| {
+                w.block("match socket.recv().await? {", "}", |w| {
+                    w.line("Ok(ws::Message::Text(text)) => break text,");
+                    w.line("Ok(ws::Message::Ping(_) | ws::Message::Pong(_)) => continue,");
+                    w.line("_ => return None,");
+                });
+            });
+            w.line("let message: serde_json::Value = serde_json::from_str(&text).ok()?;");
+            w.block("if message[\"type\"] != \"auth\" {", "}", |w| {
+                w.line("return None;");
+            });
+            w.block(
+                "for (name, value) in message[\"headers\"].as_object()? {",
+                "}",
+                |w| {
+                    w.line("insert_fallback(&mut headers, name, value.as_str()?);");
+                },
+            );
+            w.line("Some(headers)");
+        });
+        writer.empty_line();
+    }
+
+    if runtime.websocket {
+        writer.line(
+            "/// Sends every item as a JSON text message and closes normally once the stream ends",
+        );
+        writer.line("async fn send_stream<T: serde::Serialize>(");
+        writer.line("    mut socket: ws::WebSocket,");
+        writer.line("    stream: impl futures_util::Stream<Item = T>,");
+        writer.block(
+            ") {",
+            "}",
+            |w| {
+                w.line("let mut stream = std::pin::pin!(stream);");
+                w.block("loop {", "}", |w| {
+                    w.block("let item = {", "};", |w| {
+                        w.line("let received = std::pin::pin!(socket.recv());");
+                        w.block(
+                            "match futures_util::future::select(stream.next(), received).await {",
+                            "}",
+                            |w| {
+                                w.line("futures_util::future::Either::Left((Some(item), _)) => item,");
+                                w.line("futures_util::future::Either::Left((None, _)) => break,");
+                                w.line("// Clients only send control frames; anything else ends the stream");
+                                w.block(
+                                    "futures_util::future::Either::Right((message, _)) => match message {",
+                                    "},",
+                                    |w| {
+                                        w.line("Some(Ok(ws::Message::Ping(_) | ws::Message::Pong(_))) => continue,");
+                                        w.line("_ => return,");
+                                    },
+                                );
+                            },
+                        );
+                    });
+                    w.block("let text = match serde_json::to_string(&item) {", "};", |w| {
+                        w.line("Ok(text) => text,");
+                        w.line("Err(error) => return close(socket, ws::close_code::ERROR, &error.to_string()).await,");
+                    });
+                    w.block("if socket.send(ws::Message::Text(text.into())).await.is_err() {", "}", |w| {
+                        w.line("return;");
+                    });
+                });
+                w.line("close(socket, ws::close_code::NORMAL, \"\").await;");
+            },
+        );
+        writer.empty_line();
+
+        if runtime.message_auth {
+            writer.line("/// Closes a socket whose request was rejected after the upgrade");
+            writer.block(
+                "async fn close_rejected(socket: ws::WebSocket, rejection: response::Response) {",
+                "}",
+                |w| {
+                    w.block(
+                        "let code = if rejection.status().is_server_error() {",
+                        "} else {",
+                        |w| {
+                            w.line("ws::close_code::ERROR");
+                        },
+                    );
+                    w.indent();
+                    w.line("ws::close_code::POLICY");
+                    w.dedent();
+                    w.line("};");
+                    w.line("close(socket, code, &rejection.status().to_string()).await;");
+                },
+            );
+            writer.empty_line();
+        }
+
+        writer.block(
+            "async fn close(mut socket: ws::WebSocket, code: u16, reason: &str) {",
+            "}",
+            |w| {
+                w.block("let frame = ws::CloseFrame {", "};", |w| {
+                    w.line("code,");
+                    w.line("reason: reason.into(),");
+                });
+                w.line("let _ = socket.send(ws::Message::Close(Some(frame))).await;");
+            },
+        );
+        writer.empty_line();
+    }
+}
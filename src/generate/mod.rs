@@ -1,2 +1,42 @@
+pub mod axum;
+pub mod python;
+pub mod registry;
+pub mod rust;
 pub mod typescript;
 pub mod writer;
+
+use std::path::PathBuf;
+
+use anyhow::Result;
+use serde::de::DeserializeOwned;
+
+use crate::aat::AAT;
+
+pub use registry::GeneratorRegistry;
+
+/// A code generator that turns an AAT into a set of files
+pub trait Generator {
+    /// Generator-specific settings; registry lookups deserialize them from JSON
+    type Options: Default + DeserializeOwned;
+
+    /// Name the generator is registered and selected under (`typescript`, ...)
+    fn name(&self) -> &str;
+
+    fn generate(&self, aat: &AAT, options: &Self::Options) -> Result<Vec<OutputFile>>;
+}
+
+/// A generated file, with a path relative to the output directory
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutputFile {
+    pub path: PathBuf,
+    pub content: String,
+}
+
+impl OutputFile {
+    pub fn new(path: impl Into<PathBuf>, content: impl Into<String>) -> Self {
+        Self {
+            path: path.into(),
+            content: content.into(),
+        }
+    }
+}
@@ -0,0 +1,691 @@
+mod options;
+mod runtime;
+mod types;
+
+pub use options::*;
+
+use std::collections::HashSet;
+
+use anyhow::{Result, bail};
+use types::{generate_types, py_ident, py_string, py_type};
+
+use crate::aat::{
+    AAT, Endpoint, FieldType, Header, HeaderFormat, HeaderPart, HeaderValue, HttpMethod,
+    PathSegment, Service,
+};
+use crate::case::to_pascal_case;
+use crate::generate::writer::CodeWriter;
+use crate::generate::{Generator, OutputFile};
+
+/// Generates `client.py`: the spec's types as `TypedDict`s and aliases, and a client class per
+/// service with a method per endpoint sending requests through `requests`
+///
+/// The generated module needs Python 3.11 and `requests`, plus `websocket-client` for WebSocket
+/// endpoints, which return an iterator of the received items. WebSocket headers are always sent
+/// natively, so `ws_auth` fallbacks are not used.
+pub struct PythonGenerator;
+
+impl Generator for PythonGenerator {
+    type Options = PythonOptions;
+
+    fn name(&self) -> &str {
+        "python"
+    }
+
+    fn generate(&self, aat: &AAT, options: &PythonOptions) -> Result<Vec<OutputFile>> {
+        Ok(vec![OutputFile::new(
+            "client.py",
+            Self::generate_with_options(aat, options)?,
+        )])
+    }
+}
+
+impl PythonGenerator {
+    pub fn generate(aat: &AAT) -> Result<String> {
+        Self::generate_with_options(aat, &PythonOptions::default())
+    }
+
+    pub fn generate_with_options(aat: &AAT, options: &PythonOptions) -> Result<String> {
+        let services = aat
+            .services
+            .iter()
+            .map(ClientService::new)
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut classes = vec!["ApiError".to_string(), options.client_name.clone()];
+        classes.extend(services.iter().map(|service| service.class_name.clone()));
+        if let Some(class) = classes.iter().find(|class| aat.find_type(class).is_some()) {
+            bail!(
+                "Type '{}' clashes with the generated client's `{}`; rename it with `TypeNaming`",
+                class,
+                class
+            );
+        }
+
+        let root_params = header_params(&aat.headers)?;
+        check_unique("The client", &root_params)?;
+
+        let headers = aat
+            .headers
+            .iter()
+            .chain(aat.services.iter().flat_map(|service| &service.headers))
+            .chain(
+                aat.services
+                    .iter()
+                    .flat_map(|service| &service.endpoints)
+                    .flat_map(|endpoint| &endpoint.headers),
+            );
+        let mut base64 = false;
+        for header in headers {
+            base64 |= uses_base64(header);
+        }
+        let websocket = services
+            .iter()
+            .flat_map(|service| &service.endpoints)
+            .any(|endpoint| matches!(endpoint.response, Response::Stream(_)));
+
+        let mut writer = CodeWriter::with_indent("    ");
+        writer.line("# Generated by Damascus");
+        writer.line("# Do not edit this file directly");
+        writer.empty_line();
+        if base64 {
+            writer.line("import base64");
+        }
+        writer.line("import json");
+        writer.line(
+            "from typing import Any, Dict, Iterator, List, Literal, NotRequired, Optional, Tuple, TypedDict, Union",
+        );
+        if websocket {
+            writer.line("from urllib.parse import quote, urlencode");
+        } else {
+            writer.line("from urllib.parse import quote");
+        }
+        writer.empty_line();
+        writer.line("import requests");
+        writer.empty_line();
+        writer.empty_line();
+
+        generate_types(&mut writer, &aat.types)?;
+
+        write_section(&mut writer, runtime::ERROR);
+        generate_client(&mut writer, aat, options, &services, &root_params);
+        for service in &services {
+            generate_service(&mut writer, options, service);
+        }
+
+        write_section(&mut writer, runtime::HELPERS);
+        if base64 {
+            write_section(&mut writer, runtime::BASE64);
+        }
+        if websocket {
+            write_section(&mut writer, runtime::WEBSOCKET);
+        }
+
+        let mut output = writer.into_string().trim_end().to_string();
+        output.push('\n');
+        Ok(output)
+    }
+}
+
+/// Writes a top-level definition followed by the two blank lines PEP 8 asks for
+fn write_section(writer: &mut CodeWriter, section: &str) {
+    for line in section.lines() {
+        if line.is_empty() {
+            writer.empty_line();
+        } else {
+            writer.line(line);
+        }
+    }
+    writer.empty_line();
+    writer.empty_line();
+}
+
+/// A service with its header arguments and endpoints
+struct ClientService<'a> {
+    service: &'a Service,
+    class_name: String,
+    params: Vec<Param>,
+    endpoints: Vec<ClientEndpoint<'a>>,
+}
+
+impl<'a> ClientService<'a> {
+    fn new(service: &'a Service) -> Result<Self> {
+        let params = header_params(&service.headers)?;
+        check_unique(&format!("Service '{}'", service.name), &params)?;
+        Ok(Self {
+            service,
+            class_name: format!("{}Client", to_pascal_case(&service.name)),
+            params,
+            endpoints: service
+                .endpoints
+                .iter()
+                .map(|endpoint| ClientEndpoint::new(service, endpoint))
+                .collect::<Result<_>>()?,
+        })
+    }
+}
+
+/// `self`, the locals and builtins of the generated methods and the arguments of `__init__`,
+/// which arguments are renamed around
+const METHOD_NAMES: &[&str] = &["self", "base_url", "session", "headers", "url", "dict"];
+
+/// [`py_ident`] for an argument, with a trailing underscore when it would shadow one of
+/// [`METHOD_NAMES`]
+fn param_ident(name: &str) -> String {
+    let ident = py_ident(name);
+    if METHOD_NAMES.contains(&ident.as_str()) {
+        format!("{}_", ident)
+    } else {
+        ident
+    }
+}
+
+/// An argument of a generated method. Optional arguments default to `None`.
+struct Param {
+    ident: String,
+    py_type: String,
+    optional: bool,
+}
+
+impl Param {
+    fn new(name: &str, field_type: &FieldType) -> Result<Self> {
+        Ok(Self {
+            ident: param_ident(name),
+            py_type: py_type(field_type)?,
+            optional: matches!(field_type, FieldType::Optional(_)),
+        })
+    }
+
+    fn declaration(&self) -> String {
+        if self.optional {
+            format!("{}: {} = None", self.ident, self.py_type)
+        } else {
+            format!("{}: {}", self.ident, self.py_type)
+        }
+    }
+}
+
+/// `self` and the declarations of `params`, required ones first
+fn declarations(params: &[Param]) -> Vec<String> {
+    let mut declarations = vec!["self".to_string()];
+    declarations.extend(
+        params
+            .iter()
+            .filter(|p| !p.optional)
+            .map(Param::declaration),
+    );
+    declarations.extend(params.iter().filter(|p| p.optional).map(Param::declaration));
+    declarations
+}
+
+/// An endpoint with its arguments and response resolved to Python types
+struct ClientEndpoint<'a> {
+    endpoint: &'a Endpoint,
+    method: &'static str,
+    params: Vec<Param>,
+    /// The URL after the base URL, as the contents of an f-string
+    path: String,
+    response: Response,
+}
+
+enum Response {
+    /// Read from the JSON body
+    Json(String),
+    /// Nothing to read
+    Empty,
+    /// Items of a WebSocket, one JSON message each
+    Stream(String),
+}
+
+impl<'a> ClientEndpoint<'a> {
+    fn new(service: &Service, endpoint: &'a Endpoint) -> Result<Self> {
+        let qualified_name = format!("{}.{}", service.name, endpoint.name);
+        let mut params = header_params(&endpoint.headers)?;
+
+        let mut path = String::new();
+        for segment in &endpoint.path {
+            match segment {
+                PathSegment::Literal(literal) => {
+                    path.push('/');
+                    path.push_str(&f_string_literal(literal.trim_matches('/')));
+                }
+                PathSegment::Parameter { name, r#type } => {
+                    path.push_str(&format!("/{{_path(_text({}))}}", param_ident(name)));
+                    params.push(Param::new(name, r#type)?);
+                }
+                PathSegment::Wildcard { name } => {
+                    path.push_str(&format!("/{{_path({}, True)}}", param_ident(name)));
+                    params.push(Param {
+                        ident: param_ident(name),
+                        py_type: "str".to_string(),
+                        optional: false,
+                    });
+                }
+            }
+        }
+
+        if let Some(query) = &endpoint.query {
+            params.push(Param::new("query", query)?);
+        }
+        if let Some(body) = &endpoint.body {
+            params.push(Param::new("body", body)?);
+        }
+        check_unique(&format!("Endpoint '{}'", qualified_name), &params)?;
+
+        let response = match (&endpoint.upgrade, &endpoint.response) {
+            (Some(_), FieldType::Stream(item)) => Response::Stream(py_type(item)?),
+            (Some(_), item) => Response::Stream(py_type(item)?),
+            (None, FieldType::Stream(_)) => bail!(
+                "Endpoint '{}' streams its response without a WebSocket upgrade",
+                qualified_name
+            ),
+            (None, FieldType::Any) => Response::Empty,
+            (None, response) => Response::Json(py_type(response)?),
+        };
+
+        Ok(Self {
+            endpoint,
+            method: match endpoint.method {
+                HttpMethod::Get => "GET",
+                HttpMethod::Post => "POST",
+                HttpMethod::Put => "PUT",
+                HttpMethod::Delete => "DELETE",
+                HttpMethod::Patch => "PATCH",
+            },
+            params,
+            path,
+            response,
+        })
+    }
+
+    /// The route as the server declares it, e.g. `/machines/{name}`
+    fn route(&self) -> String {
+        let route: String = self
+            .endpoint
+            .path
+            .iter()
+            .map(|segment| match segment {
+                PathSegment::Literal(literal) => format!("/{}", literal.trim_matches('/')),
+                PathSegment::Parameter { name, .. } => format!("/{{{}}}", name),
+                PathSegment::Wildcard { name } => format!("/{{*{}}}", name),
+            })
+            .collect();
+        if route.is_empty() {
+            "/".to_string()
+        } else {
+            route
+        }
+    }
+}
+
+/// Arguments for the parameterized headers in `headers`, in declaration order
+fn header_params(headers: &[Header]) -> Result<Vec<Param>> {
+    let mut params = Vec::new();
+    for header in headers {
+        for (name, field_type) in header.value.params() {
+            params.push(Param::new(name, field_type)?);
+        }
+    }
+    Ok(params)
+}
+
+fn check_unique(owner: &str, params: &[Param]) -> Result<()> {
+    let mut seen = HashSet::new();
+    for param in params {
+        if !seen.insert(&param.ident) {
+            bail!(
+                "{} has more than one argument named '{}'",
+                owner,
+                param.ident
+            );
+        }
+    }
+    Ok(())
+}
+
+fn generate_client(
+    writer: &mut CodeWriter,
+    aat: &AAT,
+    options: &PythonOptions,
+    services: &[ClientService],
+    params: &[Param],
+) {
+    writer.line(&format!("class {}:", options.client_name));
+    writer.indent();
+    writer.line("\"\"\"Sends requests to every service of the spec\"\"\"");
+    writer.empty_line();
+
+    let mut init_params = vec!["self".to_string(), "base_url: str".to_string()];
+    init_params.extend(declarations(params).into_iter().skip(1));
+    init_params.push("session: Optional[requests.Session] = None".to_string());
+    writer.line(&format!(
+        "def __init__({}) -> None:",
+        init_params.join(", ")
+    ));
+    writer.indent();
+    writer.line("self._base_url = base_url.rstrip(\"/\")");
+    writer.line("self._session = session or requests.Session()");
+    writer.line("self._headers: Dict[str, str] = {}");
+    for header in &aat.headers {
+        write_header(writer, "self._headers", header);
+    }
+    writer.dedent();
+
+    for service in services {
+        writer.empty_line();
+        writer.line(&format!(
+            "def {}({}) -> {}:",
+            py_ident(&service.service.name),
+            declarations(&service.params).join(", "),
+            py_string(&service.class_name)
+        ));
+        writer.indent();
+        writer.line(&format!(
+            "\"\"\"The `{}` service\"\"\"",
+            service.service.name
+        ));
+        if service.service.headers.is_empty() {
+            writer.line(&format!(
+                "return {}(self, self._headers)",
+                service.class_name
+            ));
+        } else {
+            writer.line("headers = dict(self._headers)");
+            for header in &service.service.headers {
+                write_header(writer, "headers", header);
+            }
+            writer.line(&format!("return {}(self, headers)", service.class_name));
+        }
+        writer.dedent();
+    }
+
+    writer.dedent();
+    writer.empty_line();
+    writer.empty_line();
+}
+
+fn generate_service(writer: &mut CodeWriter, options: &PythonOptions, service: &ClientService) {
+    writer.line(&format!("class {}:", service.class_name));
+    writer.indent();
+    writer.line(&format!(
+        "\"\"\"Endpoints of the `{}` service\"\"\"",
+        service.service.name
+    ));
+    writer.empty_line();
+    writer.line(&format!(
+        "def __init__(self, client: {}, headers: Dict[str, str]) -> None:",
+        options.client_name
+    ));
+    writer.indent();
+    writer.line("self._client = client");
+    writer.line("self._headers = headers");
+    writer.dedent();
+
+    for endpoint in &service.endpoints {
+        writer.empty_line();
+        generate_endpoint(writer, endpoint);
+    }
+
+    writer.dedent();
+    writer.empty_line();
+    writer.empty_line();
+}
+
+fn generate_endpoint(writer: &mut CodeWriter, endpoint: &ClientEndpoint) {
+    let output = match &endpoint.response {
+        Response::Json(py_type) => py_type.clone(),
+        Response::Empty => "None".to_string(),
+        Response::Stream(item) => format!("Iterator[{}]", item),
+    };
+    writer.line(&format!(
+        "def {}({}) -> {}:",
+        py_ident(&endpoint.endpoint.name),
+        declarations(&endpoint.params).join(", "),
+        output
+    ));
+    writer.indent();
+    let upgrade = if matches!(endpoint.response, Response::Stream(_)) {
+        " (WebSocket)"
+    } else {
+        ""
+    };
+    writer.line(&format!(
+        "\"\"\"`{} {}`{}\"\"\"",
+        endpoint.method,
+        endpoint.route(),
+        upgrade
+    ));
+
+    let headers = if endpoint.endpoint.headers.is_empty() {
+        "self._headers"
+    } else {
+        writer.line("headers = dict(self._headers)");
+        for header in &endpoint.endpoint.headers {
+            write_header(writer, "headers", header);
+        }
+        "headers"
+    };
+    writer.line(&format!(
+        "url = f\"{{self._client._base_url}}{}\"",
+        endpoint.path
+    ));
+
+    if let Response::Stream(_) = endpoint.response {
+        let query = if endpoint.endpoint.query.is_some() {
+            "_query(query)"
+        } else {
+            "{}"
+        };
+        writer.line(&format!("return _connect(url, {}, {})", headers, query));
+    } else {
+        let mut args = vec![
+            "self._client._session".to_string(),
+            py_string(endpoint.method),
+            "url".to_string(),
+            headers.to_string(),
+        ];
+        if endpoint.endpoint.query.is_some() {
+            args.push("params=_query(query)".to_string());
+        }
+        if endpoint.endpoint.body.is_some() {
+            args.push("json=body".to_string());
+        }
+        let send = format!("_send({})", args.join(", "));
+        match endpoint.response {
+            Response::Json(_) => writer.line(&format!("return {}.json()", send)),
+            _ => writer.line(&send),
+        }
+    }
+    writer.dedent();
+}
+
+/// Sets `header` in the dict `target`, reading its parameters from the arguments of the same
+/// name. Headers with optional parameters are only sent once all of them are given.
+fn write_header(writer: &mut CodeWriter, target: &str, header: &Header) {
+    let optional: Vec<String> = header
+        .value
+        .params()
+        .iter()
+        .filter(|(_, field_type)| matches!(field_type, FieldType::Optional(_)))
+        .map(|(name, _)| param_ident(name))
+        .collect();
+
+    let value = match &header.value {
+        HeaderValue::Literal(value) => py_string(value),
+        HeaderValue::Parameter { name, .. } => format!("_text({})", param_ident(name)),
+        HeaderValue::Pattern { parts } => {
+            let mut value = String::from("f\"");
+            for part in parts {
+                match part {
+                    HeaderPart::Literal(literal) => value.push_str(&f_string_literal(literal)),
+                    HeaderPart::Param { name, format, .. } => {
+                        let text = format!("_text({})", param_ident(name));
+                        value.push('{');
+                        value.push_str(&match format {
+                            Some(HeaderFormat::Base64) => format!("_base64({})", text),
+                            Some(HeaderFormat::Lowercase) => format!("{}.lower()", text),
+                            // Dates are already ISO 8601 strings
+                            Some(HeaderFormat::IsoDate) | None => text,
+                        });
+                        value.push('}');
+                    }
+                }
+            }
+            value.push('"');
+            value
+        }
+    };
+
+    let assignment = format!("{}[{}] = {}", target, py_string(&header.name), value);
+    if optional.is_empty() {
+        writer.line(&assignment);
+    } else {
+        let condition: Vec<String> = optional
+            .iter()
+            .map(|ident| format!("{} is not None", ident))
+            .collect();
+        writer.line(&format!("if {}:", condition.join(" and ")));
+        writer.indent();
+        writer.line(&assignment);
+        writer.dedent();
+    }
+}
+
+/// Literal text inside a double-quoted f-string
+fn f_string_literal(text: &str) -> String {
+    let quoted = py_string(text);
+    quoted[1..quoted.len() - 1]
+        .replace('{', "{{")
+        .replace('}', "}}")
+}
+
+fn uses_base64(header: &Header) -> bool {
+    match &header.value {
+        HeaderValue::Pattern { parts } => parts.iter().any(|part| {
+            matches!(
+                part,
+                HeaderPart::Param {
+                    format: Some(HeaderFormat::Base64),
+                    ..
+                }
+            )
+        }),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spec::{self, PathSegment, Spec, Type};
+    use schemars::JsonSchema;
+
+    #[derive(JsonSchema)]
+    #[allow(dead_code)]
+    struct Machine {
+        name: String,
+        #[serde(rename = "restart-policy")]
+        restart_policy: Option<String>,
+        build: Build,
+    }
+
+    #[derive(JsonSchema)]
+    #[allow(dead_code)]
+    #[serde(rename_all = "snake_case")]
+    enum Build {
+        Auto,
+        Docker { image: String },
+        Options(BuildOptions),
+    }
+
+    #[derive(JsonSchema)]
+    #[allow(dead_code)]
+    struct BuildOptions {
+        dir: String,
+    }
+
+    fn client() -> String {
+        let spec = Spec::new("test").service("machines", |service| {
+            service
+                .header(
+                    "x-namespace",
+                    spec::HeaderValue::Type {
+                        name: "namespace".to_string(),
+                        r#type: Type::optional(Type::schema::<String>()),
+                    },
+                )
+                .get(
+                    "list",
+                    vec![
+                        PathSegment::Literal("machines".into()),
+                        PathSegment::Type {
+                            name: "pool".to_string(),
+                            r#type: Type::schema::<String>(),
+                        },
+                    ],
+                    |e| e.response(Type::list(Type::schema::<Machine>())),
+                )
+        });
+        PythonGenerator::generate(&AAT::from_spec(&spec).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn declares_types_as_typed_dicts() {
+        let client = client();
+        // `restart-policy` is no identifier, so the functional syntax is needed
+        assert!(client.contains(
+            "Machine = TypedDict(\n    \"Machine\",\n    {\n        \"build\": \"Build\",\n        \"name\": str,\n        \"restart-policy\": NotRequired[Optional[str]],\n    },\n)"
+        ));
+        assert!(
+            client.contains("class BuildDocker(TypedDict):\n    docker: \"BuildDockerFields\"")
+        );
+        // `BuildOptions` is taken by the spec's own type
+        assert!(
+            client.contains("class BuildOptionsVariant(TypedDict):\n    options: \"BuildOptions\"")
+        );
+        assert!(
+            client.contains("Build = Union[Literal[\"auto\"], BuildDocker, BuildOptionsVariant]")
+        );
+    }
+
+    #[test]
+    fn sends_optional_headers_only_when_given() {
+        let client = client();
+        assert!(client.contains(
+            "    def machines(self, namespace: Optional[str] = None) -> \"MachinesClient\":\n        \"\"\"The `machines` service\"\"\"\n        headers = dict(self._headers)\n        if namespace is not None:\n            headers[\"x-namespace\"] = _text(namespace)\n"
+        ));
+        assert!(client.contains(
+            "        url = f\"{self._client._base_url}/machines/{_path(_text(pool))}\"\n        return _send(self._client._session, \"GET\", url, self._headers).json()"
+        ));
+        // No WebSocket endpoints and no base64 headers
+        assert!(!client.contains("def _connect"));
+        assert!(!client.contains("import base64"));
+    }
+
+    #[test]
+    fn renames_arguments_named_like_the_methods_variables() {
+        let spec = Spec::new("test").service("files", |service| {
+            service.get(
+                "read",
+                vec![
+                    PathSegment::Literal("files".into()),
+                    PathSegment::Wildcard("url".to_string()),
+                ],
+                |e| {
+                    e.response(Type::schema::<String>()).header(
+                        "x-headers",
+                        spec::HeaderValue::Type {
+                            name: "headers".to_string(),
+                            r#type: Type::schema::<String>(),
+                        },
+                    )
+                },
+            )
+        });
+        let client = PythonGenerator::generate(&AAT::from_spec(&spec).unwrap()).unwrap();
+        assert!(client.contains("    def read(self, headers_: str, url_: str) -> str:"));
+        assert!(client.contains(
+            "        headers = dict(self._headers)\n        headers[\"x-headers\"] = _text(headers_)\n        url = f\"{self._client._base_url}/files/{_path(url_, True)}\"\n"
+        ));
+    }
+}
@@ -0,0 +1,17 @@
+use serde::{Deserialize, Serialize};
+
+/// Options controlling the generated Python client
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PythonOptions {
+    /// Name of the class handing out the service clients
+    pub client_name: String,
+}
+
+impl Default for PythonOptions {
+    fn default() -> Self {
+        Self {
+            client_name: "Client".to_string(),
+        }
+    }
+}
@@ -0,0 +1,64 @@
+//! Helpers the generated client calls, written at the end of `client.py`
+
+pub const ERROR: &str = r#"class ApiError(Exception):
+    """The server answered with an error status"""
+
+    def __init__(self, status: int, body: str) -> None:
+        super().__init__(f"Server answered {status}: {body}")
+        self.status = status
+        self.body = body"#;
+
+pub const HELPERS: &str = r#"def _send(session: requests.Session, method: str, url: str, headers: Dict[str, str], **kwargs: Any) -> requests.Response:
+    """Sends a request, raising `ApiError` for error statuses"""
+    response = session.request(method, url, headers=headers, **kwargs)
+    if not response.ok:
+        raise ApiError(response.status_code, response.text)
+    return response
+
+
+def _text(value: Any) -> str:
+    """The text of a path, query or header argument: strings as they are, other values as JSON"""
+    if isinstance(value, str):
+        return value
+    return json.dumps(value, separators=(",", ":"))
+
+
+def _path(text: str, keep_slashes: bool = False) -> str:
+    """Percent-encodes a path argument, keeping `/` when it fills a trailing wildcard"""
+    return quote(text, safe="/" if keep_slashes else "")
+
+
+def _query(values: Optional[Dict[str, Any]]) -> Dict[str, Any]:
+    """Query parameters as text, leaving out missing values and repeating lists"""
+    params: Dict[str, Any] = {}
+    for name, value in (values or {}).items():
+        if value is None:
+            continue
+        if isinstance(value, list):
+            params[name] = [_text(item) for item in value]
+        else:
+            params[name] = _text(value)
+    return params"#;
+
+pub const BASE64: &str = r#"def _base64(text: str) -> str:
+    """Standard base64 of the UTF-8 text"""
+    return base64.b64encode(text.encode()).decode()"#;
+
+pub const WEBSOCKET: &str = r#"def _connect(url: str, headers: Dict[str, str], params: Dict[str, Any]) -> Iterator[Any]:
+    """Opens a WebSocket once iterated, yielding every message as a JSON item until it closes.
+    Needs the `websocket-client` package."""
+    import websocket
+
+    if params:
+        url += "?" + urlencode(params, doseq=True)
+    url = "ws" + url[len("http"):]
+    socket = websocket.create_connection(url, header=[f"{name}: {value}" for name, value in headers.items()])
+    try:
+        while True:
+            try:
+                message = socket.recv()
+            except websocket.WebSocketConnectionClosedException:
+                return
+            yield json.loads(message)
+    finally:
+        socket.close()"#;
@@ -0,0 +1,262 @@
+use std::collections::HashSet;
+
+use anyhow::{Result, bail};
+
+use crate::aat::{
+    FieldType, LiteralType, NamedType, ObjectType, PrimitiveType, UnionType, UnionTypeVariantMode,
+    get_type_name,
+};
+use crate::case::{to_pascal_case, to_snake_case};
+use crate::generate::writer::CodeWriter;
+
+const KEYWORDS: &[&str] = &[
+    "False", "None", "True", "and", "as", "assert", "async", "await", "break", "class", "continue",
+    "def", "del", "elif", "else", "except", "finally", "for", "from", "global", "if", "import",
+    "in", "is", "lambda", "nonlocal", "not", "or", "pass", "raise", "return", "try", "while",
+    "with", "yield",
+];
+
+/// A snake_case identifier for a spec name, escaping keywords
+pub fn py_ident(name: &str) -> String {
+    let ident = to_snake_case(name);
+    if ident.is_empty() || ident.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("_{}", ident)
+    } else if KEYWORDS.contains(&ident.as_str()) {
+        format!("{}_", ident)
+    } else {
+        ident
+    }
+}
+
+/// Whether `name` can be a TypedDict field declared with class syntax
+fn is_identifier(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && !KEYWORDS.contains(&name)
+}
+
+/// `value` as a Python string literal
+pub fn py_string(value: &str) -> String {
+    let mut literal = String::from("\"");
+    for c in value.chars() {
+        match c {
+            '\\' => literal.push_str("\\\\"),
+            '"' => literal.push_str("\\\""),
+            '\n' => literal.push_str("\\n"),
+            '\r' => literal.push_str("\\r"),
+            '\t' => literal.push_str("\\t"),
+            c if c.is_control() => literal.push_str(&format!("\\u{:04x}", c as u32)),
+            c => literal.push(c),
+        }
+    }
+    literal.push('"');
+    literal
+}
+
+/// The value of a literal inside `Literal[...]`, or `None` for floats, which it can't hold
+fn literal_value(literal: &LiteralType) -> Option<String> {
+    match literal {
+        LiteralType::String(value) => Some(py_string(value)),
+        LiteralType::Int(value) => Some(value.to_string()),
+        LiteralType::Bool(true) => Some("True".to_string()),
+        LiteralType::Bool(false) => Some("False".to_string()),
+        LiteralType::Null => Some("None".to_string()),
+        LiteralType::Float(_) => None,
+    }
+}
+
+fn literal_type(literals: &[&LiteralType]) -> String {
+    let values: Vec<String> = literals
+        .iter()
+        .filter_map(|lit| literal_value(lit))
+        .collect();
+    let has_float = values.len() < literals.len();
+    match (values.is_empty(), has_float) {
+        (true, _) => "float".to_string(),
+        (false, false) => format!("Literal[{}]", values.join(", ")),
+        (false, true) => format!("Union[Literal[{}], float]", values.join(", ")),
+    }
+}
+
+/// The annotation for a field type. Named types are quoted so they may be declared later.
+pub fn py_type(field_type: &FieldType) -> Result<String> {
+    Ok(match field_type {
+        FieldType::Primitive(prim) => match prim {
+            PrimitiveType::Bool => "bool",
+            PrimitiveType::Int(_) => "int",
+            PrimitiveType::Float => "float",
+            PrimitiveType::String(_) => "str",
+        }
+        .to_string(),
+        FieldType::Literal(lit) => literal_type(&[lit]),
+        FieldType::Optional(inner) => format!("Optional[{}]", py_type(inner)?),
+        FieldType::List(inner) => format!("List[{}]", py_type(inner)?),
+        FieldType::Map(inner) => format!("Dict[str, {}]", py_type(inner)?),
+        FieldType::Reference(name) => py_string(name),
+        FieldType::Tuple(types) => format!(
+            "Tuple[{}]",
+            types
+                .iter()
+                .map(py_type)
+                .collect::<Result<Vec<_>>>()?
+                .join(", ")
+        ),
+        FieldType::Intersection(_) | FieldType::Any => "Any".to_string(),
+        FieldType::Stream(_) => bail!("Stream types are only supported as WebSocket responses"),
+    })
+}
+
+/// Declares the spec's named types: objects as `TypedDict`s, unions and enums as aliases
+pub fn generate_types(writer: &mut CodeWriter, types: &[NamedType]) -> Result<()> {
+    let mut taken: HashSet<String> = types.iter().map(|t| get_type_name(t).to_string()).collect();
+    for named_type in types {
+        match named_type {
+            NamedType::Object(object) => {
+                let fields = object_fields(&object.fields)?;
+                typed_dict(writer, &object.name, &fields);
+            }
+            NamedType::Union(union) => generate_union(writer, union, &mut taken)?,
+            NamedType::Enum(enumeration) => {
+                let values: Vec<&LiteralType> = enumeration
+                    .variants
+                    .iter()
+                    .map(|variant| &variant.value)
+                    .collect();
+                writer.line(&format!("{} = {}", enumeration.name, literal_type(&values)));
+                writer.empty_line();
+                writer.empty_line();
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Wire names and annotations of object fields. Optional fields may be left out.
+fn object_fields(fields: &[crate::aat::Field]) -> Result<Vec<(String, String)>> {
+    fields
+        .iter()
+        .map(|field| {
+            let annotation = py_type(&field.r#type)?;
+            Ok(match &*field.r#type {
+                FieldType::Optional(_) => {
+                    (field.name.clone(), format!("NotRequired[{}]", annotation))
+                }
+                _ => (field.name.clone(), annotation),
+            })
+        })
+        .collect()
+}
+
+fn typed_dict(writer: &mut CodeWriter, name: &str, fields: &[(String, String)]) {
+    if fields.iter().all(|(field, _)| is_identifier(field)) {
+        writer.line(&format!("class {}(TypedDict):", name));
+        writer.indent();
+        if fields.is_empty() {
+            writer.line("pass");
+        }
+        for (field, annotation) in fields {
+            writer.line(&format!("{}: {}", field, annotation));
+        }
+        writer.dedent();
+    } else {
+        // Keys that aren't identifiers need the functional syntax
+        writer.line(&format!("{} = TypedDict(", name));
+        writer.indent();
+        writer.line(&format!("{},", py_string(name)));
+        writer.line("{");
+        writer.indent();
+        for (field, annotation) in fields {
+            writer.line(&format!("{}: {},", py_string(field), annotation));
+        }
+        writer.dedent();
+        writer.line("},");
+        writer.dedent();
+        writer.line(")");
+    }
+    writer.empty_line();
+    writer.empty_line();
+}
+
+/// Variant objects become `TypedDict`s named after the union and the variant, shaped like their
+/// JSON: flat with the discriminator as a literal field, or wrapped in their name when untagged
+fn generate_union(
+    writer: &mut CodeWriter,
+    union: &UnionType,
+    taken: &mut HashSet<String>,
+) -> Result<()> {
+    let mut members = Vec::new();
+    let mut literals = Vec::new();
+    for (index, variant) in union.variants.iter().enumerate() {
+        let object = match &*variant.mode {
+            UnionTypeVariantMode::Literal(lit) => {
+                literals.push(lit);
+                continue;
+            }
+            UnionTypeVariantMode::Object(object) => object,
+        };
+        let mut class_name = format!(
+            "{}{}",
+            union.name,
+            match &variant.name {
+                Some(name) => to_pascal_case(name),
+                None => format!("Variant{}", index + 1),
+            }
+        );
+        while !taken.insert(class_name.clone()) {
+            class_name.push_str("Variant");
+        }
+        let fields = variant_fields(union, variant.name.as_deref(), object, &class_name, writer)?;
+        typed_dict(writer, &class_name, &fields);
+        // Declared just above, so it needs no quotes
+        members.push(class_name);
+    }
+    if !literals.is_empty() {
+        members.insert(0, literal_type(&literals));
+    }
+    let alias = match members.len() {
+        0 => "Any".to_string(),
+        1 => members.remove(0),
+        _ => format!("Union[{}]", members.join(", ")),
+    };
+    writer.line(&format!("{} = {}", union.name, alias));
+    writer.empty_line();
+    writer.empty_line();
+    Ok(())
+}
+
+fn variant_fields(
+    union: &UnionType,
+    name: Option<&str>,
+    object: &ObjectType,
+    class_name: &str,
+    writer: &mut CodeWriter,
+) -> Result<Vec<(String, String)>> {
+    let mut fields = object_fields(&object.fields)?;
+    match (&union.discriminator, name) {
+        (Some(discriminator), name) => {
+            let has_tag = fields
+                .iter()
+                .any(|(field, _)| *field == discriminator.property_name);
+            if let (false, Some(name)) = (has_tag, name) {
+                let tag = format!("Literal[{}]", py_string(&discriminator.tag_for(name)));
+                fields.insert(0, (discriminator.property_name.clone(), tag));
+            }
+            Ok(fields)
+        }
+        (None, Some(name))
+            if object.fields.len() == 1
+                && matches!(*object.fields[0].r#type, FieldType::Reference(_)) =>
+        {
+            Ok(vec![(name.to_string(), py_type(&object.fields[0].r#type)?)])
+        }
+        (None, Some(name)) if object.fields.len() == 1 && object.fields[0].name == name => {
+            Ok(fields)
+        }
+        (None, Some(name)) => {
+            let inner = format!("{}Fields", class_name);
+            typed_dict(writer, &inner, &fields);
+            Ok(vec![(name.to_string(), py_string(&inner))])
+        }
+        (None, None) => Ok(fields),
+    }
+}
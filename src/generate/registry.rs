@@ -0,0 +1,203 @@
+use std::collections::BTreeMap;
+
+use anyhow::{Context, Result, bail};
+use serde_json::Value;
+
+use super::axum::AxumGenerator;
+use super::python::PythonGenerator;
+use super::rust::RustGenerator;
+use super::typescript::TypeScriptGenerator;
+use super::{Generator, OutputFile};
+use crate::aat::AAT;
+
+/// Generators selectable by name, with options given as JSON
+///
+/// Downstream crates add their own generators with [`GeneratorRegistry::register`]:
+///
+/// ```
+/// # use anyhow::Result;
+/// # use damascus::aat::AAT;
+/// # use damascus::generate::{Generator, GeneratorRegistry, OutputFile};
+/// # use serde::Deserialize;
+/// # use serde_json::json;
+/// #[derive(Default, Deserialize)]
+/// struct KotlinOptions {
+///     package: String,
+/// }
+///
+/// struct KotlinGenerator;
+///
+/// impl Generator for KotlinGenerator {
+///     type Options = KotlinOptions;
+///
+///     fn name(&self) -> &str {
+///         "kotlin"
+///     }
+///
+///     fn generate(&self, aat: &AAT, options: &KotlinOptions) -> Result<Vec<OutputFile>> {
+///         let client = format!("package {}\n// {} services", options.package, aat.services.len());
+///         Ok(vec![OutputFile::new("Client.kt", client)])
+///     }
+/// }
+///
+/// # fn main() -> Result<()> {
+/// # let aat = AAT::from_spec(&damascus::spec::Spec::new("api"))?;
+/// let registry = GeneratorRegistry::with_builtin().register(KotlinGenerator);
+/// let files = registry.generate("kotlin", &aat, &json!({ "package": "com.example.api" }))?;
+/// assert_eq!(files[0].content, "package com.example.api\n// 0 services");
+/// # Ok(())
+/// # }
+/// ```
+pub struct GeneratorRegistry {
+    generators: BTreeMap<String, Box<dyn DynGenerator>>,
+}
+
+impl GeneratorRegistry {
+    /// An empty registry
+    pub fn new() -> Self {
+        Self {
+            generators: BTreeMap::new(),
+        }
+    }
+
+    /// A registry with the generators shipped with Damascus
+    pub fn with_builtin() -> Self {
+        Self::new()
+            .register(TypeScriptGenerator::new())
+            .register(AxumGenerator)
+            .register(RustGenerator)
+            .register(PythonGenerator)
+    }
+
+    /// Adds a generator, replacing any generator registered under the same name
+    pub fn register<G: Generator + 'static>(mut self, generator: G) -> Self {
+        self.generators
+            .insert(generator.name().to_string(), Box::new(generator));
+        self
+    }
+
+    /// Names of the registered generators, in alphabetical order
+    pub fn names(&self) -> Vec<&str> {
+        self.generators.keys().map(|name| name.as_str()).collect()
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.generators.contains_key(name)
+    }
+
+    /// Runs the generator registered under `name`. `options` is deserialized into the
+    /// generator's options type; `null` selects the defaults.
+    pub fn generate(&self, name: &str, aat: &AAT, options: &Value) -> Result<Vec<OutputFile>> {
+        let Some(generator) = self.generators.get(name) else {
+            bail!(
+                "Unknown generator '{}' (available: {})",
+                name,
+                self.names().join(", ")
+            );
+        };
+        generator.generate_json(aat, options)
+    }
+}
+
+impl Default for GeneratorRegistry {
+    fn default() -> Self {
+        Self::with_builtin()
+    }
+}
+
+/// Object-safe view of a [`Generator`] taking its options as JSON
+trait DynGenerator {
+    fn generate_json(&self, aat: &AAT, options: &Value) -> Result<Vec<OutputFile>>;
+}
+
+impl<G: Generator> DynGenerator for G {
+    fn generate_json(&self, aat: &AAT, options: &Value) -> Result<Vec<OutputFile>> {
+        let options = if options.is_null() {
+            G::Options::default()
+        } else {
+            serde_json::from_value(options.clone())
+                .with_context(|| format!("Invalid options for generator '{}'", self.name()))?
+        };
+        self.generate(aat, &options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{Machine, returning};
+    use crate::generate::typescript::{Layout, TypeScriptOptions};
+    use crate::spec::Type;
+    use serde_json::json;
+
+    fn aat() -> AAT {
+        returning(Type::schema::<Machine>())
+    }
+
+    fn paths(files: &[OutputFile]) -> Vec<String> {
+        files
+            .iter()
+            .map(|file| file.path.display().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn lists_the_builtin_generators() {
+        let registry = GeneratorRegistry::with_builtin();
+        assert_eq!(registry.names(), ["axum", "python", "rust", "typescript"]);
+        assert!(registry.contains("typescript"));
+        assert!(!registry.contains("kotlin"));
+        assert!(GeneratorRegistry::new().names().is_empty());
+    }
+
+    #[test]
+    fn passes_json_options_to_the_generator() {
+        let registry = GeneratorRegistry::with_builtin();
+        let files = registry
+            .generate("typescript", &aat(), &Value::Null)
+            .unwrap();
+        assert_eq!(paths(&files), ["client.ts"]);
+
+        let files = registry
+            .generate("typescript", &aat(), &json!({ "layout": "modules" }))
+            .unwrap();
+        let expected = TypeScriptGenerator::new()
+            .generate(
+                &aat(),
+                &TypeScriptOptions {
+                    layout: Layout::Modules,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        assert_eq!(files, expected);
+
+        let files = registry
+            .generate("axum", &aat(), &json!({ "types_module": "crate::api" }))
+            .unwrap();
+        assert!(files[0].content.contains("use crate::api::*;"));
+    }
+
+    #[test]
+    fn rejects_unknown_generators() {
+        let error = GeneratorRegistry::with_builtin()
+            .generate("kotlin", &aat(), &Value::Null)
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Unknown generator 'kotlin' (available: axum, python, rust, typescript)"
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_options() {
+        let error = GeneratorRegistry::with_builtin()
+            .generate("typescript", &aat(), &json!({ "layout": "folders" }))
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Invalid options for generator 'typescript'"
+        );
+        assert!(format!("{:#}", error).contains("unknown variant `folders`"));
+    }
+}
@@ -0,0 +1,143 @@
+//! Laying out generated Rust the way rustfmt would, so the output passes `cargo fmt --check`
+
+use crate::generate::writer::CodeWriter;
+
+/// `value` as the contents of a string literal
+pub fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Column limit the generated code is laid out for, as rustfmt would
+pub const MAX_WIDTH: usize = 100;
+
+/// Longest argument list rustfmt keeps on the line of a call
+pub const CALL_WIDTH: usize = 60;
+
+/// Writes `{prefix}({items}){suffix}` on one line, or one item per line when the line, indented
+/// by `indent` columns, would be too long
+pub fn list(writer: &mut CodeWriter, indent: usize, prefix: &str, items: &[String], suffix: &str) {
+    let line = format!("{}({}){}", prefix, items.join(", "), suffix);
+    if indent + line.len() <= MAX_WIDTH {
+        writer.line(&line);
+    } else {
+        list_vertical(writer, prefix, items, suffix);
+    }
+}
+
+/// Like [`list`] for call arguments, which are also broken up when they're long on their own
+pub fn call(writer: &mut CodeWriter, indent: usize, prefix: &str, args: &[String], suffix: &str) {
+    if args.join(", ").len() > CALL_WIDTH {
+        list_vertical(writer, prefix, args, suffix);
+    } else {
+        list(writer, indent, prefix, args, suffix);
+    }
+}
+
+/// Writes `let {binding} = {function}({args}){suffix}`. Like rustfmt, a call that would be broken
+/// up moves to its own line instead when it fits there.
+pub fn let_call(
+    writer: &mut CodeWriter,
+    indent: usize,
+    binding: &str,
+    function: &str,
+    args: &[String],
+    suffix: &str,
+) {
+    let joined = args.join(", ");
+    let value = format!("{}({}){}", function, joined, suffix);
+    let line = format!("let {} = {}", binding, value);
+    if joined.len() <= CALL_WIDTH
+        && indent + line.len() > MAX_WIDTH
+        && indent + 4 + value.len() <= MAX_WIDTH
+    {
+        writer.line(&format!("let {} =", binding));
+        writer.indent();
+        writer.line(&value);
+        writer.dedent();
+    } else {
+        call(
+            writer,
+            indent,
+            &format!("let {} = {}", binding, function),
+            args,
+            suffix,
+        );
+    }
+}
+
+pub fn list_vertical(writer: &mut CodeWriter, prefix: &str, items: &[String], suffix: &str) {
+    writer.line(&format!("{}(", prefix));
+    writer.indent();
+    for item in items {
+        writer.line(&format!("{},", item));
+    }
+    writer.dedent();
+    writer.line(&format!("){}", suffix));
+}
+
+/// Like [`call`] for macros, which rustfmt leaves without a trailing comma when broken up
+pub fn macro_call(
+    writer: &mut CodeWriter,
+    indent: usize,
+    prefix: &str,
+    args: &[String],
+    suffix: &str,
+) {
+    let line = format!("{}({}){}", prefix, args.join(", "), suffix);
+    if args.join(", ").len() <= CALL_WIDTH && indent + line.len() <= MAX_WIDTH {
+        writer.line(&line);
+        return;
+    }
+    writer.line(&format!("{}(", prefix));
+    writer.indent();
+    for (index, arg) in args.iter().enumerate() {
+        if index + 1 == args.len() {
+            writer.line(arg);
+        } else {
+            writer.line(&format!("{},", arg));
+        }
+    }
+    writer.dedent();
+    writer.line(&format!("){}", suffix));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn let_lines(indent: usize, binding: &str, args: &[&str]) -> String {
+        let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+        let mut writer = CodeWriter::with_indent("    ");
+        let_call(&mut writer, indent, binding, "read", &args, "?;");
+        writer.into_string()
+    }
+
+    #[test]
+    fn let_call_stays_on_one_line_when_it_fits() {
+        assert_eq!(
+            let_lines(4, "value", &["&headers", "0"]),
+            "let value = read(&headers, 0)?;\n"
+        );
+    }
+
+    #[test]
+    fn let_call_moves_the_call_to_its_own_line() {
+        let binding = format!("value: {}", "Wrapper".repeat(8));
+        assert_eq!(
+            let_lines(4, &binding, &["&headers", "\"x-signature\"", "1"]),
+            format!(
+                "let {} =\n    read(&headers, \"x-signature\", 1)?;\n",
+                binding
+            )
+        );
+    }
+
+    #[test]
+    fn let_call_breaks_up_long_arguments() {
+        let long = "x".repeat(CALL_WIDTH);
+        assert_eq!(
+            let_lines(4, "value", &[&long, "1"]),
+            format!("let value = read(\n    {},\n    1,\n)?;\n", long)
+        );
+    }
+}
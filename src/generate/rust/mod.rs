@@ -0,0 +1,817 @@
+pub(crate) mod layout;
+mod options;
+mod runtime;
+pub(crate) mod types;
+
+pub use options::*;
+
+use std::collections::HashSet;
+
+use anyhow::{Result, bail};
+use layout::{MAX_WIDTH, call, escape, list, macro_call};
+use runtime::{Runtime, generate_runtime};
+use types::{param_ident, pascal_case, rust_ident, rust_type};
+
+use crate::aat::{
+    AAT, Endpoint, FieldType, Header, HeaderFormat, HeaderPart, HeaderValue, HttpMethod,
+    PathSegment, Service,
+};
+use crate::generate::writer::CodeWriter;
+use crate::generate::{Generator, OutputFile};
+
+/// Generates `client.rs`: a `Client` handing out one client per service, with an async method
+/// per endpoint sending requests through `reqwest`
+///
+/// The generated module needs `reqwest` 0.12 with the `json` feature, `serde` and `serde_json`,
+/// plus `tokio-tungstenite` 0.29 and `futures-util` for WebSocket endpoints. WebSocket headers
+/// are always sent natively, so `ws_auth` fallbacks are not used.
+pub struct RustGenerator;
+
+impl Generator for RustGenerator {
+    type Options = RustOptions;
+
+    fn name(&self) -> &str {
+        "rust"
+    }
+
+    fn generate(&self, aat: &AAT, options: &RustOptions) -> Result<Vec<OutputFile>> {
+        Ok(vec![OutputFile::new(
+            "client.rs",
+            Self::generate_with_options(aat, options)?,
+        )])
+    }
+}
+
+impl RustGenerator {
+    pub fn generate(aat: &AAT) -> Result<String> {
+        Self::generate_with_options(aat, &RustOptions::default())
+    }
+
+    pub fn generate_with_options(aat: &AAT, options: &RustOptions) -> Result<String> {
+        let services = aat
+            .services
+            .iter()
+            .map(ClientService::new)
+            .collect::<Result<Vec<_>>>()?;
+
+        // The generated items shadow the spec's types, which are glob-imported
+        let mut items = vec!["Client".to_string(), "Error".to_string()];
+        items.extend(services.iter().map(|service| service.struct_name.clone()));
+        let mut runtime = Runtime::default();
+        for endpoint in services.iter().flat_map(|service| &service.endpoints) {
+            endpoint.register(&mut runtime);
+        }
+        if runtime.websocket {
+            items.push("Stream".to_string());
+        }
+        if let Some(item) = items.iter().find(|item| aat.find_type(item).is_some()) {
+            bail!(
+                "Type '{}' clashes with the generated client's `{}`; rename it with `TypeNaming`",
+                item,
+                item
+            );
+        }
+        for header in &aat.headers {
+            runtime.register_header(header);
+        }
+        for service in &aat.services {
+            for header in &service.headers {
+                runtime.register_header(header);
+            }
+        }
+
+        let root_params = header_params(&aat.headers)?;
+        check_unique("The client", &root_params)?;
+
+        let mut writer = CodeWriter::with_indent("    ");
+        writer.line("// Generated by Damascus");
+        writer.line("// Do not edit this file directly");
+        writer.empty_line();
+        writer.block("#![allow(", ")]", |w| {
+            w.line("clippy::too_many_arguments,");
+            w.line("clippy::result_large_err,");
+            w.line("clippy::vec_init_then_push");
+        });
+        writer.empty_line();
+        if runtime.websocket {
+            writer.line("use futures_util::StreamExt as _;");
+            writer.empty_line();
+        }
+        if !aat.types.is_empty() {
+            writer.line(&format!("use {}::*;", options.types_module));
+            writer.empty_line();
+        }
+
+        generate_error(&mut writer, &runtime);
+        writer.empty_line();
+
+        generate_client(&mut writer, aat, &services, &root_params);
+        writer.empty_line();
+
+        for service in &services {
+            generate_service(&mut writer, service);
+            writer.empty_line();
+        }
+
+        generate_runtime(&mut writer, &runtime);
+
+        let mut output = writer.into_string().trim_end().to_string();
+        output.push('\n');
+        Ok(output)
+    }
+}
+
+/// A service with its header arguments and endpoints
+struct ClientService<'a> {
+    service: &'a Service,
+    struct_name: String,
+    params: Vec<Param>,
+    endpoints: Vec<ClientEndpoint<'a>>,
+}
+
+impl<'a> ClientService<'a> {
+    fn new(service: &'a Service) -> Result<Self> {
+        let params = header_params(&service.headers)?;
+        check_unique(&format!("Service '{}'", service.name), &params)?;
+        Ok(Self {
+            service,
+            struct_name: format!("{}Client", pascal_case(&service.name)),
+            params,
+            endpoints: service
+                .endpoints
+                .iter()
+                .map(|endpoint| ClientEndpoint::new(service, endpoint))
+                .collect::<Result<_>>()?,
+        })
+    }
+
+    fn ident(&self) -> String {
+        rust_ident(&self.service.name)
+    }
+}
+
+/// The runtime functions and locals of the generated client, which arguments are renamed around
+const CLIENT_NAMES: &[&str] = &[
+    "http", "base_url", "headers", "url", "request", "response", "value", "send", "json", "text",
+    "path", "base64", "connect",
+];
+
+/// An argument of a generated function
+struct Param {
+    ident: String,
+    rust_type: String,
+}
+
+impl Param {
+    fn declaration(&self) -> String {
+        format!("{}: {}", self.ident, self.rust_type)
+    }
+}
+
+/// An endpoint with its arguments and response resolved to Rust types
+struct ClientEndpoint<'a> {
+    endpoint: &'a Endpoint,
+    /// `reqwest::Client` method name
+    method: &'static str,
+    params: Vec<Param>,
+    /// `format!` template of the path and its arguments
+    path: (String, Vec<String>),
+    response: Response,
+}
+
+enum Response {
+    /// Read from the JSON body
+    Json(String),
+    /// Nothing to read
+    Empty,
+    /// Items of a WebSocket, one JSON message each
+    Stream(String),
+}
+
+impl<'a> ClientEndpoint<'a> {
+    fn new(service: &Service, endpoint: &'a Endpoint) -> Result<Self> {
+        let qualified_name = format!("{}.{}", service.name, endpoint.name);
+        let mut params = header_params(&endpoint.headers)?;
+
+        let mut template = String::new();
+        let mut args = Vec::new();
+        for segment in &endpoint.path {
+            match segment {
+                PathSegment::Literal(literal) => {
+                    template.push('/');
+                    template.push_str(&format_literal(literal.trim_matches('/')));
+                }
+                PathSegment::Parameter { name, r#type } => {
+                    let ident = param_ident(name, CLIENT_NAMES);
+                    template.push_str("/{}");
+                    args.push(format!("path(&text(&{}), false)", ident));
+                    params.push(Param {
+                        ident,
+                        rust_type: rust_type(r#type)?,
+                    });
+                }
+                PathSegment::Wildcard { name } => {
+                    let ident = param_ident(name, CLIENT_NAMES);
+                    template.push_str("/{}");
+                    args.push(format!("path(&{}, true)", ident));
+                    params.push(Param {
+                        ident,
+                        rust_type: "String".to_string(),
+                    });
+                }
+            }
+        }
+
+        if let Some(query) = &endpoint.query {
+            params.push(Param {
+                ident: "query".to_string(),
+                rust_type: format!("&{}", rust_type(query)?),
+            });
+        }
+        if let Some(body) = &endpoint.body {
+            params.push(Param {
+                ident: "body".to_string(),
+                rust_type: format!("&{}", rust_type(body)?),
+            });
+        }
+        check_unique(&format!("Endpoint '{}'", qualified_name), &params)?;
+
+        let response = match (&endpoint.upgrade, &endpoint.response) {
+            (Some(_), FieldType::Stream(item)) => Response::Stream(rust_type(item)?),
+            (Some(_), item) => Response::Stream(rust_type(item)?),
+            (None, FieldType::Stream(_)) => bail!(
+                "Endpoint '{}' streams its response without a WebSocket upgrade",
+                qualified_name
+            ),
+            (None, FieldType::Any) => Response::Empty,
+            (None, response) => Response::Json(rust_type(response)?),
+        };
+
+        Ok(Self {
+            endpoint,
+            method: match endpoint.method {
+                HttpMethod::Get => "get",
+                HttpMethod::Post => "post",
+                HttpMethod::Put => "put",
+                HttpMethod::Delete => "delete",
+                HttpMethod::Patch => "patch",
+            },
+            params,
+            path: (template, args),
+            response,
+        })
+    }
+
+    fn register(&self, runtime: &mut Runtime) {
+        runtime.websocket |= matches!(self.response, Response::Stream(_));
+        for header in &self.endpoint.headers {
+            runtime.register_header(header);
+        }
+    }
+
+    /// The route as the server declares it, e.g. `/machines/{name}`
+    fn route(&self) -> String {
+        let route: String = self
+            .endpoint
+            .path
+            .iter()
+            .map(|segment| match segment {
+                PathSegment::Literal(literal) => format!("/{}", literal.trim_matches('/')),
+                PathSegment::Parameter { name, .. } => format!("/{{{}}}", name),
+                PathSegment::Wildcard { name } => format!("/{{*{}}}", name),
+            })
+            .collect();
+        if route.is_empty() {
+            "/".to_string()
+        } else {
+            route
+        }
+    }
+}
+
+/// Arguments for the parameterized headers in `headers`, in declaration order
+fn header_params(headers: &[Header]) -> Result<Vec<Param>> {
+    let mut params = Vec::new();
+    for header in headers {
+        for (name, field_type) in header.value.params() {
+            params.push(Param {
+                ident: param_ident(name, CLIENT_NAMES),
+                rust_type: rust_type(field_type)?,
+            });
+        }
+    }
+    Ok(params)
+}
+
+fn check_unique(owner: &str, params: &[Param]) -> Result<()> {
+    let mut seen = HashSet::new();
+    for param in params {
+        if !seen.insert(&param.ident) {
+            bail!(
+                "{} has more than one argument named '{}'",
+                owner,
+                param.ident
+            );
+        }
+    }
+    Ok(())
+}
+
+fn generate_error(writer: &mut CodeWriter, runtime: &Runtime) {
+    writer.line("/// Why a request failed");
+    writer.line("#[derive(Debug)]");
+    writer.block("pub enum Error {", "}", |w| {
+        w.line("/// The request could not be sent or its response could not be read");
+        w.line("Http(reqwest::Error),");
+        w.line("/// The server answered with an error status");
+        w.block("Status {", "},", |w| {
+            w.line("status: reqwest::StatusCode,");
+            w.line("body: String,");
+        });
+        w.line("/// The response is not the JSON the spec describes");
+        w.line("Json(serde_json::Error),");
+        if runtime.websocket {
+            w.line("/// The WebSocket could not be opened or failed");
+            w.line("WebSocket(tokio_tungstenite::tungstenite::Error),");
+        }
+    });
+    writer.empty_line();
+
+    writer.block("impl std::fmt::Display for Error {", "}", |w| {
+        w.block(
+            "fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {",
+            "}",
+            |w| {
+                w.block("match self {", "}", |w| {
+                    w.line("Error::Http(error) => write!(f, \"Request failed: {}\", error),");
+                    w.block("Error::Status { status, body } => {", "}", |w| {
+                        w.line("write!(f, \"Server answered {}: {}\", status, body)");
+                    });
+                    w.line("Error::Json(error) => write!(f, \"Invalid response: {}\", error),");
+                    if runtime.websocket {
+                        w.line("Error::WebSocket(error) => write!(f, \"WebSocket failed: {}\", error),");
+                    }
+                });
+            },
+        );
+    });
+    writer.empty_line();
+
+    writer.block("impl std::error::Error for Error {", "}", |w| {
+        w.block(
+            "fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {",
+            "}",
+            |w| {
+                w.block("match self {", "}", |w| {
+                    w.line("Error::Http(error) => Some(error),");
+                    w.line("Error::Status { .. } => None,");
+                    w.line("Error::Json(error) => Some(error),");
+                    if runtime.websocket {
+                        w.line("Error::WebSocket(error) => Some(error),");
+                    }
+                });
+            },
+        );
+    });
+
+    let mut conversions = vec![("reqwest::Error", "Http"), ("serde_json::Error", "Json")];
+    if runtime.websocket {
+        conversions.push(("tokio_tungstenite::tungstenite::Error", "WebSocket"));
+    }
+    for (error_type, variant) in conversions {
+        writer.empty_line();
+        writer.block(
+            &format!("impl From<{}> for Error {{", error_type),
+            "}",
+            |w| {
+                w.block(
+                    &format!("fn from(error: {}) -> Self {{", error_type),
+                    "}",
+                    |w| {
+                        w.line(&format!("Error::{}(error)", variant));
+                    },
+                );
+            },
+        );
+    }
+}
+
+fn generate_client(
+    writer: &mut CodeWriter,
+    aat: &AAT,
+    services: &[ClientService],
+    params: &[Param],
+) {
+    writer.line("/// Sends requests to every service of the spec");
+    writer.line("#[derive(Clone)]");
+    writer.block("pub struct Client {", "}", |w| {
+        w.line("http: reqwest::Client,");
+        w.line("base_url: String,");
+        w.line("headers: Headers,");
+    });
+    writer.empty_line();
+
+    let declarations: Vec<String> = params.iter().map(Param::declaration).collect();
+    let idents: Vec<String> = params.iter().map(|param| param.ident.clone()).collect();
+    writer.block("impl Client {", "}", |w| {
+        w.line("/// A client for the API at `base_url`, e.g. `https://api.example.com`");
+        let mut new_params = vec!["base_url: impl Into<String>".to_string()];
+        new_params.extend(declarations.iter().cloned());
+        list(w, 4, "pub fn new", &new_params, " -> Self {");
+        w.indent();
+        let mut args = vec!["reqwest::Client::new()".to_string(), "base_url".to_string()];
+        args.extend(idents.iter().cloned());
+        call(w, 8, "Self::with_http_client", &args, "");
+        w.dedent();
+        w.line("}");
+        w.empty_line();
+
+        w.line("/// Like [`Client::new`], sending requests through `http`");
+        let mut with_params = vec![
+            "http: reqwest::Client".to_string(),
+            "base_url: impl Into<String>".to_string(),
+        ];
+        with_params.extend(declarations.iter().cloned());
+        list(w, 4, "pub fn with_http_client", &with_params, " -> Self {");
+        w.indent();
+        let headers = if aat.headers.is_empty() {
+            "Vec::new()"
+        } else {
+            w.line("let mut headers = Headers::new();");
+            for header in &aat.headers {
+                write_header(w, 8, header);
+            }
+            "headers"
+        };
+        w.block("Self {", "}", |w| {
+            w.line("http,");
+            w.line("base_url: base_url.into().trim_end_matches('/').to_string(),");
+            if headers == "headers" {
+                w.line("headers,");
+            } else {
+                w.line(&format!("headers: {},", headers));
+            }
+        });
+        w.dedent();
+        w.line("}");
+
+        for service in services {
+            w.empty_line();
+            w.line(&format!("/// The `{}` service", service.service.name));
+            let mut accessor_params = vec!["&self".to_string()];
+            accessor_params.extend(service.params.iter().map(Param::declaration));
+            list(
+                w,
+                4,
+                &format!("pub fn {}", service.ident()),
+                &accessor_params,
+                &format!(" -> {} {{", service.struct_name),
+            );
+            w.indent();
+            if service.service.headers.is_empty() {
+                w.block(&format!("{} {{", service.struct_name), "}", |w| {
+                    w.line("client: self.clone(),");
+                    w.line("headers: self.headers.clone(),");
+                });
+            } else {
+                w.line("let mut headers = self.headers.clone();");
+                for header in &service.service.headers {
+                    write_header(w, 8, header);
+                }
+                w.block(&format!("{} {{", service.struct_name), "}", |w| {
+                    w.line("client: self.clone(),");
+                    w.line("headers,");
+                });
+            }
+            w.dedent();
+            w.line("}");
+        }
+    });
+}
+
+fn generate_service(writer: &mut CodeWriter, service: &ClientService) {
+    writer.line(&format!(
+        "/// Endpoints of the `{}` service",
+        service.service.name
+    ));
+    writer.line("#[derive(Clone)]");
+    writer.block(
+        &format!("pub struct {} {{", service.struct_name),
+        "}",
+        |w| {
+            w.line("client: Client,");
+            w.line("headers: Headers,");
+        },
+    );
+    writer.empty_line();
+
+    writer.block(&format!("impl {} {{", service.struct_name), "}", |w| {
+        for (index, endpoint) in service.endpoints.iter().enumerate() {
+            if index > 0 {
+                w.empty_line();
+            }
+            generate_endpoint(w, endpoint);
+        }
+    });
+}
+
+fn generate_endpoint(writer: &mut CodeWriter, endpoint: &ClientEndpoint) {
+    let upgrade = if matches!(endpoint.response, Response::Stream(_)) {
+        " (WebSocket)"
+    } else {
+        ""
+    };
+    writer.line(&format!(
+        "/// `{} {}`{}",
+        endpoint.method.to_ascii_uppercase(),
+        endpoint.route(),
+        upgrade
+    ));
+    let output = match &endpoint.response {
+        Response::Json(rust_type) => rust_type.clone(),
+        Response::Empty => "()".to_string(),
+        Response::Stream(item) => format!("Stream<{}>", item),
+    };
+    let mut params = vec!["&self".to_string()];
+    params.extend(endpoint.params.iter().map(Param::declaration));
+    list(
+        writer,
+        4,
+        &format!("pub async fn {}", rust_ident(&endpoint.endpoint.name)),
+        &params,
+        &format!(" -> Result<{}, Error> {{", output),
+    );
+    writer.indent();
+
+    let headers = if endpoint.endpoint.headers.is_empty() {
+        "&self.headers"
+    } else {
+        writer.line("let mut headers = self.headers.clone();");
+        for header in &endpoint.endpoint.headers {
+            write_header(writer, 8, header);
+        }
+        "&headers"
+    };
+
+    let (template, args) = &endpoint.path;
+    let mut format_args = vec![format!("\"{{}}{}\"", template)];
+    format_args.push("self.client.base_url".to_string());
+    format_args.extend(args.iter().cloned());
+    macro_call(writer, 8, "let url = format!", &format_args, ";");
+
+    let mut request = format!("self.client.http.{}(url)", endpoint.method);
+    if endpoint.endpoint.query.is_some() {
+        request.push_str(".query(query)");
+    }
+    if endpoint.endpoint.body.is_some() {
+        request.push_str(".json(body)");
+    }
+    match &endpoint.response {
+        Response::Json(_) => {
+            writer.line(&format!("let request = {};", request));
+            writer.line(&format!(
+                "let response = send(request, {}).await?;",
+                headers
+            ));
+            writer.line("json(response).await");
+        }
+        Response::Empty => {
+            writer.line(&format!("let request = {};", request));
+            writer.line(&format!("send(request, {}).await?;", headers));
+            writer.line("Ok(())");
+        }
+        Response::Stream(_) => {
+            // Only used to encode the query
+            writer.line(&format!("let request = {}.build()?;", request));
+            writer.line(&format!(
+                "connect(request.url().clone(), {}).await",
+                headers
+            ));
+        }
+    }
+
+    writer.dedent();
+    writer.line("}");
+}
+
+/// Pushes `header` onto `headers`, reading its parameters from the arguments of the same name.
+/// Headers with optional parameters are only sent once all of them are given.
+fn write_header(writer: &mut CodeWriter, indent: usize, header: &Header) {
+    let params = header.value.params();
+    let optional: Vec<String> = params
+        .iter()
+        .filter(|(_, field_type)| matches!(field_type, FieldType::Optional(_)))
+        .map(|(name, _)| param_ident(name, CLIENT_NAMES))
+        .collect();
+    // Optional arguments are bound by reference once they are known to be present
+    let access = |name: &str| {
+        let ident = param_ident(name, CLIENT_NAMES);
+        if optional.contains(&ident) {
+            ident
+        } else {
+            format!("&{}", ident)
+        }
+    };
+
+    let (template, args) = match &header.value {
+        HeaderValue::Literal(value) => (format_literal(value), vec![]),
+        HeaderValue::Parameter { name, .. } => {
+            ("{}".to_string(), vec![format!("text({})", access(name))])
+        }
+        HeaderValue::Pattern { parts } => {
+            let mut template = String::new();
+            let mut args = Vec::new();
+            for part in parts {
+                match part {
+                    HeaderPart::Literal(literal) => template.push_str(&format_literal(literal)),
+                    HeaderPart::Param { name, format, .. } => {
+                        template.push_str("{}");
+                        let text = format!("text({})", access(name));
+                        args.push(match format {
+                            Some(HeaderFormat::Base64) => format!("base64(&{})", text),
+                            Some(HeaderFormat::Lowercase) => format!("{}.to_lowercase()", text),
+                            // Dates are already ISO 8601 strings
+                            Some(HeaderFormat::IsoDate) | None => text,
+                        });
+                    }
+                }
+            }
+            (template, args)
+        }
+    };
+
+    let mut indent = indent;
+    if !optional.is_empty() {
+        let (bindings, values) = if optional.len() == 1 {
+            (
+                format!("Some({})", optional[0]),
+                format!("&{}", optional[0]),
+            )
+        } else {
+            (
+                format!(
+                    "({})",
+                    optional
+                        .iter()
+                        .map(|ident| format!("Some({})", ident))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+                format!(
+                    "({})",
+                    optional
+                        .iter()
+                        .map(|ident| format!("&{}", ident))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+            )
+        };
+        writer.line(&format!("if let {} = {} {{", bindings, values));
+        writer.indent();
+        indent += 4;
+    }
+
+    let name = format!("\"{}\"", escape(&header.name));
+    let value = match args.len() {
+        0 => format!(
+            "\"{}\".to_string()",
+            template.replace("{{", "{").replace("}}", "}")
+        ),
+        // `format!("{}", value)` is just the value
+        1 if template == "{}" => args[0].clone(),
+        _ => {
+            let mut format_args = vec![format!("\"{}\"", template)];
+            format_args.extend(args.iter().cloned());
+            format!("format!({})", format_args.join(", "))
+        }
+    };
+    let line = format!("headers.push(({}, {}));", name, value);
+    if indent + line.len() <= MAX_WIDTH {
+        writer.line(&line);
+    } else {
+        let mut format_args = vec![format!("\"{}\"", template)];
+        format_args.extend(args.iter().cloned());
+        macro_call(writer, indent, "let value = format!", &format_args, ";");
+        writer.line(&format!("headers.push(({}, value));", name));
+    }
+
+    if !optional.is_empty() {
+        writer.dedent();
+        writer.line("}");
+    }
+}
+
+/// Literal text inside a `format!` string
+fn format_literal(text: &str) -> String {
+    escape(text).replace('{', "{{").replace('}', "}}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{Machine, machines};
+    use crate::spec::{self, HeaderParam, Spec, Type};
+    use schemars::JsonSchema;
+
+    fn generate(spec: Spec) -> Result<String> {
+        RustGenerator::generate(&AAT::from_spec(&spec)?)
+    }
+
+    fn pattern(
+        pattern: &str,
+        params: Vec<(&str, Type, Option<spec::HeaderFormat>)>,
+    ) -> spec::HeaderValue {
+        spec::HeaderValue::Pattern {
+            pattern: pattern.to_string(),
+            params: params
+                .into_iter()
+                .map(|(name, r#type, format)| HeaderParam {
+                    name: name.to_string(),
+                    r#type,
+                    format,
+                })
+                .collect(),
+        }
+    }
+
+    fn string() -> Type {
+        Type::schema::<String>()
+    }
+
+    #[test]
+    fn sends_headers_once_all_their_optional_arguments_are_given() {
+        let client = generate(machines(|e| {
+            e.response(Type::schema::<Machine>()).header(
+                "authorization",
+                pattern(
+                    "{scheme} {token}",
+                    vec![
+                        ("scheme", Type::optional(string()), None),
+                        ("token", Type::optional(string()), None),
+                    ],
+                ),
+            )
+        }))
+        .unwrap();
+        assert!(client.contains(
+            "        if let (Some(scheme), Some(token)) = (&scheme, &token) {\n            headers.push((\"authorization\", format!(\"{} {}\", text(scheme), text(token))));\n        }"
+        ));
+    }
+
+    #[test]
+    fn formats_header_parameters() {
+        let client = generate(machines(|e| {
+            e.header(
+                "x-signature",
+                pattern(
+                    "{key}:\"{sig}\"",
+                    vec![
+                        ("key", string(), Some(spec::HeaderFormat::Lowercase)),
+                        ("sig", string(), Some(spec::HeaderFormat::Base64)),
+                    ],
+                ),
+            )
+        }))
+        .unwrap();
+        assert!(
+            client.contains(
+                r#"format!("{}:\"{}\"", text(&key).to_lowercase(), base64(&text(&sig)))"#
+            )
+        );
+        assert!(client.contains("fn base64(text: &str) -> String {"));
+        // Nothing streams, so the WebSocket helpers are left out
+        assert!(!client.contains("tokio_tungstenite"));
+    }
+
+    #[test]
+    fn rejects_types_named_like_generated_items() {
+        #[derive(JsonSchema)]
+        #[allow(dead_code)]
+        struct Error {
+            message: String,
+        }
+
+        let error = generate(machines(|e| e.response(Type::schema::<Error>()))).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Type 'Error' clashes with the generated client's `Error`; rename it with `TypeNaming`"
+        );
+    }
+
+    #[test]
+    fn rejects_duplicate_arguments() {
+        let name = || spec::HeaderValue::Type {
+            name: "name".to_string(),
+            r#type: string(),
+        };
+        let error = generate(machines(|e| {
+            e.header("x-name", name()).header("x-other-name", name())
+        }))
+        .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Endpoint 'machines.get' has more than one argument named 'name'"
+        );
+    }
+}
@@ -0,0 +1,152 @@
+//! Helpers the generated client calls, written at the end of `client.rs`
+
+use crate::aat::{Header, HeaderFormat, HeaderPart, HeaderValue};
+use crate::generate::writer::CodeWriter;
+
+/// Which optional helpers the generated endpoints need
+#[derive(Default)]
+pub struct Runtime {
+    /// Some endpoint opens a WebSocket
+    pub websocket: bool,
+    /// Some header is base64 encoded
+    pub base64: bool,
+}
+
+impl Runtime {
+    pub fn register_header(&mut self, header: &Header) {
+        if let HeaderValue::Pattern { parts } = &header.value {
+            self.base64 |= parts.iter().any(|part| {
+                matches!(
+                    part,
+                    HeaderPart::Param {
+                        format: Some(HeaderFormat::Base64),
+                        ..
+                    }
+                )
+            });
+        }
+    }
+}
+
+const HEADERS: &str = r#"type Headers = Vec<(&'static str, String)>;"#;
+
+const SEND: &str = r#"/// Sends `request` with `headers`, turning error statuses into [`Error::Status`]
+async fn send(
+    mut request: reqwest::RequestBuilder,
+    headers: &Headers,
+) -> Result<reqwest::Response, Error> {
+    for (name, value) in headers {
+        request = request.header(*name, value);
+    }
+    let response = request.send().await?;
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+    let body = response.text().await.unwrap_or_default();
+    Err(Error::Status { status, body })
+}
+
+/// Reads the JSON body of a response
+async fn json<T: serde::de::DeserializeOwned>(response: reqwest::Response) -> Result<T, Error> {
+    let body = response.bytes().await?;
+    Ok(serde_json::from_slice(&body)?)
+}
+
+/// The text of a path or header argument: strings as they are, other values as JSON
+fn text<T: serde::Serialize + ?Sized>(value: &T) -> String {
+    match serde_json::to_value(value) {
+        Ok(serde_json::Value::String(text)) => text,
+        Ok(value) => value.to_string(),
+        Err(_) => String::new(),
+    }
+}
+
+/// Percent-encodes a path argument, keeping `/` when it fills a trailing wildcard
+fn path(text: &str, keep_slashes: bool) -> String {
+    let mut encoded = String::new();
+    for byte in text.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                encoded.push(byte as char)
+            }
+            b'/' if keep_slashes => encoded.push('/'),
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}"#;
+
+const BASE64: &str = r#"/// Standard base64 with padding
+fn base64(text: &str) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::new();
+    for chunk in text.as_bytes().chunks(3) {
+        let byte = |index: usize| u32::from(chunk.get(index).copied().unwrap_or(0));
+        let bits = (byte(0) << 16) | (byte(1) << 8) | byte(2);
+        for index in 0..4 {
+            if index <= chunk.len() {
+                encoded.push(ALPHABET[((bits >> (18 - 6 * index)) & 63) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}"#;
+
+const WEBSOCKET: &str = r#"/// Items received over a WebSocket, one JSON message each
+pub type Stream<T> = futures_util::stream::BoxStream<'static, Result<T, Error>>;
+
+/// Opens a WebSocket to `url`, reading every text or binary message as an item
+async fn connect<T: serde::de::DeserializeOwned + Send + 'static>(
+    mut url: reqwest::Url,
+    headers: &Headers,
+) -> Result<Stream<T>, Error> {
+    use tokio_tungstenite::tungstenite::client::IntoClientRequest as _;
+    use tokio_tungstenite::tungstenite::{self, Message, http};
+
+    let scheme = if url.scheme() == "https" { "wss" } else { "ws" };
+    // Both schemes are special, so switching between them can't fail
+    let _ = url.set_scheme(scheme);
+    let mut request = url.as_str().into_client_request()?;
+    for (name, value) in headers {
+        let name = http::HeaderName::from_bytes(name.as_bytes())
+            .map_err(|error| tungstenite::Error::HttpFormat(error.into()))?;
+        let value = http::HeaderValue::from_str(value)
+            .map_err(|error| tungstenite::Error::HttpFormat(error.into()))?;
+        request.headers_mut().append(name, value);
+    }
+    let (socket, _) = tokio_tungstenite::connect_async(request).await?;
+    let items = socket.filter_map(|message| async move {
+        match message {
+            Ok(Message::Text(text)) => Some(serde_json::from_str(&text).map_err(Error::from)),
+            Ok(Message::Binary(bytes)) => Some(serde_json::from_slice(&bytes).map_err(Error::from)),
+            Ok(_) => None,
+            Err(error) => Some(Err(Error::from(error))),
+        }
+    });
+    Ok(items.boxed())
+}"#;
+
+pub fn generate_runtime(writer: &mut CodeWriter, runtime: &Runtime) {
+    let mut sections = vec![HEADERS, SEND];
+    if runtime.base64 {
+        sections.push(BASE64);
+    }
+    if runtime.websocket {
+        sections.push(WEBSOCKET);
+    }
+    for (index, section) in sections.iter().enumerate() {
+        if index > 0 {
+            writer.empty_line();
+        }
+        for line in section.lines() {
+            if line.is_empty() {
+                writer.empty_line();
+            } else {
+                writer.line(line);
+            }
+        }
+    }
+}
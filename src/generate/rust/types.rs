@@ -0,0 +1,122 @@
+use anyhow::{Result, bail};
+
+use crate::aat::{FieldType, IntFormat, LiteralType, PrimitiveType};
+
+const KEYWORDS: &[&str] = &[
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "do", "dyn",
+    "else", "enum", "extern", "false", "final", "fn", "for", "gen", "if", "impl", "in", "let",
+    "loop", "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref", "return",
+    "static", "struct", "trait", "true", "try", "type", "typeof", "unsafe", "unsized", "use",
+    "virtual", "where", "while", "yield",
+];
+
+/// The Rust type a field type is (de)serialized from. Named types are expected to be in scope
+/// under their AAT name.
+pub fn rust_type(field_type: &FieldType) -> Result<String> {
+    Ok(match field_type {
+        FieldType::Primitive(prim) => primitive_type(prim).to_string(),
+        FieldType::Literal(lit) => match lit {
+            LiteralType::String(_) => "String",
+            LiteralType::Int(_) => "i64",
+            LiteralType::Float(_) => "f64",
+            LiteralType::Bool(_) => "bool",
+            LiteralType::Null => "()",
+        }
+        .to_string(),
+        FieldType::Optional(inner) => format!("Option<{}>", rust_type(inner)?),
+        FieldType::List(inner) => format!("Vec<{}>", rust_type(inner)?),
+        FieldType::Map(inner) => {
+            format!("std::collections::HashMap<String, {}>", rust_type(inner)?)
+        }
+        FieldType::Reference(name) => name.clone(),
+        FieldType::Tuple(types) => {
+            let types = types.iter().map(rust_type).collect::<Result<Vec<_>>>()?;
+            if types.len() == 1 {
+                format!("({},)", types[0])
+            } else {
+                format!("({})", types.join(", "))
+            }
+        }
+        FieldType::Intersection(_) | FieldType::Any => "serde_json::Value".to_string(),
+        FieldType::Stream(_) => bail!("Stream types are only supported as WebSocket responses"),
+    })
+}
+
+fn primitive_type(prim: &PrimitiveType) -> &'static str {
+    match prim {
+        PrimitiveType::Bool => "bool",
+        PrimitiveType::Float => "f64",
+        PrimitiveType::String(_) => "String",
+        PrimitiveType::Int(None) => "i64",
+        PrimitiveType::Int(Some(format)) => match format {
+            IntFormat::Int8 => "i8",
+            IntFormat::Int16 => "i16",
+            IntFormat::Int32 => "i32",
+            IntFormat::Int64 => "i64",
+            IntFormat::Int128 => "i128",
+            IntFormat::Int => "isize",
+            IntFormat::UInt8 => "u8",
+            IntFormat::UInt16 => "u16",
+            IntFormat::UInt32 => "u32",
+            IntFormat::UInt64 => "u64",
+            IntFormat::UInt128 => "u128",
+            IntFormat::UInt => "usize",
+        },
+    }
+}
+
+/// A snake_case identifier for a spec name, escaping keywords
+pub fn rust_ident(name: &str) -> String {
+    let mut ident = String::new();
+    let mut previous_lower = false;
+    for c in name.chars() {
+        if c.is_ascii_alphanumeric() {
+            if c.is_ascii_uppercase() && previous_lower {
+                ident.push('_');
+            }
+            previous_lower = c.is_ascii_lowercase() || c.is_ascii_digit();
+            ident.push(c.to_ascii_lowercase());
+        } else {
+            if !ident.is_empty() && !ident.ends_with('_') {
+                ident.push('_');
+            }
+            previous_lower = false;
+        }
+    }
+    if ident.is_empty() || ident.starts_with(|c: char| c.is_ascii_digit()) {
+        ident.insert(0, '_');
+    }
+    match ident.as_str() {
+        // Can't be raw identifiers
+        "crate" | "self" | "super" => format!("{}_", ident),
+        _ if KEYWORDS.contains(&ident.as_str()) => format!("r#{}", ident),
+        _ => ident,
+    }
+}
+
+/// [`rust_ident`] for an argument of generated code, with a trailing underscore when it would
+/// shadow one of the `reserved` helpers or locals the code refers to
+pub fn param_ident(name: &str, reserved: &[&str]) -> String {
+    let ident = rust_ident(name);
+    if reserved.contains(&ident.as_str()) {
+        format!("{}_", ident)
+    } else {
+        ident
+    }
+}
+
+/// A PascalCase identifier for a spec name (`machine-pools` becomes `MachinePools`)
+pub fn pascal_case(name: &str) -> String {
+    rust_ident(name)
+        .trim_start_matches("r#")
+        .split('_')
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let mut chars = part.chars();
+            match chars.next() {
+                Some(first) => first.to_ascii_uppercase().to_string() + chars.as_str(),
+                None => String::new(),
+            }
+        })
+        .collect()
+}
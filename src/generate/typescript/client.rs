@@ -2,68 +2,262 @@ use crate::aat::*;
 use crate::generate::writer::CodeWriter;
 use anyhow::Result;
 
+use super::TypeScriptOptions;
+use super::symbols::Symbols;
 use super::utils::*;
+use super::validation;
+
+/// `RequestOptions`, `RetryPolicy` and `sendRequest`, which applies timeouts, cancellation and
+/// retries around `fetch`, plus the `Middleware` hooks run by `runMiddleware` around each call
+pub fn generate_request_runtime(writer: &mut CodeWriter, symbols: &mut Symbols) -> Result<()> {
+    symbols.exports_type("RetryPolicy");
+    writer.block("export interface RetryPolicy {", "}", |w| {
+        w.line("/** Total number of attempts, including the first (default 3) */");
+        w.line("attempts?: number;");
+        w.line(
+            "/** Delay before the first retry, doubled for every further retry (default 200) */",
+        );
+        w.line("backoffMs?: number;");
+        w.line("/** Upper bound for the delay between attempts (default 10000) */");
+        w.line("maxBackoffMs?: number;");
+        w.line("/** Response statuses that are retried (default 408, 429, 500, 502, 503, 504) */");
+        w.line("retryOn?: number[];");
+        w.line("/** Also retry POST and PATCH requests, which may not be safe to repeat */");
+        w.line("retryNonIdempotent?: boolean;");
+    });
+    writer.empty_line();
+
+    symbols.exports_type("RequestOptions");
+    writer.block("export interface RequestOptions {", "}", |w| {
+        w.line("signal?: AbortSignal;");
+        w.line("timeoutMs?: number;");
+        w.line("headers?: Record<string, string>;");
+        w.line("/** `false` disables retries configured in the client defaults */");
+        w.line("retry?: RetryPolicy | false;");
+    });
+    writer.empty_line();
+
+    writer.line("const DEFAULT_RETRY_ON = [408, 429, 500, 502, 503, 504];");
+    writer.line("const IDEMPOTENT_METHODS = ['GET', 'HEAD', 'OPTIONS', 'PUT', 'DELETE'];");
+    writer.empty_line();
+
+    writer.block(
+        "function sleep(ms: number, signal: AbortSignal | undefined): Promise<void> {",
+        "}",
+        |w| {
+            w.block("return new Promise((resolve, reject) => {", "});", |w| {
+                w.block("if (signal?.aborted) {", "}", |w| {
+                    w.line("reject(signal.reason);");
+                    w.line("return;");
+                });
+                w.block("const onAbort = () => {", "};", |w| {
+                    w.line("clearTimeout(timer);");
+                    w.line("reject(signal?.reason);");
+                });
+                w.block("const timer = setTimeout(() => {", "}, ms);", |w| {
+                    w.line("signal?.removeEventListener('abort', onAbort);");
+                    w.line("resolve();");
+                });
+                w.line("signal?.addEventListener('abort', onAbort, { once: true });");
+            });
+        },
+    );
+    writer.empty_line();
+
+    writer.line(
+        "/** `response` with a body that calls `release` once it has been read or cancelled */",
+    );
+    writer.block(
+        "function releaseWhenRead(response: Response, release: () => void): Response {",
+        "}",
+        |w| {
+            w.block("if (!response.body) {", "}", |w| {
+                w.line("release();");
+                w.line("return response;");
+            });
+            w.line("const reader = response.body.getReader();");
+            w.block("const body = new ReadableStream<Uint8Array>({", "});", |w| {
+                w.block("async pull(controller) {", "},", |w| {
+                    w.block("try {", "} catch (error) {", |w| {
+                        w.line("const { done, value } = await reader.read();");
+                        w.block("if (done) {", "} else {", |w| {
+                            w.line("release();");
+                            w.line("controller.close();");
+                        });
+                        w.indent();
+                        w.line("controller.enqueue(value);");
+                        w.dedent();
+                        w.line("}");
+                    });
+                    w.indent();
+                    w.line("release();");
+                    w.line("controller.error(error);");
+                    w.dedent();
+                    w.line("}");
+                });
+                w.block("cancel(reason) {", "},", |w| {
+                    w.line("release();");
+                    w.line("return reader.cancel(reason);");
+                });
+            });
+            w.line("return new Response(body, { status: response.status, statusText: response.statusText, headers: response.headers });");
+        },
+    );
+    writer.empty_line();
+
+    symbols.exports_value("sendRequest");
+    writer.block(
+        "export async function sendRequest(fetchImpl: typeof fetch, url: string, init: RequestInit, defaults: RequestOptions | undefined, options: RequestOptions | undefined): Promise<Response> {",
+        "}",
+        |w| {
+            w.line("const signal = options?.signal ?? init.signal ?? undefined;");
+            w.line("const timeoutMs = options?.timeoutMs ?? defaults?.timeoutMs;");
+            w.line("const retry = options?.retry ?? defaults?.retry;");
+            w.line("const policy = retry === false ? undefined : retry;");
+            w.line("const idempotent = IDEMPOTENT_METHODS.includes((init.method ?? 'GET').toUpperCase());");
+            w.line("const attempts = policy && (idempotent || policy.retryNonIdempotent) ? Math.max(1, policy.attempts ?? 3) : 1;");
+            w.line("const retryOn = policy?.retryOn ?? DEFAULT_RETRY_ON;");
+            w.empty_line();
+            w.block("for (let attempt = 1; ; attempt++) {", "}", |w| {
+                w.line("const controller = new AbortController();");
+                w.line("const onAbort = () => controller.abort(signal?.reason);");
+                w.block("if (signal?.aborted) {", "}", |w| {
+                    w.line("onAbort();");
+                });
+                w.line("signal?.addEventListener('abort', onAbort, { once: true });");
+                w.line("const release = () => signal?.removeEventListener('abort', onAbort);");
+                w.line("const timer = timeoutMs !== undefined");
+                w.indent();
+                w.line("? setTimeout(() => controller.abort(new DOMException(`Request timed out after ${timeoutMs}ms`, 'TimeoutError')), timeoutMs)");
+                w.line(": undefined;");
+                w.dedent();
+                w.empty_line();
+                w.block("try {", "} catch (error) {", |w| {
+                    w.line("const response = await fetchImpl(url, { ...init, signal: controller.signal });");
+                    w.block("if (attempt >= attempts || !retryOn.includes(response.status)) {", "}", |w| {
+                        w.line("// The caller's signal still cancels the request while the body is read");
+                        w.line("return signal ? releaseWhenRead(response, release) : response;");
+                    });
+                    w.line("release();");
+                    w.line("// Discard the body so the connection can be reused by the next attempt");
+                    w.line("await response.body?.cancel().catch(() => undefined);");
+                });
+                w.indent();
+                w.line("release();");
+                w.line("// Network errors and timeouts are retried; cancellation by the caller is not");
+                w.block("if (attempt >= attempts || signal?.aborted) {", "}", |w| {
+                    w.line("throw error;");
+                });
+                w.dedent();
+                w.line("} finally {");
+                w.indent();
+                w.line("clearTimeout(timer);");
+                w.dedent();
+                w.line("}");
+                w.empty_line();
+                w.line("const backoff = (policy?.backoffMs ?? 200) * 2 ** (attempt - 1);");
+                w.line("await sleep(Math.min(backoff, policy?.maxBackoffMs ?? 10000), signal);");
+            });
+        },
+    );
+    writer.empty_line();
+
+    symbols.exports_type("RequestContext");
+    writer.block("export interface RequestContext {", "}", |w| {
+        w.line("/** Service name as declared in the spec */");
+        w.line("service: string;");
+        w.line("/** Endpoint name as declared in the spec */");
+        w.line("endpoint: string;");
+        w.line("url: string;");
+        w.line("init: RequestInit & { headers: Record<string, string> };");
+    });
+    writer.empty_line();
+
+    symbols.exports_type("Middleware");
+    writer.line("/** Hooks around every HTTP call. WebSocket streams are opened without them. */");
+    writer.block("export interface Middleware {", "}", |w| {
+        w.line("/** Runs before the request is sent; may return a replacement context */");
+        w.line("onRequest?(context: RequestContext): RequestContext | void | Promise<RequestContext | void>;");
+        w.line("/** Runs once a response arrives, before its status is checked; may return a replacement response */");
+        w.line("onResponse?(context: RequestContext, response: Response): Response | void | Promise<Response | void>;");
+        w.line("/** Runs when the call fails; may throw a different error or return a response to recover with */");
+        w.line("onError?(context: RequestContext, error: unknown): Response | void | Promise<Response | void>;");
+    });
+    writer.empty_line();
+
+    symbols.exports_value("runMiddleware");
+    writer.block(
+        "export async function runMiddleware(middleware: Middleware[], context: RequestContext, send: (context: RequestContext) => Promise<Response>): Promise<Response> {",
+        "}",
+        |w| {
+            w.block("for (const m of middleware) {", "}", |w| {
+                w.line("context = (await m.onRequest?.(context)) ?? context;");
+            });
+            w.block("try {", "} catch (error) {", |w| {
+                w.line("let response = await send(context);");
+                w.block("for (const m of middleware) {", "}", |w| {
+                    w.line("response = (await m.onResponse?.(context, response)) ?? response;");
+                });
+                w.block("if (!response.ok) {", "}", |w| {
+                    w.line("throw new Error(`HTTP error! status: ${response.status}`);");
+                });
+                w.line("return response;");
+            });
+            w.indent();
+            w.block("for (const m of middleware) {", "}", |w| {
+                w.line("const recovered = await m.onError?.(context, error);");
+                w.block("if (recovered) {", "}", |w| {
+                    w.line("return recovered;");
+                });
+            });
+            w.line("throw error;");
+            w.dedent();
+            w.line("}");
+        },
+    );
+    Ok(())
+}
+
+pub fn generate_api_client(
+    writer: &mut CodeWriter,
+    aat: &AAT,
+    options: &TypeScriptOptions,
+    symbols: &mut Symbols,
+) -> Result<()> {
+    symbols.exports_type("ClientConfig");
+    symbols.exports_value("Client");
+    symbols.uses("RequestOptions");
+    symbols.uses("Middleware");
 
-pub fn generate_api_client(writer: &mut CodeWriter, aat: &AAT) -> Result<()> {
     // Generate ClientConfig interface
     writer.block("export interface ClientConfig {", "}", |w| {
         w.line("baseUrl: string;");
 
         // Add root-level header parameters to config
-        for header in &aat.headers {
-            match &header.value {
-                HeaderValue::Parameter { name, field_type } => {
-                    let ts_type = field_type_to_ts(field_type);
-                    let is_optional = matches!(field_type, FieldType::Optional(_));
-                    if is_optional {
-                        w.line(&format!("{}?: {};", name, ts_type));
-                    } else {
-                        w.line(&format!("{}: {};", name, ts_type));
-                    }
-                }
-                HeaderValue::Pattern {
-                    param_name,
-                    field_type,
-                    ..
-                } => {
-                    let ts_type = field_type_to_ts(field_type);
-                    let is_optional = matches!(field_type, FieldType::Optional(_));
-                    if is_optional {
-                        w.line(&format!("{}?: {};", param_name, ts_type));
-                    } else {
-                        w.line(&format!("{}: {};", param_name, ts_type));
-                    }
-                }
-                HeaderValue::Literal(_) => {
-                    // Literals don't need parameters
-                }
-            }
+        for param in header_params(&aat.headers, options, symbols) {
+            w.line(&format!("{};", param.declaration()));
         }
 
         w.line("options?: RequestInit;");
+        w.line("defaults?: Omit<RequestOptions, 'signal'>;");
+        w.line("middleware?: Middleware[];");
         w.line("fetchImpl?: typeof fetch;");
         w.line("WebSocketImpl?: typeof WebSocket;");
+        w.line("/** Send real WebSocket headers; defaults to `true` when a `WebSocketImpl` such as Node's `ws` is given */");
+        w.line("webSocketHeaders?: boolean;");
+        if options.validators {
+            w.line("validate?: boolean;");
+        }
     });
     writer.empty_line();
 
     // Generate Client class
     writer.block("export class Client {", "}", |w| {
         // Build root-level header storage fields
-        let mut root_header_storage = Vec::new();
-        for header in &aat.headers {
-            match &header.value {
-                HeaderValue::Parameter { name, .. } => {
-                    root_header_storage.push(name.clone());
-                }
-                HeaderValue::Pattern {
-                    param_name,
-                    ..
-                } => {
-                    root_header_storage.push(param_name.clone());
-                }
-                HeaderValue::Literal(_) => {}
-            }
-        }
+        let root_header_storage: Vec<String> = header_params(&aat.headers, options, symbols)
+            .into_iter()
+            .map(|param| param.name)
+            .collect();
 
         // Declare private fields
         w.line("private readonly baseUrl: string;");
@@ -71,8 +265,14 @@ pub fn generate_api_client(writer: &mut CodeWriter, aat: &AAT) -> Result<()> {
             w.line(&format!("private readonly rootHeader_{}: any;", name));
         }
         w.line("private readonly options?: RequestInit;");
+        w.line("private readonly defaults?: RequestOptions;");
+        w.line("private readonly middleware: Middleware[];");
         w.line("private readonly fetchImpl: typeof fetch;");
         w.line("private readonly WebSocketImpl: typeof WebSocket;");
+        w.line("private readonly webSocketHeaders: boolean;");
+        if options.validators {
+            w.line("private readonly validate: boolean;");
+        }
         w.empty_line();
 
         // Constructor
@@ -83,46 +283,26 @@ pub fn generate_api_client(writer: &mut CodeWriter, aat: &AAT) -> Result<()> {
                 w.line(&format!("this.rootHeader_{} = config.{};", name, name));
             }
             w.line("this.options = config.options;");
+            w.line("this.defaults = config.defaults;");
+            w.line("this.middleware = config.middleware ?? [];");
             w.line("this.fetchImpl = config.fetchImpl || globalThis.fetch;");
             w.line("this.WebSocketImpl = config.WebSocketImpl || globalThis.WebSocket;");
+            w.line("this.webSocketHeaders = config.webSocketHeaders ?? (config.WebSocketImpl !== undefined && config.WebSocketImpl !== globalThis.WebSocket);");
+            if options.validators {
+                w.line("this.validate = config.validate ?? false;");
+            }
         });
         w.empty_line();
 
         // Generate service factory methods
         for service in &aat.services {
             let class_name = to_pascal_case(&service.name);
+            symbols.uses(&format!("{}Client", class_name));
 
-            // Build service-level header parameters
-            let mut service_header_params = Vec::new();
-            for header in &service.headers {
-                match &header.value {
-                    HeaderValue::Parameter { name, field_type } => {
-                        let ts_type = field_type_to_ts(field_type);
-                        let is_optional = matches!(field_type, FieldType::Optional(_));
-                        if is_optional {
-                            service_header_params.push(format!("{}?: {}", name, ts_type));
-                        } else {
-                            service_header_params.push(format!("{}: {}", name, ts_type));
-                        }
-                    }
-                    HeaderValue::Pattern {
-                        param_name,
-                        field_type,
-                        ..
-                    } => {
-                        let ts_type = field_type_to_ts(field_type);
-                        let is_optional = matches!(field_type, FieldType::Optional(_));
-                        if is_optional {
-                            service_header_params.push(format!("{}?: {}", param_name, ts_type));
-                        } else {
-                            service_header_params.push(format!("{}: {}", param_name, ts_type));
-                        }
-                    }
-                    HeaderValue::Literal(_) => {
-                        // Literals don't need parameters
-                    }
-                }
-            }
+            let service_header_params: Vec<String> = header_params(&service.headers, options, symbols)
+                .iter()
+                .map(MethodParam::declaration)
+                .collect();
 
             let method_params = if service_header_params.is_empty() {
                 String::new()
@@ -146,83 +326,21 @@ pub fn generate_api_client(writer: &mut CodeWriter, aat: &AAT) -> Result<()> {
             // Build root headers object
             w.line("const rootHeaders: Record<string, string> = {};");
             for header in &aat.headers {
-                match &header.value {
-                    HeaderValue::Literal(value) => {
-                        w.line(&format!("rootHeaders['{}'] = '{}';", header.name, value));
-                    }
-                    HeaderValue::Parameter { name, .. } => {
-                        w.line(&format!(
-                            "rootHeaders['{}'] = String(this.rootHeader_{});",
-                            header.name, name
-                        ));
-                    }
-                    HeaderValue::Pattern {
-                        pattern,
-                        param_name,
-                        ..
-                    } => {
-                        // Replace {param_name} in pattern with the parameter value
-                        let placeholder = format!("{{{}}}", param_name);
-                        let pattern_expr = pattern.replace(
-                            &placeholder,
-                            &format!("${{String(this.rootHeader_{})}}", param_name),
-                        );
-                        w.line(&format!(
-                            "rootHeaders['{}'] = `{}`;",
-                            header.name, pattern_expr
-                        ));
-                    }
-                }
+                w.line(&header_assignment("rootHeaders", header, options, |name| {
+                    format!("this.rootHeader_{}", name)
+                }));
             }
 
             // Build service headers object
             w.line("const serviceHeaders: Record<string, string> = {};");
             for header in &service.headers {
-                match &header.value {
-                    HeaderValue::Literal(value) => {
-                        w.line(&format!("serviceHeaders['{}'] = '{}';", header.name, value));
-                    }
-                    HeaderValue::Parameter { name, field_type } => {
-                        let is_optional = matches!(field_type, FieldType::Optional(_));
-                        if is_optional {
-                            w.line(&format!(
-                                "if ({} !== undefined) serviceHeaders['{}'] = String({});",
-                                name, header.name, name
-                            ));
-                        } else {
-                            w.line(&format!(
-                                "serviceHeaders['{}'] = String({});",
-                                header.name, name
-                            ));
-                        }
-                    }
-                    HeaderValue::Pattern {
-                        pattern,
-                        param_name,
-                        field_type,
-                    } => {
-                        let is_optional = matches!(field_type, FieldType::Optional(_));
-                        let placeholder = format!("{{{}}}", param_name);
-                        let pattern_expr =
-                            pattern.replace(&placeholder, &format!("${{String({})}}", param_name));
-                        if is_optional {
-                            w.line(&format!(
-                                "if ({} !== undefined) serviceHeaders['{}'] = `{}`;",
-                                param_name, header.name, pattern_expr
-                            ));
-                        } else {
-                            w.line(&format!(
-                                "serviceHeaders['{}'] = `{}`;",
-                                header.name, pattern_expr
-                            ));
-                        }
-                    }
-                }
+                w.line(&header_assignment("serviceHeaders", header, options, str::to_string));
             }
 
+            let validate_arg = if options.validators { ", this.validate" } else { "" };
             w.line(&format!(
-                "return new {}Client(this.baseUrl, rootHeaders, serviceHeaders, this.options, this.fetchImpl, this.WebSocketImpl);",
-                class_name
+                "return new {}Client(this.baseUrl, rootHeaders, serviceHeaders, this.options, this.defaults, this.middleware, this.fetchImpl, this.WebSocketImpl, this.webSocketHeaders{});",
+                class_name, validate_arg
             ));
             w.dedent();
             w.line("}");
@@ -232,22 +350,33 @@ pub fn generate_api_client(writer: &mut CodeWriter, aat: &AAT) -> Result<()> {
     Ok(())
 }
 
-pub fn generate_service(writer: &mut CodeWriter, service: &Service) -> Result<()> {
+pub fn generate_service(
+    writer: &mut CodeWriter,
+    service: &Service,
+    options: &TypeScriptOptions,
+    symbols: &mut Symbols,
+) -> Result<()> {
     // Pre-generate all endpoint methods to avoid borrowing issues
     let mut methods = Vec::new();
     for endpoint in &service.endpoints {
-        methods.push(generate_endpoint_method_str(endpoint)?);
+        methods.push(generate_endpoint_method_str(
+            service, endpoint, options, symbols,
+        )?);
     }
 
     let class_name = to_pascal_case(&service.name);
+    symbols.exports_value(&format!("{}Client", class_name));
     writer
         .block(&format!("export class {}Client {{", class_name), "}", |w| {
             // Constructor
-            w.line("constructor(private baseUrl: string, private rootHeaders: Record<string, string>, private serviceHeaders: Record<string, string>, private options: RequestInit | undefined, private fetchImpl: typeof fetch, private WebSocketImpl: typeof WebSocket) {}");
+            let validate_param = if options.validators { ", private validate: boolean" } else { "" };
+            symbols.uses("RequestOptions");
+            symbols.uses("Middleware");
+            w.line(&format!("constructor(private baseUrl: string, private rootHeaders: Record<string, string>, private serviceHeaders: Record<string, string>, private options: RequestInit | undefined, private defaults: RequestOptions | undefined, private middleware: Middleware[], private fetchImpl: typeof fetch, private WebSocketImpl: typeof WebSocket, private webSocketHeaders: boolean{}) {{}}", validate_param));
             w.empty_line();
 
             // Add private helper method for merging headers
-            w.block("private mergeHeaders(endpointHeaders: Record<string, string>): Record<string, string> {", "}", |w| {
+            w.block("private mergeHeaders(endpointHeaders: Record<string, string>, requestOptions?: RequestOptions): Record<string, string> {", "}", |w| {
                 w.line("const optionsHeaders: Record<string, string> = {};");
                 w.block("if (this.options?.headers) {", "}", |w| {
                     w.block("if (this.options.headers instanceof Headers) {", "}", |w| {
@@ -266,7 +395,16 @@ pub fn generate_service(writer: &mut CodeWriter, service: &Service) -> Result<()
                     w.dedent();
                     w.line("}");
                 });
-                w.line("return { ...this.rootHeaders, ...this.serviceHeaders, ...endpointHeaders, ...optionsHeaders };");
+                w.line("return { ...this.rootHeaders, ...this.serviceHeaders, ...endpointHeaders, ...optionsHeaders, ...this.defaults?.headers, ...requestOptions?.headers };");
+            });
+            w.empty_line();
+
+            // Add private helper method running a call through the middleware chain
+            symbols.uses("RequestContext");
+            symbols.uses("runMiddleware");
+            symbols.uses("sendRequest");
+            w.block("private send(context: RequestContext, requestOptions?: RequestOptions): Promise<Response> {", "}", |w| {
+                w.line("return runMiddleware(this.middleware, context, (ctx) => sendRequest(this.fetchImpl, ctx.url, ctx.init, this.defaults, requestOptions));");
             });
             w.empty_line();
 
@@ -281,136 +419,79 @@ pub fn generate_service(writer: &mut CodeWriter, service: &Service) -> Result<()
     Ok(())
 }
 
-fn generate_endpoint_method_str(endpoint: &Endpoint) -> Result<String> {
+fn generate_endpoint_method_str(
+    service: &Service,
+    endpoint: &Endpoint,
+    options: &TypeScriptOptions,
+    symbols: &mut Symbols,
+) -> Result<String> {
     let mut w = CodeWriter::new();
-    generate_endpoint_method_inner(&mut w, endpoint)?;
+    generate_endpoint_method_inner(&mut w, service, endpoint, options, symbols)?;
     Ok(w.into_string())
 }
 
-fn generate_endpoint_method_inner(w: &mut CodeWriter, endpoint: &Endpoint) -> Result<()> {
-    let method_name = to_camel_case(&endpoint.name);
+fn generate_endpoint_method_inner(
+    w: &mut CodeWriter,
+    service: &Service,
+    endpoint: &Endpoint,
+    options: &TypeScriptOptions,
+    symbols: &mut Symbols,
+) -> Result<()> {
+    let method_name = options.naming.method_name(&endpoint.name);
     let is_websocket = matches!(endpoint.upgrade, Some(Upgrade::Ws));
 
-    // Build parameter lists - separate required and optional to maintain correct order
-    let mut required_params = Vec::new();
-    let mut optional_params = Vec::new();
-
-    // Endpoint-level header parameters
-    for header in &endpoint.headers {
-        match &header.value {
-            HeaderValue::Parameter { name, field_type } => {
-                let ts_type = field_type_to_ts(field_type);
-                let is_optional = matches!(field_type, FieldType::Optional(_));
-                if is_optional {
-                    optional_params.push(format!("{}?: {}", name, ts_type));
-                } else {
-                    required_params.push(format!("{}: {}", name, ts_type));
-                }
-            }
-            HeaderValue::Pattern {
-                param_name,
-                field_type,
-                ..
-            } => {
-                let ts_type = field_type_to_ts(field_type);
-                let is_optional = matches!(field_type, FieldType::Optional(_));
-                if is_optional {
-                    optional_params.push(format!("{}?: {}", param_name, ts_type));
-                } else {
-                    required_params.push(format!("{}: {}", param_name, ts_type));
-                }
-            }
-            HeaderValue::Literal(_) => {
-                // Literals don't need parameters
-            }
-        }
-    }
-
-    // Path parameters
-    for segment in &endpoint.path {
-        if let PathSegment::Parameter { name, r#type } = segment {
-            let ts_type = field_type_to_ts(r#type);
-            let is_optional = matches!(r#type, FieldType::Optional(_));
-            if is_optional {
-                optional_params.push(format!("{}?: {}", name, ts_type));
-            } else {
-                required_params.push(format!("{}: {}", name, ts_type));
-            }
-        }
-    }
-
-    // Query parameters
-    if let Some(query_type) = &endpoint.query {
-        let ts_type = field_type_to_ts(query_type);
-        let is_optional = matches!(query_type, FieldType::Optional(_));
-        if is_optional {
-            optional_params.push(format!("query?: {}", ts_type));
-        } else {
-            required_params.push(format!("query: {}", ts_type));
-        }
-    }
-
-    // Body parameter
-    if let Some(body_type) = &endpoint.body {
-        let ts_type = field_type_to_ts(body_type);
-        let is_optional = matches!(body_type, FieldType::Optional(_));
-        if is_optional {
-            optional_params.push(format!("body?: {}", ts_type));
-        } else {
-            required_params.push(format!("body: {}", ts_type));
-        }
-    }
-
-    // Combine parameters: required first, then optional
-    let mut params = required_params;
-    params.extend(optional_params);
+    // Required parameters first, then optional ones, then per-call options
+    let mut params: Vec<String> = endpoint_params(endpoint, options, symbols)
+        .iter()
+        .map(MethodParam::declaration)
+        .collect();
+    let options_param = if is_websocket {
+        let item_type = match &endpoint.response {
+            FieldType::Stream(inner) => field_type_to_ts(inner, options, symbols),
+            other => field_type_to_ts(other, options, symbols),
+        };
+        symbols.uses("StreamOptions");
+        params.push(format!("streamOptions?: StreamOptions<{}>", item_type));
+        "streamOptions"
+    } else {
+        symbols.uses("RequestOptions");
+        params.push("requestOptions?: RequestOptions".to_string());
+        "requestOptions"
+    };
 
     let params_str = params.join(", ");
     let is_void_response = matches!(endpoint.response, FieldType::Any);
+    // Plain JSON numbers cannot carry 64-bit integers without losing precision
+    let (stringify, read_json) = if options.bigints {
+        ("stringifyJson", "parseJson(await response.text())")
+    } else {
+        ("JSON.stringify", "await response.json()")
+    };
     let return_type = if is_void_response {
         "void".to_string()
     } else {
-        field_type_to_ts(&endpoint.response)
+        field_type_to_ts(&endpoint.response, options, symbols)
     };
 
     // For WebSocket endpoints, return the stream directly without Promise wrapper
     let method_signature = if is_websocket {
         format!("{}({}): {}", method_name, params_str, return_type)
     } else {
-        format!("async {}({}): Promise<{}>", method_name, params_str, return_type)
+        format!(
+            "async {}({}): Promise<{}>",
+            method_name, params_str, return_type
+        )
     };
 
     w.block(&format!("{} {{", method_signature), "}", |w| {
         // Build endpoint-level headers
         w.line("const endpointHeaders: Record<string, string> = {};");
         for header in &endpoint.headers {
-            match &header.value {
-                HeaderValue::Literal(value) => {
-                    w.line(&format!("endpointHeaders['{}'] = '{}';", header.name, value));
-                }
-                HeaderValue::Parameter { name, field_type } => {
-                    let is_optional = matches!(field_type, FieldType::Optional(_));
-                    if is_optional {
-                        w.line(&format!("if ({} !== undefined) endpointHeaders['{}'] = String({});", name, header.name, name));
-                    } else {
-                        w.line(&format!("endpointHeaders['{}'] = String({});", header.name, name));
-                    }
-                }
-                HeaderValue::Pattern { pattern, param_name, field_type } => {
-                    let is_optional = matches!(field_type, FieldType::Optional(_));
-                    let placeholder = format!("{{{}}}", param_name);
-                    let pattern_expr = pattern.replace(&placeholder, &format!("${{String({})}}", param_name));
-                    if is_optional {
-                        w.line(&format!("if ({} !== undefined) endpointHeaders['{}'] = `{}`;", param_name, header.name, pattern_expr));
-                    } else {
-                        w.line(&format!("endpointHeaders['{}'] = `{}`;", header.name, pattern_expr));
-                    }
-                }
-            }
+            w.line(&header_assignment("endpointHeaders", header, options, str::to_string));
         }
 
         // Merge all headers using helper method
-        w.line("const mergedHeaders = this.mergeHeaders(endpointHeaders);");
+        w.line(&format!("const mergedHeaders = this.mergeHeaders(endpointHeaders, {});", options_param));
         w.empty_line();
 
         // Build path
@@ -418,7 +499,16 @@ fn generate_endpoint_method_inner(w: &mut CodeWriter, endpoint: &Endpoint) -> Re
         for segment in &endpoint.path {
             match segment {
                 PathSegment::Literal(lit) => path_str.push_str(&format!("/{}", lit)),
-                PathSegment::Parameter { name, .. } => path_str.push_str(&format!("/${{{}}}", name)),
+                PathSegment::Parameter { name, r#type } => {
+                    let value = match r#type {
+                        FieldType::Primitive(prim) => {
+                            primitive_serializer_expr(prim, name, options).unwrap_or_else(|| name.clone())
+                        }
+                        _ => name.clone(),
+                    };
+                    path_str.push_str(&format!("/${{{}}}", value))
+                }
+                PathSegment::Wildcard { name } => path_str.push_str(&format!("/${{{}}}", name)),
             }
         }
         w.line(&format!("const path = `{}`;", path_str));
@@ -426,8 +516,8 @@ fn generate_endpoint_method_inner(w: &mut CodeWriter, endpoint: &Endpoint) -> Re
         // Build URL with query params
         if let Some(query_type) = &endpoint.query {
             // Serialize query if needed
-            if needs_serialization(query_type) {
-                let serializer = get_serializer_call(query_type);
+            if needs_serialization(query_type, options) {
+                let serializer = get_serializer_call(query_type, options, symbols);
                 w.line(&format!("const serializedQuery = {}(query);", serializer));
                 w.line("const params = new URLSearchParams();");
                 w.block("for (const [key, value] of Object.entries(serializedQuery)) {", "}", |w| {
@@ -452,12 +542,21 @@ fn generate_endpoint_method_inner(w: &mut CodeWriter, endpoint: &Endpoint) -> Re
             // Generate WebSocket connection code
             // Extract the inner type from Stream<T>
             if let FieldType::Stream(inner_type) = &endpoint.response {
-                let deserializer = if needs_serialization(inner_type) {
-                    get_deserializer_call(inner_type)
+                let deserializer = if needs_serialization(inner_type, options) {
+                    get_deserializer_call(inner_type, options, symbols)
                 } else {
                     "(data: any) => data".to_string()
                 };
-                w.line(&format!("const stream = new WebSocketStream(url, {}, mergedHeaders, this.WebSocketImpl);", deserializer));
+                let auth = match &endpoint.ws_auth {
+                    Some(WsAuth::Query) => ", auth: 'query'".to_string(),
+                    Some(WsAuth::Protocol(protocol)) => {
+                        format!(", auth: {{ protocol: {} }}", json_string(protocol))
+                    }
+                    Some(WsAuth::Message) => ", auth: 'message'".to_string(),
+                    None => String::new(),
+                };
+                symbols.uses("WebSocketStream");
+                w.line(&format!("const stream = new WebSocketStream(url, {}, mergedHeaders, this.WebSocketImpl, {{ nativeHeaders: this.webSocketHeaders{} }}, streamOptions);", deserializer, auth));
                 w.line("return stream;");
             }
         } else {
@@ -470,32 +569,55 @@ fn generate_endpoint_method_inner(w: &mut CodeWriter, endpoint: &Endpoint) -> Re
                 HttpMethod::Patch => "PATCH",
             };
 
+            // Validate body before sending
+            if let Some(body_type) = &endpoint.body
+                && options.validators
+                && !matches!(body_type, FieldType::Any)
+            {
+                w.block("if (this.validate) {", "}", |w| {
+                    symbols.uses("ValidationIssue");
+                    symbols.uses("assertValid");
+                    w.line("const issues: ValidationIssue[] = [];");
+                    validation::generate_checks(w, body_type, None, "body", "", 0, options, symbols);
+                    w.line("assertValid(issues, 'Invalid request body');");
+                });
+            }
+
             // Serialize body if needed
             if let Some(body_type) = &endpoint.body {
-                if needs_serialization(body_type) {
-                    let serializer = get_serializer_call(body_type);
+                if needs_serialization(body_type, options) {
+                    let serializer = get_serializer_call(body_type, options, symbols);
                     w.line(&format!("const serializedBody = {}(body);", serializer));
                 }
             }
 
-            w.block("const response = await this.fetchImpl(url, {", "});", |w| {
-                w.line(&format!("method: '{}',", http_method));
-                if endpoint.body.is_some() {
-                    w.line("headers: { 'Content-Type': 'application/json', ...mergedHeaders },");
-                    if needs_serialization(endpoint.body.as_ref().unwrap()) {
-                        w.line("body: JSON.stringify(serializedBody),");
+            let call = if is_void_response {
+                "await this.send({"
+            } else {
+                "const response = await this.send({"
+            };
+            w.block(call, "}, requestOptions);", |w| {
+                w.line(&format!("service: '{}',", service.name));
+                w.line(&format!("endpoint: '{}',", endpoint.name));
+                w.line("url,");
+                w.block("init: {", "},", |w| {
+                    // Option headers are already part of mergedHeaders
+                    w.line("...this.options,");
+                    w.line(&format!("method: '{}',", http_method));
+                    if endpoint.body.is_some() {
+                        if options.bigints {
+                            symbols.uses("stringifyJson");
+                        }
+                        w.line("headers: { 'Content-Type': 'application/json', ...mergedHeaders },");
+                        if needs_serialization(endpoint.body.as_ref().unwrap(), options) {
+                            w.line(&format!("body: {}(serializedBody),", stringify));
+                        } else {
+                            w.line(&format!("body: {}(body),", stringify));
+                        }
                     } else {
-                        w.line("body: JSON.stringify(body),");
+                        w.line("headers: mergedHeaders,");
                     }
-                } else {
-                    w.line("headers: mergedHeaders,");
-                }
-                w.line("...this.options,");
-            });
-
-            w.empty_line();
-            w.block("if (!response.ok) {", "}", |w| {
-                w.line("throw new Error(`HTTP error! status: ${response.status}`);");
+                });
             });
 
             // Handle void response type
@@ -503,10 +625,33 @@ fn generate_endpoint_method_inner(w: &mut CodeWriter, endpoint: &Endpoint) -> Re
                 // Don't return anything for void
             } else {
                 w.empty_line();
+                if options.bigints {
+                    symbols.uses("parseJson");
+                }
                 // Deserialize response if needed
-                if needs_serialization(&endpoint.response) {
-                    let deserializer = get_deserializer_call(&endpoint.response);
-                    w.line("const data = await response.json();");
+                if options.validators {
+                    if needs_serialization(&endpoint.response, options) {
+                        let deserializer = get_deserializer_call(&endpoint.response, options, symbols);
+                        w.line(&format!("const data = {};", read_json));
+                        if deserializer.starts_with("(v: any)") || deserializer.starts_with("(data: any)") {
+                            w.line(&format!("const result = ({})(data);", deserializer));
+                        } else {
+                            w.line(&format!("const result = {}(data);", deserializer));
+                        }
+                    } else {
+                        w.line(&format!("const result = {};", read_json));
+                    }
+                    w.block("if (this.validate) {", "}", |w| {
+                        symbols.uses("ValidationIssue");
+                        symbols.uses("assertValid");
+                        w.line("const issues: ValidationIssue[] = [];");
+                        validation::generate_checks(w, &endpoint.response, None, "result", "", 0, options, symbols);
+                        w.line("assertValid(issues, 'Invalid response');");
+                    });
+                    w.line("return result;");
+                } else if needs_serialization(&endpoint.response, options) {
+                    let deserializer = get_deserializer_call(&endpoint.response, options, symbols);
+                    w.line(&format!("const data = {};", read_json));
                     // Wrap inline lambdas in parentheses
                     if deserializer.starts_with("(v: any)") || deserializer.starts_with("(data: any)") {
                         w.line(&format!("return ({})(data);", deserializer));
@@ -514,7 +659,11 @@ fn generate_endpoint_method_inner(w: &mut CodeWriter, endpoint: &Endpoint) -> Re
                         w.line(&format!("return {}(data);", deserializer));
                     }
                 } else {
-                    w.line("return response.json();");
+                    if options.bigints {
+                        w.line("return parseJson(await response.text());");
+                    } else {
+                        w.line("return response.json();");
+                    }
                 }
             }
         }
@@ -522,25 +671,186 @@ fn generate_endpoint_method_inner(w: &mut CodeWriter, endpoint: &Endpoint) -> Re
     Ok(())
 }
 
-fn needs_serialization(field_type: &FieldType) -> bool {
+/// A positional argument of a generated client method
+pub struct MethodParam {
+    pub name: String,
+    pub ts_type: String,
+    pub optional: bool,
+}
+
+impl MethodParam {
+    fn new(
+        name: &str,
+        field_type: &FieldType,
+        options: &TypeScriptOptions,
+        symbols: &mut Symbols,
+    ) -> Self {
+        Self {
+            name: name.to_string(),
+            ts_type: field_type_to_ts(field_type, options, symbols),
+            optional: matches!(field_type, FieldType::Optional(_)),
+        }
+    }
+
+    pub fn declaration(&self) -> String {
+        if self.optional {
+            format!("{}?: {}", self.name, self.ts_type)
+        } else {
+            format!("{}: {}", self.name, self.ts_type)
+        }
+    }
+}
+
+/// Arguments for the parameterized headers in `headers`, in declaration order
+pub fn header_params(
+    headers: &[Header],
+    options: &TypeScriptOptions,
+    symbols: &mut Symbols,
+) -> Vec<MethodParam> {
+    headers
+        .iter()
+        .flat_map(|header| header.value.params())
+        .map(|(name, field_type)| MethodParam::new(name, field_type, options, symbols))
+        .collect()
+}
+
+/// A statement setting `header` in the `target` record, reading parameters through `access`.
+/// Headers with optional parameters are only set once all of them are.
+fn header_assignment(
+    target: &str,
+    header: &Header,
+    options: &TypeScriptOptions,
+    access: impl Fn(&str) -> String,
+) -> String {
+    let value = match &header.value {
+        HeaderValue::Literal(value) => format!("'{}'", value),
+        HeaderValue::Parameter { name, .. } => format!("String({})", access(name)),
+        HeaderValue::Pattern { parts } => {
+            let value: String = parts
+                .iter()
+                .map(|part| match part {
+                    HeaderPart::Literal(lit) => lit
+                        .replace('\\', "\\\\")
+                        .replace('`', "\\`")
+                        .replace("${", "\\${"),
+                    HeaderPart::Param {
+                        name,
+                        field_type,
+                        format,
+                    } => format!(
+                        "${{{}}}",
+                        header_param_expr(&access(name), field_type, format.as_ref(), options)
+                    ),
+                })
+                .collect();
+            format!("`{}`", value)
+        }
+    };
+    let optional: Vec<String> = header
+        .value
+        .params()
+        .into_iter()
+        .filter(|(_, field_type)| matches!(field_type, FieldType::Optional(_)))
+        .map(|(name, _)| format!("{} !== undefined", access(name)))
+        .collect();
+    let assignment = format!("{}['{}'] = {};", target, header.name, value);
+    if optional.is_empty() {
+        assignment
+    } else {
+        format!("if ({}) {}", optional.join(" && "), assignment)
+    }
+}
+
+/// Expression for the text of a header pattern parameter in its wire format
+fn header_param_expr(
+    value: &str,
+    field_type: &FieldType,
+    format: Option<&HeaderFormat>,
+    options: &TypeScriptOptions,
+) -> String {
+    match format {
+        None => format!("String({})", value),
+        Some(HeaderFormat::Lowercase) => format!("String({}).toLowerCase()", value),
+        Some(HeaderFormat::Base64) => format!(
+            "btoa(String.fromCharCode(...new TextEncoder().encode(String({}))))",
+            value
+        ),
+        Some(HeaderFormat::IsoDate) => {
+            let prim = match field_type {
+                FieldType::Optional(inner) => inner.as_ref(),
+                field_type => field_type,
+            };
+            match prim {
+                FieldType::Primitive(prim) => primitive_serializer_expr(prim, value, options),
+                _ => None,
+            }
+            .unwrap_or_else(|| format!("String({})", value))
+        }
+    }
+}
+
+/// Arguments of an endpoint method in call order: headers, path parameters, query and body, with
+/// required arguments before optional ones. The trailing per-call options are not included.
+pub fn endpoint_params(
+    endpoint: &Endpoint,
+    options: &TypeScriptOptions,
+    symbols: &mut Symbols,
+) -> Vec<MethodParam> {
+    let mut params = header_params(&endpoint.headers, options, symbols);
+    for segment in &endpoint.path {
+        match segment {
+            PathSegment::Parameter { name, r#type } => {
+                params.push(MethodParam::new(name, r#type, options, symbols));
+            }
+            PathSegment::Wildcard { name } => {
+                let r#type = FieldType::Primitive(PrimitiveType::String(None));
+                params.push(MethodParam::new(name, &r#type, options, symbols));
+            }
+            PathSegment::Literal(_) => {}
+        }
+    }
+    if let Some(query_type) = &endpoint.query {
+        params.push(MethodParam::new("query", query_type, options, symbols));
+    }
+    if let Some(body_type) = &endpoint.body {
+        params.push(MethodParam::new("body", body_type, options, symbols));
+    }
+    // Stable, so each group keeps its declaration order
+    params.sort_by_key(|param| param.optional);
+    params
+}
+
+fn needs_serialization(field_type: &FieldType, options: &TypeScriptOptions) -> bool {
     match field_type {
-        FieldType::Reference(_) => true,
-        FieldType::List(inner) => needs_serialization(inner),
-        FieldType::Optional(inner) => needs_serialization(inner),
-        FieldType::Map(inner) => needs_serialization(inner),
-        FieldType::Stream(inner) => needs_serialization(inner),
-        FieldType::Tuple(types) => types.iter().any(|t| needs_serialization(t)),
-        FieldType::Intersection(types) => types.iter().any(|t| needs_serialization(t)),
+        FieldType::Reference(_) => options.uses_serializers(),
+        FieldType::Primitive(prim) => options.uses_date(prim) || options.uses_bigint(prim),
+        FieldType::List(inner) => needs_serialization(inner, options),
+        FieldType::Optional(inner) => needs_serialization(inner, options),
+        FieldType::Map(inner) => needs_serialization(inner, options),
+        FieldType::Stream(inner) => needs_serialization(inner, options),
+        FieldType::Tuple(types) => types.iter().any(|t| needs_serialization(t, options)),
+        FieldType::Intersection(types) => types.iter().any(|t| needs_serialization(t, options)),
         _ => false,
     }
 }
 
-fn get_serializer_call(field_type: &FieldType) -> String {
+fn get_serializer_call(
+    field_type: &FieldType,
+    options: &TypeScriptOptions,
+    symbols: &mut Symbols,
+) -> String {
     match field_type {
-        FieldType::Reference(name) => format!("serialize{}", name),
+        FieldType::Reference(name) => {
+            symbols.uses(&format!("serialize{}", name));
+            format!("serialize{}", name)
+        }
+        FieldType::Primitive(prim) => match primitive_serializer_expr(prim, "v", options) {
+            Some(expr) => format!("(v: any) => {}", expr),
+            None => "(v: any) => v".to_string(),
+        },
         FieldType::List(inner) => {
-            if needs_serialization(inner) {
-                let inner_serializer = get_serializer_call(inner);
+            if needs_serialization(inner, options) {
+                let inner_serializer = get_serializer_call(inner, options, symbols);
                 // Wrap inline lambdas in parentheses when calling them
                 if inner_serializer.starts_with("(") {
                     format!("(v: any) => v.map((x: any) => ({})(x))", inner_serializer)
@@ -552,8 +862,8 @@ fn get_serializer_call(field_type: &FieldType) -> String {
             }
         }
         FieldType::Optional(inner) => {
-            if needs_serialization(inner) {
-                let inner_serializer = get_serializer_call(inner);
+            if needs_serialization(inner, options) {
+                let inner_serializer = get_serializer_call(inner, options, symbols);
                 // Wrap inline lambdas in parentheses when calling them
                 if inner_serializer.starts_with("(") {
                     format!(
@@ -571,33 +881,43 @@ fn get_serializer_call(field_type: &FieldType) -> String {
             }
         }
         FieldType::Map(inner) => {
-            if needs_serialization(inner) {
-                let inner_serializer = get_serializer_call(inner);
+            if needs_serialization(inner, options) {
+                let inner_serializer = get_serializer_call(inner, options, symbols);
                 // Wrap inline lambdas in parentheses when calling them
                 if inner_serializer.starts_with("(") {
-                    format!("(v: any) => Object.fromEntries(Object.entries(v).map(([k, val]) => [k, ({})(val)]))", inner_serializer)
+                    format!(
+                        "(v: any) => Object.fromEntries(Object.entries(v).map(([k, val]) => [k, ({})(val)]))",
+                        inner_serializer
+                    )
                 } else {
-                    format!("(v: any) => Object.fromEntries(Object.entries(v).map(([k, val]) => [k, {}(val)]))", inner_serializer)
+                    format!(
+                        "(v: any) => Object.fromEntries(Object.entries(v).map(([k, val]) => [k, {}(val)]))",
+                        inner_serializer
+                    )
                 }
             } else {
                 "(v: any) => v".to_string()
             }
         }
         FieldType::Tuple(types) => {
-            if types.iter().any(|t| needs_serialization(t)) {
-                let serializers: Vec<String> = types.iter().enumerate().map(|(i, t)| {
-                    if needs_serialization(t) {
-                        let serializer = get_serializer_call(t);
-                        // Wrap inline lambdas in parentheses when calling them
-                        if serializer.starts_with("(") {
-                            format!("({})(v[{}])", serializer, i)
+            if types.iter().any(|t| needs_serialization(t, options)) {
+                let serializers: Vec<String> = types
+                    .iter()
+                    .enumerate()
+                    .map(|(i, t)| {
+                        if needs_serialization(t, options) {
+                            let serializer = get_serializer_call(t, options, symbols);
+                            // Wrap inline lambdas in parentheses when calling them
+                            if serializer.starts_with("(") {
+                                format!("({})(v[{}])", serializer, i)
+                            } else {
+                                format!("{}(v[{}])", serializer, i)
+                            }
                         } else {
-                            format!("{}(v[{}])", serializer, i)
+                            format!("v[{}]", i)
                         }
-                    } else {
-                        format!("v[{}]", i)
-                    }
-                }).collect();
+                    })
+                    .collect();
                 format!("(v: any) => [{}]", serializers.join(", "))
             } else {
                 "(v: any) => v".to_string()
@@ -607,12 +927,23 @@ fn get_serializer_call(field_type: &FieldType) -> String {
     }
 }
 
-fn get_deserializer_call(field_type: &FieldType) -> String {
+fn get_deserializer_call(
+    field_type: &FieldType,
+    options: &TypeScriptOptions,
+    symbols: &mut Symbols,
+) -> String {
     match field_type {
-        FieldType::Reference(name) => format!("deserialize{}", name),
+        FieldType::Reference(name) => {
+            symbols.uses(&format!("deserialize{}", name));
+            format!("deserialize{}", name)
+        }
+        FieldType::Primitive(prim) => match primitive_deserializer_expr(prim, "v", options) {
+            Some(expr) => format!("(v: any) => {}", expr),
+            None => "(v: any) => v".to_string(),
+        },
         FieldType::List(inner) => {
-            if needs_serialization(inner) {
-                let inner_deserializer = get_deserializer_call(inner);
+            if needs_serialization(inner, options) {
+                let inner_deserializer = get_deserializer_call(inner, options, symbols);
                 // Wrap inline lambdas in parentheses when calling them
                 if inner_deserializer.starts_with("(") {
                     format!("(v: any) => v.map((x: any) => ({})(x))", inner_deserializer)
@@ -624,8 +955,8 @@ fn get_deserializer_call(field_type: &FieldType) -> String {
             }
         }
         FieldType::Optional(inner) => {
-            if needs_serialization(inner) {
-                let inner_deserializer = get_deserializer_call(inner);
+            if needs_serialization(inner, options) {
+                let inner_deserializer = get_deserializer_call(inner, options, symbols);
                 // Wrap inline lambdas in parentheses when calling them
                 if inner_deserializer.starts_with("(") {
                     format!(
@@ -643,33 +974,43 @@ fn get_deserializer_call(field_type: &FieldType) -> String {
             }
         }
         FieldType::Map(inner) => {
-            if needs_serialization(inner) {
-                let inner_deserializer = get_deserializer_call(inner);
+            if needs_serialization(inner, options) {
+                let inner_deserializer = get_deserializer_call(inner, options, symbols);
                 // Wrap inline lambdas in parentheses when calling them
                 if inner_deserializer.starts_with("(") {
-                    format!("(v: any) => Object.fromEntries(Object.entries(v).map(([k, val]) => [k, ({})(val)]))", inner_deserializer)
+                    format!(
+                        "(v: any) => Object.fromEntries(Object.entries(v).map(([k, val]) => [k, ({})(val)]))",
+                        inner_deserializer
+                    )
                 } else {
-                    format!("(v: any) => Object.fromEntries(Object.entries(v).map(([k, val]) => [k, {}(val)]))", inner_deserializer)
+                    format!(
+                        "(v: any) => Object.fromEntries(Object.entries(v).map(([k, val]) => [k, {}(val)]))",
+                        inner_deserializer
+                    )
                 }
             } else {
                 "(v: any) => v".to_string()
             }
         }
         FieldType::Tuple(types) => {
-            if types.iter().any(|t| needs_serialization(t)) {
-                let deserializers: Vec<String> = types.iter().enumerate().map(|(i, t)| {
-                    if needs_serialization(t) {
-                        let deserializer = get_deserializer_call(t);
-                        // Wrap inline lambdas in parentheses when calling them
-                        if deserializer.starts_with("(") {
-                            format!("({})(v[{}])", deserializer, i)
+            if types.iter().any(|t| needs_serialization(t, options)) {
+                let deserializers: Vec<String> = types
+                    .iter()
+                    .enumerate()
+                    .map(|(i, t)| {
+                        if needs_serialization(t, options) {
+                            let deserializer = get_deserializer_call(t, options, symbols);
+                            // Wrap inline lambdas in parentheses when calling them
+                            if deserializer.starts_with("(") {
+                                format!("({})(v[{}])", deserializer, i)
+                            } else {
+                                format!("{}(v[{}])", deserializer, i)
+                            }
                         } else {
-                            format!("{}(v[{}])", deserializer, i)
+                            format!("v[{}]", i)
                         }
-                    } else {
-                        format!("v[{}]", i)
-                    }
-                }).collect();
+                    })
+                    .collect();
                 format!("(v: any) => [{}]", deserializers.join(", "))
             } else {
                 "(v: any) => v".to_string()
@@ -678,3 +1019,322 @@ fn get_deserializer_call(field_type: &FieldType) -> String {
         _ => "(v: any) => v".to_string(),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request_runtime() -> String {
+        let mut writer = CodeWriter::new();
+        generate_request_runtime(&mut writer, &mut Symbols::default()).unwrap();
+        writer.into_string()
+    }
+
+    fn send_request(runtime: &str) -> &str {
+        let start = runtime.find("export async function sendRequest(").unwrap();
+        let end = runtime[start..].find("\n}\n").unwrap();
+        &runtime[start..start + end]
+    }
+
+    #[test]
+    fn retries_only_retryable_attempts() {
+        let runtime = request_runtime();
+        let send = send_request(&runtime);
+        assert!(send.contains(
+            "const attempts = policy && (idempotent || policy.retryNonIdempotent) ? Math.max(1, policy.attempts ?? 3) : 1;"
+        ));
+        assert!(send.contains("if (attempt >= attempts || !retryOn.includes(response.status)) {"));
+        assert!(send.contains(
+            "const backoff = (policy?.backoffMs ?? 200) * 2 ** (attempt - 1);\n    \
+             await sleep(Math.min(backoff, policy?.maxBackoffMs ?? 10000), signal);"
+        ));
+        assert!(runtime.contains("const DEFAULT_RETRY_ON = [408, 429, 500, 502, 503, 504];"));
+    }
+
+    #[test]
+    fn discards_the_body_of_a_retried_response() {
+        let runtime = request_runtime();
+        let send = send_request(&runtime);
+        let cancel = send
+            .find("await response.body?.cancel().catch(() => undefined);")
+            .unwrap();
+        let sleep = send.find("await sleep(").unwrap();
+        assert!(cancel < sleep);
+    }
+
+    #[test]
+    fn times_out_each_attempt() {
+        let runtime = request_runtime();
+        let send = send_request(&runtime);
+        assert!(send.contains(
+            "? setTimeout(() => controller.abort(new DOMException(`Request timed out after ${timeoutMs}ms`, 'TimeoutError')), timeoutMs)"
+        ));
+        assert!(send.contains("} finally {\n      clearTimeout(timer);\n    }"));
+    }
+
+    #[test]
+    fn caller_aborts_are_not_retried() {
+        let runtime = request_runtime();
+        let send = send_request(&runtime);
+        assert!(send.contains(
+            "} catch (error) {\n      \
+             release();\n      \
+             // Network errors and timeouts are retried; cancellation by the caller is not\n      \
+             if (attempt >= attempts || signal?.aborted) {\n        \
+             throw error;"
+        ));
+        // The backoff ends early when the caller aborts
+        assert!(runtime.contains(
+            "const onAbort = () => {\n      clearTimeout(timer);\n      reject(signal?.reason);"
+        ));
+    }
+
+    #[test]
+    fn caller_signal_covers_reading_the_body() {
+        let runtime = request_runtime();
+        let send = send_request(&runtime);
+        assert!(send.contains("return signal ? releaseWhenRead(response, release) : response;"));
+        // Only `release` stops listening to the caller's signal
+        assert_eq!(send.matches("removeEventListener").count(), 1);
+        assert!(!send[send.find("} finally {").unwrap()..].contains("release()"));
+
+        let start = runtime.find("function releaseWhenRead(").unwrap();
+        let release_when_read = &runtime[start..];
+        assert!(
+            release_when_read
+                .contains("if (done) {\n          release();\n          controller.close();")
+        );
+        assert!(
+            release_when_read.contains(
+                "} catch (error) {\n        release();\n        controller.error(error);"
+            )
+        );
+        assert!(
+            release_when_read.contains(
+                "cancel(reason) {\n      release();\n      return reader.cancel(reason);"
+            )
+        );
+    }
+
+    fn run_middleware(runtime: &str) -> &str {
+        let start = runtime
+            .find("export async function runMiddleware(")
+            .unwrap();
+        &runtime[start..]
+    }
+
+    #[test]
+    fn middleware_runs_in_order_around_the_request() {
+        let runtime = request_runtime();
+        let run = run_middleware(&runtime);
+        let on_request = run
+            .find("context = (await m.onRequest?.(context)) ?? context;")
+            .unwrap();
+        let send = run.find("let response = await send(context);").unwrap();
+        let on_response = run
+            .find("response = (await m.onResponse?.(context, response)) ?? response;")
+            .unwrap();
+        let status = run.find("if (!response.ok) {").unwrap();
+        let on_error = run
+            .find("const recovered = await m.onError?.(context, error);")
+            .unwrap();
+        assert!(on_request < send && send < on_response && on_response < status);
+        assert!(status < on_error);
+        // Every hook walks the middleware in the order it was configured
+        assert_eq!(run.matches("for (const m of middleware) {").count(), 3);
+    }
+
+    #[test]
+    fn first_recovered_error_short_circuits() {
+        let runtime = request_runtime();
+        assert!(run_middleware(&runtime).contains(
+            "const recovered = await m.onError?.(context, error);\n      \
+             if (recovered) {\n        \
+             return recovered;\n      \
+             }\n    \
+             }\n    \
+             throw error;"
+        ));
+    }
+
+    #[test]
+    fn requests_are_sent_as_the_middleware_left_them() {
+        use crate::generate::typescript::TypeScriptGenerator;
+        use crate::spec::{PathSegment, Spec, Type};
+
+        let spec = Spec::new("test").service("machines", |service| {
+            service.post(
+                "start",
+                vec![PathSegment::Literal("machines".into())],
+                |e| {
+                    e.body(Type::schema::<String>())
+                        .response(Type::schema::<String>())
+                },
+            )
+        });
+        let aat = AAT::from_spec(&spec).unwrap();
+        let client =
+            TypeScriptGenerator::generate_with_options(&aat, &TypeScriptOptions::default())
+                .unwrap();
+        assert!(client.contains(
+            "return runMiddleware(this.middleware, context, (ctx) => sendRequest(this.fetchImpl, ctx.url, ctx.init, this.defaults, requestOptions));"
+        ));
+        assert!(client.contains(
+            "const response = await this.send({\n      \
+             service: 'machines',\n      \
+             endpoint: 'start',\n      \
+             url,"
+        ));
+        assert!(client.contains("this.middleware = config.middleware ?? [];"));
+    }
+
+    fn streaming_client(ws_auth: Option<crate::spec::WsAuth>) -> String {
+        use crate::generate::typescript::TypeScriptGenerator;
+        use crate::spec::{PathSegment, Spec, Type, Upgrade};
+
+        let spec = Spec::new("test").service("logs", |service| {
+            service.get("tail", vec![PathSegment::Literal("logs".into())], |e| {
+                let e = e
+                    .upgrade(Upgrade::Ws)
+                    .response(Type::stream(Type::schema::<String>()));
+                match ws_auth {
+                    Some(ws_auth) => e.ws_auth(ws_auth),
+                    None => e,
+                }
+            })
+        });
+        let aat = AAT::from_spec(&spec).unwrap();
+        TypeScriptGenerator::generate_with_options(&aat, &TypeScriptOptions::default()).unwrap()
+    }
+
+    fn stream_construction(client: &str) -> &str {
+        let start = client.find("const stream = new WebSocketStream(").unwrap();
+        let end = client[start..].find('\n').unwrap();
+        &client[start..start + end]
+    }
+
+    #[test]
+    fn websocket_headers_without_a_fallback() {
+        let client = streaming_client(None);
+        assert!(stream_construction(&client).ends_with(
+            "mergedHeaders, this.WebSocketImpl, { nativeHeaders: this.webSocketHeaders }, streamOptions);"
+        ));
+    }
+
+    #[test]
+    fn websocket_headers_in_the_query() {
+        let client = streaming_client(Some(crate::spec::WsAuth::Query));
+        assert!(
+            stream_construction(&client).ends_with(
+                "{ nativeHeaders: this.webSocketHeaders, auth: 'query' }, streamOptions);"
+            )
+        );
+        assert!(client.contains("const url = this.socketUrl(auth === 'query' ? headers : {});"));
+    }
+
+    #[test]
+    fn websocket_headers_in_the_protocol() {
+        let client = streaming_client(Some(crate::spec::WsAuth::Protocol(
+            "it's \"quoted\"".to_string(),
+        )));
+        assert!(stream_construction(&client).ends_with(
+            "{ nativeHeaders: this.webSocketHeaders, auth: { protocol: \"it's \\\"quoted\\\"\" } }, streamOptions);"
+        ));
+        assert!(client.contains(
+            "const protocols = typeof auth === 'object' ? [auth.protocol, base64url(JSON.stringify(headers))] : undefined;"
+        ));
+    }
+
+    #[test]
+    fn websocket_headers_in_a_first_message() {
+        let client = streaming_client(Some(crate::spec::WsAuth::Message));
+        assert!(stream_construction(&client).ends_with(
+            "{ nativeHeaders: this.webSocketHeaders, auth: 'message' }, streamOptions);"
+        ));
+        assert!(client.contains(
+            "if (auth === 'message') {\n          ws.send(JSON.stringify({ type: 'auth', headers }));"
+        ));
+        // Native headers take precedence over every fallback
+        assert!(client.contains(
+            "const auth = this.transport.nativeHeaders ? undefined : this.transport.auth;"
+        ));
+    }
+
+    fn signed_client(options: &TypeScriptOptions) -> String {
+        use crate::fixtures::{Timestamp, machines};
+        use crate::generate::typescript::TypeScriptGenerator;
+        use crate::spec::{HeaderFormat, HeaderParam, HeaderValue, Type};
+
+        let param = |name: &str, r#type, format| HeaderParam {
+            name: name.to_string(),
+            r#type,
+            format,
+        };
+        let spec = machines(|e| {
+            e.header(
+                "x-signature",
+                HeaderValue::Pattern {
+                    pattern: "key={key}, at={at}; {sig}".to_string(),
+                    params: vec![
+                        param(
+                            "key",
+                            Type::schema::<String>(),
+                            Some(HeaderFormat::Lowercase),
+                        ),
+                        param(
+                            "at",
+                            Type::schema::<Timestamp>(),
+                            Some(HeaderFormat::IsoDate),
+                        ),
+                        param("sig", Type::schema::<String>(), Some(HeaderFormat::Base64)),
+                    ],
+                },
+            )
+            .header(
+                "x-trace",
+                HeaderValue::Pattern {
+                    pattern: "{trace}@{since}".to_string(),
+                    params: vec![
+                        param("trace", Type::optional(Type::schema::<String>()), None),
+                        param(
+                            "since",
+                            Type::optional(Type::schema::<Timestamp>()),
+                            Some(HeaderFormat::IsoDate),
+                        ),
+                    ],
+                },
+            )
+        });
+        let aat = AAT::from_spec(&spec).unwrap();
+        TypeScriptGenerator::generate_with_options(&aat, options).unwrap()
+    }
+
+    #[test]
+    fn formats_header_parameters() {
+        let client = signed_client(&TypeScriptOptions::default());
+        assert!(client.contains(
+            "endpointHeaders['x-signature'] = `key=${String(key).toLowerCase()}, at=${String(at)}; \
+             ${btoa(String.fromCharCode(...new TextEncoder().encode(String(sig))))}`;"
+        ));
+    }
+
+    #[test]
+    fn iso_dates_are_written_from_date_values() {
+        let client = signed_client(&TypeScriptOptions {
+            dates: true,
+            ..Default::default()
+        });
+        assert!(client.contains("at: Date"));
+        assert!(client.contains(", at=${at.toISOString()}; "));
+        assert!(client.contains("`${String(trace)}@${since.toISOString()}`"));
+    }
+
+    #[test]
+    fn headers_with_optional_parameters_are_set_once_all_are_given() {
+        let client = signed_client(&TypeScriptOptions::default());
+        assert!(client.contains(
+            "if (trace !== undefined && since !== undefined) \
+             endpointHeaders['x-trace'] = `${String(trace)}@${String(since)}`;"
+        ));
+    }
+}
@@ -0,0 +1,477 @@
+use anyhow::Result;
+
+use crate::aat::*;
+use crate::generate::writer::CodeWriter;
+
+use super::TypeScriptOptions;
+use super::client::{MethodParam, endpoint_params, header_params};
+use super::symbols::Symbols;
+use super::utils::*;
+
+/// How an endpoint is exposed through TanStack Query
+enum HookKind {
+    Query,
+    Mutation,
+    Subscription,
+}
+
+/// An endpoint together with everything its hook needs
+struct Hook<'a> {
+    service: &'a Service,
+    endpoint: &'a Endpoint,
+    kind: HookKind,
+    /// Service header arguments followed by the endpoint's own arguments
+    params: Vec<MethodParam>,
+    service_arg_count: usize,
+}
+
+impl Hook<'_> {
+    fn base_name(&self) -> String {
+        format!(
+            "{}{}",
+            to_pascal_case(&self.service.name),
+            to_pascal_case(&self.endpoint.name)
+        )
+    }
+
+    fn params_type(&self) -> Option<String> {
+        if self.params.is_empty() {
+            None
+        } else {
+            Some(format!("{}Params", self.base_name()))
+        }
+    }
+
+    /// `client.<service>.<method>(<arguments>` without the closing parenthesis, so per-call options
+    /// can be appended
+    fn call(&self, options: &TypeScriptOptions) -> String {
+        let args: Vec<String> = self
+            .params
+            .iter()
+            .map(|param| format!("params.{}", param.name))
+            .collect();
+        let (service_args, endpoint_args) = args.split_at(self.service_arg_count);
+        let service = if self.service_arg_count == 0 {
+            self.service.name.clone()
+        } else {
+            format!("{}({})", self.service.name, service_args.join(", "))
+        };
+        format!(
+            "client.{}.{}({}",
+            service,
+            options.naming.method_name(&self.endpoint.name),
+            endpoint_args.join(", ")
+        )
+    }
+}
+
+/// `hooks.ts`: TanStack Query hooks on top of the generated `Client`. GET endpoints become query
+/// hooks, other methods mutation hooks and WebSocket endpoints subscription hooks.
+/// `client_module` is the import specifier of the module exporting the client and its types.
+pub fn generate_hooks(
+    aat: &AAT,
+    options: &TypeScriptOptions,
+    client_module: &str,
+) -> Result<String> {
+    // Everything the hooks refer to is noted while writing them, to import it from the client
+    let mut symbols = Symbols::default();
+    let mut hooks = Vec::new();
+    for service in &aat.services {
+        for endpoint in &service.endpoints {
+            let kind = if matches!(endpoint.upgrade, Some(Upgrade::Ws)) {
+                HookKind::Subscription
+            } else if matches!(endpoint.method, HttpMethod::Get) {
+                HookKind::Query
+            } else {
+                HookKind::Mutation
+            };
+            let mut params = header_params(&service.headers, options, &mut symbols);
+            let service_arg_count = params.len();
+            params.extend(endpoint_params(endpoint, options, &mut symbols));
+            hooks.push(Hook {
+                service,
+                endpoint,
+                kind,
+                params,
+                service_arg_count,
+            });
+        }
+    }
+
+    let mut body = CodeWriter::new();
+    generate_context(&mut body);
+
+    for hook in &hooks {
+        if let Some(params_type) = hook.params_type() {
+            body.block(&format!("export interface {} {{", params_type), "}", |w| {
+                for param in &hook.params {
+                    w.line(&format!("{};", param.declaration()));
+                }
+            });
+            body.empty_line();
+        }
+    }
+
+    generate_query_keys(&mut body, aat, &hooks, options);
+
+    let has_subscriptions = hooks
+        .iter()
+        .any(|hook| matches!(hook.kind, HookKind::Subscription));
+    if has_subscriptions {
+        generate_subscription_types(&mut body, &mut symbols);
+    }
+
+    for hook in &hooks {
+        match hook.kind {
+            HookKind::Query => generate_query_hook(&mut body, hook, options, &mut symbols),
+            HookKind::Mutation => generate_mutation_hook(&mut body, hook, options, &mut symbols),
+            HookKind::Subscription => {
+                generate_subscription_hook(&mut body, hook, options, &mut symbols)
+            }
+        }
+        body.empty_line();
+    }
+    let body = body.into_string();
+
+    let mut writer = CodeWriter::new();
+    writer.line("// Generated by Damascus");
+    writer.line("// Do not edit this file directly");
+    writer.empty_line();
+
+    let mut react = vec!["createContext", "createElement", "useContext"];
+    if has_subscriptions {
+        react.extend(["useEffect", "useRef", "useState"]);
+    }
+    writer.line(&format!("import {{ {} }} from 'react';", react.join(", ")));
+    writer.line("import type { ReactNode } from 'react';");
+
+    let mut tanstack = Vec::new();
+    let mut tanstack_types = Vec::new();
+    if hooks
+        .iter()
+        .any(|hook| matches!(hook.kind, HookKind::Mutation))
+    {
+        tanstack.push("useMutation");
+        tanstack_types.push("UseMutationOptions");
+    }
+    if hooks
+        .iter()
+        .any(|hook| matches!(hook.kind, HookKind::Query))
+    {
+        tanstack.push("useQuery");
+        tanstack_types.push("UseQueryOptions");
+    }
+    if hooks.iter().any(|hook| {
+        matches!(hook.kind, HookKind::Mutation) && !hook.endpoint.invalidates.is_empty()
+    }) {
+        tanstack.push("useQueryClient");
+    }
+    if !tanstack.is_empty() {
+        writer.line(&format!(
+            "import {{ {} }} from '@tanstack/react-query';",
+            tanstack.join(", ")
+        ));
+        writer.line(&format!(
+            "import type {{ {} }} from '@tanstack/react-query';",
+            tanstack_types.join(", ")
+        ));
+    }
+    writer.line(&format!(
+        "import type {{ {} }} from '{}';",
+        client_imports(aat, &symbols).join(", "),
+        client_module
+    ));
+    writer.empty_line();
+
+    let mut content = writer.into_string();
+    content.push_str(&body);
+    Ok(content)
+}
+
+/// Generated names used by the hooks: the client, the referenced types and stream helpers
+fn client_imports<'a>(aat: &'a AAT, used: &Symbols) -> Vec<&'a str> {
+    let mut imports = vec!["Client"];
+    for name in ["StreamClose", "StreamOptions"] {
+        if used.uses.contains(name) {
+            imports.push(name);
+        }
+    }
+    for named_type in &aat.types {
+        let name = get_type_name(named_type);
+        if used.uses.contains(name) {
+            imports.push(name);
+        }
+    }
+    imports.sort_unstable();
+    imports
+}
+
+fn generate_context(w: &mut CodeWriter) {
+    w.line("const ClientContext = createContext<Client | null>(null);");
+    w.empty_line();
+
+    w.block(
+        "export function ClientProvider({ client, children }: { client: Client; children?: ReactNode }) {",
+        "}",
+        |w| {
+            w.line("return createElement(ClientContext.Provider, { value: client }, children);");
+        },
+    );
+    w.empty_line();
+
+    w.block("export function useClient(): Client {", "}", |w| {
+        w.line("const client = useContext(ClientContext);");
+        w.block("if (!client) {", "}", |w| {
+            w.line("throw new Error('useClient must be used within a ClientProvider');");
+        });
+        w.line("return client;");
+    });
+    w.empty_line();
+}
+
+/// `queryKeys.<service>.<method>(params)`, yielding `[service, endpoint, params]` so that
+/// invalidating `[service]` or `[service, endpoint]` matches by prefix
+fn generate_query_keys(w: &mut CodeWriter, aat: &AAT, hooks: &[Hook], options: &TypeScriptOptions) {
+    w.block("export const queryKeys = {", "};", |w| {
+        for service in &aat.services {
+            w.block(&format!("{}: {{", service.name), "},", |w| {
+                w.line(&format!("all: ['{}'] as const,", service.name));
+                for hook in hooks {
+                    if hook.service.name != service.name || !matches!(hook.kind, HookKind::Query) {
+                        continue;
+                    }
+                    let method = options.naming.method_name(&hook.endpoint.name);
+                    match hook.params_type() {
+                        Some(params_type) => w.line(&format!(
+                            "{}: (params: {}) => ['{}', '{}', params] as const,",
+                            method, params_type, service.name, hook.endpoint.name
+                        )),
+                        None => w.line(&format!(
+                            "{}: () => ['{}', '{}'] as const,",
+                            method, service.name, hook.endpoint.name
+                        )),
+                    }
+                }
+            });
+        }
+    });
+    w.empty_line();
+}
+
+fn generate_subscription_types(w: &mut CodeWriter, symbols: &mut Symbols) {
+    w.block("export interface Subscription<T> {", "}", |w| {
+        w.line("/** The most recent message */");
+        w.line("data: T | undefined;");
+        w.line("error: Error | undefined;");
+        w.line("/** Set once the stream has ended */");
+        symbols.uses("StreamClose");
+        w.line("close: StreamClose | undefined;");
+    });
+    w.empty_line();
+
+    w.block("export interface SubscriptionOptions<T> {", "}", |w| {
+        w.line("/** `false` holds off connecting */");
+        w.line("enabled?: boolean;");
+        w.line("/** Called for every message, e.g. to append log lines to a list */");
+        w.line("onMessage?: (data: T) => void;");
+        w.line("/** Read when connecting; changing them doesn't reconnect */");
+        symbols.uses("StreamOptions");
+        w.line("stream?: StreamOptions<T>;");
+    });
+    w.empty_line();
+}
+
+fn hook_signature(hook: &Hook, name: &str, options_param: &str, return_type: &str) -> String {
+    let mut params = Vec::new();
+    if let Some(params_type) = hook.params_type() {
+        if hook.params.iter().all(|param| param.optional) {
+            params.push(format!("params: {} = {{}}", params_type));
+        } else {
+            params.push(format!("params: {}", params_type));
+        }
+    }
+    params.push(options_param.to_string());
+    format!(
+        "export function {}({}){} {{",
+        name,
+        params.join(", "),
+        return_type
+    )
+}
+
+fn generate_query_hook(
+    w: &mut CodeWriter,
+    hook: &Hook,
+    options: &TypeScriptOptions,
+    symbols: &mut Symbols,
+) {
+    let is_void = matches!(hook.endpoint.response, FieldType::Any);
+    // TanStack Query rejects `undefined` as query data
+    let data_type = if is_void {
+        "null".to_string()
+    } else {
+        field_type_to_ts(&hook.endpoint.response, options, symbols)
+    };
+    let signature = hook_signature(
+        hook,
+        &format!("use{}", hook.base_name()),
+        &format!(
+            "options?: Omit<UseQueryOptions<{}>, 'queryKey' | 'queryFn'>",
+            data_type
+        ),
+        "",
+    );
+    let key_args = if hook.params.is_empty() { "" } else { "params" };
+    let separator = if hook.params.len() > hook.service_arg_count {
+        ", "
+    } else {
+        ""
+    };
+
+    w.block(&signature, "}", |w| {
+        w.line("const client = useClient();");
+        w.block("return useQuery({", "});", |w| {
+            w.line("...options,");
+            w.line(&format!(
+                "queryKey: queryKeys.{}.{}({}),",
+                hook.service.name,
+                options.naming.method_name(&hook.endpoint.name),
+                key_args
+            ));
+            let call = format!("{}{}{{ signal }})", hook.call(options), separator);
+            if is_void {
+                w.block("queryFn: async ({ signal }) => {", "},", |w| {
+                    w.line(&format!("await {};", call));
+                    w.line("return null;");
+                });
+            } else {
+                w.line(&format!("queryFn: ({{ signal }}) => {},", call));
+            }
+        });
+    });
+}
+
+fn generate_mutation_hook(
+    w: &mut CodeWriter,
+    hook: &Hook,
+    options: &TypeScriptOptions,
+    symbols: &mut Symbols,
+) {
+    let data_type = if matches!(hook.endpoint.response, FieldType::Any) {
+        "void".to_string()
+    } else {
+        field_type_to_ts(&hook.endpoint.response, options, symbols)
+    };
+    let variables_type = hook.params_type().unwrap_or_else(|| "void".to_string());
+    let signature = format!(
+        "export function use{}Mutation(options?: Omit<UseMutationOptions<{}, Error, {}>, 'mutationKey' | 'mutationFn'>) {{",
+        hook.base_name(),
+        data_type,
+        variables_type
+    );
+    let invalidates = &hook.endpoint.invalidates;
+
+    w.block(&signature, "}", |w| {
+        w.line("const client = useClient();");
+        if !invalidates.is_empty() {
+            w.line("const queryClient = useQueryClient();");
+        }
+        w.block("return useMutation({", "});", |w| {
+            w.line("...options,");
+            w.line(&format!(
+                "mutationKey: ['{}', '{}'],",
+                hook.service.name, hook.endpoint.name
+            ));
+            match hook.params_type() {
+                Some(params_type) => w.line(&format!(
+                    "mutationFn: (params: {}) => {}),",
+                    params_type,
+                    hook.call(options)
+                )),
+                None => w.line(&format!("mutationFn: () => {}),", hook.call(options))),
+            }
+            if !invalidates.is_empty() {
+                w.block("onSuccess: async (...args) => {", "},", |w| {
+                    w.block("await Promise.all([", "]);", |w| {
+                        for target in invalidates {
+                            let key: Vec<String> = target
+                                .split('.')
+                                .map(|part| format!("'{}'", part))
+                                .collect();
+                            w.line(&format!(
+                                "queryClient.invalidateQueries({{ queryKey: [{}] }}),",
+                                key.join(", ")
+                            ));
+                        }
+                    });
+                    w.line("return options?.onSuccess?.(...args);");
+                });
+            }
+        });
+    });
+}
+
+fn generate_subscription_hook(
+    w: &mut CodeWriter,
+    hook: &Hook,
+    options: &TypeScriptOptions,
+    symbols: &mut Symbols,
+) {
+    let item_type = match &hook.endpoint.response {
+        FieldType::Stream(inner) => field_type_to_ts(inner, options, symbols),
+        other => field_type_to_ts(other, options, symbols),
+    };
+    let signature = hook_signature(
+        hook,
+        &format!("use{}Subscription", hook.base_name()),
+        &format!("options: SubscriptionOptions<{}> = {{}}", item_type),
+        &format!(": Subscription<{}>", item_type),
+    );
+    let separator = if hook.params.len() > hook.service_arg_count {
+        ", "
+    } else {
+        ""
+    };
+    let key = if hook.params.is_empty() {
+        "''"
+    } else {
+        "JSON.stringify(params)"
+    };
+
+    w.block(&signature, "}", |w| {
+        w.line("const client = useClient();");
+        w.line(&format!(
+            "const [state, setState] = useState<Subscription<{}>>({{ data: undefined, error: undefined, close: undefined }});",
+            item_type
+        ));
+        w.line("const onMessage = useRef(options.onMessage);");
+        w.line("onMessage.current = options.onMessage;");
+        w.line(&format!("const key = {};", key));
+        w.empty_line();
+        w.block("useEffect(() => {", "}, [client, key, options.enabled]);", |w| {
+            w.block("if (options.enabled === false) {", "}", |w| {
+                w.line("return;");
+            });
+            w.line("setState({ data: undefined, error: undefined, close: undefined });");
+            w.line(&format!(
+                "const stream = {}{}options.stream);",
+                hook.call(options),
+                separator
+            ));
+            w.block("const unsubscribe = [", "];", |w| {
+                w.block("stream.onMessage((data) => {", "}),", |w| {
+                    w.line("onMessage.current?.(data);");
+                    w.line("setState((state) => ({ ...state, data }));");
+                });
+                w.line("stream.onError((error) => setState((state) => ({ ...state, error }))),");
+                w.line("stream.onClose((close) => setState((state) => ({ ...state, close }))),");
+            });
+            w.line("stream.connect().catch(() => {});");
+            w.block("return () => {", "};", |w| {
+                w.line("unsubscribe.forEach((off) => off());");
+                w.line("stream.close();");
+            });
+        });
+        w.line("return state;");
+    });
+}
@@ -1,180 +1,647 @@
 mod client;
+mod hooks;
+mod modules;
+mod naming;
+mod options;
 mod serialization;
+mod symbols;
 mod types;
 mod utils;
+mod validation;
+mod zod;
+
+pub use options::*;
 
 use crate::aat::{AAT, FieldType};
 use crate::generate::writer::CodeWriter;
+use crate::generate::{Generator, OutputFile};
 use anyhow::Result;
+use symbols::Symbols;
 
 pub struct TypeScriptGenerator {
     writer: CodeWriter,
+    options: TypeScriptOptions,
+}
+
+impl Generator for TypeScriptGenerator {
+    type Options = TypeScriptOptions;
+
+    fn name(&self) -> &str {
+        "typescript"
+    }
+
+    fn generate(&self, aat: &AAT, options: &TypeScriptOptions) -> Result<Vec<OutputFile>> {
+        Self::generate_files(aat, options)
+    }
 }
 
 impl TypeScriptGenerator {
     pub fn new() -> Self {
         Self {
             writer: CodeWriter::new(),
+            options: TypeScriptOptions::default(),
         }
     }
 
     pub fn generate(aat: &AAT) -> Result<String> {
+        Self::generate_with_options(aat, &TypeScriptOptions::default())
+    }
+
+    pub fn generate_with_options(aat: &AAT, options: &TypeScriptOptions) -> Result<String> {
         let mut generator = Self::new();
-        generator.generate_client(aat)?;
+        generator.options = options.clone();
+        generator.generate_client(&prepare(aat, options)?)?;
         Ok(generator.writer.into_string())
     }
 
+    /// All files for the configured layout, plus package scaffolding when requested.
+    /// [`TypeScriptGenerator::generate_with_options`] always produces the single-file client.
+    pub fn generate_files(aat: &AAT, options: &TypeScriptOptions) -> Result<Vec<OutputFile>> {
+        let mut files = match options.layout {
+            Layout::SingleFile => vec![OutputFile::new(
+                "client.ts",
+                Self::generate_with_options(aat, options)?,
+            )],
+            Layout::Modules | Layout::RustModules => modules::generate_modules(aat, options)?,
+        };
+        if options.react_query {
+            let client_module = match options.layout {
+                Layout::SingleFile => "./client.js",
+                Layout::Modules | Layout::RustModules => "./index.js",
+            };
+            files.push(OutputFile::new(
+                "hooks.ts",
+                hooks::generate_hooks(&naming::prepare(aat, options), options, client_module)?,
+            ));
+        }
+        if let Some(package) = &options.package {
+            files.extend(modules::generate_package(package, options)?);
+        }
+        Ok(files)
+    }
+
     fn generate_client(&mut self, aat: &AAT) -> Result<()> {
+        // A single file needs no imports, so the noted symbols are dropped
+        let mut symbols = Symbols::default();
+
         // Generate header
         self.writer.line("// Generated by Damascus");
         self.writer.line("// Do not edit this file directly");
         self.writer.empty_line();
 
+        if self.options.zod != ZodOutput::None {
+            self.writer.line("import { z } from 'zod';");
+            self.writer.empty_line();
+        }
+
         // Generate types
-        for named_type in &aat.types {
-            types::generate_type(&mut self.writer, named_type)?;
+        if self.options.zod != ZodOutput::Instead {
+            for named_type in &aat.types {
+                types::generate_type(&mut self.writer, named_type, &self.options, &mut symbols)?;
+                self.writer.empty_line();
+            }
+        }
+
+        // Generate Zod schemas
+        if self.options.zod != ZodOutput::None {
+            for named_type in &aat.types {
+                zod::generate_schema(&mut self.writer, named_type, &self.options, &mut symbols)?;
+                if self.options.zod == ZodOutput::Instead {
+                    zod::generate_inferred_type(&mut self.writer, named_type, &mut symbols)?;
+                }
+                self.writer.empty_line();
+            }
+        }
+
+        // Generate precision-safe JSON helpers for bigint values
+        if self.options.bigints {
+            serialization::generate_json_helpers(&mut self.writer, &mut symbols)?;
             self.writer.empty_line();
         }
 
         // Generate WebSocketStream base class if needed
-        if self.needs_websocket_stream(aat) {
-            self.generate_websocket_stream_class()?;
+        if needs_websocket_stream(aat) {
+            generate_websocket_stream_class(&mut self.writer, &self.options, &mut symbols)?;
             self.writer.empty_line();
         }
 
+        // Generate request options and the retrying fetch wrapper
+        client::generate_request_runtime(&mut self.writer, &mut symbols)?;
+        self.writer.empty_line();
+
         // Generate serializers and deserializers
-        for named_type in &aat.types {
-            serialization::generate_serializer(&mut self.writer, named_type)?;
+        let serialized_types = if self.options.uses_serializers() {
+            aat.types.as_slice()
+        } else {
+            &[]
+        };
+        for named_type in serialized_types {
+            serialization::generate_serializer(
+                &mut self.writer,
+                named_type,
+                &self.options,
+                &mut symbols,
+            )?;
+            self.writer.empty_line();
+            serialization::generate_deserializer(
+                &mut self.writer,
+                named_type,
+                &self.options,
+                &mut symbols,
+            )?;
             self.writer.empty_line();
-            serialization::generate_deserializer(&mut self.writer, named_type)?;
+        }
+
+        // Generate runtime validators
+        if self.options.validators {
+            validation::generate_validation_runtime(&mut self.writer, &mut symbols)?;
             self.writer.empty_line();
+            for named_type in &aat.types {
+                validation::generate_validator(
+                    &mut self.writer,
+                    named_type,
+                    &self.options,
+                    &mut symbols,
+                )?;
+                self.writer.empty_line();
+            }
         }
 
         // Generate client class for each service
         for service in &aat.services {
-            client::generate_service(&mut self.writer, service)?;
+            client::generate_service(&mut self.writer, service, &self.options, &mut symbols)?;
             self.writer.empty_line();
         }
 
         // Generate top-level ApiClient
-        client::generate_api_client(&mut self.writer, aat)?;
+        client::generate_api_client(&mut self.writer, aat, &self.options, &mut symbols)?;
         self.writer.empty_line();
 
         Ok(())
     }
+}
 
-    fn needs_websocket_stream(&self, aat: &AAT) -> bool {
-        use crate::aat::Upgrade;
+/// The AAT as the generators see it: type names follow the naming options and, when validators
+/// or Zod schemas are emitted, field patterns are in JavaScript syntax
+fn prepare(aat: &AAT, options: &TypeScriptOptions) -> Result<AAT> {
+    let aat = naming::prepare(aat, options);
+    if options.validators || options.zod != ZodOutput::None {
+        validation::prepare_patterns(aat)
+    } else {
+        Ok(aat)
+    }
+}
 
-        for service in &aat.services {
-            for endpoint in &service.endpoints {
-                if matches!(endpoint.upgrade, Some(Upgrade::Ws)) {
-                    return true;
-                }
-                if self.field_type_has_stream(&endpoint.response) {
-                    return true;
-                }
+fn needs_websocket_stream(aat: &AAT) -> bool {
+    use crate::aat::Upgrade;
+
+    for service in &aat.services {
+        for endpoint in &service.endpoints {
+            if matches!(endpoint.upgrade, Some(Upgrade::Ws)) {
+                return true;
+            }
+            if field_type_has_stream(&endpoint.response) {
+                return true;
             }
         }
-        false
     }
+    false
+}
 
-    fn field_type_has_stream(&self, field_type: &FieldType) -> bool {
-        match field_type {
-            FieldType::Stream(_) => true,
-            FieldType::Optional(inner) | FieldType::List(inner) | FieldType::Map(inner) => {
-                self.field_type_has_stream(inner)
-            }
-            FieldType::Tuple(types) | FieldType::Intersection(types) => {
-                types.iter().any(|t| self.field_type_has_stream(t))
-            }
-            _ => false,
+fn field_type_has_stream(field_type: &FieldType) -> bool {
+    match field_type {
+        FieldType::Stream(_) => true,
+        FieldType::Optional(inner) | FieldType::List(inner) | FieldType::Map(inner) => {
+            field_type_has_stream(inner)
         }
+        FieldType::Tuple(types) | FieldType::Intersection(types) => {
+            types.iter().any(field_type_has_stream)
+        }
+        _ => false,
     }
+}
 
-    fn generate_websocket_stream_class(&mut self) -> Result<()> {
-        self.writer.block("export class WebSocketStream<T> {", "}", |w| {
-            w.line("private ws: WebSocket | null = null;");
-            w.line("private messageHandlers: Set<(data: T) => void> = new Set();");
-            w.line("private errorHandlers: Set<(error: Error) => void> = new Set();");
-            w.line("private closeHandlers: Set<() => void> = new Set();");
-            w.empty_line();
+fn generate_websocket_stream_class(
+    writer: &mut CodeWriter,
+    options: &TypeScriptOptions,
+    symbols: &mut Symbols,
+) -> Result<()> {
+    let parse = if options.bigints {
+        symbols.uses("parseJson");
+        "parseJson"
+    } else {
+        "JSON.parse"
+    };
 
-            w.block("constructor(", ") {}", |w| {
-                w.line("private url: string,");
-                w.line("private deserialize: (data: any) => T,");
-                w.line("private headers: Record<string, string> | undefined,");
-                w.line("private WebSocketImpl: typeof WebSocket");
-            });
-            w.empty_line();
+    symbols.exports_value("CloseCode");
+    writer.block("export const CloseCode = {", "} as const;", |w| {
+        w.line("Normal: 1000,");
+        w.line("GoingAway: 1001,");
+        w.line("ProtocolError: 1002,");
+        w.line("UnsupportedData: 1003,");
+        w.line("NoStatus: 1005,");
+        w.line("Abnormal: 1006,");
+        w.line("InvalidPayload: 1007,");
+        w.line("PolicyViolation: 1008,");
+        w.line("MessageTooBig: 1009,");
+        w.line("MandatoryExtension: 1010,");
+        w.line("InternalError: 1011,");
+        w.line("ServiceRestart: 1012,");
+        w.line("TryAgainLater: 1013,");
+    });
+    writer.empty_line();
+    writer.line("export type CloseCode = (typeof CloseCode)[keyof typeof CloseCode];");
+    writer.empty_line();
+
+    symbols.exports_type("StreamClose");
+    writer.block("export interface StreamClose {", "}", |w| {
+        w.line("/** One of `CloseCode`, or an application-defined code in 4000-4999 */");
+        w.line("code: CloseCode | number;");
+        w.line("reason: string;");
+        w.line("/** The close handshake completed with `CloseCode.Normal` */");
+        w.line("clean: boolean;");
+    });
+    writer.empty_line();
 
-            w.block("async connect(): Promise<void> {", "}", |w| {
-                w.block("return new Promise((resolve, reject) => {", "});", |w| {
-                    w.line("const wsUrl = this.url.replace(/^http/, 'ws');");
-                    w.line("this.ws = new this.WebSocketImpl(wsUrl);");
-                    w.empty_line();
+    symbols.exports_value("StreamClosedError");
+    writer.block("export class StreamClosedError extends Error {", "}", |w| {
+        w.block("constructor(public readonly close: StreamClose) {", "}", |w| {
+            w.line("super(`WebSocket closed with code ${close.code}${close.reason ? `: ${close.reason}` : ''}`);");
+            w.line("this.name = 'StreamClosedError';");
+        });
+    });
+    writer.empty_line();
 
-                    w.block("this.ws.onopen = () => {", "};", |w| {
-                        w.line("resolve();");
+    symbols.exports_type("ReconnectPolicy");
+    writer.block("export interface ReconnectPolicy {", "}", |w| {
+        w.line("/** Consecutive reconnection attempts before giving up (default unlimited) */");
+        w.line("attempts?: number;");
+        w.line("/** Delay before the first attempt, doubled for every further attempt (default 500) */");
+        w.line("backoffMs?: number;");
+        w.line("/** Upper bound for the delay between attempts (default 30000) */");
+        w.line("maxBackoffMs?: number;");
+    });
+    writer.empty_line();
+
+    symbols.exports_type("StreamOptions");
+    writer.block("export interface StreamOptions<T> {", "}", |w| {
+        w.line("headers?: Record<string, string>;");
+        w.line("/** Reconnect after abnormal closes; `true` uses the default policy */");
+        w.line("reconnect?: ReconnectPolicy | boolean;");
+        w.line("/** Query parameter set to a wire value taken from the last message when reconnecting */");
+        w.line("resume?: { param: string; from: (last: T) => string | number | bigint };");
+    });
+    writer.empty_line();
+
+    symbols.exports_type("WebSocketTransport");
+    writer.block("export interface WebSocketTransport {", "}", |w| {
+        w.line("/** The WebSocket implementation takes headers as `new WebSocket(url, protocols, { headers })`, like Node's `ws` */");
+        w.line("nativeHeaders: boolean;");
+        w.line("/** How headers are sent otherwise; without one they are dropped */");
+        w.line("auth?: 'query' | 'message' | { protocol: string };");
+    });
+    writer.empty_line();
+
+    writer.block("function base64url(value: string): string {", "}", |w| {
+        w.line("const bytes = new TextEncoder().encode(value);");
+        w.line("let binary = '';");
+        w.line("bytes.forEach(byte => { binary += String.fromCharCode(byte); });");
+        w.line("return btoa(binary).replace(/\\+/g, '-').replace(/\\//g, '_').replace(/=+$/, '');");
+    });
+    writer.empty_line();
+
+    symbols.exports_value("WebSocketStream");
+    writer.block("export class WebSocketStream<T> implements AsyncIterable<T> {", "}", |w| {
+        w.line("private ws: WebSocket | null = null;");
+        w.line("private opened = false;");
+        w.line("private closed = false;");
+        w.line("private attempt = 0;");
+        w.line("private reconnectTimer: ReturnType<typeof setTimeout> | undefined;");
+        w.line("private resumeValue: string | undefined;");
+        w.line("private messageHandlers: Set<(data: T) => void> = new Set();");
+        w.line("private errorHandlers: Set<(error: Error) => void> = new Set();");
+        w.line("private closeHandlers: Set<(close: StreamClose) => void> = new Set();");
+        w.line("private reconnectHandlers: Set<(attempt: number, close: StreamClose) => void> = new Set();");
+        w.empty_line();
+
+        w.block("constructor(", ") {}", |w| {
+            w.line("private url: string,");
+            w.line("private deserialize: (data: any) => T,");
+            w.line("private headers: Record<string, string> | undefined,");
+            w.line("private WebSocketImpl: typeof WebSocket,");
+            w.line("private transport: WebSocketTransport = { nativeHeaders: false },");
+            w.line("private options: StreamOptions<T> = {}");
+        });
+        w.empty_line();
+
+        w.line("/** Opens the socket; a stream ended by `close()` stays closed, even while reconnecting */");
+        w.block("async connect(): Promise<void> {", "}", |w| {
+            w.block("if (this.closed) {", "}", |w| {
+                w.line("throw new Error('WebSocket stream was closed');");
+            });
+            w.block("return new Promise((resolve, reject) => {", "});", |w| {
+                w.line("const headers = this.headers ?? {};");
+                w.line("const auth = this.transport.nativeHeaders ? undefined : this.transport.auth;");
+                w.line("const url = this.socketUrl(auth === 'query' ? headers : {});");
+                w.line("const protocols = typeof auth === 'object' ? [auth.protocol, base64url(JSON.stringify(headers))] : undefined;");
+                w.line("const ws = this.transport.nativeHeaders");
+                w.indent();
+                w.line("? new (this.WebSocketImpl as any)(url, protocols, { headers }) as WebSocket");
+                w.line(": new this.WebSocketImpl(url, protocols);");
+                w.dedent();
+                w.line("this.ws = ws;");
+                w.empty_line();
+
+                w.block("ws.onopen = () => {", "};", |w| {
+                    w.block("if (auth === 'message') {", "}", |w| {
+                        w.line("ws.send(JSON.stringify({ type: 'auth', headers }));");
                     });
-                    w.empty_line();
+                    w.line("this.opened = true;");
+                    w.line("this.attempt = 0;");
+                    w.line("resolve();");
+                });
+                w.empty_line();
 
-                    w.block("this.ws.onmessage = (event) => {", "};", |w| {
-                        w.block("try {", "} catch (error) {", |w| {
-                            w.line("const data = JSON.parse(event.data);");
-                            w.line("const deserialized = this.deserialize(data);");
-                            w.line("this.messageHandlers.forEach(handler => handler(deserialized));");
+                w.block("ws.onmessage = (event) => {", "};", |w| {
+                    w.block("try {", "} catch (error) {", |w| {
+                        w.line(&format!("const data = {}(event.data);", parse));
+                        w.line("const deserialized = this.deserialize(data);");
+                        w.block("if (this.options.resume) {", "}", |w| {
+                            w.line("this.resumeValue = String(this.options.resume.from(deserialized));");
                         });
-                        w.line("this.errorHandlers.forEach(handler => handler(error as Error));");
-                        w.line("}");
+                        w.line("this.messageHandlers.forEach(handler => handler(deserialized));");
                     });
-                    w.empty_line();
+                    w.indent();
+                    w.line("this.errorHandlers.forEach(handler => handler(error as Error));");
+                    w.dedent();
+                    w.line("}");
+                });
+                w.empty_line();
 
-                    w.block("this.ws.onerror = (event) => {", "};", |w| {
-                        w.line("const error = new Error('WebSocket error');");
-                        w.line("this.errorHandlers.forEach(handler => handler(error));");
-                        w.line("reject(error);");
-                    });
-                    w.empty_line();
+                w.block("ws.onerror = () => {", "};", |w| {
+                    w.line("const error = new Error('WebSocket error');");
+                    w.line("this.errorHandlers.forEach(handler => handler(error));");
+                    w.line("reject(error);");
+                });
+                w.empty_line();
 
-                    w.block("this.ws.onclose = () => {", "};", |w| {
-                        w.line("this.closeHandlers.forEach(handler => handler());");
+                w.block("ws.onclose = (event) => {", "};", |w| {
+                    w.block("if (this.ws !== ws) {", "}", |w| {
+                        w.line("return;");
+                    });
+                    w.line("this.ws = null;");
+                    w.line("const close: StreamClose = { code: event.code, reason: event.reason, clean: event.wasClean && event.code === CloseCode.Normal };");
+                    w.block("if (!this.scheduleReconnect(close)) {", "}", |w| {
+                        w.line("this.closeHandlers.forEach(handler => handler(close));");
                     });
                 });
             });
-            w.empty_line();
+        });
+        w.empty_line();
 
-            w.block("onMessage(handler: (data: T) => void): () => void {", "}", |w| {
+        w.block("private socketUrl(params: Record<string, string>): string {", "}", |w| {
+            w.line("const url = new URL(this.url.replace(/^http/, 'ws'));");
+            w.block("for (const [name, value] of Object.entries(params)) {", "}", |w| {
+                w.line("url.searchParams.set(name, value);");
+            });
+            w.block("if (this.options.resume && this.resumeValue !== undefined) {", "}", |w| {
+                w.line("url.searchParams.set(this.options.resume.param, this.resumeValue);");
+            });
+            w.line("return url.toString();");
+        });
+        w.empty_line();
+
+        w.line("/** Streams that never opened, or were closed normally or by `close()`, are not reconnected */");
+        w.block("private scheduleReconnect(close: StreamClose): boolean {", "}", |w| {
+            w.line("const policy: ReconnectPolicy | false | undefined = this.options.reconnect === true ? {} : this.options.reconnect;");
+            w.block("if (!policy || this.closed || !this.opened || close.clean || this.attempt >= (policy.attempts ?? Infinity)) {", "}", |w| {
+                w.line("return false;");
+            });
+            w.line("const attempt = ++this.attempt;");
+            w.line("this.reconnectHandlers.forEach(handler => handler(attempt, close));");
+            w.block("if (this.closed) {", "}", |w| {
+                w.line("// A handler called `close()`");
+                w.line("return false;");
+            });
+            w.line("const backoff = (policy.backoffMs ?? 500) * 2 ** (attempt - 1);");
+            w.block("this.reconnectTimer = setTimeout(() => {", "}, Math.min(backoff, policy.maxBackoffMs ?? 30000));", |w| {
+                w.line("this.reconnectTimer = undefined;");
+                w.line("// A failed attempt closes the socket, which schedules the next one");
+                w.line("this.connect().catch(() => {});");
+            });
+            w.line("return true;");
+        });
+        w.empty_line();
+
+        w.block(
+            "onMessage(handler: (data: T) => void): () => void {",
+            "}",
+            |w| {
                 w.line("this.messageHandlers.add(handler);");
                 w.block("return () => {", "};", |w| {
                     w.line("this.messageHandlers.delete(handler);");
                 });
-            });
-            w.empty_line();
+            },
+        );
+        w.empty_line();
 
-            w.block("onError(handler: (error: Error) => void): () => void {", "}", |w| {
+        w.block(
+            "onError(handler: (error: Error) => void): () => void {",
+            "}",
+            |w| {
                 w.line("this.errorHandlers.add(handler);");
                 w.block("return () => {", "};", |w| {
                     w.line("this.errorHandlers.delete(handler);");
                 });
+            },
+        );
+        w.empty_line();
+
+        w.line("/** Called once the stream has ended for good, after any reconnection attempts */");
+        w.block("onClose(handler: (close: StreamClose) => void): () => void {", "}", |w| {
+            w.line("this.closeHandlers.add(handler);");
+            w.block("return () => {", "};", |w| {
+                w.line("this.closeHandlers.delete(handler);");
             });
-            w.empty_line();
+        });
+        w.empty_line();
 
-            w.block("onClose(handler: () => void): () => void {", "}", |w| {
-                w.line("this.closeHandlers.add(handler);");
+        w.block(
+            "onReconnect(handler: (attempt: number, close: StreamClose) => void): () => void {",
+            "}",
+            |w| {
+                w.line("this.reconnectHandlers.add(handler);");
                 w.block("return () => {", "};", |w| {
-                    w.line("this.closeHandlers.delete(handler);");
+                    w.line("this.reconnectHandlers.delete(handler);");
                 });
+            },
+        );
+        w.empty_line();
+
+        w.block("close(code: number = CloseCode.Normal, reason = ''): void {", "}", |w| {
+            w.line("this.closed = true;");
+            w.block("if (this.reconnectTimer !== undefined) {", "}", |w| {
+                w.line("clearTimeout(this.reconnectTimer);");
+                w.line("this.reconnectTimer = undefined;");
+                w.line("this.closeHandlers.forEach(handler => handler({ code, reason, clean: true }));");
+            });
+            w.line("this.ws?.close(code, reason);");
+        });
+        w.empty_line();
+
+        w.line("/** Connects if needed; ends after a normal close and throws `StreamClosedError` otherwise */");
+        w.block("[Symbol.asyncIterator](): AsyncIterator<T> {", "}", |w| {
+            w.line("const buffer: T[] = [];");
+            w.line("let waiting: ((result: Promise<IteratorResult<T>>) => void) | undefined;");
+            w.line("let ended: StreamClose | undefined;");
+            w.empty_line();
+            w.block("const finish = (close: StreamClose): Promise<IteratorResult<T>> => {", "};", |w| {
+                w.line("offMessage();");
+                w.line("offClose();");
+                w.line("return close.clean || this.closed");
+                w.indent();
+                w.line("? Promise.resolve({ value: undefined, done: true })");
+                w.line(": Promise.reject(new StreamClosedError(close));");
+                w.dedent();
+            });
+            w.block("const offMessage = this.onMessage((data) => {", "});", |w| {
+                w.block("if (waiting) {", "}", |w| {
+                    w.line("waiting(Promise.resolve({ value: data, done: false }));");
+                    w.line("waiting = undefined;");
+                });
+                w.line("else {");
+                w.indent();
+                w.line("buffer.push(data);");
+                w.dedent();
+                w.line("}");
+            });
+            w.block("const offClose = this.onClose((close) => {", "});", |w| {
+                w.line("ended = close;");
+                w.block("if (waiting) {", "}", |w| {
+                    w.line("waiting(finish(close));");
+                    w.line("waiting = undefined;");
+                });
+            });
+            w.block("if (!this.ws && !this.opened) {", "}", |w| {
+                w.line("this.connect().catch(() => {});");
             });
             w.empty_line();
 
-            w.block("close(): void {", "}", |w| {
-                w.block("if (this.ws) {", "}", |w| {
-                    w.line("this.ws.close();");
-                    w.line("this.ws = null;");
+            w.block("return {", "};", |w| {
+                w.block("next: () => {", "},", |w| {
+                    w.block("if (buffer.length > 0) {", "}", |w| {
+                        w.line("return Promise.resolve({ value: buffer.shift()!, done: false });");
+                    });
+                    w.block("if (ended) {", "}", |w| {
+                        w.line("return finish(ended);");
+                    });
+                    w.line("return new Promise<IteratorResult<T>>((resolve) => { waiting = resolve; });");
+                });
+                w.block("return: () => {", "},", |w| {
+                    w.line("offMessage();");
+                    w.line("offClose();");
+                    w.line("this.close();");
+                    w.line("return Promise.resolve({ value: undefined, done: true });");
                 });
             });
         });
-        Ok(())
+    });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stream_class() -> String {
+        let mut writer = CodeWriter::new();
+        generate_websocket_stream_class(
+            &mut writer,
+            &TypeScriptOptions::default(),
+            &mut Symbols::default(),
+        )
+        .unwrap();
+        writer.into_string()
+    }
+
+    fn method<'a>(class: &'a str, signature: &str) -> &'a str {
+        let start = class.find(signature).unwrap();
+        let end = class[start..].find("\n  }\n").unwrap();
+        &class[start..start + end]
+    }
+
+    #[test]
+    fn close_ends_the_stream_for_good() {
+        let class = stream_class();
+        let connect = method(&class, "async connect(): Promise<void> {");
+        assert!(connect.contains(
+            "if (this.closed) {\n      throw new Error('WebSocket stream was closed');\n    }"
+        ));
+        assert!(!connect.contains("this.closed = false"));
+        let close = method(
+            &class,
+            "close(code: number = CloseCode.Normal, reason = ''): void {",
+        );
+        assert!(close.starts_with(
+            "close(code: number = CloseCode.Normal, reason = ''): void {\n    this.closed = true;"
+        ));
+        assert!(close.contains("clearTimeout(this.reconnectTimer);"));
+    }
+
+    #[test]
+    fn reconnects_only_after_abnormal_closes() {
+        let class = stream_class();
+        let schedule = method(
+            &class,
+            "private scheduleReconnect(close: StreamClose): boolean {",
+        );
+        assert!(schedule.contains(
+            "if (!policy || this.closed || !this.opened || close.clean || this.attempt >= (policy.attempts ?? Infinity)) {\n      return false;"
+        ));
+        // `close()` from a reconnect handler wins over the pending attempt
+        let notify = schedule
+            .find("this.reconnectHandlers.forEach(handler => handler(attempt, close));")
+            .unwrap();
+        let recheck = schedule.rfind("if (this.closed) {").unwrap();
+        let timer = schedule.find("this.reconnectTimer = setTimeout(").unwrap();
+        assert!(notify < recheck && recheck < timer);
+        assert!(
+            schedule.contains("const backoff = (policy.backoffMs ?? 500) * 2 ** (attempt - 1);")
+        );
+        assert!(schedule.contains("}, Math.min(backoff, policy.maxBackoffMs ?? 30000));"));
+    }
+
+    #[test]
+    fn reconnects_resume_after_the_last_message() {
+        let class = stream_class();
+        assert!(class.contains(
+            "if (this.options.resume) {\n            \
+             this.resumeValue = String(this.options.resume.from(deserialized));"
+        ));
+        let socket_url = method(&class, "private socketUrl(");
+        assert!(socket_url.contains(
+            "if (this.options.resume && this.resumeValue !== undefined) {\n      \
+             url.searchParams.set(this.options.resume.param, this.resumeValue);"
+        ));
+    }
+
+    #[test]
+    fn closes_are_clean_only_after_a_normal_handshake() {
+        let class = stream_class();
+        assert!(class.contains(
+            "const close: StreamClose = { code: event.code, reason: event.reason, clean: event.wasClean && event.code === CloseCode.Normal };"
+        ));
+        // Sockets replaced by a reconnect no longer report
+        assert!(class.contains("if (this.ws !== ws) {\n          return;"));
+        assert!(class.contains(
+            "if (!this.scheduleReconnect(close)) {\n          \
+             this.closeHandlers.forEach(handler => handler(close));"
+        ));
+        // Iteration ends quietly after a clean close and throws otherwise
+        assert!(class.contains(
+            "return close.clean || this.closed\n        \
+             ? Promise.resolve({ value: undefined, done: true })\n        \
+             : Promise.reject(new StreamClosedError(close));"
+        ));
     }
 }
@@ -0,0 +1,701 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use anyhow::Result;
+use serde::Serialize;
+use serde_json::json;
+
+use crate::aat::{AAT, NamedType};
+use crate::generate::OutputFile;
+use crate::generate::writer::CodeWriter;
+
+use super::symbols::Symbols;
+use super::{
+    Layout, PackageOptions, TypeScriptOptions, ZodOutput, client, generate_websocket_stream_class,
+    needs_websocket_stream, prepare, serialization, types, validation, zod,
+};
+
+/// A generated module before imports are resolved; `path` has no extension
+struct Module {
+    path: String,
+    uses_zod: bool,
+    body: String,
+    symbols: Symbols,
+}
+
+impl Module {
+    /// Generates the module's body, noting the symbols it exports and uses
+    fn new(
+        path: String,
+        uses_zod: bool,
+        body: impl FnOnce(&mut Symbols) -> Result<String>,
+    ) -> Result<Self> {
+        let mut symbols = Symbols::default();
+        let body = body(&mut symbols)?;
+        Ok(Self {
+            path,
+            uses_zod,
+            body,
+            symbols,
+        })
+    }
+}
+
+/// Generates the `Layout::Modules` tree: one module per type, serializer, validator and service,
+/// a shared `runtime.ts`, the top-level `client.ts` and an `index.ts` barrel
+pub fn generate_modules(aat: &AAT, options: &TypeScriptOptions) -> Result<Vec<OutputFile>> {
+    let aat = &prepare(aat, options)?;
+    let mut modules = Vec::new();
+
+    for named_type in &aat.types {
+        let name = type_module(named_type, options);
+
+        let uses_zod = options.zod != ZodOutput::None;
+        modules.push(Module::new(
+            format!("types/{}", name),
+            uses_zod,
+            |symbols| {
+                let mut sections = Vec::new();
+                if options.zod != ZodOutput::Instead {
+                    sections.push(write(|w| {
+                        types::generate_type(w, named_type, options, symbols)
+                    })?);
+                }
+                if options.zod != ZodOutput::None {
+                    sections.push(write(|w| {
+                        zod::generate_schema(w, named_type, options, symbols)?;
+                        if options.zod == ZodOutput::Instead {
+                            zod::generate_inferred_type(w, named_type, symbols)?;
+                        }
+                        Ok(())
+                    })?);
+                }
+                Ok(sections.join("\n"))
+            },
+        )?);
+
+        if options.uses_serializers() {
+            modules.push(Module::new(
+                format!("serialization/{}", name),
+                false,
+                |symbols| {
+                    write(|w| {
+                        serialization::generate_serializer(w, named_type, options, symbols)?;
+                        w.empty_line();
+                        serialization::generate_deserializer(w, named_type, options, symbols)
+                    })
+                },
+            )?);
+        }
+
+        if options.validators {
+            modules.push(Module::new(
+                format!("validation/{}", name),
+                false,
+                |symbols| {
+                    write(|w| validation::generate_validator(w, named_type, options, symbols))
+                },
+            )?);
+        }
+    }
+
+    modules.push(Module::new("runtime".to_string(), false, |symbols| {
+        let mut runtime = Vec::new();
+        if options.bigints {
+            runtime.push(write(|w| serialization::generate_json_helpers(w, symbols))?);
+        }
+        if needs_websocket_stream(aat) {
+            runtime.push(write(|w| {
+                generate_websocket_stream_class(w, options, symbols)
+            })?);
+        }
+        runtime.push(write(|w| client::generate_request_runtime(w, symbols))?);
+        if options.validators {
+            runtime.push(write(|w| {
+                validation::generate_validation_runtime(w, symbols)
+            })?);
+        }
+        Ok(runtime.join("\n"))
+    })?);
+
+    for service in &aat.services {
+        modules.push(Module::new(
+            format!("clients/{}", service.name),
+            false,
+            |symbols| write(|w| client::generate_service(w, service, options, symbols)),
+        )?);
+    }
+
+    modules.push(Module::new("client".to_string(), false, |symbols| {
+        write(|w| client::generate_api_client(w, aat, options, symbols))
+    })?);
+
+    let mut files = resolve_imports(&modules);
+
+    let mut index = CodeWriter::new();
+    write_header(&mut index);
+    for module in &modules {
+        index.line(&format!("export * from './{}.js';", module.path));
+    }
+    files.push(OutputFile::new("index.ts", index.into_string()));
+
+    Ok(files)
+}
+
+/// Adds the imports each module needs: every symbol it uses that another module exports
+fn resolve_imports(modules: &[Module]) -> Vec<OutputFile> {
+    // symbol -> (module path, type-only)
+    let mut exports: HashMap<&str, (&str, bool)> = HashMap::new();
+    for module in modules {
+        for (symbol, type_only) in &module.symbols.exports {
+            exports.insert(symbol, (&module.path, *type_only));
+        }
+    }
+
+    modules
+        .iter()
+        .map(|module| {
+            // module path -> (type-only imports, value imports)
+            let mut imports: BTreeMap<&str, (BTreeSet<&str>, BTreeSet<&str>)> = BTreeMap::new();
+            for symbol in &module.symbols.uses {
+                let Some((path, type_only)) = exports.get(symbol.as_str()) else {
+                    continue;
+                };
+                if *path == module.path {
+                    continue;
+                }
+                let entry = imports.entry(path).or_default();
+                if *type_only {
+                    entry.0.insert(symbol);
+                } else {
+                    entry.1.insert(symbol);
+                }
+            }
+            let mut writer = CodeWriter::new();
+            write_header(&mut writer);
+            if module.uses_zod {
+                writer.line("import { z } from 'zod';");
+            }
+            for (path, (type_symbols, value_symbols)) in &imports {
+                let specifier = relative_specifier(&module.path, path);
+                if !type_symbols.is_empty() {
+                    let symbols: Vec<&str> = type_symbols.iter().copied().collect();
+                    writer.line(&format!(
+                        "import type {{ {} }} from '{}';",
+                        symbols.join(", "),
+                        specifier
+                    ));
+                }
+                if !value_symbols.is_empty() {
+                    let symbols: Vec<&str> = value_symbols.iter().copied().collect();
+                    writer.line(&format!(
+                        "import {{ {} }} from '{}';",
+                        symbols.join(", "),
+                        specifier
+                    ));
+                }
+            }
+            if module.uses_zod || !imports.is_empty() {
+                writer.empty_line();
+            }
+
+            let mut content = writer.into_string();
+            content.push_str(&module.body);
+            OutputFile::new(format!("{}.ts", module.path), content)
+        })
+        .collect()
+}
+
+/// Import specifier for `to` as seen from `from` (both relative to the output root, without
+/// extension). The `.js` extension resolves under both bundlers and Node's ESM loader.
+fn relative_specifier(from: &str, to: &str) -> String {
+    let from_dir = from.rsplit_once('/').map(|(dir, _)| dir).unwrap_or("");
+    let (to_dir, to_file) = to.rsplit_once('/').unwrap_or(("", to));
+    if from_dir == to_dir {
+        return format!("./{}.js", to_file);
+    }
+    let up = if from_dir.is_empty() {
+        "./".to_string()
+    } else {
+        "../".repeat(from_dir.split('/').count())
+    };
+    format!("{}{}.js", up, to)
+}
+
+/// `package.json`, `tsconfig.json` and `tsconfig.cjs.json` building the client to `dist/esm`
+/// and `dist/cjs`
+pub fn generate_package(
+    package: &PackageOptions,
+    options: &TypeScriptOptions,
+) -> Result<Vec<OutputFile>> {
+    let entry = match options.layout {
+        Layout::SingleFile => "client",
+        Layout::Modules | Layout::RustModules => "index",
+    };
+
+    let entry_points = |dir: &str, module: &str| EntryPoint {
+        types: format!("./dist/{}/{}.d.ts", dir, module),
+        default: format!("./dist/{}/{}.js", dir, module),
+    };
+    let subpath = |module: &str| Exports {
+        import: entry_points("esm", module),
+        require: entry_points("cjs", module),
+    };
+    let mut exports = BTreeMap::from([(".", subpath(entry))]);
+    let mut dependencies = BTreeMap::new();
+    if options.zod != ZodOutput::None {
+        dependencies.insert("zod", "^3.23.0");
+    }
+    let mut peer_dependencies = BTreeMap::new();
+    if options.react_query {
+        exports.insert("./hooks", subpath("hooks"));
+        peer_dependencies.insert("@tanstack/react-query", "^5.0.0");
+        peer_dependencies.insert("react", ">=18");
+    }
+    let manifest = Manifest {
+        name: &package.name,
+        version: &package.version,
+        module_type: "module",
+        main: format!("./dist/cjs/{}.js", entry),
+        module: format!("./dist/esm/{}.js", entry),
+        types: format!("./dist/esm/{}.d.ts", entry),
+        exports,
+        files: vec!["dist"],
+        side_effects: false,
+        scripts: BTreeMap::from([(
+            "build",
+            "tsc -p tsconfig.json && tsc -p tsconfig.cjs.json && node -e \"require('fs').writeFileSync('dist/cjs/package.json', JSON.stringify({ type: 'commonjs' }))\"",
+        )]),
+        dependencies,
+        peer_dependencies,
+        dev_dependencies: BTreeMap::from([("typescript", "^5.4.0")]),
+    };
+
+    let tsconfig = json!({
+        "compilerOptions": {
+            "target": "ES2020",
+            "lib": ["ES2020", "DOM"],
+            "module": "ES2020",
+            "moduleResolution": "bundler",
+            "declaration": true,
+            "strict": true,
+            "skipLibCheck": true,
+            "outDir": "dist/esm",
+        },
+        "include": ["**/*.ts"],
+        "exclude": ["dist", "node_modules"],
+    });
+
+    let tsconfig_cjs = json!({
+        "extends": "./tsconfig.json",
+        "compilerOptions": {
+            "module": "CommonJS",
+            "moduleResolution": "node10",
+            "outDir": "dist/cjs",
+        },
+    });
+
+    Ok(vec![
+        OutputFile::new("package.json", pretty(&manifest)?),
+        OutputFile::new("tsconfig.json", pretty(&tsconfig)?),
+        OutputFile::new("tsconfig.cjs.json", pretty(&tsconfig_cjs)?),
+    ])
+}
+
+/// `package.json` fields, in conventional order (the `types` condition must come first)
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Manifest<'a> {
+    name: &'a str,
+    version: &'a str,
+    #[serde(rename = "type")]
+    module_type: &'a str,
+    main: String,
+    module: String,
+    types: String,
+    exports: BTreeMap<&'a str, Exports>,
+    files: Vec<&'a str>,
+    side_effects: bool,
+    scripts: BTreeMap<&'a str, &'a str>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    dependencies: BTreeMap<&'a str, &'a str>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    peer_dependencies: BTreeMap<&'a str, &'a str>,
+    dev_dependencies: BTreeMap<&'a str, &'a str>,
+}
+
+#[derive(Serialize)]
+struct Exports {
+    import: EntryPoint,
+    require: EntryPoint,
+}
+
+#[derive(Serialize)]
+struct EntryPoint {
+    types: String,
+    default: String,
+}
+
+fn pretty(value: &impl Serialize) -> Result<String> {
+    Ok(format!("{}\n", serde_json::to_string_pretty(value)?))
+}
+
+fn write_header(writer: &mut CodeWriter) {
+    writer.line("// Generated by Damascus");
+    writer.line("// Do not edit this file directly");
+    writer.empty_line();
+}
+
+/// Runs a generator section into a fresh writer, normalizing trailing blank lines
+fn write(f: impl FnOnce(&mut CodeWriter) -> Result<()>) -> Result<String> {
+    let mut writer = CodeWriter::new();
+    f(&mut writer)?;
+    let mut content = writer.into_string().trim_end().to_string();
+    content.push('\n');
+    Ok(content)
+}
+
+fn type_name(named_type: &NamedType) -> &str {
+    match named_type {
+        NamedType::Object(obj) => &obj.name,
+        NamedType::Union(union) => &union.name,
+        NamedType::Enum(enum_type) => &enum_type.name,
+    }
+}
+
+/// Path of a type's modules below `types/`, `serialization/` and `validation/`: its name, nested
+/// after its Rust module in the [`Layout::RustModules`] layout
+fn type_module(named_type: &NamedType, options: &TypeScriptOptions) -> String {
+    let name = type_name(named_type);
+    let rust_path = match named_type {
+        NamedType::Object(obj) => obj.rust_path.as_deref(),
+        NamedType::Union(union) => union.rust_path.as_deref(),
+        NamedType::Enum(enum_type) => enum_type.rust_path.as_deref(),
+    };
+    let Some(rust_path) = rust_path.filter(|_| options.layout == Layout::RustModules) else {
+        return name.to_string();
+    };
+    // `fleet::v2::Machine<fleet::Id>` is in `v2`; the crate and the type's own name are dropped
+    let path = rust_path.split('<').next().unwrap_or(rust_path);
+    let segments: Vec<&str> = path.split("::").collect();
+    let modules = segments
+        .get(1..segments.len().saturating_sub(1))
+        .unwrap_or_default();
+    modules
+        .iter()
+        .copied()
+        .chain([name])
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::machines;
+    use crate::generate::typescript::{FieldCase, Naming, TypeScriptGenerator};
+    use crate::spec::{PathSegment, Type, Upgrade};
+    use schemars::JsonSchema;
+
+    #[derive(JsonSchema)]
+    #[allow(dead_code)]
+    struct Machine {
+        name: String,
+        state: State,
+    }
+
+    #[crate::rust_path]
+    #[derive(JsonSchema)]
+    #[allow(dead_code)]
+    #[serde(rename_all = "snake_case")]
+    enum State {
+        Running { since: u64 },
+        Stopped { code: i32 },
+    }
+
+    #[derive(JsonSchema)]
+    #[allow(dead_code)]
+    struct Event {
+        #[schemars(extend("format" = "date-time"))]
+        at: String,
+        machine: Machine,
+    }
+
+    fn files(options: TypeScriptOptions) -> Vec<OutputFile> {
+        let spec =
+            machines(|e| e.response(Type::schema::<Machine>())).service("events", |service| {
+                service.get("watch", vec![PathSegment::Literal("events".into())], |e| {
+                    e.upgrade(Upgrade::Ws)
+                        .response(Type::stream(Type::schema::<Event>()))
+                })
+            });
+        let aat = AAT::from_spec(&spec).unwrap();
+        TypeScriptGenerator::generate_files(&aat, &options).unwrap()
+    }
+
+    fn modules(options: TypeScriptOptions) -> Vec<OutputFile> {
+        files(TypeScriptOptions {
+            layout: Layout::Modules,
+            ..options
+        })
+    }
+
+    fn validated_modules() -> Vec<OutputFile> {
+        modules(TypeScriptOptions {
+            validators: true,
+            ..Default::default()
+        })
+    }
+
+    fn content<'a>(files: &'a [OutputFile], path: &str) -> &'a str {
+        &files
+            .iter()
+            .find(|file| file.path.to_str() == Some(path))
+            .unwrap_or_else(|| panic!("no file {}", path))
+            .content
+    }
+
+    fn imports(files: &[OutputFile], path: &str) -> Vec<String> {
+        content(files, path)
+            .lines()
+            .filter(|line| line.starts_with("import "))
+            .map(str::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn imports_what_each_module_uses() {
+        let files = validated_modules();
+        assert_eq!(
+            imports(&files, "types/Machine.ts"),
+            ["import type { State } from './State.js';"]
+        );
+        assert_eq!(
+            imports(&files, "clients/machines.ts"),
+            [
+                "import type { Middleware, RequestContext, RequestOptions, ValidationIssue } from '../runtime.js';",
+                "import { assertValid, runMiddleware, sendRequest } from '../runtime.js';",
+                "import { deserializeMachine } from '../serialization/Machine.js';",
+                "import type { Machine } from '../types/Machine.js';",
+                "import { validateMachine } from '../validation/Machine.js';",
+            ]
+        );
+        assert_eq!(
+            imports(&files, "clients/events.ts"),
+            [
+                "import type { Middleware, RequestContext, RequestOptions, StreamOptions } from '../runtime.js';",
+                "import { WebSocketStream, runMiddleware, sendRequest } from '../runtime.js';",
+                "import { deserializeEvent } from '../serialization/Event.js';",
+                "import type { Event } from '../types/Event.js';",
+            ]
+        );
+        assert_eq!(
+            imports(&files, "client.ts"),
+            [
+                "import { EventsClient } from './clients/events.js';",
+                "import { MachinesClient } from './clients/machines.js';",
+                "import type { Middleware, RequestOptions } from './runtime.js';",
+            ]
+        );
+        assert!(imports(&files, "runtime.ts").is_empty());
+    }
+
+    #[test]
+    fn rust_modules_nest_types_after_their_module() {
+        let files = files(TypeScriptOptions {
+            layout: Layout::RustModules,
+            validators: true,
+            ..Default::default()
+        });
+        // `State` is only reached through `Machine`, and knows its module from `rust_path`
+        let module = "generate/typescript/modules/tests";
+        assert_eq!(
+            imports(&files, &format!("types/{}/Machine.ts", module)),
+            ["import type { State } from './State.js';"]
+        );
+        assert_eq!(
+            imports(&files, &format!("validation/{}/State.ts", module)),
+            ["import type { ValidationIssue } from '../../../../../runtime.js';"]
+        );
+        assert!(imports(&files, "clients/machines.ts").contains(&format!(
+            "import type {{ Machine }} from '../types/{}/Machine.js';",
+            module
+        )));
+        assert!(content(&files, "index.ts").contains(&format!(
+            "export * from './serialization/{}/State.js';",
+            module
+        )));
+    }
+
+    #[test]
+    fn names_in_strings_are_not_imported() {
+        // The validator's messages mention `State`, but its code never refers to the type
+        let files = validated_modules();
+        assert!(content(&files, "validation/State.ts").contains("any variant of State"));
+        assert_eq!(
+            imports(&files, "validation/State.ts"),
+            ["import type { ValidationIssue } from '../runtime.js';"]
+        );
+    }
+
+    #[test]
+    fn index_reexports_every_module() {
+        let files = validated_modules();
+        let exports: Vec<&str> = content(&files, "index.ts")
+            .lines()
+            .filter(|line| line.starts_with("export "))
+            .collect();
+        assert_eq!(
+            exports,
+            [
+                "export * from './types/Event.js';",
+                "export * from './serialization/Event.js';",
+                "export * from './validation/Event.js';",
+                "export * from './types/Machine.js';",
+                "export * from './serialization/Machine.js';",
+                "export * from './validation/Machine.js';",
+                "export * from './types/State.js';",
+                "export * from './serialization/State.js';",
+                "export * from './validation/State.js';",
+                "export * from './runtime.js';",
+                "export * from './clients/events.js';",
+                "export * from './clients/machines.js';",
+                "export * from './client.js';",
+            ]
+        );
+
+        // Without validators or serializers, only types, the runtime and clients remain
+        let plain = modules(TypeScriptOptions {
+            naming: Naming {
+                fields: FieldCase::Preserve,
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+        let paths: Vec<&str> = plain.iter().filter_map(|file| file.path.to_str()).collect();
+        assert_eq!(
+            paths,
+            [
+                "types/Event.ts",
+                "types/Machine.ts",
+                "types/State.ts",
+                "runtime.ts",
+                "clients/events.ts",
+                "clients/machines.ts",
+                "client.ts",
+                "index.ts",
+            ]
+        );
+    }
+
+    #[test]
+    fn zod_schemas_import_the_schemas_they_refer_to() {
+        let files = modules(TypeScriptOptions {
+            zod: ZodOutput::Alongside,
+            ..Default::default()
+        });
+        assert_eq!(
+            imports(&files, "types/Machine.ts"),
+            [
+                "import { z } from 'zod';",
+                "import type { State } from './State.js';",
+                "import { StateSchema } from './State.js';",
+            ]
+        );
+        // Only type modules use Zod
+        assert_eq!(
+            imports(&files, "serialization/Machine.ts"),
+            [
+                "import { deserializeState, serializeState } from './State.js';",
+                "import type { Machine } from '../types/Machine.js';",
+            ]
+        );
+    }
+
+    #[test]
+    fn inferred_types_import_only_schemas() {
+        let files = modules(TypeScriptOptions {
+            zod: ZodOutput::Instead,
+            ..Default::default()
+        });
+        assert_eq!(
+            imports(&files, "types/Machine.ts"),
+            [
+                "import { z } from 'zod';",
+                "import { StateSchema } from './State.js';",
+            ]
+        );
+        assert!(content(&files, "types/Machine.ts").contains("export type Machine = z.infer"));
+        // The inferred type is still imported where it is named
+        assert_eq!(
+            imports(&files, "clients/machines.ts")[3],
+            "import type { Machine } from '../types/Machine.js';"
+        );
+    }
+
+    #[test]
+    fn bigint_clients_import_the_json_helpers() {
+        let files = modules(TypeScriptOptions {
+            dates: true,
+            bigints: true,
+            ..Default::default()
+        });
+        assert_eq!(
+            imports(&files, "clients/machines.ts")[1],
+            "import { parseJson, runMiddleware, sendRequest } from '../runtime.js';"
+        );
+        // `Date` is a global, so date fields need no import
+        assert_eq!(
+            imports(&files, "types/Event.ts"),
+            ["import type { Machine } from './Machine.js';"]
+        );
+        assert!(content(&files, "types/Event.ts").contains("at: Date;"));
+        assert!(content(&files, "runtime.ts").contains("export function parseJson("));
+    }
+
+    #[test]
+    fn hooks_import_from_the_client_entry_point() {
+        let expected = |module: &str| {
+            format!(
+                "import type {{ Client, Event, Machine, StreamClose, StreamOptions }} from '{}';",
+                module
+            )
+        };
+        let options = TypeScriptOptions {
+            react_query: true,
+            ..Default::default()
+        };
+
+        let tree = modules(options.clone());
+        assert_eq!(
+            imports(&tree, "hooks.ts").last(),
+            Some(&expected("./index.js"))
+        );
+        assert!(!content(&tree, "index.ts").contains("hooks"));
+
+        let single_file = files(options);
+        let paths: Vec<&str> = single_file
+            .iter()
+            .filter_map(|file| file.path.to_str())
+            .collect();
+        assert_eq!(paths, ["client.ts", "hooks.ts"]);
+        assert_eq!(
+            imports(&single_file, "hooks.ts").last(),
+            Some(&expected("./client.js"))
+        );
+    }
+
+    #[test]
+    fn single_file_has_no_relative_imports() {
+        let single_file = files(TypeScriptOptions {
+            validators: true,
+            zod: ZodOutput::Alongside,
+            dates: true,
+            bigints: true,
+            ..Default::default()
+        });
+        assert_eq!(
+            imports(&single_file, "client.ts"),
+            ["import { z } from 'zod';"]
+        );
+    }
+}
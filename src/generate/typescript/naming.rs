@@ -0,0 +1,188 @@
+use crate::aat::*;
+
+use super::TypeScriptOptions;
+use super::utils::is_valid_ts_identifier;
+
+/// Reserved words of JavaScript (including strict mode) that cannot name a variable or parameter
+const RESERVED_WORDS: &[&str] = &[
+    "arguments",
+    "await",
+    "break",
+    "case",
+    "catch",
+    "class",
+    "const",
+    "continue",
+    "debugger",
+    "default",
+    "delete",
+    "do",
+    "else",
+    "enum",
+    "eval",
+    "export",
+    "extends",
+    "false",
+    "finally",
+    "for",
+    "function",
+    "if",
+    "implements",
+    "import",
+    "in",
+    "instanceof",
+    "interface",
+    "let",
+    "new",
+    "null",
+    "package",
+    "private",
+    "protected",
+    "public",
+    "return",
+    "static",
+    "super",
+    "switch",
+    "this",
+    "throw",
+    "true",
+    "try",
+    "typeof",
+    "var",
+    "void",
+    "while",
+    "with",
+    "yield",
+];
+
+/// Built-in type names, and globals the generated code relies on, that a generated type must not
+/// shadow
+const RESERVED_TYPE_NAMES: &[&str] = &[
+    "any",
+    "bigint",
+    "boolean",
+    "never",
+    "null",
+    "number",
+    "object",
+    "string",
+    "symbol",
+    "undefined",
+    "unknown",
+    "void",
+    "Array",
+    "BigInt",
+    "Boolean",
+    "Date",
+    "Error",
+    "Headers",
+    "JSON",
+    "Map",
+    "Number",
+    "Object",
+    "Promise",
+    "Record",
+    "RegExp",
+    "RequestInit",
+    "Response",
+    "Set",
+    "String",
+    "URLSearchParams",
+    "WebSocket",
+];
+
+/// Makes a name usable as a variable or parameter
+pub fn escape_identifier(name: &str) -> String {
+    if RESERVED_WORDS.contains(&name) {
+        format!("{}_", name)
+    } else {
+        name.to_string()
+    }
+}
+
+/// Class methods may use reserved words, except `constructor`
+pub fn escape_method_name(name: &str) -> String {
+    if name == "constructor" || !is_valid_ts_identifier(name) {
+        format!(
+            "{}_",
+            name.replace(|c: char| !c.is_alphanumeric() && c != '_' && c != '$', "_")
+        )
+    } else {
+        name.to_string()
+    }
+}
+
+pub fn escape_type_name(name: &str) -> String {
+    if RESERVED_TYPE_NAMES.contains(&name) || RESERVED_WORDS.contains(&name) {
+        format!("{}_", name)
+    } else {
+        name.to_string()
+    }
+}
+
+/// Applies the configured type names and escapes parameter names, so the rest of the generator
+/// can use AAT names directly
+pub fn prepare(aat: &AAT, options: &TypeScriptOptions) -> AAT {
+    let naming = &options.naming;
+    let mut aat = aat.clone();
+    let mut rename = |name: &mut String| *name = naming.type_name(name);
+
+    for named_type in &mut aat.types {
+        match named_type {
+            NamedType::Object(obj) => rename(&mut obj.name),
+            NamedType::Union(union) => rename(&mut union.name),
+            NamedType::Enum(enum_type) => rename(&mut enum_type.name),
+        }
+        named_type.visit_references_mut(&mut rename);
+    }
+
+    prepare_headers(&mut aat.headers, &mut rename);
+    for service in &mut aat.services {
+        prepare_headers(&mut service.headers, &mut rename);
+        for endpoint in &mut service.endpoints {
+            prepare_headers(&mut endpoint.headers, &mut rename);
+            for segment in &mut endpoint.path {
+                match segment {
+                    PathSegment::Parameter { name, r#type } => {
+                        *name = escape_identifier(name);
+                        r#type.visit_references_mut(&mut rename);
+                    }
+                    PathSegment::Wildcard { name } => *name = escape_identifier(name),
+                    PathSegment::Literal(_) => {}
+                }
+            }
+            for field_type in [&mut endpoint.query, &mut endpoint.body]
+                .into_iter()
+                .flatten()
+            {
+                field_type.visit_references_mut(&mut rename);
+            }
+            endpoint.response.visit_references_mut(&mut rename);
+        }
+    }
+
+    aat
+}
+
+fn prepare_headers(headers: &mut [Header], rename: &mut impl FnMut(&mut String)) {
+    for header in headers {
+        match &mut header.value {
+            HeaderValue::Literal(_) => {}
+            HeaderValue::Parameter { name, field_type } => {
+                *name = escape_identifier(name);
+                field_type.visit_references_mut(rename);
+            }
+            HeaderValue::Pattern { parts } => {
+                for part in parts {
+                    if let HeaderPart::Param {
+                        name, field_type, ..
+                    } = part
+                    {
+                        *name = escape_identifier(name);
+                        field_type.visit_references_mut(rename);
+                    }
+                }
+            }
+        }
+    }
+}
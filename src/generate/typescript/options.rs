@@ -0,0 +1,159 @@
+use crate::aat::{PrimitiveType, StringFormat};
+use crate::case::{to_camel_case, to_snake_case};
+
+use super::naming::{escape_method_name, escape_type_name};
+use serde::{Deserialize, Serialize};
+
+/// Options controlling the generated TypeScript client
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TypeScriptOptions {
+    /// Emit `validate<Type>(value)` functions and let the client check bodies and responses
+    pub validators: bool,
+    /// Emit Zod schemas for every named type
+    pub zod: ZodOutput,
+    /// Represent `date-time` and `date` strings as `Date` values
+    pub dates: bool,
+    /// Represent integers wider than 53 bits (`i64`, `u64`, ...) as `bigint`. Responses are read
+    /// exactly only where `JSON.parse` passes the source text to revivers (Node 22+, current
+    /// browsers); elsewhere values beyond 2^53 arrive rounded.
+    pub bigints: bool,
+    /// One `client.ts`, or a tree of modules with an `index.ts` barrel
+    pub layout: Layout,
+    /// Also write `package.json` and `tsconfig` files building ESM and CommonJS output
+    pub package: Option<PackageOptions>,
+    /// Naming conventions for generated types, properties and methods
+    pub naming: Naming,
+    /// Also write `hooks.ts` with TanStack Query hooks for every endpoint
+    pub react_query: bool,
+}
+
+impl TypeScriptOptions {
+    /// Whether values need converting between their wire and TypeScript shapes. With wire field
+    /// names and no `Date` or `bigint` mappings, serializers would be identity functions.
+    pub(crate) fn uses_serializers(&self) -> bool {
+        self.naming.fields != FieldCase::Preserve || self.dates || self.bigints
+    }
+
+    /// Whether the primitive is represented by a `Date` in the generated code
+    pub(crate) fn uses_date(&self, prim: &PrimitiveType) -> bool {
+        self.dates
+            && matches!(
+                prim,
+                PrimitiveType::String(Some(StringFormat::DateTime | StringFormat::Date))
+            )
+    }
+
+    /// Whether the primitive is represented by a `bigint` in the generated code
+    pub(crate) fn uses_bigint(&self, prim: &PrimitiveType) -> bool {
+        self.bigints
+            && matches!(prim, PrimitiveType::Int(Some(format)) if format.exceeds_f64_precision())
+    }
+}
+
+/// How Zod schemas relate to the generated interfaces
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ZodOutput {
+    /// No Zod schemas
+    #[default]
+    None,
+    /// `<Type>Schema` constants next to the plain interfaces
+    Alongside,
+    /// `<Type>Schema` constants, with types exported through `z.infer`
+    Instead,
+}
+
+/// Naming conventions applied by the TypeScript generator. Reserved words are always escaped
+/// with a trailing underscore where they would otherwise produce invalid code.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Naming {
+    /// Property names of the generated types
+    pub fields: FieldCase,
+    /// Prepended to every generated type name
+    pub type_prefix: String,
+    /// Appended to every generated type name
+    pub type_suffix: String,
+    /// Client method names
+    pub methods: MethodCase,
+}
+
+impl Naming {
+    pub(crate) fn field_name(&self, wire_name: &str) -> String {
+        match self.fields {
+            FieldCase::Camel => to_camel_case(wire_name),
+            FieldCase::Preserve => wire_name.to_string(),
+        }
+    }
+
+    pub(crate) fn method_name(&self, endpoint_name: &str) -> String {
+        let name = match self.methods {
+            MethodCase::Camel => to_camel_case(endpoint_name),
+            MethodCase::Snake => to_snake_case(endpoint_name),
+            MethodCase::Preserve => endpoint_name.to_string(),
+        };
+        escape_method_name(&name)
+    }
+
+    pub(crate) fn type_name(&self, name: &str) -> String {
+        escape_type_name(&format!("{}{}{}", self.type_prefix, name, self.type_suffix))
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FieldCase {
+    /// `created_at` on the wire becomes `createdAt`, with serializers converting between them
+    #[default]
+    Camel,
+    /// Wire names are used as-is
+    Preserve,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MethodCase {
+    #[default]
+    Camel,
+    Snake,
+    /// Endpoint names are used as-is
+    Preserve,
+}
+
+/// File layout of the generated client
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Layout {
+    /// Everything in `client.ts`
+    #[default]
+    SingleFile,
+    /// `types/`, `serialization/`, `validation/` and `clients/` modules, `runtime.ts`,
+    /// `client.ts` and an `index.ts` barrel
+    Modules,
+    /// [`Layout::Modules`] with the modules of each type nested after the Rust module it came
+    /// from, below its crate: `types/v2/Machine.ts` for `fleet::v2::Machine`. Types without a
+    /// recorded Rust path stay at the top.
+    RustModules,
+}
+
+/// npm package scaffolding for the generated client
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PackageOptions {
+    pub name: String,
+    #[serde(default = "default_package_version")]
+    pub version: String,
+}
+
+impl PackageOptions {
+    pub fn new(name: impl AsRef<str>) -> Self {
+        Self {
+            name: name.as_ref().to_string(),
+            version: default_package_version(),
+        }
+    }
+}
+
+fn default_package_version() -> String {
+    "0.1.0".to_string()
+}
@@ -2,32 +2,82 @@ use crate::aat::*;
 use crate::generate::writer::CodeWriter;
 use anyhow::Result;
 
+use super::TypeScriptOptions;
+use super::symbols::Symbols;
 use super::utils::*;
 
-/// Check if a field type is a reference (either directly or wrapped in Optional)
-fn is_reference_type(field_type: &FieldType) -> bool {
-    matches!(field_type, FieldType::Reference(_)) ||
-    matches!(field_type, FieldType::Optional(inner) if matches!(&**inner, FieldType::Reference(_)))
-}
-
-pub fn generate_serializer(writer: &mut CodeWriter, named_type: &NamedType) -> Result<()> {
+pub fn generate_serializer(
+    writer: &mut CodeWriter,
+    named_type: &NamedType,
+    options: &TypeScriptOptions,
+    symbols: &mut Symbols,
+) -> Result<()> {
     match named_type {
-        NamedType::Object(obj) => generate_object_serializer(writer, obj),
-        NamedType::Union(union) => generate_union_serializer(writer, union),
-        NamedType::Enum(enum_type) => generate_enum_serializer(writer, enum_type),
+        NamedType::Object(obj) => generate_object_serializer(writer, obj, options, symbols),
+        NamedType::Union(union) => generate_union_serializer(writer, union, options, symbols),
+        NamedType::Enum(enum_type) => generate_enum_serializer(writer, enum_type, symbols),
     }
 }
 
-pub fn generate_deserializer(writer: &mut CodeWriter, named_type: &NamedType) -> Result<()> {
+pub fn generate_deserializer(
+    writer: &mut CodeWriter,
+    named_type: &NamedType,
+    options: &TypeScriptOptions,
+    symbols: &mut Symbols,
+) -> Result<()> {
     match named_type {
-        NamedType::Object(obj) => generate_object_deserializer(writer, obj),
-        NamedType::Union(union) => generate_union_deserializer(writer, union),
-        NamedType::Enum(enum_type) => generate_enum_deserializer(writer, enum_type),
+        NamedType::Object(obj) => generate_object_deserializer(writer, obj, options, symbols),
+        NamedType::Union(union) => generate_union_deserializer(writer, union, options, symbols),
+        NamedType::Enum(enum_type) => generate_enum_deserializer(writer, enum_type, symbols),
     }
 }
 
-fn generate_object_serializer(writer: &mut CodeWriter, obj: &ObjectType) -> Result<()> {
+/// `parseJson` and `stringifyJson` keep integers beyond `Number.MAX_SAFE_INTEGER` exact by
+/// reading them as `bigint` and writing `bigint` values back as plain JSON numbers. Reading relies on
+/// the reviver's `context.source`; runtimes without it fall back to the already rounded number.
+pub fn generate_json_helpers(writer: &mut CodeWriter, symbols: &mut Symbols) -> Result<()> {
+    writer.line("const BIGINT_MARKER = '__damascus_bigint__';");
+    writer.empty_line();
+
+    symbols.exports_value("parseJson");
+    writer.block("export function parseJson(text: string): any {", "}", |w| {
+        w.block(
+            "return JSON.parse(text, (_key: string, value: any, context?: { source?: string }) => {",
+            "});",
+            |w| {
+                w.line("// Where the reviver sees the source text, unsafe integers are read exactly");
+                w.block(
+                    "if (typeof value === 'number' && !Number.isSafeInteger(value) && context?.source !== undefined && /^-?\\d+$/.test(context.source)) {",
+                    "}",
+                    |w| {
+                        w.line("return BigInt(context.source);");
+                    },
+                );
+                w.line("return value;");
+            },
+        );
+    });
+    writer.empty_line();
+
+    symbols.exports_value("stringifyJson");
+    writer.block("export function stringifyJson(value: any): string {", "}", |w| {
+        w.line("return JSON.stringify(value, (_key, v) => typeof v === 'bigint' ? `${BIGINT_MARKER}${v}` : v)");
+        w.indent();
+        w.line(".replace(new RegExp(`\"${BIGINT_MARKER}(-?\\\\d+)\"`, 'g'), '$1');");
+        w.dedent();
+    });
+    Ok(())
+}
+
+fn generate_object_serializer(
+    writer: &mut CodeWriter,
+    obj: &ObjectType,
+    options: &TypeScriptOptions,
+    symbols: &mut Symbols,
+) -> Result<()> {
     let func_name = format!("serialize{}", obj.name);
+    symbols.exports_value(&func_name);
+    symbols.uses(&obj.name);
     writer.block(
         &format!("export function {}(value: {}): any {{", func_name, obj.name),
         "}",
@@ -35,18 +85,24 @@ fn generate_object_serializer(writer: &mut CodeWriter, obj: &ObjectType) -> Resu
             w.line("return {");
             w.indent();
             for field in &obj.fields {
-                let camel_name = to_camel_case(&field.name);
+                let camel_name = options.naming.field_name(&field.name);
                 let original_name = &field.name;
 
                 // Check if field needs serialization
-                if needs_serialization(&field.r#type) {
-                    let serializer_expr = get_serializer_expr(&field.r#type, &format!("value.{}", camel_name));
+                if needs_serialization(&field.r#type, options) {
+                    let serializer_expr = get_serializer_expr(
+                        &field.r#type,
+                        &property_access("value", &camel_name),
+                        options,
+                        symbols,
+                    );
+                    w.line(&format!("\"{}\": {},", original_name, serializer_expr));
+                } else {
                     w.line(&format!(
                         "\"{}\": {},",
-                        original_name, serializer_expr
+                        original_name,
+                        property_access("value", &camel_name)
                     ));
-                } else {
-                    w.line(&format!("\"{}\": value.{},", original_name, camel_name));
                 }
             }
             w.dedent();
@@ -56,8 +112,15 @@ fn generate_object_serializer(writer: &mut CodeWriter, obj: &ObjectType) -> Resu
     Ok(())
 }
 
-fn generate_object_deserializer(writer: &mut CodeWriter, obj: &ObjectType) -> Result<()> {
+fn generate_object_deserializer(
+    writer: &mut CodeWriter,
+    obj: &ObjectType,
+    options: &TypeScriptOptions,
+    symbols: &mut Symbols,
+) -> Result<()> {
     let func_name = format!("deserialize{}", obj.name);
+    symbols.exports_value(&func_name);
+    symbols.uses(&obj.name);
     writer.block(
         &format!("export function {}(value: any): {} {{", func_name, obj.name),
         "}",
@@ -65,18 +128,28 @@ fn generate_object_deserializer(writer: &mut CodeWriter, obj: &ObjectType) -> Re
             w.line("return {");
             w.indent();
             for field in &obj.fields {
-                let camel_name = to_camel_case(&field.name);
+                let camel_name = options.naming.field_name(&field.name);
                 let original_name = &field.name;
 
                 // Check if field needs deserialization
-                if needs_serialization(&field.r#type) {
-                    let deserializer_expr = get_deserializer_expr(&field.r#type, &format!("value[\"{}\"]", original_name));
+                if needs_serialization(&field.r#type, options) {
+                    let deserializer_expr = get_deserializer_expr(
+                        &field.r#type,
+                        &format!("value[\"{}\"]", original_name),
+                        options,
+                        symbols,
+                    );
                     w.line(&format!(
                         "{}: {},",
-                        camel_name, deserializer_expr
+                        quote_if_needed(&camel_name),
+                        deserializer_expr
                     ));
                 } else {
-                    w.line(&format!("{}: value[\"{}\"],", camel_name, original_name));
+                    w.line(&format!(
+                        "{}: value[\"{}\"],",
+                        quote_if_needed(&camel_name),
+                        original_name
+                    ));
                 }
             }
             w.dedent();
@@ -86,17 +159,26 @@ fn generate_object_deserializer(writer: &mut CodeWriter, obj: &ObjectType) -> Re
     Ok(())
 }
 
-fn generate_union_serializer(writer: &mut CodeWriter, union: &UnionType) -> Result<()> {
+fn generate_union_serializer(
+    writer: &mut CodeWriter,
+    union: &UnionType,
+    options: &TypeScriptOptions,
+    symbols: &mut Symbols,
+) -> Result<()> {
     // Check if any variant has object fields that need serialization
-    let has_objects = union.variants.iter().any(|v| {
-        matches!(&*v.mode, UnionTypeVariantMode::Object(obj) if !obj.fields.is_empty())
-    });
-    let has_literals = union.variants.iter().any(|v| {
-        matches!(&*v.mode, UnionTypeVariantMode::Literal(_))
-    });
+    let has_objects = union
+        .variants
+        .iter()
+        .any(|v| matches!(&*v.mode, UnionTypeVariantMode::Object(obj) if !obj.fields.is_empty()));
+    let has_literals = union
+        .variants
+        .iter()
+        .any(|v| matches!(&*v.mode, UnionTypeVariantMode::Literal(_)));
 
     if has_objects {
         let func_name = format!("serialize{}", union.name);
+        symbols.exports_value(&func_name);
+        symbols.uses(&union.name);
         writer.block(
             &format!("export function {}(value: {}): any {{", func_name, union.name),
             "}",
@@ -130,13 +212,13 @@ fn generate_union_serializer(writer: &mut CodeWriter, union: &UnionType) -> Resu
                             if is_tuple_variant {
                                 // Tuple variant: serialize fields directly without variant wrapper
                                 let field = &obj.fields[0];
-                                let camel_key = to_camel_case(&field.name);
+                                let camel_key = options.naming.field_name(&field.name);
                                 let original_key = &field.name;
 
                                 w.line(&format!("if (key === \"{}\") {{", camel_key));
                                 w.indent();
-                                if needs_serialization(&field.r#type) {
-                                    let serializer_expr = get_serializer_expr(&field.r#type, "val as any");
+                                if needs_serialization(&field.r#type, options) {
+                                    let serializer_expr = get_serializer_expr(&field.r#type, "val as any", options, symbols);
                                     w.line(&format!("result[\"{}\"] = {};", original_key, serializer_expr));
                                 } else {
                                     w.line(&format!("result[\"{}\"] = val;", original_key));
@@ -152,8 +234,8 @@ fn generate_union_serializer(writer: &mut CodeWriter, union: &UnionType) -> Resu
                                 if is_newtype {
                                     // Newtype pattern: serialize the value directly
                                     let field = &obj.fields[0];
-                                    if needs_serialization(&field.r#type) {
-                                        let serializer_expr = get_serializer_expr(&field.r#type, "val as any");
+                                    if needs_serialization(&field.r#type, options) {
+                                        let serializer_expr = get_serializer_expr(&field.r#type, "val as any", options, symbols);
                                         w.line(&format!("result[\"{}\"] = {};", variant_name, serializer_expr));
                                     } else {
                                         w.line(&format!("result[\"{}\"] = val;", variant_name));
@@ -165,17 +247,17 @@ fn generate_union_serializer(writer: &mut CodeWriter, union: &UnionType) -> Resu
                                     w.indent();
 
                                     // Check if any fields need serialization
-                                    let has_serializable_fields = obj.fields.iter().any(|f| needs_serialization(&f.r#type));
+                                    let has_serializable_fields = obj.fields.iter().any(|f| needs_serialization(&f.r#type, options));
 
                                     if has_serializable_fields {
                                         for field in &obj.fields {
-                                            let camel_name = to_camel_case(&field.name);
+                                            let camel_name = options.naming.field_name(&field.name);
                                             let original_name = &field.name;
 
-                                            if needs_serialization(&field.r#type) {
+                                            if needs_serialization(&field.r#type, options) {
                                                 w.line(&format!("if (innerKey === \"{}\") {{", camel_name));
                                                 w.indent();
-                                                let serializer_expr = get_serializer_expr(&field.r#type, "innerVal as any");
+                                                let serializer_expr = get_serializer_expr(&field.r#type, "innerVal as any", options, symbols);
                                                 w.line(&format!("inner[\"{}\"] = {};", original_name, serializer_expr));
                                                 w.line("continue;");
                                                 w.dedent();
@@ -199,13 +281,13 @@ fn generate_union_serializer(writer: &mut CodeWriter, union: &UnionType) -> Resu
                             // Unnamed variant: handle field serialization directly
                             if obj.fields.len() == 1 {
                                 let field = &obj.fields[0];
-                                let camel_key = to_camel_case(&field.name);
+                                let camel_key = options.naming.field_name(&field.name);
                                 let original_key = &field.name;
 
-                                if needs_serialization(&field.r#type) {
+                                if needs_serialization(&field.r#type, options) {
                                     w.line(&format!("if (key === \"{}\") {{", camel_key));
                                     w.indent();
-                                    let serializer_expr = get_serializer_expr(&field.r#type, "val as any");
+                                    let serializer_expr = get_serializer_expr(&field.r#type, "val as any", options, symbols);
                                     w.line(&format!("result[\"{}\"] = {};", original_key, serializer_expr));
                                     w.line("continue;");
                                     w.dedent();
@@ -229,17 +311,26 @@ fn generate_union_serializer(writer: &mut CodeWriter, union: &UnionType) -> Resu
     Ok(())
 }
 
-fn generate_union_deserializer(writer: &mut CodeWriter, union: &UnionType) -> Result<()> {
+fn generate_union_deserializer(
+    writer: &mut CodeWriter,
+    union: &UnionType,
+    options: &TypeScriptOptions,
+    symbols: &mut Symbols,
+) -> Result<()> {
     // Check if any variant has object fields that need deserialization
-    let has_objects = union.variants.iter().any(|v| {
-        matches!(&*v.mode, UnionTypeVariantMode::Object(obj) if !obj.fields.is_empty())
-    });
-    let has_literals = union.variants.iter().any(|v| {
-        matches!(&*v.mode, UnionTypeVariantMode::Literal(_))
-    });
+    let has_objects = union
+        .variants
+        .iter()
+        .any(|v| matches!(&*v.mode, UnionTypeVariantMode::Object(obj) if !obj.fields.is_empty()));
+    let has_literals = union
+        .variants
+        .iter()
+        .any(|v| matches!(&*v.mode, UnionTypeVariantMode::Literal(_)));
 
     if has_objects {
         let func_name = format!("deserialize{}", union.name);
+        symbols.exports_value(&func_name);
+        symbols.uses(&union.name);
         let union_name = &union.name;
         writer.block(
             &format!("export function {}(value: any): {} {{", func_name, union_name),
@@ -274,13 +365,13 @@ fn generate_union_deserializer(writer: &mut CodeWriter, union: &UnionType) -> Re
                             if is_tuple_variant {
                                 // Tuple variant: deserialize fields directly without variant wrapper
                                 let field = &obj.fields[0];
-                                let camel_key = to_camel_case(&field.name);
+                                let camel_key = options.naming.field_name(&field.name);
                                 let original_key = &field.name;
 
                                 w.line(&format!("if (key === \"{}\") {{", original_key));
                                 w.indent();
-                                if needs_serialization(&field.r#type) {
-                                    let deserializer_expr = get_deserializer_expr(&field.r#type, "val as any");
+                                if needs_serialization(&field.r#type, options) {
+                                    let deserializer_expr = get_deserializer_expr(&field.r#type, "val as any", options, symbols);
                                     w.line(&format!("result[\"{}\"] = {};", camel_key, deserializer_expr));
                                 } else {
                                     w.line(&format!("result[\"{}\"] = val;", camel_key));
@@ -296,8 +387,8 @@ fn generate_union_deserializer(writer: &mut CodeWriter, union: &UnionType) -> Re
                                 if is_newtype {
                                     // Newtype pattern: deserialize the value directly
                                     let field = &obj.fields[0];
-                                    if needs_serialization(&field.r#type) {
-                                        let deserializer_expr = get_deserializer_expr(&field.r#type, "val as any");
+                                    if needs_serialization(&field.r#type, options) {
+                                        let deserializer_expr = get_deserializer_expr(&field.r#type, "val as any", options, symbols);
                                         w.line(&format!("result[\"{}\"] = {};", variant_name, deserializer_expr));
                                     } else {
                                         w.line(&format!("result[\"{}\"] = val;", variant_name));
@@ -309,17 +400,17 @@ fn generate_union_deserializer(writer: &mut CodeWriter, union: &UnionType) -> Re
                                     w.indent();
 
                                     // Check if any fields need deserialization
-                                    let has_deserializable_fields = obj.fields.iter().any(|f| needs_serialization(&f.r#type));
+                                    let has_deserializable_fields = obj.fields.iter().any(|f| needs_serialization(&f.r#type, options));
 
                                     if has_deserializable_fields {
                                         for field in &obj.fields {
-                                            let camel_name = to_camel_case(&field.name);
+                                            let camel_name = options.naming.field_name(&field.name);
                                             let original_name = &field.name;
 
-                                            if needs_serialization(&field.r#type) {
+                                            if needs_serialization(&field.r#type, options) {
                                                 w.line(&format!("if (innerKey === \"{}\") {{", original_name));
                                                 w.indent();
-                                                let deserializer_expr = get_deserializer_expr(&field.r#type, "innerVal as any");
+                                                let deserializer_expr = get_deserializer_expr(&field.r#type, "innerVal as any", options, symbols);
                                                 w.line(&format!("inner[\"{}\"] = {};", camel_name, deserializer_expr));
                                                 w.line("continue;");
                                                 w.dedent();
@@ -343,13 +434,13 @@ fn generate_union_deserializer(writer: &mut CodeWriter, union: &UnionType) -> Re
                             // Unnamed variant: handle field deserialization directly
                             if obj.fields.len() == 1 {
                                 let field = &obj.fields[0];
-                                let camel_key = to_camel_case(&field.name);
+                                let camel_key = options.naming.field_name(&field.name);
                                 let original_key = &field.name;
 
-                                if needs_serialization(&field.r#type) {
+                                if needs_serialization(&field.r#type, options) {
                                     w.line(&format!("if (key === \"{}\") {{", original_key));
                                     w.indent();
-                                    let deserializer_expr = get_deserializer_expr(&field.r#type, "val as any");
+                                    let deserializer_expr = get_deserializer_expr(&field.r#type, "val as any", options, symbols);
                                     w.line(&format!("result[\"{}\"] = {};", camel_key, deserializer_expr));
                                     w.line("continue;");
                                     w.dedent();
@@ -373,12 +464,21 @@ fn generate_union_deserializer(writer: &mut CodeWriter, union: &UnionType) -> Re
     Ok(())
 }
 
-fn generate_enum_serializer(writer: &mut CodeWriter, enum_type: &EnumType) -> Result<()> {
+fn generate_enum_serializer(
+    writer: &mut CodeWriter,
+    enum_type: &EnumType,
+    symbols: &mut Symbols,
+) -> Result<()> {
     // Enums are just string literals, but we still generate pass-through functions
     // in case they're referenced by other types
     let func_name = format!("serialize{}", enum_type.name);
+    symbols.exports_value(&func_name);
+    symbols.uses(&enum_type.name);
     writer.block(
-        &format!("export function {}(value: {}): any {{", func_name, enum_type.name),
+        &format!(
+            "export function {}(value: {}): any {{",
+            func_name, enum_type.name
+        ),
         "}",
         |w| {
             w.line("return value;");
@@ -387,12 +487,21 @@ fn generate_enum_serializer(writer: &mut CodeWriter, enum_type: &EnumType) -> Re
     Ok(())
 }
 
-fn generate_enum_deserializer(writer: &mut CodeWriter, enum_type: &EnumType) -> Result<()> {
+fn generate_enum_deserializer(
+    writer: &mut CodeWriter,
+    enum_type: &EnumType,
+    symbols: &mut Symbols,
+) -> Result<()> {
     // Enums are just string literals, but we still generate pass-through functions
     // in case they're referenced by other types
     let func_name = format!("deserialize{}", enum_type.name);
+    symbols.exports_value(&func_name);
+    symbols.uses(&enum_type.name);
     writer.block(
-        &format!("export function {}(value: any): {} {{", func_name, enum_type.name),
+        &format!(
+            "export function {}(value: any): {} {{",
+            func_name, enum_type.name
+        ),
         "}",
         |w| {
             w.line("return value;");
@@ -401,29 +510,42 @@ fn generate_enum_deserializer(writer: &mut CodeWriter, enum_type: &EnumType) ->
     Ok(())
 }
 
-fn needs_serialization(field_type: &FieldType) -> bool {
+fn needs_serialization(field_type: &FieldType, options: &TypeScriptOptions) -> bool {
     match field_type {
-        FieldType::Reference(_) => true,
-        FieldType::List(inner) => needs_serialization(inner),
-        FieldType::Optional(inner) => needs_serialization(inner),
+        FieldType::Reference(_) => options.uses_serializers(),
+        FieldType::Primitive(prim) => options.uses_date(prim) || options.uses_bigint(prim),
+        FieldType::List(inner) => needs_serialization(inner, options),
+        FieldType::Optional(inner) => needs_serialization(inner, options),
+        FieldType::Map(inner) => needs_serialization(inner, options),
         _ => false,
     }
 }
 
-fn get_serializer_call(field_type: &FieldType) -> String {
+fn get_serializer_call(
+    field_type: &FieldType,
+    options: &TypeScriptOptions,
+    symbols: &mut Symbols,
+) -> String {
     match field_type {
-        FieldType::Reference(name) => format!("serialize{}", name),
+        FieldType::Reference(name) => {
+            symbols.uses(&format!("serialize{}", name));
+            format!("serialize{}", name)
+        }
+        FieldType::Primitive(prim) => match primitive_serializer_expr(prim, "v", options) {
+            Some(expr) => format!("(v: any) => {}", expr),
+            None => "(v: any) => v".to_string(),
+        },
         FieldType::List(inner) => {
-            if needs_serialization(inner) {
-                let inner_serializer = get_serializer_call(inner);
+            if needs_serialization(inner, options) {
+                let inner_serializer = get_serializer_call(inner, options, symbols);
                 format!("(v: any) => v.map((x: any) => {}(x))", inner_serializer)
             } else {
                 "(v: any) => v".to_string()
             }
         }
         FieldType::Optional(inner) => {
-            if needs_serialization(inner) {
-                let inner_serializer = get_serializer_call(inner);
+            if needs_serialization(inner, options) {
+                let inner_serializer = get_serializer_call(inner, options, symbols);
                 format!(
                     "(v: any) => v !== undefined ? {}(v) : undefined",
                     inner_serializer
@@ -436,20 +558,31 @@ fn get_serializer_call(field_type: &FieldType) -> String {
     }
 }
 
-fn get_deserializer_call(field_type: &FieldType) -> String {
+fn get_deserializer_call(
+    field_type: &FieldType,
+    options: &TypeScriptOptions,
+    symbols: &mut Symbols,
+) -> String {
     match field_type {
-        FieldType::Reference(name) => format!("deserialize{}", name),
+        FieldType::Reference(name) => {
+            symbols.uses(&format!("deserialize{}", name));
+            format!("deserialize{}", name)
+        }
+        FieldType::Primitive(prim) => match primitive_deserializer_expr(prim, "v", options) {
+            Some(expr) => format!("(v: any) => {}", expr),
+            None => "(v: any) => v".to_string(),
+        },
         FieldType::List(inner) => {
-            if needs_serialization(inner) {
-                let inner_deserializer = get_deserializer_call(inner);
+            if needs_serialization(inner, options) {
+                let inner_deserializer = get_deserializer_call(inner, options, symbols);
                 format!("(v: any) => v.map((x: any) => {}(x))", inner_deserializer)
             } else {
                 "(v: any) => v".to_string()
             }
         }
         FieldType::Optional(inner) => {
-            if needs_serialization(inner) {
-                let inner_deserializer = get_deserializer_call(inner);
+            if needs_serialization(inner, options) {
+                let inner_deserializer = get_deserializer_call(inner, options, symbols);
                 format!(
                     "(v: any) => v !== undefined && v !== null ? {}(v) : undefined",
                     inner_deserializer
@@ -463,45 +596,99 @@ fn get_deserializer_call(field_type: &FieldType) -> String {
 }
 
 /// Generate an inline serialization expression for a given value
-fn get_serializer_expr(field_type: &FieldType, value_expr: &str) -> String {
+fn get_serializer_expr(
+    field_type: &FieldType,
+    value_expr: &str,
+    options: &TypeScriptOptions,
+    symbols: &mut Symbols,
+) -> String {
     match field_type {
-        FieldType::Reference(name) => format!("serialize{}({})", name, value_expr),
+        FieldType::Reference(name) => {
+            symbols.uses(&format!("serialize{}", name));
+            format!("serialize{}({})", name, value_expr)
+        }
+        FieldType::Primitive(prim) => primitive_serializer_expr(prim, value_expr, options)
+            .unwrap_or_else(|| value_expr.to_string()),
         FieldType::List(inner) => {
-            if needs_serialization(inner) {
-                let item_expr = get_serializer_expr(inner, "x");
+            if needs_serialization(inner, options) {
+                let item_expr = get_serializer_expr(inner, "x", options, symbols);
+                if item_expr == "x" {
+                    return value_expr.to_string();
+                }
                 format!("{}.map((x: any) => {})", value_expr, item_expr)
             } else {
                 value_expr.to_string()
             }
         }
         FieldType::Optional(inner) => {
-            if needs_serialization(inner) {
-                let inner_expr = get_serializer_expr(inner, value_expr);
+            if needs_serialization(inner, options) {
+                let inner_expr = get_serializer_expr(inner, value_expr, options, symbols);
+                if inner_expr == value_expr {
+                    return value_expr.to_string();
+                }
                 format!("{} !== undefined ? {} : undefined", value_expr, inner_expr)
             } else {
                 value_expr.to_string()
             }
         }
+        FieldType::Map(inner) => {
+            if needs_serialization(inner, options) {
+                let item_expr = get_serializer_expr(inner, "x", options, symbols);
+                if item_expr == "x" {
+                    return value_expr.to_string();
+                }
+                format!(
+                    "Object.fromEntries(Object.entries({}).map(([k, x]: [string, any]) => [k, {}]))",
+                    value_expr, item_expr
+                )
+            } else {
+                value_expr.to_string()
+            }
+        }
         _ => value_expr.to_string(),
     }
 }
 
 /// Generate an inline deserialization expression for a given value
-fn get_deserializer_expr(field_type: &FieldType, value_expr: &str) -> String {
+fn get_deserializer_expr(
+    field_type: &FieldType,
+    value_expr: &str,
+    options: &TypeScriptOptions,
+    symbols: &mut Symbols,
+) -> String {
     match field_type {
-        FieldType::Reference(name) => format!("deserialize{}({})", name, value_expr),
+        FieldType::Reference(name) => {
+            symbols.uses(&format!("deserialize{}", name));
+            format!("deserialize{}({})", name, value_expr)
+        }
+        FieldType::Primitive(prim) => primitive_deserializer_expr(prim, value_expr, options)
+            .unwrap_or_else(|| value_expr.to_string()),
         FieldType::List(inner) => {
-            if needs_serialization(inner) {
-                let item_expr = get_deserializer_expr(inner, "x");
+            if needs_serialization(inner, options) {
+                let item_expr = get_deserializer_expr(inner, "x", options, symbols);
                 format!("{}.map((x: any) => {})", value_expr, item_expr)
             } else {
                 value_expr.to_string()
             }
         }
         FieldType::Optional(inner) => {
-            if needs_serialization(inner) {
-                let inner_expr = get_deserializer_expr(inner, value_expr);
-                format!("{} !== undefined && {} !== null ? {} : undefined", value_expr, value_expr, inner_expr)
+            if needs_serialization(inner, options) {
+                let inner_expr = get_deserializer_expr(inner, value_expr, options, symbols);
+                format!(
+                    "{} !== undefined && {} !== null ? {} : undefined",
+                    value_expr, value_expr, inner_expr
+                )
+            } else {
+                value_expr.to_string()
+            }
+        }
+        FieldType::Map(inner) => {
+            if needs_serialization(inner, options) {
+                let item_expr = get_deserializer_expr(inner, "x", options, symbols);
+                format!(
+                    "Object.fromEntries(Object.entries({}).map(([k, x]: [string, any]) => [k, {}]))",
+                    value_expr, item_expr
+                )
             } else {
                 value_expr.to_string()
             }
@@ -509,3 +696,118 @@ fn get_deserializer_expr(field_type: &FieldType, value_expr: &str) -> String {
         _ => value_expr.to_string(),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::aat::AAT;
+    use crate::generate::typescript::{TypeScriptGenerator, TypeScriptOptions};
+    use crate::spec::{PathSegment, Spec, Type};
+    use schemars::JsonSchema;
+
+    #[derive(JsonSchema)]
+    #[allow(dead_code)]
+    struct Sample {
+        #[schemars(extend("format" = "date-time"))]
+        taken_at: String,
+        #[schemars(extend("format" = "date"))]
+        day: String,
+        reading: u64,
+        history: Vec<i64>,
+        previous: Option<u64>,
+        count: u32,
+        index: usize,
+    }
+
+    fn generate(options: TypeScriptOptions) -> String {
+        let spec = Spec::new("test").service("samples", |service| {
+            service.post(
+                "record",
+                vec![PathSegment::Literal("samples".into())],
+                |e| {
+                    e.body(Type::schema::<Sample>())
+                        .response(Type::schema::<Sample>())
+                },
+            )
+        });
+        let aat = AAT::from_spec(&spec).unwrap();
+        TypeScriptGenerator::generate_with_options(&aat, &options).unwrap()
+    }
+
+    fn mapped() -> String {
+        generate(TypeScriptOptions {
+            dates: true,
+            bigints: true,
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn dates_and_wide_integers_get_their_own_types() {
+        let client = mapped();
+        for line in [
+            "  day: Date;",
+            "  takenAt: Date;",
+            "  reading: bigint;",
+            "  history: bigint[];",
+            "  previous?: bigint;",
+            // Narrow and pointer-sized integers fit in a double
+            "  count: number;",
+            "  index: number;",
+        ] {
+            assert!(client.contains(line), "missing {}", line);
+        }
+    }
+
+    #[test]
+    fn dates_and_wide_integers_are_converted() {
+        let client = mapped();
+        for line in [
+            "    \"day\": value.day.toISOString().slice(0, 10),",
+            "    \"taken_at\": value.takenAt.toISOString(),",
+            // `stringifyJson` writes a bigint as it is
+            "    \"reading\": value.reading,",
+            "    day: new Date(value[\"day\"]),",
+            "    takenAt: new Date(value[\"taken_at\"]),",
+            "    reading: BigInt(value[\"reading\"]),",
+            "    history: value[\"history\"].map((x: any) => BigInt(x)),",
+            "    previous: value[\"previous\"] !== undefined && value[\"previous\"] !== null ? BigInt(value[\"previous\"]) : undefined,",
+            "    count: value[\"count\"],",
+        ] {
+            assert!(client.contains(line), "missing {}", line);
+        }
+    }
+
+    #[test]
+    fn parse_json_reads_unsafe_integers_from_the_source() {
+        let client = mapped();
+        assert!(client.contains(
+            "return JSON.parse(text, (_key: string, value: any, context?: { source?: string }) => {"
+        ));
+        assert!(client.contains(
+            "if (typeof value === 'number' && !Number.isSafeInteger(value) && context?.source !== undefined && /^-?\\d+$/.test(context.source)) {\n      \
+             return BigInt(context.source);"
+        ));
+        assert!(client.contains("const data = parseJson(await response.text());"));
+    }
+
+    #[test]
+    fn stringify_json_writes_bigints_as_numbers() {
+        let client = mapped();
+        assert!(client.contains(
+            "return JSON.stringify(value, (_key, v) => typeof v === 'bigint' ? `${BIGINT_MARKER}${v}` : v)\n    \
+             .replace(new RegExp(`\"${BIGINT_MARKER}(-?\\\\d+)\"`, 'g'), '$1');"
+        ));
+        assert!(client.contains("body: stringifyJson(serializedBody),"));
+    }
+
+    #[test]
+    fn plain_json_without_the_mappings() {
+        let client = generate(TypeScriptOptions::default());
+        assert!(client.contains("  takenAt: string;"));
+        assert!(client.contains("  reading: number;"));
+        assert!(client.contains("const data = await response.json();"));
+        assert!(client.contains("body: JSON.stringify(serializedBody),"));
+        assert!(!client.contains("parseJson"));
+        assert!(!client.contains("stringifyJson"));
+    }
+}
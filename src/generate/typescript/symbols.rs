@@ -0,0 +1,27 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+/// The names a generated module exports and the names it refers to, noted by the generators as
+/// they write it. The modules layout imports every referenced name from the module exporting it.
+#[derive(Debug, Default)]
+pub struct Symbols {
+    /// Exported name -> whether it only exists as a type
+    pub exports: BTreeMap<String, bool>,
+    pub uses: BTreeSet<String>,
+}
+
+impl Symbols {
+    /// Notes a reference to `name`, declared either in this module or another one
+    pub fn uses(&mut self, name: &str) {
+        self.uses.insert(name.to_string());
+    }
+
+    /// Notes an exported interface or type alias
+    pub fn exports_type(&mut self, name: &str) {
+        self.exports.insert(name.to_string(), true);
+    }
+
+    /// Notes an exported function, class or constant
+    pub fn exports_value(&mut self, name: &str) {
+        self.exports.insert(name.to_string(), false);
+    }
+}
@@ -2,23 +2,29 @@ use crate::aat::*;
 use crate::generate::writer::CodeWriter;
 use anyhow::Result;
 
+use super::TypeScriptOptions;
+use super::symbols::Symbols;
 use super::utils::*;
 
-/// Check if a field type is a reference (either directly or wrapped in Optional)
-fn is_reference_type(field_type: &FieldType) -> bool {
-    matches!(field_type, FieldType::Reference(_)) ||
-    matches!(field_type, FieldType::Optional(inner) if matches!(&**inner, FieldType::Reference(_)))
-}
-
-pub fn generate_type(writer: &mut CodeWriter, named_type: &NamedType) -> Result<()> {
+pub fn generate_type(
+    writer: &mut CodeWriter,
+    named_type: &NamedType,
+    options: &TypeScriptOptions,
+    symbols: &mut Symbols,
+) -> Result<()> {
     match named_type {
-        NamedType::Object(obj) => generate_object_type(writer, obj),
-        NamedType::Union(union) => generate_union_type(writer, union),
-        NamedType::Enum(enum_type) => generate_enum_type(writer, enum_type),
+        NamedType::Object(obj) => generate_object_type(writer, obj, options, symbols),
+        NamedType::Union(union) => generate_union_type(writer, union, options, symbols),
+        NamedType::Enum(enum_type) => generate_enum_type(writer, enum_type, symbols),
     }
 }
 
-fn generate_object_type(writer: &mut CodeWriter, obj: &ObjectType) -> Result<()> {
+fn generate_object_type(
+    writer: &mut CodeWriter,
+    obj: &ObjectType,
+    options: &TypeScriptOptions,
+    symbols: &mut Symbols,
+) -> Result<()> {
     // Convert field types outside the closure to avoid borrowing issues
     let fields: Vec<_> = obj
         .fields
@@ -28,15 +34,15 @@ fn generate_object_type(writer: &mut CodeWriter, obj: &ObjectType) -> Result<()>
             let ts_type = if is_optional {
                 // For optional fields, get the inner type without the | undefined
                 if let FieldType::Optional(inner) = &*f.r#type {
-                    field_type_to_ts(inner)
+                    field_type_to_ts(inner, options, symbols)
                 } else {
-                    field_type_to_ts(&f.r#type)
+                    field_type_to_ts(&f.r#type, options, symbols)
                 }
             } else {
-                field_type_to_ts(&f.r#type)
+                field_type_to_ts(&f.r#type, options, symbols)
             };
             (
-                to_camel_case(&f.name),
+                options.naming.field_name(&f.name),
                 ts_type,
                 f.name.clone(),
                 is_optional,
@@ -57,21 +63,27 @@ fn generate_object_type(writer: &mut CodeWriter, obj: &ObjectType) -> Result<()>
         }
     }
 
-    writer
-        .block_with_newline(&format!("export interface {} {{", obj.name), "}", |w| {
-            for (name, ts_type, _, is_optional) in fields {
-                let quoted_name = quote_if_needed(&name);
-                if is_optional {
-                    w.line(&format!("{}?: {};", quoted_name, ts_type));
-                } else {
-                    w.line(&format!("{}: {};", quoted_name, ts_type));
-                }
+    symbols.exports_type(&obj.name);
+    writer.block_with_newline(&format!("export interface {} {{", obj.name), "}", |w| {
+        for (name, ts_type, _, is_optional) in fields {
+            let quoted_name = quote_if_needed(&name);
+            if is_optional {
+                w.line(&format!("{}?: {};", quoted_name, ts_type));
+            } else {
+                w.line(&format!("{}: {};", quoted_name, ts_type));
             }
-        });
+        }
+    });
     Ok(())
 }
 
-fn generate_union_type(writer: &mut CodeWriter, union: &UnionType) -> Result<()> {
+fn generate_union_type(
+    writer: &mut CodeWriter,
+    union: &UnionType,
+    options: &TypeScriptOptions,
+    symbols: &mut Symbols,
+) -> Result<()> {
+    symbols.exports_type(&union.name);
     writer.line(&format!("export type {} =", union.name));
     writer.indent();
 
@@ -95,14 +107,14 @@ fn generate_union_type(writer: &mut CodeWriter, union: &UnionType) -> Result<()>
                     let field_type = if is_optional {
                         // For optional fields, get the inner type
                         if let FieldType::Optional(inner) = &*field.r#type {
-                            field_type_to_ts(inner)
+                            field_type_to_ts(inner, options, symbols)
                         } else {
-                            field_type_to_ts(&field.r#type)
+                            field_type_to_ts(&field.r#type, options, symbols)
                         }
                     } else {
-                        field_type_to_ts(&field.r#type)
+                        field_type_to_ts(&field.r#type, options, symbols)
                     };
-                    let field_name = to_camel_case(&field.name);
+                    let field_name = options.naming.field_name(&field.name);
                     if !seen_fields.insert(field_name.clone()) {
                         anyhow::bail!(
                             "Duplicate field name '{}' in union type '{}' after camelCase conversion (original: '{}')",
@@ -124,25 +136,32 @@ fn generate_union_type(writer: &mut CodeWriter, union: &UnionType) -> Result<()>
                     let quoted_variant_name = quote_if_needed(variant_name);
 
                     // Check if this is a newtype pattern (single field that is a reference)
-                    let is_newtype = obj.fields.len() == 1 && is_reference_type(&obj.fields[0].r#type);
+                    let is_newtype =
+                        obj.fields.len() == 1 && is_reference_type(&obj.fields[0].r#type);
 
                     // Check if this is a tuple variant (single field with same name as variant)
                     // This indicates a Rust tuple enum variant that should be flattened
-                    let is_tuple_variant = obj.fields.len() == 1 &&
-                                          obj.fields[0].name == *variant_name;
+                    let is_tuple_variant =
+                        obj.fields.len() == 1 && obj.fields[0].name == *variant_name;
 
                     if is_newtype {
                         // Newtype pattern: wrap with variant name
                         let field = &obj.fields[0];
-                        let field_type = field_type_to_ts(&field.r#type);
-                        writer.line(&format!("{{ {}: {} }}{}", quoted_variant_name, field_type, separator));
+                        let field_type = field_type_to_ts(&field.r#type, options, symbols);
+                        writer.line(&format!(
+                            "{{ {}: {} }}{}",
+                            quoted_variant_name, field_type, separator
+                        ));
                     } else if is_tuple_variant {
                         // Tuple variant: flatten completely (remove variant name wrapper)
                         writer.line(&format!("{{ {} }}{}", parts.join(", "), separator));
                     } else {
                         // Regular struct variant: wrap with variant name
                         let inner_obj = format!("{{ {} }}", parts.join(", "));
-                        writer.line(&format!("{{ {}: {} }}{}", quoted_variant_name, inner_obj, separator));
+                        writer.line(&format!(
+                            "{{ {}: {} }}{}",
+                            quoted_variant_name, inner_obj, separator
+                        ));
                     }
                 } else {
                     // No variant name: use fields directly
@@ -171,7 +190,12 @@ fn generate_union_type(writer: &mut CodeWriter, union: &UnionType) -> Result<()>
     Ok(())
 }
 
-fn generate_enum_type(writer: &mut CodeWriter, enum_type: &EnumType) -> Result<()> {
+fn generate_enum_type(
+    writer: &mut CodeWriter,
+    enum_type: &EnumType,
+    symbols: &mut Symbols,
+) -> Result<()> {
+    symbols.exports_type(&enum_type.name);
     writer.line(&format!("export type {} =", enum_type.name));
     writer.indent();
 
@@ -1,29 +1,52 @@
 use crate::aat::*;
+pub(crate) use crate::case::{to_camel_case, to_pascal_case};
 
-/// Convert field type to TypeScript type string
-pub fn field_type_to_ts(field_type: &FieldType) -> String {
+use super::TypeScriptOptions;
+use super::symbols::Symbols;
+
+/// Check if a field type is a reference (either directly or wrapped in Optional)
+pub fn is_reference_type(field_type: &FieldType) -> bool {
+    matches!(field_type, FieldType::Reference(_))
+        || matches!(field_type, FieldType::Optional(inner) if matches!(&**inner, FieldType::Reference(_)))
+}
+
+/// Convert field type to TypeScript type string, noting the types it refers to
+pub fn field_type_to_ts(
+    field_type: &FieldType,
+    options: &TypeScriptOptions,
+    symbols: &mut Symbols,
+) -> String {
     match field_type {
-        FieldType::Primitive(prim) => primitive_to_ts(prim),
+        FieldType::Primitive(prim) => primitive_to_ts(prim, options),
         FieldType::Literal(lit) => literal_to_ts(lit),
-        FieldType::Optional(inner) => format!("{} | undefined", field_type_to_ts(inner)),
-        FieldType::List(inner) => format!("{}[]", field_type_to_ts(inner)),
+        FieldType::Optional(inner) => {
+            format!("{} | undefined", field_type_to_ts(inner, options, symbols))
+        }
+        FieldType::List(inner) => format!("{}[]", field_type_to_ts(inner, options, symbols)),
         FieldType::Map(inner) => {
-            format!("{{ [key: string]: {} }}", field_type_to_ts(inner))
+            format!(
+                "{{ [key: string]: {} }}",
+                field_type_to_ts(inner, options, symbols)
+            )
         }
         FieldType::Stream(inner) => {
-            let inner_ts = field_type_to_ts(inner);
+            let inner_ts = field_type_to_ts(inner, options, symbols);
+            symbols.uses("WebSocketStream");
             format!("WebSocketStream<{}>", inner_ts)
         }
-        FieldType::Reference(name) => name.clone(),
+        FieldType::Reference(name) => {
+            symbols.uses(name);
+            name.clone()
+        }
         FieldType::Intersection(types) => types
             .iter()
-            .map(|t| field_type_to_ts(t))
+            .map(|t| field_type_to_ts(t, options, symbols))
             .collect::<Vec<_>>()
             .join(" & "),
         FieldType::Tuple(types) => {
             let inner = types
                 .iter()
-                .map(|t| field_type_to_ts(t))
+                .map(|t| field_type_to_ts(t, options, symbols))
                 .collect::<Vec<_>>()
                 .join(", ");
             format!("[{}]", inner)
@@ -32,11 +55,13 @@ pub fn field_type_to_ts(field_type: &FieldType) -> String {
     }
 }
 
-pub fn primitive_to_ts(prim: &PrimitiveType) -> String {
+pub fn primitive_to_ts(prim: &PrimitiveType, options: &TypeScriptOptions) -> String {
     match prim {
         PrimitiveType::Bool => "boolean".to_string(),
-        PrimitiveType::Int => "number".to_string(),
+        PrimitiveType::Int(_) if options.uses_bigint(prim) => "bigint".to_string(),
+        PrimitiveType::Int(_) => "number".to_string(),
         PrimitiveType::Float => "number".to_string(),
+        PrimitiveType::String(_) if options.uses_date(prim) => "Date".to_string(),
         PrimitiveType::String(_) => "string".to_string(),
     }
 }
@@ -61,36 +86,6 @@ pub fn literal_to_ts_with_camel(lit: &LiteralType) -> String {
     }
 }
 
-pub fn to_pascal_case(s: &str) -> String {
-    s.split(&['-', '_'][..])
-        .filter(|part| !part.is_empty())
-        .map(|part| {
-            let mut chars = part.chars();
-            match chars.next() {
-                None => String::new(),
-                Some(first) => first.to_uppercase().chain(chars).collect(),
-            }
-        })
-        .collect()
-}
-
-pub fn to_camel_case(s: &str) -> String {
-    let parts: Vec<&str> = s.split(&['-', '_'][..]).filter(|p| !p.is_empty()).collect();
-    if parts.is_empty() {
-        return String::new();
-    }
-
-    let mut result = parts[0].to_lowercase();
-    for part in &parts[1..] {
-        let mut chars = part.chars();
-        if let Some(first) = chars.next() {
-            result.push_str(&first.to_uppercase().collect::<String>());
-            result.push_str(&chars.collect::<String>().to_lowercase());
-        }
-    }
-    result
-}
-
 /// Checks if a string is a valid TypeScript identifier
 /// Returns true if it can be used unquoted as a property name
 pub fn is_valid_ts_identifier(s: &str) -> bool {
@@ -124,3 +119,51 @@ pub fn quote_if_needed(s: &str) -> String {
         format!("\"{}\"", s)
     }
 }
+
+/// `object.name`, or `object["name"]` when the name is not a valid identifier
+pub fn property_access(object: &str, name: &str) -> String {
+    if is_valid_ts_identifier(name) {
+        format!("{}.{}", object, name)
+    } else {
+        format!("{}[{}]", object, json_string(name))
+    }
+}
+
+/// A double-quoted string literal, valid in both JSON and TypeScript
+pub fn json_string(s: &str) -> String {
+    serde_json::Value::String(s.to_string()).to_string()
+}
+
+/// Expression converting a `Date` or `bigint` primitive to its wire form, if it needs one
+pub fn primitive_serializer_expr(
+    prim: &PrimitiveType,
+    value_expr: &str,
+    options: &TypeScriptOptions,
+) -> Option<String> {
+    if options.uses_date(prim) {
+        match prim {
+            PrimitiveType::String(Some(StringFormat::Date)) => {
+                Some(format!("{}.toISOString().slice(0, 10)", value_expr))
+            }
+            _ => Some(format!("{}.toISOString()", value_expr)),
+        }
+    } else {
+        // A `bigint` stays as it is; `stringifyJson` writes it as a plain JSON number
+        None
+    }
+}
+
+/// Expression converting the wire form of a `Date` or `bigint` primitive, if it needs one
+pub fn primitive_deserializer_expr(
+    prim: &PrimitiveType,
+    value_expr: &str,
+    options: &TypeScriptOptions,
+) -> Option<String> {
+    if options.uses_date(prim) {
+        Some(format!("new Date({})", value_expr))
+    } else if options.uses_bigint(prim) {
+        Some(format!("BigInt({})", value_expr))
+    } else {
+        None
+    }
+}
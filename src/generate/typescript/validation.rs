@@ -0,0 +1,1027 @@
+use crate::aat::*;
+use crate::generate::writer::CodeWriter;
+use anyhow::{Result, anyhow};
+
+use super::TypeScriptOptions;
+use super::symbols::Symbols;
+use super::utils::*;
+
+pub fn generate_validation_runtime(writer: &mut CodeWriter, symbols: &mut Symbols) -> Result<()> {
+    symbols.exports_type("ValidationIssue");
+    symbols.exports_value("ValidationError");
+    symbols.exports_value("assertValid");
+    writer.block("export interface ValidationIssue {", "}", |w| {
+        w.line("path: string;");
+        w.line("message: string;");
+    });
+    writer.empty_line();
+
+    writer.block("export class ValidationError extends Error {", "}", |w| {
+        w.block("constructor(context: string, public readonly issues: ValidationIssue[]) {", "}", |w| {
+            w.line("super(`${context}: ${issues.map(issue => `${issue.path || '/'} ${issue.message}`).join('; ')}`);");
+            w.line("this.name = 'ValidationError';");
+        });
+    });
+    writer.empty_line();
+
+    writer.block(
+        "export function assertValid(issues: ValidationIssue[], context: string): void {",
+        "}",
+        |w| {
+            w.block("if (issues.length > 0) {", "}", |w| {
+                w.line("throw new ValidationError(context, issues);");
+            });
+        },
+    );
+    writer.empty_line();
+
+    symbols.exports_value("matchesPattern");
+    writer.line("const compiledPatterns = new Map<string, RegExp>();");
+    writer.empty_line();
+    writer.line("/** Tests `value` against a field's pattern, compiling each pattern only once */");
+    writer.block(
+        "export function matchesPattern(value: string, pattern: string): boolean {",
+        "}",
+        |w| {
+            w.line("let regex = compiledPatterns.get(pattern);");
+            w.block("if (regex === undefined) {", "}", |w| {
+                w.line("regex = new RegExp(pattern, 'u');");
+                w.line("compiledPatterns.set(pattern, regex);");
+            });
+            w.line("return regex.test(value);");
+        },
+    );
+    Ok(())
+}
+
+/// Rewrites the `pattern` constraints of every field into JavaScript `RegExp` syntax, failing on
+/// Rust `regex` syntax that has no JavaScript equivalent
+pub fn prepare_patterns(mut aat: AAT) -> Result<AAT> {
+    for named_type in &mut aat.types {
+        let objects: Vec<&mut ObjectType> = match named_type {
+            NamedType::Object(obj) => vec![obj],
+            NamedType::Union(union) => union
+                .variants
+                .iter_mut()
+                .filter_map(|v| match &mut *v.mode {
+                    UnionTypeVariantMode::Object(obj) => Some(obj),
+                    UnionTypeVariantMode::Literal(_) => None,
+                })
+                .collect(),
+            NamedType::Enum(_) => vec![],
+        };
+        for field in objects.into_iter().flat_map(|obj| &mut obj.fields) {
+            if let Some(pattern) = field.constraints.as_mut().and_then(|c| c.pattern.as_mut()) {
+                *pattern = js_pattern(pattern)?;
+            }
+        }
+    }
+    Ok(aat)
+}
+
+/// Translates a Rust `regex` pattern into the syntax of a JavaScript `RegExp` with the `u` flag
+fn js_pattern(pattern: &str) -> Result<String> {
+    let unsupported = |what: &str| {
+        anyhow!(
+            "Pattern '{}' uses {}, which JavaScript regular expressions don't support",
+            pattern,
+            what
+        )
+    };
+    let mut out = String::with_capacity(pattern.len());
+    let mut chars = pattern.chars().peekable();
+    let mut in_class = false;
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some('A') if !in_class => out.push('^'),
+                Some('z') if !in_class => out.push('$'),
+                // `\pL` is short for `\p{L}`
+                Some(p @ ('p' | 'P')) if chars.peek().is_some_and(|c| *c != '{') => {
+                    out.push_str(&format!("\\{}{{{}}}", p, chars.next().unwrap_or_default()));
+                }
+                Some(escaped) => {
+                    out.push('\\');
+                    out.push(escaped);
+                }
+                None => out.push('\\'),
+            },
+            '[' if in_class => {
+                return Err(if chars.peek() == Some(&':') {
+                    unsupported("POSIX character classes")
+                } else {
+                    unsupported("nested character classes")
+                });
+            }
+            '[' => {
+                in_class = true;
+                out.push('[');
+                if chars.next_if_eq(&'^').is_some() {
+                    out.push('^');
+                }
+                // A `]` opening a class is a literal in Rust but closes the class in JavaScript
+                if chars.next_if_eq(&']').is_some() {
+                    out.push_str("\\]");
+                }
+            }
+            ']' if in_class => {
+                in_class = false;
+                out.push(']');
+            }
+            '&' | '-' | '~' if in_class && chars.peek() == Some(&c) => {
+                return Err(unsupported("character class set operations"));
+            }
+            '(' if !in_class && chars.next_if_eq(&'?').is_some() => {
+                out.push_str("(?");
+                match chars.peek() {
+                    // Named groups are `(?<name>...)` in JavaScript
+                    Some('P') => {
+                        chars.next();
+                    }
+                    Some(':' | '<') => {}
+                    _ => return Err(unsupported("inline flags")),
+                }
+            }
+            _ => out.push(c),
+        }
+    }
+    Ok(out)
+}
+
+pub fn generate_validator(
+    writer: &mut CodeWriter,
+    named_type: &NamedType,
+    options: &TypeScriptOptions,
+    symbols: &mut Symbols,
+) -> Result<()> {
+    let name = match named_type {
+        NamedType::Object(obj) => &obj.name,
+        NamedType::Union(union) => &union.name,
+        NamedType::Enum(enum_type) => &enum_type.name,
+    };
+    symbols.exports_value(&format!("validate{}", name));
+    symbols.uses("ValidationIssue");
+
+    writer.block(
+        &format!("export function validate{}(value: any, path: string = ''): ValidationIssue[] {{", name),
+        "}",
+        |w| {
+            w.line("const issues: ValidationIssue[] = [];");
+            match named_type {
+                NamedType::Object(obj) => {
+                    w.block("if (typeof value !== 'object' || value === null || Array.isArray(value)) {", "}", |w| {
+                        push_issue(w, "${path}", "Expected an object");
+                        w.line("return issues;");
+                    });
+                    generate_field_checks(w, &obj.fields, "value", "${path}", 0, options, symbols);
+                }
+                NamedType::Enum(enum_type) => {
+                    let allowed: Vec<String> = enum_type.variants.iter().map(|v| literal_to_ts(&v.value)).collect();
+                    w.block(&format!("if (![{}].includes(value)) {{", allowed.join(", ")), "}", |w| {
+                        push_issue(w, "${path}", &format!("Expected one of {}", allowed.join(", ")));
+                    });
+                }
+                NamedType::Union(union) => generate_union_checks(w, union, options, symbols),
+            }
+            w.line("return issues;");
+        },
+    );
+    Ok(())
+}
+
+fn generate_union_checks(
+    w: &mut CodeWriter,
+    union: &UnionType,
+    options: &TypeScriptOptions,
+    symbols: &mut Symbols,
+) {
+    if let Some(discriminator) = &union.discriminator {
+        generate_discriminated_checks(w, union, discriminator, options, symbols);
+        return;
+    }
+
+    let literals: Vec<String> = union
+        .variants
+        .iter()
+        .filter_map(|v| match &*v.mode {
+            UnionTypeVariantMode::Literal(lit) => Some(literal_to_ts(lit)),
+            _ => None,
+        })
+        .collect();
+    if !literals.is_empty() {
+        w.block(
+            &format!("if ([{}].includes(value)) {{", literals.join(", ")),
+            "}",
+            |w| {
+                w.line("return issues;");
+            },
+        );
+    }
+
+    let objects: Vec<(&Option<String>, &ObjectType)> = union
+        .variants
+        .iter()
+        .filter_map(|v| match &*v.mode {
+            UnionTypeVariantMode::Object(obj) => Some((&v.name, obj)),
+            _ => None,
+        })
+        .collect();
+
+    if !objects.is_empty() {
+        w.block("if (typeof value === 'object' && value !== null && !Array.isArray(value)) {", "}", |w| {
+            // Mirror the shapes emitted by `types::generate_union_type`
+            for (variant_name, obj) in objects {
+                match variant_name {
+                    Some(variant_name) if obj.fields.len() == 1 && is_reference_type(&obj.fields[0].r#type) => {
+                        let key = variant_name.clone();
+                        w.block(&format!("if ({} in value) {{", json_string(&key)), "}", |w| {
+                            generate_checks(
+                                w,
+                                &obj.fields[0].r#type,
+                                obj.fields[0].constraints.as_ref(),
+                                &format!("value[{}]", json_string(&key)),
+                                &child_path("${path}", &key),
+                                0,
+                                options,
+                                symbols,
+                            );
+                            w.line("return issues;");
+                        });
+                    }
+                    Some(variant_name) if obj.fields.len() == 1 && obj.fields[0].name == *variant_name => {
+                        let key = options.naming.field_name(&obj.fields[0].name);
+                        w.block(&format!("if ({} in value) {{", json_string(&key)), "}", |w| {
+                            generate_checks(
+                                w,
+                                &obj.fields[0].r#type,
+                                obj.fields[0].constraints.as_ref(),
+                                &format!("value[{}]", json_string(&key)),
+                                &child_path("${path}", &key),
+                                0,
+                                options,
+                                symbols,
+                            );
+                            w.line("return issues;");
+                        });
+                    }
+                    Some(variant_name) => {
+                        let key = variant_name.clone();
+                        let inner_path = child_path("${path}", &key);
+                        w.block(&format!("if ({} in value) {{", json_string(&key)), "}", |w| {
+                            w.line(&format!("const inner = value[{}];", json_string(&key)));
+                            w.line("if (typeof inner !== 'object' || inner === null || Array.isArray(inner)) {");
+                            w.indent();
+                            push_issue(w, &inner_path, "Expected an object");
+                            w.dedent();
+                            w.line("} else {");
+                            w.indent();
+                            generate_field_checks(w, &obj.fields, "inner", &inner_path, 0, options, symbols);
+                            w.dedent();
+                            w.line("}");
+                            w.line("return issues;");
+                        });
+                    }
+                    None => {
+                        // Untagged variant: accept it if all of its checks pass
+                        w.block("{", "}", |w| {
+                            w.block("const variantIssues = ((): ValidationIssue[] => {", "})();", |w| {
+                                w.line("const issues: ValidationIssue[] = [];");
+                                generate_field_checks(w, &obj.fields, "value", "${path}", 0, options, symbols);
+                                w.line("return issues;");
+                            });
+                            w.block("if (variantIssues.length === 0) {", "}", |w| {
+                                w.line("return issues;");
+                            });
+                        });
+                    }
+                }
+            }
+        });
+    }
+
+    push_issue(
+        w,
+        "${path}",
+        &format!("Value does not match any variant of {}", union.name),
+    );
+}
+
+/// Picks the variant by its tag, like the Rust validator, so issues come from that variant only
+fn generate_discriminated_checks(
+    w: &mut CodeWriter,
+    union: &UnionType,
+    discriminator: &Discriminator,
+    options: &TypeScriptOptions,
+    symbols: &mut Symbols,
+) {
+    let tag = options.naming.field_name(&discriminator.property_name);
+    let access = format!("value[{}]", json_string(&tag));
+    w.block(
+        "if (typeof value !== 'object' || value === null || Array.isArray(value)) {",
+        "}",
+        |w| {
+            push_issue(w, "${path}", "Expected an object");
+            w.line("return issues;");
+        },
+    );
+    w.block(&format!("switch ({}) {{", access), "}", |w| {
+        for variant in &union.variants {
+            let UnionTypeVariantMode::Object(obj) = &*variant.mode else {
+                continue;
+            };
+            let tag_value = discriminator.tag_for(variant.name.as_deref().unwrap_or(&obj.name));
+            let fields: Vec<Field> = obj
+                .fields
+                .iter()
+                .filter(|f| f.name != discriminator.property_name)
+                .cloned()
+                .collect();
+            w.block(&format!("case {}: {{", json_string(&tag_value)), "}", |w| {
+                generate_field_checks(w, &fields, "value", "${path}", 0, options, symbols);
+                w.line("return issues;");
+            });
+        }
+    });
+    w.block(&format!("if ({} === undefined) {{", access), "}", |w| {
+        push_issue(
+            w,
+            "${path}",
+            &format!("Missing discriminator property '{}'", tag),
+        );
+        w.line("return issues;");
+    });
+    w.line(&format!(
+        "issues.push({{ path: `{}`, message: `Unknown discriminator value ${{JSON.stringify({})}} for union {}` }});",
+        child_path("${path}", &tag),
+        access,
+        union.name
+    ));
+}
+
+/// Emits required-field and per-field checks for the (camelCased) fields of an object value
+pub fn generate_field_checks(
+    w: &mut CodeWriter,
+    fields: &[Field],
+    value: &str,
+    path: &str,
+    depth: usize,
+    options: &TypeScriptOptions,
+    symbols: &mut Symbols,
+) {
+    for field in fields {
+        let name = options.naming.field_name(&field.name);
+        let access = format!("{}[{}]", value, json_string(&name));
+        let field_path = child_path(path, &name);
+
+        if matches!(&*field.r#type, FieldType::Optional(_)) {
+            generate_checks(
+                w,
+                &field.r#type,
+                field.constraints.as_ref(),
+                &access,
+                &field_path,
+                depth,
+                options,
+                symbols,
+            );
+        } else {
+            w.line(&format!("if ({} === undefined) {{", access));
+            w.indent();
+            push_issue(w, path, &format!("Missing required property '{}'", name));
+            w.dedent();
+            if !matches!(&*field.r#type, FieldType::Any) {
+                w.line("} else {");
+                w.indent();
+                generate_checks(
+                    w,
+                    &field.r#type,
+                    field.constraints.as_ref(),
+                    &access,
+                    &field_path,
+                    depth,
+                    options,
+                    symbols,
+                );
+                w.dedent();
+            }
+            w.line("}");
+        }
+    }
+}
+
+/// Emits statements that push a `ValidationIssue` onto `issues` for every violation found in `value`.
+/// `path` is the body of a template literal, e.g. `${path}/name`.
+#[allow(clippy::too_many_arguments)]
+pub fn generate_checks(
+    w: &mut CodeWriter,
+    field_type: &FieldType,
+    constraints: Option<&Constraints>,
+    value: &str,
+    path: &str,
+    depth: usize,
+    options: &TypeScriptOptions,
+    symbols: &mut Symbols,
+) {
+    match field_type {
+        FieldType::Primitive(PrimitiveType::Bool) => {
+            w.block(
+                &format!("if (typeof {} !== 'boolean') {{", value),
+                "}",
+                |w| {
+                    push_issue(w, path, "Expected a boolean");
+                },
+            );
+        }
+        FieldType::Primitive(prim) if options.uses_date(prim) => {
+            w.block(
+                &format!(
+                    "if (!({v} instanceof Date) || Number.isNaN({v}.getTime())) {{",
+                    v = value
+                ),
+                "}",
+                |w| {
+                    push_issue(w, path, "Expected a valid Date");
+                },
+            );
+        }
+        FieldType::Primitive(prim) if options.uses_bigint(prim) => {
+            let condition = format!("typeof {} !== 'bigint'", value);
+            type_check_then(
+                w,
+                &condition,
+                path,
+                "Expected a bigint",
+                |w| {
+                    if let Some(c) = constraints {
+                        generate_number_constraints(w, c, value, path, true);
+                    }
+                },
+                has_number_constraints(constraints),
+            );
+        }
+        FieldType::Primitive(PrimitiveType::Int(_)) => {
+            let condition = format!(
+                "typeof {v} !== 'number' || !Number.isInteger({v})",
+                v = value
+            );
+            type_check_then(
+                w,
+                &condition,
+                path,
+                "Expected an integer",
+                |w| {
+                    if let Some(c) = constraints {
+                        generate_number_constraints(w, c, value, path, false);
+                    }
+                },
+                has_number_constraints(constraints),
+            );
+        }
+        FieldType::Primitive(PrimitiveType::Float) => {
+            let condition = format!("typeof {} !== 'number'", value);
+            type_check_then(
+                w,
+                &condition,
+                path,
+                "Expected a number",
+                |w| {
+                    if let Some(c) = constraints {
+                        generate_number_constraints(w, c, value, path, false);
+                    }
+                },
+                has_number_constraints(constraints),
+            );
+        }
+        FieldType::Primitive(PrimitiveType::String(_)) => {
+            let condition = format!("typeof {} !== 'string'", value);
+            let has_string_constraints = constraints.is_some_and(|c| {
+                c.min_length.is_some() || c.max_length.is_some() || c.pattern.is_some()
+            });
+            type_check_then(
+                w,
+                &condition,
+                path,
+                "Expected a string",
+                |w| {
+                    if let Some(c) = constraints {
+                        generate_string_constraints(w, c, value, path, symbols);
+                    }
+                },
+                has_string_constraints,
+            );
+        }
+        FieldType::Literal(lit) => {
+            let lit = literal_to_ts(lit);
+            w.block(&format!("if ({} !== {}) {{", value, lit), "}", |w| {
+                push_issue(w, path, &format!("Expected {}", lit));
+            });
+        }
+        FieldType::Optional(inner) => {
+            if !matches!(&**inner, FieldType::Any) {
+                w.block(
+                    &format!("if ({v} !== undefined && {v} !== null) {{", v = value),
+                    "}",
+                    |w| {
+                        generate_checks(
+                            w,
+                            inner,
+                            constraints,
+                            value,
+                            path,
+                            depth,
+                            options,
+                            symbols,
+                        );
+                    },
+                );
+            }
+        }
+        FieldType::List(inner) => {
+            let has_array_constraints = constraints.is_some_and(|c| {
+                c.min_items.is_some() || c.max_items.is_some() || c.unique_items == Some(true)
+            });
+            type_check_then(
+                w,
+                &format!("!Array.isArray({})", value),
+                path,
+                "Expected an array",
+                |w| {
+                    if let Some(c) = constraints {
+                        generate_array_constraints(w, c, value, path);
+                    }
+                    if !matches!(&**inner, FieldType::Any) {
+                        let index = format!("i{}", depth);
+                        w.block(
+                            &format!(
+                                "for (let {i} = 0; {i} < {v}.length; {i}++) {{",
+                                i = index,
+                                v = value
+                            ),
+                            "}",
+                            |w| {
+                                generate_checks(
+                                    w,
+                                    inner,
+                                    None,
+                                    &format!("{}[{}]", value, index),
+                                    &format!("{}/${{{}}}", path, index),
+                                    depth + 1,
+                                    options,
+                                    symbols,
+                                );
+                            },
+                        );
+                    }
+                },
+                has_array_constraints || !matches!(&**inner, FieldType::Any),
+            );
+        }
+        FieldType::Map(inner) => {
+            let condition = format!(
+                "typeof {v} !== 'object' || {v} === null || Array.isArray({v})",
+                v = value
+            );
+            type_check_then(
+                w,
+                &condition,
+                path,
+                "Expected an object",
+                |w| {
+                    let (key, item) = (format!("k{}", depth), format!("x{}", depth));
+                    w.block(
+                        &format!(
+                            "for (const [{}, {}] of Object.entries({})) {{",
+                            key, item, value
+                        ),
+                        "}",
+                        |w| {
+                            generate_checks(
+                                w,
+                                inner,
+                                None,
+                                &item,
+                                &format!("{}/${{{}}}", path, key),
+                                depth + 1,
+                                options,
+                                symbols,
+                            );
+                        },
+                    );
+                },
+                !matches!(&**inner, FieldType::Any),
+            );
+        }
+        FieldType::Stream(inner) => {
+            generate_checks(w, inner, constraints, value, path, depth, options, symbols)
+        }
+        FieldType::Reference(name) => {
+            symbols.uses(&format!("validate{}", name));
+            w.line(&format!(
+                "issues.push(...validate{}({}, `{}`));",
+                name, value, path
+            ));
+        }
+        FieldType::Intersection(types) => {
+            for t in types {
+                generate_checks(w, t, None, value, path, depth, options, symbols);
+            }
+        }
+        FieldType::Tuple(types) => {
+            let condition = format!(
+                "!Array.isArray({v}) || {v}.length !== {n}",
+                v = value,
+                n = types.len()
+            );
+            let message = format!("Expected a tuple of {} items", types.len());
+            type_check_then(
+                w,
+                &condition,
+                path,
+                &message,
+                |w| {
+                    for (i, t) in types.iter().enumerate() {
+                        generate_checks(
+                            w,
+                            t,
+                            None,
+                            &format!("{}[{}]", value, i),
+                            &format!("{}/{}", path, i),
+                            depth + 1,
+                            options,
+                            symbols,
+                        );
+                    }
+                },
+                true,
+            );
+        }
+        FieldType::Any => {}
+    }
+}
+
+/// Emits `if (<condition>) { <issue> } else { <checks> }`, dropping the else branch when empty
+fn type_check_then<F>(
+    w: &mut CodeWriter,
+    condition: &str,
+    path: &str,
+    message: &str,
+    checks: F,
+    has_checks: bool,
+) where
+    F: FnOnce(&mut CodeWriter),
+{
+    w.line(&format!("if ({}) {{", condition));
+    w.indent();
+    push_issue(w, path, message);
+    w.dedent();
+    if has_checks {
+        w.line("} else {");
+        w.indent();
+        checks(w);
+        w.dedent();
+    }
+    w.line("}");
+}
+
+fn has_number_constraints(constraints: Option<&Constraints>) -> bool {
+    constraints.is_some_and(|c| {
+        c.minimum.is_some()
+            || c.maximum.is_some()
+            || c.exclusive_minimum.is_some()
+            || c.exclusive_maximum.is_some()
+            || c.multiple_of.is_some()
+    })
+}
+
+fn generate_number_constraints(
+    w: &mut CodeWriter,
+    c: &Constraints,
+    value: &str,
+    path: &str,
+    bigint: bool,
+) {
+    let checks = [
+        (c.minimum, "<", "must be >="),
+        (c.maximum, ">", "must be <="),
+        (c.exclusive_minimum, "<=", "must be >"),
+        (c.exclusive_maximum, ">=", "must be <"),
+    ];
+    for (bound, op, message) in checks {
+        if let Some(bound) = bound {
+            w.block(&format!("if ({} {} {}) {{", value, op, bound), "}", |w| {
+                push_issue(w, path, &format!("Value {} {}", message, bound));
+            });
+        }
+    }
+    if let Some(multiple) = c.multiple_of
+        && (!bigint || multiple.fract() == 0.0)
+    {
+        // Comparisons mix `bigint` and `number` fine, arithmetic does not
+        let condition = if bigint {
+            format!("{} % {}n !== 0n", value, multiple)
+        } else {
+            format!(
+                "Math.abs({v} / {m} - Math.round({v} / {m})) > 1e-9",
+                v = value,
+                m = multiple
+            )
+        };
+        w.block(&format!("if ({}) {{", condition), "}", |w| {
+            push_issue(
+                w,
+                path,
+                &format!("Value must be a multiple of {}", multiple),
+            );
+        });
+    }
+}
+
+fn generate_string_constraints(
+    w: &mut CodeWriter,
+    c: &Constraints,
+    value: &str,
+    path: &str,
+    symbols: &mut Symbols,
+) {
+    if let Some(min) = c.min_length {
+        w.block(
+            &format!("if ([...{}].length < {}) {{", value, min),
+            "}",
+            |w| {
+                push_issue(w, path, &format!("Length must be >= {}", min));
+            },
+        );
+    }
+    if let Some(max) = c.max_length {
+        w.block(
+            &format!("if ([...{}].length > {}) {{", value, max),
+            "}",
+            |w| {
+                push_issue(w, path, &format!("Length must be <= {}", max));
+            },
+        );
+    }
+    if let Some(pattern) = &c.pattern {
+        symbols.uses("matchesPattern");
+        w.block(
+            &format!(
+                "if (!matchesPattern({}, {})) {{",
+                value,
+                json_string(pattern)
+            ),
+            "}",
+            |w| {
+                push_issue(w, path, &format!("Must match pattern {}", pattern));
+            },
+        );
+    }
+}
+
+fn generate_array_constraints(w: &mut CodeWriter, c: &Constraints, value: &str, path: &str) {
+    if let Some(min) = c.min_items {
+        w.block(&format!("if ({}.length < {}) {{", value, min), "}", |w| {
+            push_issue(w, path, &format!("Must have at least {} items", min));
+        });
+    }
+    if let Some(max) = c.max_items {
+        w.block(&format!("if ({}.length > {}) {{", value, max), "}", |w| {
+            push_issue(w, path, &format!("Must have at most {} items", max));
+        });
+    }
+    if c.unique_items == Some(true) {
+        w.block(
+            &format!(
+                "if (new Set({v}.map((x: any) => JSON.stringify(x))).size !== {v}.length) {{",
+                v = value
+            ),
+            "}",
+            |w| {
+                push_issue(w, path, "Items must be unique");
+            },
+        );
+    }
+}
+
+fn push_issue(w: &mut CodeWriter, path: &str, message: &str) {
+    w.line(&format!(
+        "issues.push({{ path: `{}`, message: {} }});",
+        path,
+        json_string(message)
+    ));
+}
+
+/// Appends a JSON pointer segment to a template-literal path body
+fn child_path(path: &str, key: &str) -> String {
+    let segment = key
+        .replace('~', "~0")
+        .replace('/', "~1")
+        .replace('\\', "\\\\")
+        .replace('`', "\\`")
+        .replace("${", "\\${");
+    format!("{}/{}", path, segment)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generate::typescript::TypeScriptGenerator;
+    use crate::spec::{PathSegment, Spec, Type};
+    use schemars::JsonSchema;
+
+    #[derive(JsonSchema)]
+    #[allow(dead_code)]
+    struct Machine {
+        #[schemars(length(min = 1, max = 63), regex(pattern = "^[a-z]+$"))]
+        name: String,
+        #[schemars(range(min = 1, max = 64))]
+        cpus: u8,
+        tags: Option<Vec<String>>,
+        state: State,
+    }
+
+    #[derive(JsonSchema)]
+    #[allow(dead_code)]
+    #[serde(rename_all = "snake_case")]
+    enum State {
+        Running { since: u64 },
+        Stopped,
+    }
+
+    #[derive(JsonSchema)]
+    #[allow(dead_code)]
+    struct Host {
+        #[schemars(regex(pattern = "(?i)^[a-z]+$"))]
+        name: String,
+    }
+
+    fn generate_for<T: JsonSchema>(validators: bool) -> Result<String> {
+        let spec = Spec::new("test").service("machines", |service| {
+            service.post(
+                "create",
+                vec![PathSegment::Literal("machines".into())],
+                |e| e.body(Type::schema::<T>()).response(Type::schema::<T>()),
+            )
+        });
+        let aat = AAT::from_spec(&spec).unwrap();
+        let options = TypeScriptOptions {
+            validators,
+            ..Default::default()
+        };
+        TypeScriptGenerator::generate_with_options(&aat, &options)
+    }
+
+    fn generate(validators: bool) -> String {
+        generate_for::<Machine>(validators).unwrap()
+    }
+
+    /// The generated function starting with `signature`, up to its closing brace
+    fn function<'a>(client: &'a str, signature: &str) -> &'a str {
+        let start = client.find(signature).unwrap();
+        let end = start + client[start..].find("\n}\n").unwrap();
+        &client[start..end + 2]
+    }
+
+    #[test]
+    fn checks_required_properties_and_constraints() {
+        let client = generate(true);
+        let validator = function(&client, "export function validateMachine(");
+        for check in [
+            r#"issues.push({ path: `${path}`, message: "Missing required property 'name'" });"#,
+            r#"if ([...value["name"]].length < 1) {"#,
+            r#"if ([...value["name"]].length > 63) {"#,
+            r#"if (!matchesPattern(value["name"], "^[a-z]+$")) {"#,
+            r#"if (typeof value["cpus"] !== 'number' || !Number.isInteger(value["cpus"])) {"#,
+            r#"issues.push({ path: `${path}/cpus`, message: "Value must be <= 64" });"#,
+            r#"issues.push(...validateState(value["state"], `${path}/state`));"#,
+        ] {
+            assert!(validator.contains(check), "missing {}", check);
+        }
+        // Optional properties are only checked when present
+        assert!(
+            validator.contains(r#"if (value["tags"] !== undefined && value["tags"] !== null) {"#)
+        );
+        assert!(!validator.contains("Missing required property 'tags'"));
+    }
+
+    #[test]
+    fn checks_union_variants() {
+        let client = generate(true);
+        let validator = function(&client, "export function validateState(");
+        assert!(validator.contains(r#"if (["stopped"].includes(value)) {"#));
+        assert!(validator.contains(r#"if ("running" in value) {"#));
+        assert!(validator.contains(
+            r#"issues.push({ path: `${path}/running`, message: "Missing required property 'since'" });"#
+        ));
+        assert!(validator.contains("Value does not match any variant of State"));
+    }
+
+    #[test]
+    fn dispatches_discriminated_unions_on_their_tag() {
+        let field = |name: &str, r#type: FieldType| Field {
+            name: name.to_string(),
+            r#type: Box::new(r#type),
+            constraints: None,
+            examples: vec![],
+        };
+        let variant = |name: &str, field_name: &str| UnionTypeVariant {
+            name: Some(name.to_string()),
+            mode: Box::new(UnionTypeVariantMode::Object(ObjectType {
+                name: name.to_string(),
+                fields: vec![
+                    field("kind", FieldType::Literal(LiteralType::String(name.into()))),
+                    field(field_name, FieldType::Primitive(PrimitiveType::Float)),
+                ],
+                examples: vec![],
+                rust_path: None,
+            })),
+        };
+        let shape = NamedType::Union(UnionType {
+            name: "Shape".to_string(),
+            discriminator: Some(Discriminator {
+                property_name: "kind".to_string(),
+                mapping: None,
+            }),
+            variants: vec![variant("circle", "radius"), variant("square", "side")],
+            examples: vec![],
+            rust_path: None,
+        });
+        let mut writer = CodeWriter::new();
+        let options = TypeScriptOptions::default();
+        generate_validator(&mut writer, &shape, &options, &mut Symbols::default()).unwrap();
+        let validator = writer.into_string();
+
+        let circle_start = validator.find(r#"case "circle": {"#).unwrap();
+        let square_start = validator.find(r#"case "square": {"#).unwrap();
+        let circle = &validator[circle_start..square_start];
+        assert!(circle.contains("Missing required property 'radius'"));
+        assert!(!circle.contains("'side'"));
+        assert!(validator.contains(r#"switch (value["kind"]) {"#));
+        assert!(validator.contains("Missing discriminator property 'kind'"));
+        assert!(validator.contains(
+            "issues.push({ path: `${path}/kind`, message: `Unknown discriminator value ${JSON.stringify(value[\"kind\"])} for union Shape` });"
+        ));
+        // The tag has been checked by the dispatch, and no other variant is tried
+        assert!(!validator.contains("Missing required property 'kind'"));
+        assert!(!validator.contains("does not match any variant"));
+    }
+
+    #[test]
+    fn compiles_patterns_once() {
+        let client = generate(true);
+        assert_eq!(client.matches("new RegExp(").count(), 1);
+        assert!(client.contains("const compiledPatterns = new Map<string, RegExp>();"));
+    }
+
+    #[test]
+    fn translates_rust_pattern_syntax() {
+        assert_eq!(
+            js_pattern(r"\A(?P<year>\d{4})-(?:\d{2})\z").unwrap(),
+            r"^(?<year>\d{4})-(?:\d{2})$"
+        );
+        assert_eq!(js_pattern(r"^\pL[\p{N}_]*$").unwrap(), r"^\p{L}[\p{N}_]*$");
+        assert_eq!(js_pattern(r"^[]a-z]+$").unwrap(), r"^[\]a-z]+$");
+        assert_eq!(js_pattern(r"^[^]a]$").unwrap(), r"^[^\]a]$");
+
+        for (pattern, error) in [
+            ("(?i)^[a-z]+$", "inline flags"),
+            ("^[[:alpha:]]+$", "POSIX character classes"),
+            ("^[a-z[0-9]]+$", "nested character classes"),
+            (r"^[\w&&\d]+$", "character class set operations"),
+        ] {
+            let message = js_pattern(pattern).unwrap_err().to_string();
+            assert!(message.contains(error), "{}: {}", pattern, message);
+        }
+    }
+
+    #[test]
+    fn rejects_untranslatable_patterns_only_with_validators() {
+        let error = generate_for::<Host>(true).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Pattern '(?i)^[a-z]+$' uses inline flags, which JavaScript regular expressions don't support"
+        );
+        assert!(generate_for::<Host>(false).is_ok());
+    }
+
+    #[test]
+    fn client_validates_bodies_and_responses_when_enabled() {
+        let client = generate(true);
+        assert!(client.contains("  validate?: boolean;"));
+        assert!(client.contains("this.validate = config.validate ?? false;"));
+        let create = function(&client, "  async create(");
+        let body_check = create
+            .find("assertValid(issues, 'Invalid request body');")
+            .unwrap();
+        let send = create.find("await this.send({").unwrap();
+        let response_check = create
+            .find("assertValid(issues, 'Invalid response');")
+            .unwrap();
+        assert!(body_check < send && send < response_check);
+        assert!(create.contains("issues.push(...validateMachine(body, ``));"));
+        assert!(create.contains("issues.push(...validateMachine(result, ``));"));
+
+        let unvalidated = generate(false);
+        assert!(!unvalidated.contains("validate"));
+        assert!(!unvalidated.contains("ValidationIssue"));
+    }
+}
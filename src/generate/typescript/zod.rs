@@ -0,0 +1,530 @@
+use crate::aat::*;
+use crate::generate::writer::CodeWriter;
+use anyhow::Result;
+
+use super::TypeScriptOptions;
+use super::symbols::Symbols;
+use super::utils::*;
+
+pub fn generate_schema(
+    writer: &mut CodeWriter,
+    named_type: &NamedType,
+    options: &TypeScriptOptions,
+    symbols: &mut Symbols,
+) -> Result<()> {
+    symbols.exports_value(&format!("{}Schema", get_type_name(named_type)));
+    match named_type {
+        NamedType::Object(obj) => {
+            let fields = object_fields(&obj.name, &obj.fields, options, symbols)?;
+            writer.block(
+                &format!("export const {}Schema = z.object({{", obj.name),
+                "});",
+                |w| {
+                    for field in fields {
+                        w.line(&format!("{},", field));
+                    }
+                },
+            );
+        }
+        NamedType::Union(union) => {
+            let variants = union_variants(union, options, symbols)?;
+            if variants.len() == 1 {
+                writer.line(&format!(
+                    "export const {}Schema = {};",
+                    union.name, variants[0]
+                ));
+            } else {
+                let open = match &union.discriminator {
+                    Some(discriminator) if every_variant_tagged(union, discriminator, options) => {
+                        format!(
+                            "export const {}Schema = z.discriminatedUnion({}, [",
+                            union.name,
+                            json_string(&options.naming.field_name(&discriminator.property_name))
+                        )
+                    }
+                    // `z.discriminatedUnion` throws when a variant has no tag
+                    _ => format!("export const {}Schema = z.union([", union.name),
+                };
+                writer.block(&open, "]);", |w| {
+                    for variant in variants {
+                        w.line(&format!("{},", variant));
+                    }
+                });
+            }
+        }
+        NamedType::Enum(enum_type) => {
+            let values: Vec<&LiteralType> = enum_type.variants.iter().map(|v| &v.value).collect();
+            let all_strings = values.iter().all(|v| matches!(v, LiteralType::String(_)));
+            let expr = if values.len() == 1 {
+                format!("z.literal({})", literal_to_ts(values[0]))
+            } else if all_strings {
+                let items: Vec<String> = values.iter().map(|v| literal_to_ts(v)).collect();
+                format!("z.enum([{}])", items.join(", "))
+            } else {
+                let items: Vec<String> = values
+                    .iter()
+                    .map(|v| format!("z.literal({})", literal_to_ts(v)))
+                    .collect();
+                format!("z.union([{}])", items.join(", "))
+            };
+            writer.line(&format!(
+                "export const {}Schema = {};",
+                enum_type.name, expr
+            ));
+        }
+    }
+    Ok(())
+}
+
+pub fn generate_inferred_type(
+    writer: &mut CodeWriter,
+    named_type: &NamedType,
+    symbols: &mut Symbols,
+) -> Result<()> {
+    let name = get_type_name(named_type);
+    symbols.exports_type(name);
+    writer.line(&format!(
+        "export type {} = z.infer<typeof {}Schema>;",
+        name, name
+    ));
+    Ok(())
+}
+
+/// `key: schema` entries for an object, using the camelCase names of the generated interfaces
+fn object_fields(
+    type_name: &str,
+    fields: &[Field],
+    options: &TypeScriptOptions,
+    symbols: &mut Symbols,
+) -> Result<Vec<String>> {
+    let mut seen = std::collections::HashSet::new();
+    let mut entries = Vec::new();
+    for field in fields {
+        let name = options.naming.field_name(&field.name);
+        if !seen.insert(name.clone()) {
+            anyhow::bail!(
+                "Duplicate field name '{}' in type '{}' after camelCase conversion (original: '{}')",
+                name,
+                type_name,
+                field.name
+            );
+        }
+        entries.push(format!(
+            "{}: {}",
+            quote_if_needed(&name),
+            field_type_to_zod(&field.r#type, field.constraints.as_ref(), options, symbols)
+        ));
+    }
+    Ok(entries)
+}
+
+/// Whether every variant is an object with a literal for the discriminator property, as
+/// `z.discriminatedUnion` requires
+fn every_variant_tagged(
+    union: &UnionType,
+    discriminator: &Discriminator,
+    options: &TypeScriptOptions,
+) -> bool {
+    let tag_name = options.naming.field_name(&discriminator.property_name);
+    union.variants.iter().all(|variant| match &*variant.mode {
+        UnionTypeVariantMode::Object(obj) => {
+            variant.name.is_some()
+                || obj.fields.iter().any(|f| {
+                    options.naming.field_name(&f.name) == tag_name
+                        && matches!(&*f.r#type, FieldType::Literal(_))
+                })
+        }
+        UnionTypeVariantMode::Literal(_) => false,
+    })
+}
+
+fn union_variants(
+    union: &UnionType,
+    options: &TypeScriptOptions,
+    symbols: &mut Symbols,
+) -> Result<Vec<String>> {
+    let mut variants = Vec::new();
+    for variant in &union.variants {
+        match &*variant.mode {
+            UnionTypeVariantMode::Literal(lit) => {
+                variants.push(format!("z.literal({})", literal_to_ts(lit)));
+            }
+            UnionTypeVariantMode::Object(obj) => {
+                let variant_name = variant.name.as_deref();
+                let expr = match (&union.discriminator, variant_name) {
+                    (Some(discriminator), name) => {
+                        // Flat object tagged with the discriminator property
+                        let tag_name = options.naming.field_name(&discriminator.property_name);
+                        let mut fields = object_fields(&union.name, &obj.fields, options, symbols)?;
+                        let has_tag = obj
+                            .fields
+                            .iter()
+                            .any(|f| options.naming.field_name(&f.name) == tag_name);
+                        if !has_tag && let Some(name) = name {
+                            fields.insert(
+                                0,
+                                format!(
+                                    "{}: z.literal({})",
+                                    quote_if_needed(&tag_name),
+                                    json_string(&discriminator.tag_for(name))
+                                ),
+                            );
+                        }
+                        format!("z.object({{ {} }})", fields.join(", "))
+                    }
+                    (None, Some(name))
+                        if obj.fields.len() == 1 && is_reference_type(&obj.fields[0].r#type) =>
+                    {
+                        format!(
+                            "z.object({{ {}: {} }})",
+                            quote_if_needed(name),
+                            field_type_to_zod(
+                                &obj.fields[0].r#type,
+                                obj.fields[0].constraints.as_ref(),
+                                options,
+                                symbols
+                            )
+                        )
+                    }
+                    (None, Some(name)) if obj.fields.len() == 1 && obj.fields[0].name == name => {
+                        let fields = object_fields(&union.name, &obj.fields, options, symbols)?;
+                        format!("z.object({{ {} }})", fields.join(", "))
+                    }
+                    (None, Some(name)) => {
+                        let fields = object_fields(&union.name, &obj.fields, options, symbols)?;
+                        format!(
+                            "z.object({{ {}: z.object({{ {} }}) }})",
+                            quote_if_needed(name),
+                            fields.join(", ")
+                        )
+                    }
+                    (None, None) => {
+                        let fields = object_fields(&union.name, &obj.fields, options, symbols)?;
+                        format!("z.object({{ {} }})", fields.join(", "))
+                    }
+                };
+                variants.push(expr);
+            }
+        }
+    }
+    Ok(variants)
+}
+
+/// Convert a field type (and the constraints attached to its field) to a Zod schema expression
+pub fn field_type_to_zod(
+    field_type: &FieldType,
+    constraints: Option<&Constraints>,
+    options: &TypeScriptOptions,
+    symbols: &mut Symbols,
+) -> String {
+    match field_type {
+        FieldType::Primitive(prim) => primitive_to_zod(prim, constraints, options),
+        FieldType::Literal(lit) => format!("z.literal({})", literal_to_ts(lit)),
+        // `Option` fields are serialized as `null`, or left out with `skip_serializing_if`
+        FieldType::Optional(inner) => format!(
+            "{}.nullish()",
+            field_type_to_zod(inner, constraints, options, symbols)
+        ),
+        FieldType::List(inner) => {
+            let mut expr = format!(
+                "z.array({})",
+                field_type_to_zod(inner, None, options, symbols)
+            );
+            if let Some(c) = constraints {
+                if let Some(min) = c.min_items {
+                    expr.push_str(&format!(".min({})", min));
+                }
+                if let Some(max) = c.max_items {
+                    expr.push_str(&format!(".max({})", max));
+                }
+                if c.unique_items == Some(true) {
+                    expr.push_str(
+                        ".refine(items => new Set(items.map(item => JSON.stringify(item))).size === items.length, { message: 'Items must be unique' })",
+                    );
+                }
+            }
+            expr
+        }
+        FieldType::Map(inner) => format!(
+            "z.record(z.string(), {})",
+            field_type_to_zod(inner, None, options, symbols)
+        ),
+        FieldType::Stream(inner) => field_type_to_zod(inner, constraints, options, symbols),
+        FieldType::Reference(name) => {
+            symbols.uses(&format!("{}Schema", name));
+            format!("z.lazy(() => {}Schema)", name)
+        }
+        FieldType::Intersection(types) => {
+            let mut schemas = types
+                .iter()
+                .map(|t| field_type_to_zod(t, None, options, symbols));
+            let first = schemas.next().unwrap_or_else(|| "z.any()".to_string());
+            schemas.fold(first, |acc, schema| {
+                format!("z.intersection({}, {})", acc, schema)
+            })
+        }
+        FieldType::Tuple(types) => {
+            let items: Vec<String> = types
+                .iter()
+                .map(|t| field_type_to_zod(t, None, options, symbols))
+                .collect();
+            format!("z.tuple([{}])", items.join(", "))
+        }
+        FieldType::Any => "z.any()".to_string(),
+    }
+}
+
+fn primitive_to_zod(
+    prim: &PrimitiveType,
+    constraints: Option<&Constraints>,
+    options: &TypeScriptOptions,
+) -> String {
+    match prim {
+        PrimitiveType::Bool => "z.boolean()".to_string(),
+        PrimitiveType::Int(_) if options.uses_bigint(prim) => {
+            format!("z.bigint(){}", number_constraints(constraints, true))
+        }
+        PrimitiveType::Int(_) => {
+            format!("z.number().int(){}", number_constraints(constraints, false))
+        }
+        PrimitiveType::Float => format!("z.number(){}", number_constraints(constraints, false)),
+        PrimitiveType::String(_) if options.uses_date(prim) => "z.date()".to_string(),
+        PrimitiveType::String(format) => {
+            let mut expr = "z.string()".to_string();
+            match format {
+                Some(StringFormat::DateTime) => expr.push_str(".datetime({ offset: true })"),
+                Some(StringFormat::Date) => expr.push_str(".date()"),
+                Some(StringFormat::Time) => expr.push_str(".time()"),
+                Some(StringFormat::Uuid) => expr.push_str(".uuid()"),
+                Some(StringFormat::Email) => expr.push_str(".email()"),
+                Some(StringFormat::Uri) => expr.push_str(".url()"),
+                // Zod has no portable check for these
+                Some(StringFormat::Hostname | StringFormat::Ipv4 | StringFormat::Ipv6) | None => {}
+            }
+            if let Some(c) = constraints {
+                if let Some(min) = c.min_length {
+                    expr.push_str(&format!(".min({})", min));
+                }
+                if let Some(max) = c.max_length {
+                    expr.push_str(&format!(".max({})", max));
+                }
+                if let Some(pattern) = &c.pattern {
+                    expr.push_str(&format!(
+                        ".regex(new RegExp({}, 'u'))",
+                        json_string(pattern)
+                    ));
+                }
+            }
+            expr
+        }
+    }
+}
+
+fn number_constraints(constraints: Option<&Constraints>, bigint: bool) -> String {
+    let Some(c) = constraints else {
+        return String::new();
+    };
+    // `bigint` checks take `bigint` literals, so bounds are rounded inwards
+    let bound = |value: f64, rounded: f64| {
+        if bigint {
+            format!("{}n", rounded as i128)
+        } else {
+            value.to_string()
+        }
+    };
+    let mut expr = String::new();
+    if let Some(min) = c.minimum {
+        expr.push_str(&format!(".min({})", bound(min, min.ceil())));
+    }
+    if let Some(max) = c.maximum {
+        expr.push_str(&format!(".max({})", bound(max, max.floor())));
+    }
+    if let Some(min) = c.exclusive_minimum {
+        expr.push_str(&format!(".gt({})", bound(min, min.floor())));
+    }
+    if let Some(max) = c.exclusive_maximum {
+        expr.push_str(&format!(".lt({})", bound(max, max.ceil())));
+    }
+    if let Some(multiple) = c.multiple_of
+        && (!bigint || multiple.fract() == 0.0)
+    {
+        expr.push_str(&format!(".multipleOf({})", bound(multiple, multiple)));
+    }
+    expr
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::returning;
+    use crate::generate::typescript::{TypeScriptGenerator, ZodOutput};
+    use crate::spec::Type;
+    use schemars::JsonSchema;
+
+    #[derive(JsonSchema)]
+    #[allow(dead_code)]
+    struct Machine {
+        #[schemars(length(min = 1, max = 63), regex(pattern = "^[a-z]+$"))]
+        name: String,
+        #[schemars(range(min = 1, max = 64))]
+        cpus: u8,
+        #[schemars(range(max = 1.5e9))]
+        memory: u64,
+        tags: Option<Vec<String>>,
+        state: State,
+    }
+
+    #[derive(JsonSchema)]
+    #[allow(dead_code)]
+    #[serde(rename_all = "snake_case")]
+    enum State {
+        Running { since: u64 },
+        Stopped,
+    }
+
+    fn generate(options: TypeScriptOptions) -> String {
+        let aat = returning(Type::schema::<Machine>());
+        TypeScriptGenerator::generate_with_options(&aat, &options).unwrap()
+    }
+
+    fn zod(zod: ZodOutput) -> String {
+        generate(TypeScriptOptions {
+            zod,
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn schemas_carry_field_constraints() {
+        let client = zod(ZodOutput::Alongside);
+        assert!(client.contains("import { z } from 'zod';"));
+        for line in [
+            "export const MachineSchema = z.object({",
+            "  cpus: z.number().int().min(1).max(64),",
+            "  memory: z.number().int().min(0).max(1500000000),",
+            "  name: z.string().min(1).max(63).regex(new RegExp(\"^[a-z]+$\", 'u')),",
+            "  state: z.lazy(() => StateSchema),",
+            "  tags: z.array(z.string()).nullish(),",
+        ] {
+            assert!(client.contains(line), "missing {}", line);
+        }
+    }
+
+    #[test]
+    fn unions_become_zod_unions() {
+        let client = zod(ZodOutput::Alongside);
+        assert!(client.contains(
+            "export const StateSchema = z.union([\n  \
+             z.literal(\"stopped\"),\n  \
+             z.object({ running: z.object({ since: z.number().int().min(0) }) }),\n]);"
+        ));
+    }
+
+    #[test]
+    fn alongside_keeps_the_interfaces() {
+        let client = zod(ZodOutput::Alongside);
+        assert!(client.contains("export interface Machine {"));
+        assert!(client.contains("export type State =\n"));
+        assert!(!client.contains("z.infer"));
+    }
+
+    #[test]
+    fn instead_infers_the_types_from_the_schemas() {
+        let client = zod(ZodOutput::Instead);
+        assert!(client.contains("]);\nexport type State = z.infer<typeof StateSchema>;"));
+        assert!(client.contains("});\nexport type Machine = z.infer<typeof MachineSchema>;"));
+        assert!(!client.contains("export interface Machine"));
+    }
+
+    #[test]
+    fn no_schemas_by_default() {
+        let client = zod(ZodOutput::None);
+        assert!(!client.contains("zod"));
+        assert!(!client.contains("Schema"));
+    }
+
+    #[test]
+    fn bigint_bounds_are_bigint_literals() {
+        let client = generate(TypeScriptOptions {
+            zod: ZodOutput::Alongside,
+            bigints: true,
+            ..Default::default()
+        });
+        assert!(client.contains("  memory: z.bigint().min(0n).max(1500000000n),"));
+    }
+
+    fn shape(variant_names: [Option<&str>; 2]) -> String {
+        let variant = |name: Option<&str>, field_name: &str| UnionTypeVariant {
+            name: name.map(str::to_string),
+            mode: Box::new(UnionTypeVariantMode::Object(ObjectType {
+                name: field_name.to_string(),
+                fields: vec![Field {
+                    name: field_name.to_string(),
+                    r#type: Box::new(FieldType::Primitive(PrimitiveType::Float)),
+                    constraints: None,
+                    examples: vec![],
+                }],
+                examples: vec![],
+                rust_path: None,
+            })),
+        };
+        let shape = NamedType::Union(UnionType {
+            name: "Shape".to_string(),
+            discriminator: Some(Discriminator {
+                property_name: "kind".to_string(),
+                mapping: None,
+            }),
+            variants: vec![
+                variant(variant_names[0], "radius"),
+                variant(variant_names[1], "side"),
+            ],
+            examples: vec![],
+            rust_path: None,
+        });
+        let mut writer = CodeWriter::new();
+        let options = TypeScriptOptions::default();
+        generate_schema(&mut writer, &shape, &options, &mut Symbols::default()).unwrap();
+        writer.into_string()
+    }
+
+    #[test]
+    fn tagged_unions_are_discriminated() {
+        let schema = shape([Some("circle"), Some("square")]);
+        assert!(schema.contains("export const ShapeSchema = z.discriminatedUnion(\"kind\", ["));
+        assert!(schema.contains("z.object({ kind: z.literal(\"circle\"), radius: z.number() }),"));
+    }
+
+    #[test]
+    fn untagged_variants_fall_back_to_a_plain_union() {
+        let schema = shape([Some("circle"), None]);
+        assert!(schema.contains("export const ShapeSchema = z.union(["));
+        assert!(!schema.contains("discriminatedUnion"));
+        assert!(schema.contains("z.object({ side: z.number() }),"));
+    }
+
+    #[test]
+    fn optional_fields_accept_null() {
+        let client = zod(ZodOutput::Instead);
+        assert!(client.contains("  tags: z.array(z.string()).nullish(),"));
+    }
+
+    #[test]
+    fn patterns_are_in_javascript_syntax() {
+        #[derive(JsonSchema)]
+        #[allow(dead_code)]
+        struct Date {
+            #[schemars(regex(pattern = r"\A(?P<year>\d{4})\z"))]
+            year: String,
+        }
+
+        let aat = returning(Type::schema::<Date>());
+        let options = TypeScriptOptions {
+            zod: ZodOutput::Alongside,
+            ..Default::default()
+        };
+        let client = TypeScriptGenerator::generate_with_options(&aat, &options).unwrap();
+        assert!(
+            client.contains(r#"year: z.string().regex(new RegExp("^(?<year>\\d{4})$", 'u')),"#)
+        );
+    }
+}
@@ -0,0 +1,3 @@
+//! Importers that build an AAT from other API description formats
+
+pub mod openapi;
@@ -0,0 +1,418 @@
+//! OpenAPI 3.x import
+//!
+//! Component schemas become named types. Every operation becomes an endpoint of the service
+//! named after its first tag (or `default`), with path and query parameters, header parameters,
+//! the JSON request body and the first successful JSON response.
+
+use crate::aat::*;
+use crate::case::{to_pascal_case, to_snake_case};
+use anyhow::{Result, anyhow, bail};
+use schemars::Schema;
+use serde_json::{Map, Value, json};
+
+const METHODS: [(&str, HttpMethod); 5] = [
+    ("get", HttpMethod::Get),
+    ("post", HttpMethod::Post),
+    ("put", HttpMethod::Put),
+    ("delete", HttpMethod::Delete),
+    ("patch", HttpMethod::Patch),
+];
+
+/// Builds an AAT from an OpenAPI 3.x document
+pub fn from_openapi(document: &Value) -> Result<AAT> {
+    let version = document
+        .get("openapi")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| anyhow!("Not an OpenAPI document: missing 'openapi' version"))?;
+    if !version.starts_with("3.") {
+        bail!("Unsupported OpenAPI version '{}', expected 3.x", version);
+    }
+
+    // Component schemas that are plain aliases (`type: string`, arrays, ...) have no named
+    // counterpart in the AAT, so references to them are replaced by the schema itself
+    let components = document
+        .pointer("/components/schemas")
+        .and_then(|v| v.as_object())
+        .cloned()
+        .unwrap_or_default();
+    let aliases: Map<String, Value> = components
+        .iter()
+        .filter(|(_, schema)| is_alias(schema))
+        .map(|(name, schema)| (name.clone(), schema.clone()))
+        .collect();
+    let mut document = document.clone();
+    inline_aliases(&mut document, &aliases, 0)?;
+
+    let mut aat = AAT::new();
+
+    if let Some(Value::Object(schemas)) = document.pointer("/components/schemas") {
+        for (name, schema) in schemas {
+            if aliases.contains_key(name) {
+                continue;
+            }
+            aat.append_types_from_schema(&to_schema(schema)?, name)?;
+        }
+    }
+
+    if let Some(Value::Object(paths)) = document.get("paths") {
+        for (path, item) in paths {
+            let shared_parameters = item.get("parameters").and_then(|v| v.as_array());
+            for (method_name, method) in &METHODS {
+                let Some(operation) = item.get(*method_name) else {
+                    continue;
+                };
+                let service_name = operation
+                    .pointer("/tags/0")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("default")
+                    .to_string();
+                let mut parameters = Vec::new();
+                for parameter in shared_parameters.into_iter().flatten() {
+                    parameters.push(resolve(&document, parameter)?);
+                }
+                for parameter in operation
+                    .get("parameters")
+                    .and_then(|v| v.as_array())
+                    .into_iter()
+                    .flatten()
+                {
+                    let parameter = resolve(&document, parameter)?;
+                    // Operation parameters override path-level ones with the same name and location
+                    parameters.retain(|p: &Value| {
+                        p.get("name") != parameter.get("name") || p.get("in") != parameter.get("in")
+                    });
+                    parameters.push(parameter);
+                }
+
+                let endpoint = import_operation(
+                    &mut aat,
+                    &document,
+                    path,
+                    method_name,
+                    method.clone(),
+                    operation,
+                    &parameters,
+                )
+                .map_err(|e| anyhow!("{} {}: {}", method_name.to_uppercase(), path, e))?;
+
+                match aat.services.iter_mut().find(|s| s.name == service_name) {
+                    Some(service) => service.endpoints.push(endpoint),
+                    None => aat.services.push(Service {
+                        name: service_name,
+                        endpoints: vec![endpoint],
+                        headers: vec![],
+                    }),
+                }
+            }
+        }
+    }
+
+    aat.sort();
+    Ok(aat)
+}
+
+fn import_operation(
+    aat: &mut AAT,
+    document: &Value,
+    path: &str,
+    method_name: &str,
+    method: HttpMethod,
+    operation: &Value,
+    parameters: &[Value],
+) -> Result<Endpoint> {
+    let name = match operation.get("operationId").and_then(|v| v.as_str()) {
+        Some(id) => to_snake_case(id),
+        None => {
+            let mut parts = vec![method_name.to_string()];
+            parts.extend(
+                path.split('/')
+                    .filter(|s| !s.is_empty())
+                    .map(|s| s.trim_start_matches('{').trim_end_matches('}').to_string()),
+            );
+            to_snake_case(&parts.join("_"))
+        }
+    };
+    let type_prefix = to_pascal_case(&name);
+
+    let mut segments = Vec::new();
+    for segment in path.split('/').filter(|s| !s.is_empty()) {
+        match segment.strip_prefix('{').and_then(|s| s.strip_suffix('}')) {
+            Some(param_name) => {
+                let parameter = parameters_in(parameters, "path")
+                    .find(|p| p.get("name").and_then(|v| v.as_str()) == Some(param_name));
+                let r#type = match parameter.and_then(|p| p.get("schema")) {
+                    Some(schema) => aat.add_schema(
+                        &to_schema(schema)?,
+                        &format!("{}{}", type_prefix, to_pascal_case(param_name)),
+                    )?,
+                    None => FieldType::Primitive(PrimitiveType::String(None)),
+                };
+                segments.push(PathSegment::Parameter {
+                    name: param_name.to_string(),
+                    r#type,
+                });
+            }
+            None => segments.push(PathSegment::Literal(segment.to_string())),
+        }
+    }
+
+    // Query parameters are gathered into one object type, as the DSL does with `.query()`
+    let mut properties = Map::new();
+    let mut required = Vec::new();
+    for parameter in parameters_in(parameters, "query") {
+        let param_name = parameter_name(parameter)?;
+        properties.insert(
+            param_name.to_string(),
+            parameter
+                .get("schema")
+                .cloned()
+                .unwrap_or_else(|| json!({ "type": "string" })),
+        );
+        if parameter.get("required").and_then(|v| v.as_bool()) == Some(true) {
+            required.push(Value::String(param_name.to_string()));
+        }
+    }
+    let query = if properties.is_empty() {
+        None
+    } else {
+        let schema = json!({
+            "title": format!("{}Query", type_prefix),
+            "type": "object",
+            "properties": properties,
+            "required": required,
+        });
+        Some(aat.add_schema(&to_schema(&schema)?, &format!("{}Query", type_prefix))?)
+    };
+
+    let mut headers = Vec::new();
+    for parameter in parameters_in(parameters, "header") {
+        let header_name = parameter_name(parameter)?;
+        let mut field_type = match parameter.get("schema") {
+            Some(schema) => aat.add_schema(
+                &to_schema(schema)?,
+                &format!("{}{}", type_prefix, to_pascal_case(header_name)),
+            )?,
+            None => FieldType::Primitive(PrimitiveType::String(None)),
+        };
+        if parameter.get("required").and_then(|v| v.as_bool()) != Some(true) {
+            field_type = FieldType::Optional(Box::new(field_type));
+        }
+        headers.push(Header {
+            name: header_name.to_string(),
+            value: HeaderValue::Parameter {
+                name: header_name
+                    .to_lowercase()
+                    .replace(|c: char| !c.is_ascii_alphanumeric(), "_"),
+                field_type,
+            },
+        });
+    }
+
+    let body = match operation.get("requestBody") {
+        Some(request_body) => {
+            let request_body = resolve(document, request_body)?;
+            match json_schema(&request_body) {
+                Some(schema) => {
+                    Some(aat.add_schema(&to_schema(schema)?, &format!("{}Body", type_prefix))?)
+                }
+                None => None,
+            }
+        }
+        None => None,
+    };
+
+    let mut response = FieldType::Any;
+    if let Some(Value::Object(responses)) = operation.get("responses") {
+        let mut statuses: Vec<&String> = responses
+            .keys()
+            .filter(|status| status.starts_with('2'))
+            .collect();
+        statuses.sort();
+        if let Some(status) = statuses.first() {
+            let resolved = resolve(document, &responses[status.as_str()])?;
+            if let Some(schema) = json_schema(&resolved) {
+                response =
+                    aat.add_schema(&to_schema(schema)?, &format!("{}Response", type_prefix))?;
+            }
+        }
+    }
+
+    Ok(Endpoint {
+        name,
+        method,
+        path: segments,
+        query,
+        body,
+        response,
+        upgrade: None,
+        ws_auth: None,
+        invalidates: vec![],
+        headers,
+    })
+}
+
+/// Whether a component schema maps to an inline field type rather than a named type
+fn is_alias(schema: &Value) -> bool {
+    match schema {
+        Value::Object(obj) => {
+            !(obj.contains_key("properties")
+                || obj.contains_key("oneOf")
+                || obj.contains_key("anyOf")
+                || obj.contains_key("enum")
+                || obj.get("type").and_then(|v| v.as_str()) == Some("object"))
+        }
+        _ => true,
+    }
+}
+
+fn inline_aliases(value: &mut Value, aliases: &Map<String, Value>, depth: usize) -> Result<()> {
+    if depth > 64 {
+        bail!("Recursive schema alias");
+    }
+    match value {
+        Value::Object(obj) => {
+            let alias = obj
+                .get("$ref")
+                .and_then(|v| v.as_str())
+                .and_then(|r| r.strip_prefix("#/components/schemas/"))
+                .and_then(|name| aliases.get(name));
+            if let Some(alias) = alias {
+                *value = alias.clone();
+                return inline_aliases(value, aliases, depth + 1);
+            }
+            for child in obj.values_mut() {
+                inline_aliases(child, aliases, depth)?;
+            }
+        }
+        Value::Array(items) => {
+            for item in items {
+                inline_aliases(item, aliases, depth)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Follows a local `$ref` (`#/components/parameters/...`, `#/components/responses/...`)
+fn resolve(document: &Value, value: &Value) -> Result<Value> {
+    match value.get("$ref").and_then(|v| v.as_str()) {
+        Some(reference) => {
+            let pointer = reference
+                .strip_prefix('#')
+                .ok_or_else(|| anyhow!("Only local references are supported: {}", reference))?;
+            let target = document
+                .pointer(pointer)
+                .ok_or_else(|| anyhow!("Unresolved reference: {}", reference))?;
+            resolve(document, target)
+        }
+        None => Ok(value.clone()),
+    }
+}
+
+/// The `application/json` schema of a request body or response
+fn json_schema(value: &Value) -> Option<&Value> {
+    let content = value.get("content")?.as_object()?;
+    content
+        .get("application/json")
+        .or_else(|| {
+            content
+                .iter()
+                .find(|(media, _)| media.ends_with("+json"))
+                .map(|(_, v)| v)
+        })?
+        .get("schema")
+}
+
+fn parameters_in<'a>(
+    parameters: &'a [Value],
+    location: &'a str,
+) -> impl Iterator<Item = &'a Value> {
+    parameters
+        .iter()
+        .filter(move |p| p.get("in").and_then(|v| v.as_str()) == Some(location))
+}
+
+fn parameter_name(parameter: &Value) -> Result<&str> {
+    parameter
+        .get("name")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| anyhow!("Parameter is missing a name"))
+}
+
+fn to_schema(value: &Value) -> Result<Schema> {
+    Schema::try_from(value.clone()).map_err(|_| anyhow!("Invalid schema: {}", value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aat::diff::diff;
+
+    fn document() -> Value {
+        json!({
+            "openapi": "3.0.3",
+            "paths": {
+                "/pets/{id}": {
+                    "parameters": [
+                        { "name": "id", "in": "path", "required": true, "schema": { "type": "string" } }
+                    ],
+                    "get": {
+                        "operationId": "getPet",
+                        "tags": ["pets"],
+                        "parameters": [
+                            { "name": "verbose", "in": "query", "schema": { "type": "boolean" } },
+                            { "name": "X-Request-Id", "in": "header", "required": true, "schema": { "type": "string" } }
+                        ],
+                        "responses": {
+                            "200": {
+                                "content": {
+                                    "application/json": { "schema": { "$ref": "#/components/schemas/Pet" } }
+                                }
+                            }
+                        }
+                    }
+                }
+            },
+            "components": {
+                "schemas": {
+                    "PetId": { "type": "string", "format": "uuid" },
+                    "Pet": {
+                        "type": "object",
+                        "properties": {
+                            "id": { "$ref": "#/components/schemas/PetId" },
+                            "kind": { "type": "string", "enum": ["cat", "dog"] }
+                        },
+                        "required": ["id", "kind"]
+                    }
+                }
+            }
+        })
+    }
+
+    #[test]
+    fn imports_operations_and_components() {
+        let aat = from_openapi(&document()).unwrap();
+        aat.validate().unwrap();
+
+        let endpoint = &aat.services[0].endpoints[0];
+        assert_eq!(aat.services[0].name, "pets");
+        assert_eq!(endpoint.name, "get_pet");
+        assert!(matches!(&endpoint.response, FieldType::Reference(name) if name == "Pet"));
+        assert!(
+            matches!(&endpoint.query, Some(FieldType::Reference(name)) if name == "GetPetQuery")
+        );
+        assert_eq!(endpoint.headers[0].name, "X-Request-Id");
+
+        // The `PetId` alias is inlined rather than becoming a named type
+        assert!(aat.find_type("PetId").is_none());
+    }
+
+    #[test]
+    fn imported_aat_survives_a_json_round_trip() {
+        let aat = from_openapi(&document()).unwrap();
+        let reread = AAT::from_json(&aat.to_json().unwrap()).unwrap();
+        assert!(diff(&aat, &reread).is_empty());
+        assert_eq!(aat.to_json().unwrap(), reread.to_json().unwrap());
+    }
+}
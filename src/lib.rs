@@ -1,8 +1,15 @@
-pub use damascus_meta::{header_value, path};
+pub use damascus_meta::{endpoint, header_value, path, rust_path};
+#[doc(hidden)]
+pub use inventory;
 pub use schemars::JsonSchema;
 
+mod case;
+#[cfg(test)]
+mod fixtures;
 mod meta;
 
 pub mod aat;
+pub mod build;
 pub mod generate;
+pub mod import;
 pub mod spec;
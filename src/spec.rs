@@ -2,6 +2,9 @@ use std::collections::BTreeMap;
 
 use schemars::{JsonSchema, Schema, schema_for};
 
+/// Schema keyword holding the Rust path of the type a schema was generated from
+pub(crate) const RUST_PATH_KEY: &str = "x-rust-path";
+
 #[derive(Debug, Clone)]
 pub struct Spec {
     name: String,
@@ -11,7 +14,9 @@ pub struct Spec {
     docs: Option<String>,
     description: Option<String>,
     headers: BTreeMap<String, HeaderValue>,
+    ws_auth: Option<WsAuth>,
     services: BTreeMap<String, Service>,
+    type_naming: TypeNaming,
 }
 
 impl Spec {
@@ -26,7 +31,9 @@ impl Spec {
             docs: None,
             description: None,
             headers: BTreeMap::new(),
+            ws_auth: None,
             services: BTreeMap::new(),
+            type_naming: TypeNaming::default(),
         }
     }
 
@@ -43,6 +50,27 @@ impl Spec {
         self
     }
 
+    pub fn ws_auth_strategy(&self) -> Option<&WsAuth> {
+        self.ws_auth.as_ref()
+    }
+
+    /// Default [`WsAuth`] for WebSocket endpoints that don't declare their own
+    pub fn ws_auth(mut self, ws_auth: WsAuth) -> Self {
+        self.ws_auth = Some(ws_auth);
+        self
+    }
+
+    pub fn type_naming_rules(&self) -> &TypeNaming {
+        &self.type_naming
+    }
+
+    /// How types are named when converting to an AAT, and what happens when two different types
+    /// end up with the same name
+    pub fn type_naming(mut self, type_naming: TypeNaming) -> Self {
+        self.type_naming = type_naming;
+        self
+    }
+
     pub fn organization(mut self, organization: impl AsRef<str>) -> Self {
         self.organization = Some(organization.as_ref().to_string());
         self
@@ -82,6 +110,125 @@ impl Spec {
         self.services.insert(name.as_ref().to_string(), service);
         self
     }
+
+    /// Adds every endpoint declared with `#[damascus::endpoint]` in the program. Endpoints join
+    /// the service named after their module unless they name one, in source order.
+    pub fn registered_endpoints(mut self) -> Self {
+        let mut registrations = inventory::iter::<Registration>().collect::<Vec<_>>();
+        registrations.sort_by_key(|registration| (registration.file, registration.line));
+        for registration in registrations {
+            let name = registration.service.unwrap_or_else(|| {
+                registration
+                    .module
+                    .rsplit("::")
+                    .next()
+                    .unwrap_or(registration.module)
+            });
+            let endpoint = (registration.endpoint)();
+            self = self.service(name, |mut service| {
+                service.endpoints.push(endpoint);
+                service
+            });
+        }
+        self
+    }
+}
+
+/// An endpoint declared with `#[damascus::endpoint]`, see [`Spec::registered_endpoints`]
+#[doc(hidden)]
+pub struct Registration {
+    pub module: &'static str,
+    pub service: Option<&'static str>,
+    pub file: &'static str,
+    pub line: u32,
+    pub endpoint: fn() -> Endpoint,
+}
+
+inventory::collect!(Registration);
+
+/// Rules for naming the types of a spec, see [`Spec::type_naming`]
+#[derive(Debug, Clone, Default)]
+pub struct TypeNaming {
+    collisions: CollisionStrategy,
+    renames: Vec<TypeRename>,
+}
+
+#[derive(Debug, Clone)]
+enum TypeRename {
+    Exact { from: String, to: String },
+    With(fn(&str, Option<&str>) -> Option<String>),
+}
+
+/// What to do when two structurally different types have the same name
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CollisionStrategy {
+    /// Fail the conversion
+    #[default]
+    Error,
+    /// Prefix the name with the type's parent module, `v2::Machine` becoming `V2Machine`. Fails
+    /// for types without a known Rust path.
+    QualifyWithModule,
+    /// Prefix the name with the service that uses it, e.g. `BillingMachine`
+    QualifyWithService,
+    /// Append a number, e.g. `Machine2`
+    Suffix,
+}
+
+impl TypeNaming {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn on_collision(mut self, strategy: CollisionStrategy) -> Self {
+        self.collisions = strategy;
+        self
+    }
+
+    /// Names a type `to` instead. `from` is a schema name (`Machine`) or the end of a Rust path
+    /// (`v2::Machine`, `my_crate::v2::Machine`).
+    pub fn rename(mut self, from: impl AsRef<str>, to: impl AsRef<str>) -> Self {
+        self.renames.push(TypeRename::Exact {
+            from: from.as_ref().to_string(),
+            to: to.as_ref().to_string(),
+        });
+        self
+    }
+
+    /// Renames types with a function of their schema name and Rust path, if known. Returning
+    /// `None` keeps the name.
+    pub fn rename_with(mut self, rule: fn(&str, Option<&str>) -> Option<String>) -> Self {
+        self.renames.push(TypeRename::With(rule));
+        self
+    }
+
+    pub fn collision_strategy(&self) -> CollisionStrategy {
+        self.collisions
+    }
+
+    /// The name of a type after the first matching rename rule
+    pub fn apply(&self, name: &str, rust_path: Option<&str>) -> String {
+        for rename in &self.renames {
+            match rename {
+                TypeRename::Exact { from, to } => {
+                    let matches_path = rust_path.is_some_and(|path| {
+                        path == from
+                            || path
+                                .strip_suffix(from.as_str())
+                                .is_some_and(|prefix| prefix.ends_with("::"))
+                    });
+                    if from == name || matches_path {
+                        return to.clone();
+                    }
+                }
+                TypeRename::With(rule) => {
+                    if let Some(renamed) = rule(name, rust_path) {
+                        return renamed;
+                    }
+                }
+            }
+        }
+        name.to_string()
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -187,6 +334,8 @@ pub struct Endpoint {
     body: Option<Type>,
     response: Type,
     upgrade: Option<Upgrade>,
+    ws_auth: Option<WsAuth>,
+    invalidates: Vec<String>,
     headers: BTreeMap<String, HeaderValue>,
 }
 
@@ -204,10 +353,28 @@ pub enum Upgrade {
     Ws,
 }
 
+/// How a WebSocket endpoint's headers reach the server when the client can't set them, as in
+/// browsers. Clients that support real headers, such as Node's `ws`, always send those instead.
+#[derive(Debug, Clone)]
+pub enum WsAuth {
+    /// Every header is sent as a query parameter of the same name
+    Query,
+    /// `Sec-WebSocket-Protocol` offers the given protocol, which the server must accept, followed
+    /// by the headers as a base64url-encoded JSON object
+    Protocol(String),
+    /// The first message is `{"type":"auth","headers":{...}}`
+    Message,
+}
+
 #[derive(Debug, Clone)]
 pub enum PathSegment {
     Literal(String),
-    Type { name: String, r#type: Type },
+    Type {
+        name: String,
+        r#type: Type,
+    },
+    /// Captures the rest of the path, slashes included, as a string. Only valid last.
+    Wildcard(String),
 }
 
 #[derive(Debug, Clone)]
@@ -217,13 +384,30 @@ pub enum HeaderValue {
         name: String,
         r#type: Type,
     },
+    /// A value such as `"{scheme} {token}"` with one `{name}` placeholder per parameter
     Pattern {
         pattern: String,
-        name: String,
-        r#type: Type,
+        params: Vec<HeaderParam>,
     },
 }
 
+#[derive(Debug, Clone)]
+pub struct HeaderParam {
+    pub name: String,
+    pub r#type: Type,
+    pub format: Option<HeaderFormat>,
+}
+
+/// How a header parameter is written into its placeholder
+#[derive(Debug, Clone)]
+pub enum HeaderFormat {
+    /// Standard base64 of the value's UTF-8 text
+    Base64,
+    Lowercase,
+    /// ISO 8601, for `date` and `date-time` strings such as `chrono::DateTime`
+    IsoDate,
+}
+
 impl Endpoint {
     pub fn new(name: impl AsRef<str>, method: Method, path: Vec<PathSegment>) -> Self {
         let name_str = name.as_ref();
@@ -236,6 +420,8 @@ impl Endpoint {
             body: None,
             response: Type::Void,
             upgrade: None,
+            ws_auth: None,
+            invalidates: vec![],
             headers: BTreeMap::new(),
         }
     }
@@ -268,6 +454,14 @@ impl Endpoint {
         self.upgrade.as_ref()
     }
 
+    pub fn ws_auth_strategy(&self) -> Option<&WsAuth> {
+        self.ws_auth.as_ref()
+    }
+
+    pub fn invalidations(&self) -> &[String] {
+        &self.invalidates
+    }
+
     pub fn headers(&self) -> &BTreeMap<String, HeaderValue> {
         &self.headers
     }
@@ -296,6 +490,19 @@ impl Endpoint {
         self.upgrade = Some(upgrade);
         self
     }
+
+    /// Overrides the spec's [`WsAuth`] for this endpoint
+    pub fn ws_auth(mut self, ws_auth: WsAuth) -> Self {
+        self.ws_auth = Some(ws_auth);
+        self
+    }
+
+    /// Declares that calling this endpoint makes cached results of `target` stale, where
+    /// `target` is a service (`"machines"`) or one of its endpoints (`"machines.list"`)
+    pub fn invalidates(mut self, target: impl AsRef<str>) -> Self {
+        self.invalidates.push(target.as_ref().to_string());
+        self
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -314,8 +521,19 @@ impl Type {
         Type::Void
     }
 
+    /// The schema of `T`, remembering its Rust path so colliding names can be told apart
     pub fn schema<T: JsonSchema>() -> Self {
-        let s: Schema = schema_for!(T);
+        let mut s: Schema = schema_for!(T);
+        s.insert(RUST_PATH_KEY.to_string(), T::schema_id().into());
+        Type::Schema(s)
+    }
+
+    /// The schema of `T` under another name, e.g. `Type::schema_named::<v2::Machine>("MachineV2")`
+    pub fn schema_named<T: JsonSchema>(name: impl AsRef<str>) -> Self {
+        let Type::Schema(mut s) = Self::schema::<T>() else {
+            unreachable!()
+        };
+        s.insert("title".to_string(), name.as_ref().into());
         Type::Schema(s)
     }
 
@@ -350,6 +568,53 @@ impl Type {
     pub fn wrap_optional(&self) -> Self {
         Type::Optional(Box::new(self.clone()))
     }
+
+    /// Attaches an example value to a schema type. The value is stored in the
+    /// schema's `examples` keyword, the same place schemars puts `#[schemars(example = ...)]`.
+    ///
+    /// Wrappers pass the value on to the types inside: each item of a list example goes to the
+    /// item type, a stream example is one item, and tuples are split by position or key. Values
+    /// with nowhere to go, such as `null` for an optional or any example of `Void`, are dropped.
+    pub fn example(self, value: serde_json::Value) -> Self {
+        use serde_json::Value;
+
+        match (self, value) {
+            (Type::Schema(mut schema), value) => {
+                let obj = schema.ensure_object();
+                match obj.get_mut("examples") {
+                    Some(Value::Array(examples)) => examples.push(value),
+                    _ => {
+                        obj.insert("examples".to_string(), Value::Array(vec![value]));
+                    }
+                }
+                Type::Schema(schema)
+            }
+            (Type::Optional(inner), Value::Null) => Type::Optional(inner),
+            (Type::Optional(inner), value) => Type::Optional(Box::new(inner.example(value))),
+            (Type::Stream(inner), value) => Type::Stream(Box::new(inner.example(value))),
+            (Type::List(inner), Value::Array(items)) => {
+                let inner = items.into_iter().fold(*inner, Type::example);
+                Type::List(Box::new(inner))
+            }
+            (Type::Tuple(types), Value::Array(items)) if types.len() == items.len() => Type::Tuple(
+                types
+                    .into_iter()
+                    .zip(items)
+                    .map(|(t, item)| t.example(item))
+                    .collect(),
+            ),
+            (Type::NamedTuple(types), Value::Object(mut items)) => Type::NamedTuple(
+                types
+                    .into_iter()
+                    .map(|(name, t)| match items.remove(&name) {
+                        Some(item) => (name, t.example(item)),
+                        None => (name, t),
+                    })
+                    .collect(),
+            ),
+            (t, _) => t,
+        }
+    }
 }
 
 pub mod default {
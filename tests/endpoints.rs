@@ -0,0 +1,139 @@
+//! `#[endpoint]` registers the handlers of `examples/handlers.rs` as the spec written out by hand
+
+#[path = "../examples/handlers.rs"]
+mod handlers;
+
+use damascus::aat::AAT;
+use damascus::spec::{HeaderParam, HeaderValue, PathSegment, Spec, Type, Upgrade};
+use damascus::type_of;
+use handlers::{Log, LogRequest, Machine, Status};
+
+fn declared() -> Spec {
+    Spec::new("ignition")
+        .service("machines", |service| {
+            service
+                .get(
+                    "get",
+                    vec![
+                        PathSegment::Literal("machines".to_string()),
+                        PathSegment::Type {
+                            name: "name".to_string(),
+                            r#type: type_of!(String),
+                        },
+                    ],
+                    |e| {
+                        e.header(
+                            "x-ignition-namespace",
+                            HeaderValue::Type {
+                                name: "namespace".to_string(),
+                                r#type: type_of!(String),
+                            },
+                        )
+                        .response(type_of!(Machine))
+                    },
+                )
+                .get(
+                    "list",
+                    vec![PathSegment::Literal("machines".to_string())],
+                    |e| {
+                        e.header(
+                            "x-ignition-namespace",
+                            HeaderValue::Type {
+                                name: "namespace".to_string(),
+                                r#type: type_of!(Option<String>),
+                            },
+                        )
+                        .response(
+                            Type::tuple(vec![type_of!(Machine), type_of!(Status)]).wrap_list(),
+                        )
+                    },
+                )
+                .put(
+                    "apply",
+                    vec![PathSegment::Literal("machines".to_string())],
+                    |e| e.body(type_of!(Machine)).invalidates("machines"),
+                )
+                .get(
+                    "logs",
+                    vec![
+                        PathSegment::Literal("machines".to_string()),
+                        PathSegment::Type {
+                            name: "name".to_string(),
+                            r#type: type_of!(String),
+                        },
+                        PathSegment::Literal("logs".to_string()),
+                    ],
+                    |e| {
+                        e.query(type_of!(LogRequest))
+                            .response(type_of!(Log).wrap_stream())
+                            .upgrade(Upgrade::Ws)
+                    },
+                )
+        })
+        .service("auth", |service| {
+            service.post(
+                "login",
+                vec![
+                    PathSegment::Literal("auth".to_string()),
+                    PathSegment::Literal("login".to_string()),
+                ],
+                |e| {
+                    e.header(
+                        "authorization",
+                        HeaderValue::Pattern {
+                            pattern: "Bearer {token}".to_string(),
+                            params: vec![HeaderParam {
+                                name: "token".to_string(),
+                                r#type: type_of!(String),
+                                format: None,
+                            }],
+                        },
+                    )
+                    .response(type_of!(String))
+                },
+            )
+        })
+}
+
+fn pretty(spec: &Spec) -> String {
+    let aat = AAT::from_spec(spec).unwrap();
+    serde_json::to_string_pretty(&aat).unwrap()
+}
+
+#[test]
+fn handlers_register_their_endpoints() {
+    let registered = Spec::new("ignition").registered_endpoints();
+    assert_eq!(pretty(&registered), pretty(&declared()));
+}
+
+#[test]
+fn handlers_register_under_their_module_or_service() {
+    let registered = Spec::new("ignition").registered_endpoints();
+    let services = registered
+        .services()
+        .iter()
+        .map(|service| {
+            let endpoints = service
+                .endpoints()
+                .iter()
+                .map(|endpoint| endpoint.name().to_string())
+                .collect::<Vec<_>>();
+            (service.name().to_string(), endpoints)
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        services,
+        [
+            ("auth".to_string(), vec!["login".to_string()]),
+            (
+                "machines".to_string(),
+                vec![
+                    "get".to_string(),
+                    "list".to_string(),
+                    "apply".to_string(),
+                    "logs".to_string()
+                ]
+            ),
+        ]
+    );
+}
@@ -0,0 +1,321 @@
+// Generated by Damascus
+// Do not edit this file directly
+
+#![allow(
+    clippy::too_many_arguments,
+    clippy::result_large_err,
+    clippy::vec_init_then_push
+)]
+
+use futures_util::StreamExt as _;
+
+use crate::spec::*;
+
+/// Why a request failed
+#[derive(Debug)]
+pub enum Error {
+    /// The request could not be sent or its response could not be read
+    Http(reqwest::Error),
+    /// The server answered with an error status
+    Status {
+        status: reqwest::StatusCode,
+        body: String,
+    },
+    /// The response is not the JSON the spec describes
+    Json(serde_json::Error),
+    /// The WebSocket could not be opened or failed
+    WebSocket(tokio_tungstenite::tungstenite::Error),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Http(error) => write!(f, "Request failed: {}", error),
+            Error::Status { status, body } => {
+                write!(f, "Server answered {}: {}", status, body)
+            }
+            Error::Json(error) => write!(f, "Invalid response: {}", error),
+            Error::WebSocket(error) => write!(f, "WebSocket failed: {}", error),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Http(error) => Some(error),
+            Error::Status { .. } => None,
+            Error::Json(error) => Some(error),
+            Error::WebSocket(error) => Some(error),
+        }
+    }
+}
+
+impl From<reqwest::Error> for Error {
+    fn from(error: reqwest::Error) -> Self {
+        Error::Http(error)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(error: serde_json::Error) -> Self {
+        Error::Json(error)
+    }
+}
+
+impl From<tokio_tungstenite::tungstenite::Error> for Error {
+    fn from(error: tokio_tungstenite::tungstenite::Error) -> Self {
+        Error::WebSocket(error)
+    }
+}
+
+/// Sends requests to every service of the spec
+#[derive(Clone)]
+pub struct Client {
+    http: reqwest::Client,
+    base_url: String,
+    headers: Headers,
+}
+
+impl Client {
+    /// A client for the API at `base_url`, e.g. `https://api.example.com`
+    pub fn new(base_url: impl Into<String>, api_key: String) -> Self {
+        Self::with_http_client(reqwest::Client::new(), base_url, api_key)
+    }
+
+    /// Like [`Client::new`], sending requests through `http`
+    pub fn with_http_client(
+        http: reqwest::Client,
+        base_url: impl Into<String>,
+        api_key: String,
+    ) -> Self {
+        let mut headers = Headers::new();
+        headers.push(("x-api-key", text(&api_key)));
+        Self {
+            http,
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            headers,
+        }
+    }
+
+    /// The `files` service
+    pub fn files(&self) -> FilesClient {
+        FilesClient {
+            client: self.clone(),
+            headers: self.headers.clone(),
+        }
+    }
+
+    /// The `machines` service
+    pub fn machines(&self, region: String) -> MachinesClient {
+        let mut headers = self.headers.clone();
+        headers.push(("x-region", format!("region-{}", text(&region))));
+        MachinesClient {
+            client: self.clone(),
+            headers,
+        }
+    }
+}
+
+/// Endpoints of the `files` service
+#[derive(Clone)]
+pub struct FilesClient {
+    client: Client,
+    headers: Headers,
+}
+
+impl FilesClient {
+    /// `GET /files/{bucket}/{*path}`
+    pub async fn read(
+        &self,
+        headers_: String,
+        key_id: String,
+        signature: String,
+        bucket: String,
+        path_: String,
+    ) -> Result<String, Error> {
+        let mut headers = self.headers.clone();
+        headers.push(("x-forwarded-headers", text(&headers_)));
+        let value = format!("id={}, sig={}", text(&key_id), base64(&text(&signature)));
+        headers.push(("x-signature", value));
+        let url = format!(
+            "{}/files/{}/{}",
+            self.client.base_url,
+            path(&text(&bucket), false),
+            path(&path_, true)
+        );
+        let request = self.client.http.get(url);
+        let response = send(request, &headers).await?;
+        json(response).await
+    }
+}
+
+/// Endpoints of the `machines` service
+#[derive(Clone)]
+pub struct MachinesClient {
+    client: Client,
+    headers: Headers,
+}
+
+impl MachinesClient {
+    /// `PUT /machines`
+    pub async fn apply(&self, body: &Machine) -> Result<Machine, Error> {
+        let url = format!("{}/machines", self.client.base_url);
+        let request = self.client.http.put(url).json(body);
+        let response = send(request, &self.headers).await?;
+        json(response).await
+    }
+
+    /// `DELETE /machines/{name}`
+    pub async fn delete(&self, name: String) -> Result<(), Error> {
+        let url = format!(
+            "{}/machines/{}",
+            self.client.base_url,
+            path(&text(&name), false)
+        );
+        let request = self.client.http.delete(url);
+        send(request, &self.headers).await?;
+        Ok(())
+    }
+
+    /// `GET /machines/{name}`
+    pub async fn get(&self, namespace: Option<String>, name: String) -> Result<Machine, Error> {
+        let mut headers = self.headers.clone();
+        if let Some(namespace) = &namespace {
+            headers.push(("x-namespace", text(namespace)));
+        }
+        let url = format!(
+            "{}/machines/{}",
+            self.client.base_url,
+            path(&text(&name), false)
+        );
+        let request = self.client.http.get(url);
+        let response = send(request, &headers).await?;
+        json(response).await
+    }
+
+    /// `GET /machines/{name}/logs` (WebSocket)
+    pub async fn logs(&self, name: String, query: &LogRequest) -> Result<Stream<Log>, Error> {
+        let url = format!(
+            "{}/machines/{}/logs",
+            self.client.base_url,
+            path(&text(&name), false)
+        );
+        let request = self.client.http.get(url).query(query).build()?;
+        connect(request.url().clone(), &self.headers).await
+    }
+
+    /// `GET /machines/{name}/services/{service}`
+    pub async fn service(&self, name: String, service: String) -> Result<String, Error> {
+        let url = format!(
+            "{}/machines/{}/services/{}",
+            self.client.base_url,
+            path(&text(&name), false),
+            path(&text(&service), false)
+        );
+        let request = self.client.http.get(url);
+        let response = send(request, &self.headers).await?;
+        json(response).await
+    }
+}
+
+type Headers = Vec<(&'static str, String)>;
+
+/// Sends `request` with `headers`, turning error statuses into [`Error::Status`]
+async fn send(
+    mut request: reqwest::RequestBuilder,
+    headers: &Headers,
+) -> Result<reqwest::Response, Error> {
+    for (name, value) in headers {
+        request = request.header(*name, value);
+    }
+    let response = request.send().await?;
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+    let body = response.text().await.unwrap_or_default();
+    Err(Error::Status { status, body })
+}
+
+/// Reads the JSON body of a response
+async fn json<T: serde::de::DeserializeOwned>(response: reqwest::Response) -> Result<T, Error> {
+    let body = response.bytes().await?;
+    Ok(serde_json::from_slice(&body)?)
+}
+
+/// The text of a path or header argument: strings as they are, other values as JSON
+fn text<T: serde::Serialize + ?Sized>(value: &T) -> String {
+    match serde_json::to_value(value) {
+        Ok(serde_json::Value::String(text)) => text,
+        Ok(value) => value.to_string(),
+        Err(_) => String::new(),
+    }
+}
+
+/// Percent-encodes a path argument, keeping `/` when it fills a trailing wildcard
+fn path(text: &str, keep_slashes: bool) -> String {
+    let mut encoded = String::new();
+    for byte in text.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                encoded.push(byte as char)
+            }
+            b'/' if keep_slashes => encoded.push('/'),
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+/// Standard base64 with padding
+fn base64(text: &str) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::new();
+    for chunk in text.as_bytes().chunks(3) {
+        let byte = |index: usize| u32::from(chunk.get(index).copied().unwrap_or(0));
+        let bits = (byte(0) << 16) | (byte(1) << 8) | byte(2);
+        for index in 0..4 {
+            if index <= chunk.len() {
+                encoded.push(ALPHABET[((bits >> (18 - 6 * index)) & 63) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+/// Items received over a WebSocket, one JSON message each
+pub type Stream<T> = futures_util::stream::BoxStream<'static, Result<T, Error>>;
+
+/// Opens a WebSocket to `url`, reading every text or binary message as an item
+async fn connect<T: serde::de::DeserializeOwned + Send + 'static>(
+    mut url: reqwest::Url,
+    headers: &Headers,
+) -> Result<Stream<T>, Error> {
+    use tokio_tungstenite::tungstenite::client::IntoClientRequest as _;
+    use tokio_tungstenite::tungstenite::{self, Message, http};
+
+    let scheme = if url.scheme() == "https" { "wss" } else { "ws" };
+    // Both schemes are special, so switching between them can't fail
+    let _ = url.set_scheme(scheme);
+    let mut request = url.as_str().into_client_request()?;
+    for (name, value) in headers {
+        let name = http::HeaderName::from_bytes(name.as_bytes())
+            .map_err(|error| tungstenite::Error::HttpFormat(error.into()))?;
+        let value = http::HeaderValue::from_str(value)
+            .map_err(|error| tungstenite::Error::HttpFormat(error.into()))?;
+        request.headers_mut().append(name, value);
+    }
+    let (socket, _) = tokio_tungstenite::connect_async(request).await?;
+    let items = socket.filter_map(|message| async move {
+        match message {
+            Ok(Message::Text(text)) => Some(serde_json::from_str(&text).map_err(Error::from)),
+            Ok(Message::Binary(bytes)) => Some(serde_json::from_slice(&bytes).map_err(Error::from)),
+            Ok(_) => None,
+            Err(error) => Some(Err(Error::from(error))),
+        }
+    });
+    Ok(items.boxed())
+}
@@ -0,0 +1,334 @@
+// Generated by Damascus
+// Do not edit this file directly
+
+#![allow(clippy::too_many_arguments, clippy::result_large_err)]
+
+use std::future::Future;
+use std::sync::Arc;
+
+use axum::extract::{self, ws};
+use axum::response::{self, IntoResponse as _};
+use axum::{http, routing};
+use futures_util::StreamExt as _;
+
+use crate::spec::*;
+
+/// Endpoints of the `files` service
+pub trait FilesService: Send + Sync + 'static {
+    /// Turned into the response when an endpoint fails
+    type Error: response::IntoResponse + Send;
+
+    /// `GET /files/{bucket}/{*path}`
+    fn read(
+        &self,
+        api_key: String,
+        headers_: String,
+        key_id: String,
+        signature: String,
+        bucket: String,
+        path: String,
+    ) -> impl Future<Output = Result<String, Self::Error>> + Send;
+}
+
+/// Endpoints of the `machines` service
+pub trait MachinesService: Send + Sync + 'static {
+    /// Turned into the response when an endpoint fails
+    type Error: response::IntoResponse + Send;
+
+    /// `PUT /machines`
+    fn apply(
+        &self,
+        api_key: String,
+        region: String,
+        body: Machine,
+    ) -> impl Future<Output = Result<Machine, Self::Error>> + Send;
+
+    /// `DELETE /machines/{name}`
+    fn delete(
+        &self,
+        api_key: String,
+        region: String,
+        name: String,
+    ) -> impl Future<Output = Result<(), Self::Error>> + Send;
+
+    /// `GET /machines/{name}`
+    fn get(
+        &self,
+        api_key: String,
+        region: String,
+        namespace: Option<String>,
+        name: String,
+    ) -> impl Future<Output = Result<Machine, Self::Error>> + Send;
+
+    /// `GET /machines/{name}/logs` (WebSocket)
+    fn logs(
+        &self,
+        api_key: String,
+        region: String,
+        name: String,
+        query: LogRequest,
+    ) -> impl Future<
+        Output = Result<impl futures_util::Stream<Item = Log> + Send + 'static, Self::Error>,
+    > + Send;
+
+    /// `GET /machines/{name}/services/{service}`
+    fn service(
+        &self,
+        api_key: String,
+        region: String,
+        name: String,
+        service_: String,
+    ) -> impl Future<Output = Result<String, Self::Error>> + Send;
+}
+
+/// Serves every service of the spec
+pub fn router(files: impl FilesService, machines: impl MachinesService) -> axum::Router {
+    axum::Router::new()
+        .merge(files_router(files))
+        .merge(machines_router(machines))
+}
+
+/// Routes of the `files` service
+pub fn files_router<S: FilesService>(service: S) -> axum::Router {
+    axum::Router::new()
+        .route("/files/{bucket}/{*path}", routing::get(files_read::<S>))
+        .with_state(Arc::new(service))
+}
+
+/// Routes of the `machines` service
+pub fn machines_router<S: MachinesService>(service: S) -> axum::Router {
+    axum::Router::new()
+        .route("/machines", routing::put(machines_apply::<S>))
+        .route(
+            "/machines/{name}",
+            routing::delete(machines_delete::<S>).get(machines_get::<S>),
+        )
+        .route("/machines/{name}/logs", routing::get(machines_logs::<S>))
+        .route(
+            "/machines/{name}/services/{service}",
+            routing::get(machines_service::<S>),
+        )
+        .with_state(Arc::new(service))
+}
+
+async fn files_read<S: FilesService>(
+    extract::State(service): extract::State<Arc<S>>,
+    extract::Path((bucket, path)): extract::Path<(String, String)>,
+    headers: http::HeaderMap,
+) -> Result<response::Response, response::Response> {
+    let api_key = required_header(&headers, "x-api-key", &["", ""], 0)?;
+    let headers_ = required_header(&headers, "x-forwarded-headers", &["", ""], 0)?;
+    let key_id = required_header(&headers, "x-signature", &["id=", ", sig=", ""], 0)?;
+    let signature: Base64<String> =
+        required_header(&headers, "x-signature", &["id=", ", sig=", ""], 1)?;
+    let signature = signature.0;
+    let output = service
+        .read(api_key, headers_, key_id, signature, bucket, path)
+        .await
+        .map_err(response::IntoResponse::into_response)?;
+    Ok(axum::Json(output).into_response())
+}
+
+async fn machines_apply<S: MachinesService>(
+    extract::State(service): extract::State<Arc<S>>,
+    headers: http::HeaderMap,
+    extract::Json(body): extract::Json<Machine>,
+) -> Result<response::Response, response::Response> {
+    let api_key = required_header(&headers, "x-api-key", &["", ""], 0)?;
+    let region = required_header(&headers, "x-region", &["region-", ""], 0)?;
+    let output = service
+        .apply(api_key, region, body)
+        .await
+        .map_err(response::IntoResponse::into_response)?;
+    Ok(axum::Json(output).into_response())
+}
+
+async fn machines_delete<S: MachinesService>(
+    extract::State(service): extract::State<Arc<S>>,
+    extract::Path((name,)): extract::Path<(String,)>,
+    headers: http::HeaderMap,
+) -> Result<response::Response, response::Response> {
+    let api_key = required_header(&headers, "x-api-key", &["", ""], 0)?;
+    let region = required_header(&headers, "x-region", &["region-", ""], 0)?;
+    service
+        .delete(api_key, region, name)
+        .await
+        .map_err(response::IntoResponse::into_response)?;
+    Ok(http::StatusCode::NO_CONTENT.into_response())
+}
+
+async fn machines_get<S: MachinesService>(
+    extract::State(service): extract::State<Arc<S>>,
+    extract::Path((name,)): extract::Path<(String,)>,
+    headers: http::HeaderMap,
+) -> Result<response::Response, response::Response> {
+    let api_key = required_header(&headers, "x-api-key", &["", ""], 0)?;
+    let region = required_header(&headers, "x-region", &["region-", ""], 0)?;
+    let namespace = optional_header(&headers, "x-namespace", &["", ""], 0)?;
+    let output = service
+        .get(api_key, region, namespace, name)
+        .await
+        .map_err(response::IntoResponse::into_response)?;
+    Ok(axum::Json(output).into_response())
+}
+
+async fn machines_logs<S: MachinesService>(
+    extract::State(service): extract::State<Arc<S>>,
+    extract::Path((name,)): extract::Path<(String,)>,
+    extract::Query(query): extract::Query<LogRequest>,
+    headers: http::HeaderMap,
+    upgrade: ws::WebSocketUpgrade,
+) -> Result<response::Response, response::Response> {
+    let api_key = required_header(&headers, "x-api-key", &["", ""], 0)?;
+    let region = required_header(&headers, "x-region", &["region-", ""], 0)?;
+    let stream = service
+        .logs(api_key, region, name, query)
+        .await
+        .map_err(response::IntoResponse::into_response)?;
+    Ok(upgrade.on_upgrade(move |socket| send_stream(socket, stream)))
+}
+
+async fn machines_service<S: MachinesService>(
+    extract::State(service): extract::State<Arc<S>>,
+    extract::Path((name, service_)): extract::Path<(String, String)>,
+    headers: http::HeaderMap,
+) -> Result<response::Response, response::Response> {
+    let api_key = required_header(&headers, "x-api-key", &["", ""], 0)?;
+    let region = required_header(&headers, "x-region", &["region-", ""], 0)?;
+    let output = service
+        .service(api_key, region, name, service_)
+        .await
+        .map_err(response::IntoResponse::into_response)?;
+    Ok(axum::Json(output).into_response())
+}
+
+fn bad_request(message: String) -> response::Response {
+    (http::StatusCode::BAD_REQUEST, message).into_response()
+}
+
+fn required_header<T: std::str::FromStr>(
+    headers: &http::HeaderMap,
+    name: &str,
+    literals: &[&str],
+    index: usize,
+) -> Result<T, response::Response> {
+    optional_header(headers, name, literals, index)?
+        .ok_or_else(|| bad_request(format!("Missing header '{}'", name)))
+}
+
+/// Parses placeholder `index` of a header declared as `literals` with a placeholder between
+/// each two of them
+fn optional_header<T: std::str::FromStr>(
+    headers: &http::HeaderMap,
+    name: &str,
+    literals: &[&str],
+    index: usize,
+) -> Result<Option<T>, response::Response> {
+    let Some(value) = headers.get(name) else {
+        return Ok(None);
+    };
+    let value = value
+        .to_str()
+        .ok()
+        .and_then(|value| placeholder(value, literals, index))
+        .ok_or_else(|| {
+            bad_request(format!(
+                "Header '{}' must look like '{}'",
+                name,
+                literals.join("…")
+            ))
+        })?;
+    value
+        .parse()
+        .map(Some)
+        .map_err(|_| bad_request(format!("Invalid value for header '{}'", name)))
+}
+
+/// The text between `literals[index]` and `literals[index + 1]` in `value`
+fn placeholder<'a>(value: &'a str, literals: &[&str], index: usize) -> Option<&'a str> {
+    let (first, rest) = literals.split_first()?;
+    let (last, separators) = rest.split_last()?;
+    let mut rest = value.strip_prefix(first)?.strip_suffix(last)?;
+    let mut placeholders = Vec::new();
+    for separator in separators {
+        let (placeholder, tail) = rest.split_once(separator)?;
+        placeholders.push(placeholder);
+        rest = tail;
+    }
+    placeholders.push(rest);
+    placeholders.get(index).copied()
+}
+
+/// A header placeholder sent as the base64 of its text
+struct Base64<T>(T);
+
+impl<T: std::str::FromStr> std::str::FromStr for Base64<T> {
+    type Err = ();
+
+    fn from_str(value: &str) -> Result<Self, ()> {
+        let text = base64_decode(value).and_then(|bytes| String::from_utf8(bytes).ok());
+        text.ok_or(())?.parse().map(Base64).map_err(|_| ())
+    }
+}
+
+/// Decodes standard or URL-safe base64, with or without padding
+fn base64_decode(input: &str) -> Option<Vec<u8>> {
+    let mut bytes = Vec::with_capacity(input.len() * 3 / 4);
+    let (mut buffer, mut bits) = (0u32, 0);
+    for c in input.bytes() {
+        let value = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' | b'-' => 62,
+            b'/' | b'_' => 63,
+            b'=' => continue,
+            _ => return None,
+        };
+        buffer = (buffer << 6) | u32::from(value);
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((buffer >> bits) as u8);
+        }
+    }
+    Some(bytes)
+}
+
+/// Sends every item as a JSON text message and closes normally once the stream ends
+async fn send_stream<T: serde::Serialize>(
+    mut socket: ws::WebSocket,
+    stream: impl futures_util::Stream<Item = T>,
+) {
+    let mut stream = std::pin::pin!(stream);
+    loop {
+        let item = {
+            let received = std::pin::pin!(socket.recv());
+            match futures_util::future::select(stream.next(), received).await {
+                futures_util::future::Either::Left((Some(item), _)) => item,
+                futures_util::future::Either::Left((None, _)) => break,
+                // Clients only send control frames; anything else ends the stream
+                futures_util::future::Either::Right((message, _)) => match message {
+                    Some(Ok(ws::Message::Ping(_) | ws::Message::Pong(_))) => continue,
+                    _ => return,
+                },
+            }
+        };
+        let text = match serde_json::to_string(&item) {
+            Ok(text) => text,
+            Err(error) => return close(socket, ws::close_code::ERROR, &error.to_string()).await,
+        };
+        if socket.send(ws::Message::Text(text.into())).await.is_err() {
+            return;
+        }
+    }
+    close(socket, ws::close_code::NORMAL, "").await;
+}
+
+async fn close(mut socket: ws::WebSocket, code: u16, reason: &str) {
+    let frame = ws::CloseFrame {
+        code,
+        reason: reason.into(),
+    };
+    let _ = socket.send(ws::Message::Close(Some(frame))).await;
+}
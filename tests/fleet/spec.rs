@@ -0,0 +1,75 @@
+//! A small spec covering what the Rust generators have to get right: path, wildcard, query and
+//! body arguments, headers at every level, empty responses, a WebSocket stream and arguments
+//! named like the generated code's own variables
+
+use damascus::spec::{Spec, Upgrade};
+use damascus::{JsonSchema, path, type_of};
+use damascus_meta::header_value;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct Machine {
+    pub name: String,
+    pub image: String,
+    pub cpus: u8,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct Log {
+    pub line: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct LogRequest {
+    pub limit: u32,
+}
+
+pub fn spec() -> Spec {
+    Spec::new("fleet")
+        .header("x-api-key", header_value!(api_key: String))
+        .service("machines", |service| {
+            service
+                .header("x-region", header_value!("region-{region}" use region: String))
+                .get("get", path!("machines", name: String), |endpoint| {
+                    endpoint
+                        .response(type_of!(Machine))
+                        .header("x-namespace", header_value!(namespace: Option<String>))
+                })
+                .put("apply", path!("machines"), |endpoint| {
+                    endpoint.body(type_of!(Machine)).response(type_of!(Machine))
+                })
+                .delete("delete", path!("machines", name: String), |endpoint| {
+                    endpoint.invalidates("machines")
+                })
+                .get(
+                    "service",
+                    path!("/machines/{name: String}/services/{service: String}"),
+                    |endpoint| endpoint.response(type_of!(String)),
+                )
+                .get(
+                    "logs",
+                    path!("machines", name: String, "logs"),
+                    |endpoint| {
+                        endpoint
+                            .response(type_of!(Log).wrap_stream())
+                            .upgrade(Upgrade::Ws)
+                            .query(type_of!(LogRequest))
+                    },
+                )
+        })
+        .service("files", |service| {
+            service.get(
+                "read",
+                path!("/files/{bucket: String}/{*path}"),
+                |endpoint| {
+                    endpoint
+                        .response(type_of!(String))
+                        .header(
+                            "x-signature",
+                            header_value!("id={key_id}, sig={signature}" use key_id: String, signature: String as base64),
+                        )
+                        .header("x-forwarded-headers", header_value!(headers: String))
+                },
+            )
+        })
+}
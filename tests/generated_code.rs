@@ -0,0 +1,25 @@
+//! The generated code the round trip test compiles must match what the generators write today.
+//! Run with `DAMASCUS_UPDATE=1` to regenerate it.
+
+#[allow(dead_code)]
+#[path = "fleet/spec.rs"]
+mod spec;
+
+use damascus::build::Build;
+use damascus::generate::axum::AxumOptions;
+use damascus::generate::rust::RustOptions;
+
+#[test]
+fn fleet_code_is_up_to_date() {
+    Build::new(spec::spec())
+        .out_dir("tests/fleet")
+        .axum(AxumOptions {
+            types_module: "crate::spec".to_string(),
+        })
+        .rust(RustOptions {
+            types_module: "crate::spec".to_string(),
+        })
+        .check(std::env::var_os("DAMASCUS_UPDATE").is_none())
+        .run()
+        .unwrap();
+}
@@ -0,0 +1,40 @@
+//! `path!` splits templates into segments and keeps plain literals as they are
+
+use damascus::path;
+use damascus::spec::PathSegment;
+
+fn describe(segments: Vec<PathSegment>) -> Vec<String> {
+    segments
+        .into_iter()
+        .map(|segment| match segment {
+            PathSegment::Literal(literal) => literal,
+            PathSegment::Type { name, .. } => format!("{{{}}}", name),
+            PathSegment::Wildcard(name) => format!("{{*{}}}", name),
+        })
+        .collect()
+}
+
+#[test]
+fn templates_become_segments() {
+    assert_eq!(
+        describe(path!("/machines/{name: String}/files/{*rest}")),
+        ["machines", "{name}", "files", "{*rest}"]
+    );
+}
+
+#[test]
+fn templates_mix_with_literals_and_parameters() {
+    assert_eq!(
+        describe(path!("machines", name: String, "snapshots/{id: u64}")),
+        ["machines", "{name}", "snapshots", "{id}"]
+    );
+}
+
+#[test]
+fn plain_literals_are_single_segments() {
+    // Literals without `{` or `/` are taken as they were before templates
+    assert_eq!(
+        describe(path!("v1.0 beta", name: String)),
+        ["v1.0 beta", "{name}"]
+    );
+}
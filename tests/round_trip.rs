@@ -0,0 +1,183 @@
+//! Serves the fleet spec with the generated Axum server and calls it with the generated Rust
+//! client, so both have to compile and agree on the wire format
+
+#[path = "fleet/client.rs"]
+mod client;
+#[path = "fleet/server.rs"]
+mod server;
+#[allow(dead_code)]
+#[path = "fleet/spec.rs"]
+mod spec;
+
+use axum::http::StatusCode;
+use futures_util::StreamExt as _;
+use spec::{Log, LogRequest, Machine};
+
+struct Files;
+
+impl server::FilesService for Files {
+    type Error = StatusCode;
+
+    async fn read(
+        &self,
+        api_key: String,
+        headers: String,
+        key_id: String,
+        signature: String,
+        bucket: String,
+        path: String,
+    ) -> Result<String, StatusCode> {
+        Ok(format!(
+            "{} {} {} {} {} {}",
+            api_key, key_id, signature, headers, bucket, path
+        ))
+    }
+}
+
+struct Machines;
+
+impl server::MachinesService for Machines {
+    type Error = StatusCode;
+
+    async fn apply(
+        &self,
+        _api_key: String,
+        region: String,
+        body: Machine,
+    ) -> Result<Machine, StatusCode> {
+        Ok(Machine {
+            image: format!("{}/{}", region, body.image),
+            ..body
+        })
+    }
+
+    async fn delete(
+        &self,
+        _api_key: String,
+        _region: String,
+        name: String,
+    ) -> Result<(), StatusCode> {
+        if name == "missing" {
+            Err(StatusCode::NOT_FOUND)
+        } else {
+            Ok(())
+        }
+    }
+
+    async fn get(
+        &self,
+        _api_key: String,
+        region: String,
+        namespace: Option<String>,
+        name: String,
+    ) -> Result<Machine, StatusCode> {
+        Ok(Machine {
+            name: format!("{}/{}", namespace.unwrap_or_default(), name),
+            image: region,
+            cpus: 1,
+        })
+    }
+
+    async fn service(
+        &self,
+        _api_key: String,
+        _region: String,
+        name: String,
+        service: String,
+    ) -> Result<String, StatusCode> {
+        Ok(format!("{} {}", name, service))
+    }
+
+    async fn logs(
+        &self,
+        _api_key: String,
+        _region: String,
+        name: String,
+        query: LogRequest,
+    ) -> Result<impl futures_util::Stream<Item = Log> + Send + 'static, StatusCode> {
+        let lines = (0..query.limit).map(move |index| Log {
+            line: format!("{} {}", name, index),
+        });
+        Ok(futures_util::stream::iter(lines))
+    }
+}
+
+async fn serve() -> client::Client {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, server::router(Files, Machines))
+            .await
+            .unwrap()
+    });
+    client::Client::new(format!("http://{}/", address), "secret".to_string())
+}
+
+#[tokio::test]
+async fn sends_path_arguments_and_headers() {
+    let client = serve().await;
+    let machines = client.machines("eu".to_string());
+
+    let machine = machines
+        .get(Some("prod".to_string()), "web 1".to_string())
+        .await
+        .unwrap();
+    assert_eq!(machine.name, "prod/web 1");
+    assert_eq!(machine.image, "eu");
+
+    let machine = machines.get(None, "web".to_string()).await.unwrap();
+    assert_eq!(machine.name, "/web");
+
+    // The handler's own `service` state is renamed around the argument
+    let service = machines
+        .service("web".to_string(), "dns".to_string())
+        .await
+        .unwrap();
+    assert_eq!(service, "web dns");
+
+    let read = client
+        .files()
+        .read(
+            "accept".to_string(),
+            "k1".to_string(),
+            "sig=1".to_string(),
+            "logs".to_string(),
+            "2024/01/app.log".to_string(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(read, "secret k1 sig=1 accept logs 2024/01/app.log");
+}
+
+#[tokio::test]
+async fn sends_bodies_and_reports_error_statuses() {
+    let client = serve().await;
+    let machines = client.machines("eu".to_string());
+
+    let machine = Machine {
+        name: "web".to_string(),
+        image: "nginx".to_string(),
+        cpus: 2,
+    };
+    let applied = machines.apply(&machine).await.unwrap();
+    assert_eq!(applied.image, "eu/nginx");
+    assert_eq!(applied.cpus, 2);
+
+    machines.delete("web".to_string()).await.unwrap();
+    match machines.delete("missing".to_string()).await {
+        Err(client::Error::Status { status, .. }) => assert_eq!(status, 404),
+        other => panic!("expected a 404, got {:?}", other.map(|_| ())),
+    }
+}
+
+#[tokio::test]
+async fn streams_websocket_items() {
+    let client = serve().await;
+    let logs = client
+        .machines("eu".to_string())
+        .logs("web".to_string(), &LogRequest { limit: 3 })
+        .await
+        .unwrap();
+    let lines: Vec<String> = logs.map(|log| log.unwrap().line).collect().await;
+    assert_eq!(lines, ["web 0", "web 1", "web 2"]);
+}
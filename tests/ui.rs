@@ -0,0 +1,7 @@
+//! Macro misuse is reported at compile time. Run with `TRYBUILD=overwrite` to update the expected
+//! messages.
+
+#[test]
+fn macro_errors() {
+    trybuild::TestCases::new().compile_fail("tests/ui/*.rs");
+}
@@ -0,0 +1,8 @@
+use damascus::endpoint;
+
+#[endpoint(post, "auth/login")]
+fn login(#[header("authorization", "Bearer {key}")] token: String) -> String {
+    token
+}
+
+fn main() {}
@@ -0,0 +1,8 @@
+use damascus::endpoint;
+
+#[endpoint(get, "machines")]
+async fn list() -> impl axum::response::IntoResponse {
+    "[]"
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use damascus::endpoint;
+
+#[endpoint(get, "machines/{name}")]
+async fn get(name: String) -> Result<axum::response::Response, axum::response::Response> {
+    let _ = name;
+    Ok(axum::response::Response::default())
+}
+
+fn main() {}
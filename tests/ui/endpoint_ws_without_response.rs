@@ -0,0 +1,6 @@
+use damascus::endpoint;
+
+#[endpoint(get, "machines/logs", ws)]
+fn logs() {}
+
+fn main() {}
@@ -0,0 +1,7 @@
+use damascus::header_value;
+
+fn main() {
+    let _ = header_value!("Bearer {token" use token: String);
+    let _ = header_value!("{scheme}{token}" use scheme: String, token: String);
+    let _ = header_value!("Bearer {}" use token: String);
+}
@@ -0,0 +1,5 @@
+use damascus::header_value;
+
+fn main() {
+    let _ = header_value!("{scheme} token" use scheme: String, token: String);
+}
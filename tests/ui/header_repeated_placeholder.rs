@@ -0,0 +1,6 @@
+use damascus::header_value;
+
+fn main() {
+    let _ = header_value!("{token}, {token}" use token: String);
+    let _ = header_value!("Bearer {token}" use token: String, token: String);
+}
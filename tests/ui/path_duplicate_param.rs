@@ -0,0 +1,6 @@
+use damascus::path;
+
+fn main() {
+    let _ = path!("/machines/{name: String}/snapshots/{name: String}");
+    let _ = path!("machines", name: String, "/snapshots/{name: String}");
+}
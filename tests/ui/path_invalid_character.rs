@@ -0,0 +1,5 @@
+use damascus::path;
+
+fn main() {
+    let _ = path!("/machines/{name: String}/snap shots");
+}
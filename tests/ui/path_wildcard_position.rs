@@ -0,0 +1,6 @@
+use damascus::path;
+
+fn main() {
+    let _ = path!("/files/{*rest}/metadata");
+    let _ = path!("/files/{*rest}", "metadata");
+}
@@ -0,0 +1,7 @@
+#[damascus::rust_path("fleet::Part")]
+#[derive(schemars::JsonSchema)]
+pub struct Part {
+    pub serial: String,
+}
+
+fn main() {}